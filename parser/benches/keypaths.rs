@@ -0,0 +1,39 @@
+//! Parsing a response with a large JSON body, where building the keypath
+//! map is a large share of the work.
+
+use std::{fmt::Write, hint::black_box, str::FromStr};
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use parser::{redacted, standard};
+
+/// About 13 KB of JSON with some 1400 keypaths.
+fn large_response() -> String {
+    let mut body = String::from("{\"users\":[");
+    for i in 0..200 {
+        if i > 0 {
+            body.push(',');
+        }
+        let _ = write!(
+            body,
+            "{{\"id\":{i},\"name\":\"user{i}\",\"active\":true,\"tags\":[\"a\",\"b\"]}}"
+        );
+    }
+    body.push_str("]}");
+    format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{body}",
+        body.len()
+    )
+}
+
+fn keypaths(c: &mut Criterion) {
+    let input = large_response();
+    c.bench_function("standard large body", |b| {
+        b.iter(|| standard::Response::from_str(black_box(&input)).unwrap());
+    });
+    c.bench_function("redacted large body", |b| {
+        b.iter(|| redacted::Response::from_str(black_box(&input)).unwrap());
+    });
+}
+
+criterion_group!(benches, keypaths);
+criterion_main!(benches);
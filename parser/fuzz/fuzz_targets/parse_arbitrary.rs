@@ -0,0 +1,13 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use parser::{redacted, standard};
+
+fuzz_target!(|data: &[u8]| {
+    let _ = standard::Request::from_bytes(data);
+    let _ = standard::Response::from_bytes(data);
+    let _ = redacted::Request::from_bytes(data);
+    let _ = redacted::Request::from_bytes_lossy(data);
+    let _ = redacted::Response::from_bytes(data);
+    let _ = redacted::Response::from_bytes_lossy(data);
+});
@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use parser_fuzz::{check_request_redaction, check_response_redaction};
+
+fuzz_target!(|input: (&[u8], &[u8])| {
+    let (message, mask) = input;
+    check_request_redaction(message, mask);
+    check_response_redaction(message, mask);
+});
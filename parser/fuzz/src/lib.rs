@@ -0,0 +1,140 @@
+use std::{collections::HashMap, ops::Range};
+
+use parser::{JsonFieldRangeExt, redacted, standard};
+
+/// Parses `message` with the standard parser, reveals the units selected by
+/// `mask` and checks that every range the redacted parser reports lies inside
+/// a range of the original parse.
+pub fn check_request_redaction(message: &[u8], mask: &[u8]) {
+    let Ok(request) = standard::Request::from_bytes(message) else {
+        return;
+    };
+    let units = [
+        request.method_with_space(),
+        request.url_with_space(),
+        request.protocol_version_with_newline(),
+    ]
+    .into_iter()
+    .chain(header_units(&request.headers))
+    .chain(body_units(&request.body))
+    .collect::<Vec<_>>();
+
+    let redacted_message = redact(message, &units, mask);
+    let Ok(redacted_request) = redacted::Request::from_bytes(&redacted_message) else {
+        return;
+    };
+    let ranges = [
+        redacted_request.method.clone(),
+        redacted_request.url.clone(),
+        redacted_request.protocol_version.clone(),
+    ]
+    .into_iter()
+    .chain(redacted_ranges(
+        &redacted_request.headers,
+        &redacted_request.body,
+    ));
+    assert_within(ranges, &units, &redacted_message);
+}
+
+pub fn check_response_redaction(message: &[u8], mask: &[u8]) {
+    let Ok(response) = standard::Response::from_bytes(message) else {
+        return;
+    };
+    let units = [
+        response.protocol_version_with_space(),
+        response.status_code_with_space(),
+        response.status_with_newline(),
+    ]
+    .into_iter()
+    .chain(header_units(&response.headers))
+    .chain(body_units(&response.body))
+    .collect::<Vec<_>>();
+
+    let redacted_message = redact(message, &units, mask);
+    let Ok(redacted_response) = redacted::Response::from_bytes(&redacted_message) else {
+        return;
+    };
+    let ranges = [
+        redacted_response.protocol_version.clone(),
+        redacted_response.status_code.clone(),
+        redacted_response.status.clone(),
+    ]
+    .into_iter()
+    .chain(redacted_ranges(
+        &redacted_response.headers,
+        &redacted_response.body,
+    ));
+    assert_within(ranges, &units, &redacted_message);
+}
+
+/// Revealable units, mirroring the ranges the tlsnotary reveal rules select.
+fn header_units(
+    headers: &HashMap<String, Vec<standard::Header>>,
+) -> impl Iterator<Item = Range<usize>> + '_ {
+    headers
+        .values()
+        .flatten()
+        .flat_map(|header| [header.raw.clone(), header.name.end..header.raw.end])
+}
+
+fn body_units(body: &HashMap<String, standard::Body>) -> impl Iterator<Item = Range<usize>> + '_ {
+    body.values().flat_map(|field| match field {
+        standard::Body::KeyValue { key, value } => vec![
+            key.with_quotes_and_colon(),
+            key.full_pair_quoted(value),
+            value.clone(),
+            value.with_quotes(),
+        ],
+        standard::Body::Value(range) => vec![range.clone(), range.with_quotes()],
+    })
+}
+
+fn redacted_ranges<'a>(
+    headers: &'a HashMap<String, Vec<redacted::Header>>,
+    body: &'a HashMap<String, redacted::Body>,
+) -> impl Iterator<Item = Range<usize>> + 'a {
+    let headers = headers
+        .values()
+        .flatten()
+        .flat_map(|header| std::iter::once(header.name.clone()).chain(header.value.clone()));
+    let body = body.values().flat_map(|field| match field {
+        redacted::Body::KeyValue { key, value } => {
+            std::iter::once(key.clone()).chain(value.clone())
+        }
+        redacted::Body::Value(range)
+        | redacted::Body::PartialValue {
+            revealed: range, ..
+        } => std::iter::once(range.clone()).chain(None),
+    });
+    headers.chain(body).filter(|range| !range.is_empty())
+}
+
+fn redact(message: &[u8], units: &[Range<usize>], mask: &[u8]) -> Vec<u8> {
+    let mut redacted_message = vec![0; message.len()];
+    units
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| {
+            mask.get(index / 8 % mask.len().max(1))
+                .is_some_and(|byte| byte & (1 << (index % 8)) != 0)
+        })
+        .filter_map(|(_, unit)| Some((unit.clone(), message.get(unit.clone())?)))
+        .for_each(|(unit, bytes)| redacted_message[unit].copy_from_slice(bytes));
+    redacted_message
+}
+
+fn assert_within(
+    ranges: impl Iterator<Item = Range<usize>>,
+    units: &[Range<usize>],
+    redacted_message: &[u8],
+) {
+    for range in ranges {
+        assert!(
+            units
+                .iter()
+                .any(|unit| unit.start <= range.start && range.end <= unit.end),
+            "redacted range {range:?} is not inside any standard range in {:?}",
+            String::from_utf8_lossy(redacted_message)
+        );
+    }
+}
@@ -0,0 +1,161 @@
+use std::{collections::HashMap, ops::Range};
+
+use crate::{error::Result, standard};
+
+/// A scalar JSON value, compared independently of how it was serialized.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CanonicalValue {
+    String(String),
+    Number(f64),
+    Bool(bool),
+    Null,
+    EmptyObject,
+    EmptyArray,
+}
+
+impl CanonicalValue {
+    fn from_source(source: &str, range: &Range<usize>) -> Option<Self> {
+        let text = source.get(range.clone())?;
+        let is_quoted = range
+            .start
+            .checked_sub(1)
+            .and_then(|quote| source.as_bytes().get(quote))
+            == Some(&b'"');
+        if is_quoted {
+            return Some(Self::String(text.to_string()));
+        }
+        match text.trim() {
+            "null" => Some(Self::Null),
+            "true" => Some(Self::Bool(true)),
+            "false" => Some(Self::Bool(false)),
+            trimmed if trimmed.starts_with('{') => {
+                is_empty_container(trimmed).then_some(Self::EmptyObject)
+            }
+            trimmed if trimmed.starts_with('[') => {
+                is_empty_container(trimmed).then_some(Self::EmptyArray)
+            }
+            trimmed => trimmed.parse().ok().map(Self::Number),
+        }
+    }
+}
+
+fn is_empty_container(text: &str) -> bool {
+    text[1..text.len() - 1].trim().is_empty()
+}
+
+/// A leaf of the transcript body that the re-serialized JSON agrees with.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldCorrespondence {
+    pub keypath: String,
+    pub value: CanonicalValue,
+    pub transcript_range: Range<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldMismatch {
+    /// The transcript has a leaf the re-serialized JSON lacks.
+    MissingFromReserialized {
+        keypath: String,
+        transcript_range: Range<usize>,
+    },
+    /// The re-serialized JSON has a leaf the transcript lacks.
+    MissingFromTranscript { keypath: String },
+    ValueDiffers {
+        keypath: String,
+        transcript: CanonicalValue,
+        reserialized: CanonicalValue,
+        transcript_range: Range<usize>,
+    },
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct JsonComparison {
+    pub matched: Vec<FieldCorrespondence>,
+    pub mismatches: Vec<FieldMismatch>,
+}
+
+impl JsonComparison {
+    #[must_use]
+    pub fn is_match(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
+
+/// Compares a re-serialized JSON document against the body parsed from
+/// `source`, ignoring whitespace and key order, and maps every agreeing leaf
+/// back to its range in `source`.
+pub fn compare_reserialized_json(
+    source: &str,
+    body: &HashMap<String, standard::Body>,
+    reserialized: &str,
+) -> Result<JsonComparison> {
+    let transcript_leaves = leaves(source, body);
+    let reserialized_leaves = leaves(reserialized, &standard::parse_json_body(reserialized)?);
+
+    let mut keypaths = transcript_leaves
+        .keys()
+        .chain(reserialized_leaves.keys())
+        .collect::<Vec<_>>();
+    keypaths.sort();
+    keypaths.dedup();
+
+    Ok(keypaths
+        .into_iter()
+        .fold(JsonComparison::default(), |mut comparison, keypath| {
+            match (
+                transcript_leaves.get(keypath),
+                reserialized_leaves.get(keypath),
+            ) {
+                (Some((transcript, range)), Some((reserialized, _)))
+                    if transcript == reserialized =>
+                {
+                    comparison.matched.push(FieldCorrespondence {
+                        keypath: keypath.clone(),
+                        value: transcript.clone(),
+                        transcript_range: range.clone(),
+                    });
+                }
+                (Some((transcript, range)), Some((reserialized, _))) => {
+                    comparison.mismatches.push(FieldMismatch::ValueDiffers {
+                        keypath: keypath.clone(),
+                        transcript: transcript.clone(),
+                        reserialized: reserialized.clone(),
+                        transcript_range: range.clone(),
+                    });
+                }
+                (Some((_, range)), None) => {
+                    comparison
+                        .mismatches
+                        .push(FieldMismatch::MissingFromReserialized {
+                            keypath: keypath.clone(),
+                            transcript_range: range.clone(),
+                        });
+                }
+                (None, Some(_)) => {
+                    comparison
+                        .mismatches
+                        .push(FieldMismatch::MissingFromTranscript {
+                            keypath: keypath.clone(),
+                        });
+                }
+                (None, None) => {}
+            }
+            comparison
+        }))
+}
+
+fn leaves(
+    source: &str,
+    body: &HashMap<String, standard::Body>,
+) -> HashMap<String, (CanonicalValue, Range<usize>)> {
+    body.iter()
+        .filter_map(|(keypath, field)| {
+            let range = match field {
+                standard::Body::KeyValue { value, .. } => value,
+                standard::Body::Value(range) => range,
+            };
+            CanonicalValue::from_source(source, range)
+                .map(|value| (keypath.clone(), (value, range.clone())))
+        })
+        .collect()
+}
@@ -1,4 +1,4 @@
-use std::{collections::HashMap, ops::Range};
+use std::{borrow::Cow, collections::HashMap, ops::Range};
 
 use pest::{
     RuleType,
@@ -34,6 +34,136 @@ pub fn assert_end_of_iterator<'a, R: RuleType>(
     Ok(())
 }
 
+/// Replaces each byte that is not part of valid UTF-8 with NUL, so offsets
+/// into the input hold for the text.
+pub fn mask_invalid_utf8(input: &[u8]) -> Cow<'_, str> {
+    match std::str::from_utf8(input) {
+        Ok(valid) => Cow::Borrowed(valid),
+        Err(_) => Cow::Owned(
+            input
+                .utf8_chunks()
+                .flat_map(|chunk| {
+                    chunk
+                        .valid()
+                        .chars()
+                        .chain(std::iter::repeat_n('\0', chunk.invalid().len()))
+                })
+                .collect(),
+        ),
+    }
+}
+
+pub fn normalize_header_value(value: &str) -> String {
+    value
+        .split(['\r', '\n'])
+        .map(|line| line.trim_matches([' ', '\t']))
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Offset just past the blank line that ends the message head.
+pub fn message_body_start(input: &[u8]) -> Option<usize> {
+    input
+        .windows(4)
+        .position(|window| window == b"\r\n\r\n")
+        .map(|index| index + 4)
+        .or_else(|| {
+            input
+                .windows(2)
+                .position(|window| window == b"\n\n")
+                .map(|index| index + 2)
+        })
+}
+
+/// Where the last of the requests pipelined in `input`, one direction of a
+/// connection, starts. Each earlier request ends after the body its revealed
+/// `Content-Length` declares, or with its head when it declares neither a
+/// length nor a `Transfer-Encoding`.
+pub fn last_request_start(input: &[u8]) -> usize {
+    last_message_start(input, Some(0))
+}
+
+/// Where the last of the responses in `input` starts. A response without a
+/// revealed `Content-Length` runs to the end of the connection.
+pub fn last_response_start(input: &[u8]) -> usize {
+    last_message_start(input, None)
+}
+
+/// Skips whole messages while their length can be told from their head;
+/// the remainder is the last message. `unframed_body_len` is the body length
+/// of a message declaring no length.
+fn last_message_start(input: &[u8], unframed_body_len: Option<usize>) -> usize {
+    let mut start = 0;
+    while let Some(end) = input
+        .get(start..)
+        .and_then(|rest| message_len(rest, unframed_body_len))
+        .and_then(|len| start.checked_add(len))
+        .filter(|&end| end > start && end < input.len())
+    {
+        start = end;
+    }
+    start
+}
+
+/// Length of the message at the start of `input`, head and body. `None`
+/// when the head does not end, the body is chunked or the declared lengths
+/// are redacted or disagree.
+fn message_len(input: &[u8], unframed_body_len: Option<usize>) -> Option<usize> {
+    let body_start = message_body_start(input)?;
+    let head = String::from_utf8_lossy(input.get(..body_start)?);
+    let mut content_length = None;
+    for (name, value) in head.lines().skip(1).filter_map(|line| line.split_once(':')) {
+        let name = name.trim();
+        if name.eq_ignore_ascii_case("transfer-encoding") {
+            return None;
+        }
+        if name.eq_ignore_ascii_case("content-length") {
+            let declared = value.trim().parse::<usize>().ok()?;
+            if content_length.is_some_and(|seen| seen != declared) {
+                return None;
+            }
+            content_length = Some(declared);
+        }
+    }
+    body_start.checked_add(content_length.or(unframed_body_len)?)
+}
+
+/// Body start of a message whose non-empty body the grammars cannot read,
+/// reported instead as one opaque value under the root keypath. That is a
+/// body carrying a `Content-Encoding` other than `identity`, one whose
+/// `Content-Type` is neither JSON nor multipart, or one that is not UTF-8.
+/// A `Content-Type` with redacted bytes in it is taken as unknown.
+pub fn opaque_body_start(input: &[u8]) -> Option<usize> {
+    let body_start = message_body_start(input).filter(|&start| start < input.len())?;
+    let head = String::from_utf8_lossy(&input[..body_start]);
+    let header_values = |header: &'static str| {
+        head.lines()
+            .skip(1)
+            .filter_map(|line| line.split_once(':'))
+            .filter(move |(name, _)| name.trim().eq_ignore_ascii_case(header))
+            .map(|(_, value)| value.trim())
+    };
+
+    let encoded =
+        header_values("content-encoding").any(|value| !value.eq_ignore_ascii_case("identity"));
+    let unstructured = header_values("content-type")
+        .filter(|value| !value.contains('\0'))
+        .any(|value| !is_structured_media_type(value));
+    let binary = std::str::from_utf8(&input[body_start..]).is_err();
+    (encoded || unstructured || binary).then_some(body_start)
+}
+
+fn is_structured_media_type(content_type: &str) -> bool {
+    let essence = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    essence == "application/json" || essence.ends_with("+json") || essence.starts_with("multipart/")
+}
+
 pub trait HttpMessageBuilder: Sized {
     type Rule: RuleType + PartialEq + Copy;
     type Message;
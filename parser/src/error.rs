@@ -10,6 +10,18 @@ pub enum ParseError {
 
     #[error("Missing field: {0}")]
     MissingField(String),
+
+    #[error("Invalid Content-Length: {0}")]
+    InvalidContentLength(String),
+
+    #[error("Content-Length declares {declared} bytes but the body spans {body:?}")]
+    ContentLengthMismatch {
+        declared: usize,
+        body: std::ops::Range<usize>,
+    },
+
+    #[error("Invalid UTF-8: {0}")]
+    InvalidUtf8(#[from] std::str::Utf8Error),
 }
 
 pub type Result<T> = std::result::Result<T, ParseError>;
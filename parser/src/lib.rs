@@ -1,3 +1,4 @@
+mod canonical;
 mod common;
 mod error;
 mod path;
@@ -7,8 +8,16 @@ mod traits;
 pub mod redacted;
 pub mod standard;
 
-pub use common::{HttpMessageBuilder, assert_end_of_iterator, assert_rule};
+pub use canonical::{
+    CanonicalValue, FieldCorrespondence, FieldMismatch, JsonComparison, compare_reserialized_json,
+};
+pub use common::{
+    HttpMessageBuilder, assert_end_of_iterator, assert_rule, last_request_start,
+    last_response_start, mask_invalid_utf8, message_body_start, normalize_header_value,
+    opaque_body_start,
+};
 pub use error::{ParseError, Result};
+pub use path::keypath_matches;
 pub use range::JsonFieldRangeExt;
 pub use traits::{HttpMessage, Traverser};
 
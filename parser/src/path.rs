@@ -1,34 +1,94 @@
-use std::fmt;
+use std::fmt::{self, Write};
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub enum PathSegment {
-    Key(String),
+const WILDCARD: &str = "*";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathSegment<'a> {
+    Key(&'a str),
     Index(usize),
 }
 
+/// The keypath of the node being traversed, kept rendered so that each
+/// node's keypath is one copy rather than a re-render of every segment.
 #[derive(Debug, Clone, Default)]
 pub struct PathStack {
-    segments: Vec<PathSegment>,
+    path: String,
+    segment_starts: Vec<usize>,
 }
 
 impl PathStack {
-    pub fn push(&mut self, segment: PathSegment) {
-        self.segments.push(segment);
+    pub fn push(&mut self, segment: PathSegment<'_>) {
+        self.segment_starts.push(self.path.len());
+        match segment {
+            PathSegment::Key(key) => {
+                self.path.push('.');
+                self.path.push_str(key);
+            }
+            PathSegment::Index(index) => {
+                let _ = write!(self.path, "[{index}]");
+            }
+        }
+    }
+
+    pub fn pop(&mut self) {
+        if let Some(start) = self.segment_starts.pop() {
+            self.path.truncate(start);
+        }
     }
 
-    pub fn pop(&mut self) -> Option<PathSegment> {
-        self.segments.pop()
+    pub fn keypath(&self) -> String {
+        self.path.clone()
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.path
     }
 }
 
+/// Whether `keypath` matches `pattern`, segment by segment. A `*` key
+/// matches any one key and `[*]` any one array index, so `.data.users[*].id`
+/// matches `.data.users[0].id` but not `.data.users[0].profile.id`.
+#[must_use]
+pub fn keypath_matches(pattern: &str, keypath: &str) -> bool {
+    let (Some(pattern), Some(keypath)) = (segments(pattern), segments(keypath)) else {
+        return false;
+    };
+    pattern.len() == keypath.len()
+        && pattern
+            .iter()
+            .zip(&keypath)
+            .all(|(expected, actual)| match (expected, actual) {
+                (RawSegment::Key(WILDCARD), RawSegment::Key(_))
+                | (RawSegment::Index(WILDCARD), RawSegment::Index(_)) => true,
+                _ => expected == actual,
+            })
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum RawSegment<'a> {
+    Key(&'a str),
+    Index(&'a str),
+}
+
+/// Splits a keypath as rendered by [`PathStack`]; `None` if it is malformed.
+fn segments(mut path: &str) -> Option<Vec<RawSegment<'_>>> {
+    let mut segments = Vec::new();
+    while !path.is_empty() {
+        if let Some(rest) = path.strip_prefix('.') {
+            let end = rest.find(['.', '[']).unwrap_or(rest.len());
+            segments.push(RawSegment::Key(&rest[..end]));
+            path = &rest[end..];
+        } else {
+            let (index, rest) = path.strip_prefix('[')?.split_once(']')?;
+            segments.push(RawSegment::Index(index));
+            path = rest;
+        }
+    }
+    Some(segments)
+}
+
 impl fmt::Display for PathStack {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        for segment in &self.segments {
-            match segment {
-                PathSegment::Key(k) => write!(f, ".{k}")?,
-                PathSegment::Index(i) => write!(f, "[{i}]")?,
-            }
-        }
-        Ok(())
+        f.write_str(&self.path)
     }
 }
@@ -0,0 +1,130 @@
+use std::{collections::HashMap, fmt::Write, ops::Range};
+
+use serde::Serialize;
+
+use super::{Body, Header, revealed_ranges};
+
+/// Drawn in place of every redacted byte.
+pub const REDACTED_CHAR: char = '▓';
+const MARGIN: &str = " │ ";
+
+/// A labelled part of a redacted message, as offsets into the transcript.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Annotation {
+    /// Message field or body keypath, e.g. `method`, `header host`, `.balance`.
+    pub label: String,
+    pub range: Range<usize>,
+    pub revealed_ranges: Vec<Range<usize>>,
+    /// The range's bytes with redacted ones drawn as [`REDACTED_CHAR`].
+    pub text: String,
+}
+
+pub(super) fn annotations(
+    source: &[u8],
+    first_line: [(&str, &Range<usize>); 3],
+    headers: &HashMap<String, Vec<Header>>,
+    body: &HashMap<String, Body>,
+) -> Vec<Annotation> {
+    let header_ranges = headers.iter().flat_map(|(name, headers)| {
+        headers.iter().map(move |header| {
+            let end = header.value.as_ref().unwrap_or(&header.name).end;
+            (format!("header {name}"), header.name.start..end)
+        })
+    });
+    let body_ranges = body.iter().map(|(keypath, field)| {
+        let range = match field {
+            Body::KeyValue { key, value } => key.start..value.as_ref().unwrap_or(key).end,
+            Body::Value(range)
+            | Body::PartialValue {
+                revealed: range, ..
+            } => range.clone(),
+        };
+        (keypath.clone(), range)
+    });
+
+    let mut annotations = first_line
+        .into_iter()
+        .map(|(label, range)| (label.to_string(), range.clone()))
+        .chain(header_ranges)
+        .chain(body_ranges)
+        .map(|(label, range)| Annotation {
+            revealed_ranges: revealed_ranges(source, &range),
+            text: render(source.get(range.clone()).unwrap_or_default()),
+            label,
+            range,
+        })
+        .collect::<Vec<_>>();
+    annotations.sort_by(|a, b| (a.range.start, &a.label).cmp(&(b.range.start, &b.label)));
+    annotations
+}
+
+/// Renders `source` one row per line or annotated field, each row prefixed
+/// with its byte offset and followed by the labels of the fields it starts.
+pub(super) fn annotated_string(source: &[u8], annotations: &[Annotation]) -> String {
+    let mut boundaries = source
+        .iter()
+        .enumerate()
+        .filter(|&(_, &byte)| byte == b'\n')
+        .map(|(index, _)| index + 1)
+        .chain(annotations.iter().map(|annotation| annotation.range.start))
+        .chain([0, source.len()])
+        .filter(|&offset| offset <= source.len())
+        .collect::<Vec<_>>();
+    boundaries.sort_unstable();
+    boundaries.dedup();
+
+    let rows = boundaries
+        .windows(2)
+        .map(|row| {
+            let row_bytes = &source[row[0]..row[1]];
+            let line = row_bytes
+                .strip_suffix(b"\r\n")
+                .or_else(|| row_bytes.strip_suffix(b"\n"))
+                .unwrap_or(row_bytes);
+            let labels = annotations
+                .iter()
+                .filter(|annotation| annotation.range.start == row[0])
+                .map(|annotation| annotation.label.as_str())
+                .collect::<Vec<_>>()
+                .join(", ");
+            (row[0], render(line), labels)
+        })
+        .collect::<Vec<_>>();
+
+    let offset_width = source.len().to_string().len();
+    let width = rows
+        .iter()
+        .map(|(_, rendered, _)| rendered.chars().count())
+        .max()
+        .unwrap_or_default();
+    rows.into_iter()
+        .fold(String::new(), |mut output, (offset, rendered, labels)| {
+            let _ = writeln!(
+                output,
+                "{offset:>offset_width$}{MARGIN}{rendered:<width$}{MARGIN}{labels}"
+            );
+            output
+        })
+}
+
+/// Draws redacted bytes as [`REDACTED_CHAR`] and escapes control characters
+/// so that every row stays on one line.
+fn render(bytes: &[u8]) -> String {
+    bytes
+        .split(|&byte| byte == 0)
+        .map(|segment| {
+            String::from_utf8_lossy(segment)
+                .chars()
+                .flat_map(|c| {
+                    if c.is_control() {
+                        c.escape_default().collect::<Vec<_>>()
+                    } else {
+                        vec![c]
+                    }
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join(&REDACTED_CHAR.to_string())
+}
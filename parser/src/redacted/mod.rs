@@ -1,12 +1,32 @@
+mod annotated;
+mod projection;
 mod request;
 mod response;
 mod traversal;
 
 use std::ops::Range;
 
+pub use annotated::{Annotation, REDACTED_CHAR};
 pub use request::Request;
 pub use response::Response;
 
+pub const HIDDEN_KEY: &str = "?";
+
+pub fn revealed_ranges(source: &[u8], range: &Range<usize>) -> Vec<Range<usize>> {
+    let Some(bytes) = source.get(range.clone()) else {
+        return Vec::new();
+    };
+    bytes
+        .split(|&byte| byte == 0)
+        .scan(range.start, |offset, segment| {
+            let start = *offset;
+            *offset += segment.len() + 1;
+            Some(start..start + segment.len())
+        })
+        .filter(|segment| !segment.is_empty())
+        .collect()
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Header {
     pub name: Range<usize>,
@@ -20,4 +40,24 @@ pub enum Body {
         value: Option<Range<usize>>,
     },
     Value(Range<usize>),
+    /// A string the mask cuts through: its opening quote and the prefix in
+    /// `revealed`, possibly empty, are kept and the rest is hidden.
+    /// `total_hint` is the most bytes the whole string can hold, up to the
+    /// next revealed byte, or `None` when nothing after it is revealed.
+    PartialValue {
+        revealed: Range<usize>,
+        total_hint: Option<usize>,
+    },
+}
+
+impl Body {
+    /// `next_revealed` is the offset of the first revealed byte after the
+    /// prefix, which leaves room for the hidden closing quote.
+    pub(super) fn partial(revealed: Range<usize>, next_revealed: Option<usize>) -> Self {
+        let total_hint = next_revealed.map(|next| next.saturating_sub(revealed.start + 1));
+        Self::PartialValue {
+            revealed,
+            total_hint,
+        }
+    }
 }
@@ -0,0 +1,350 @@
+use std::{collections::HashMap, ops::Range};
+
+use super::{Body, HIDDEN_KEY, Header};
+use crate::{
+    JsonFieldRangeExt,
+    path::{PathSegment, PathStack},
+    standard,
+};
+
+/// The bytes a redacted transcript keeps of the message it was cut from.
+pub(super) struct KeepSet(Vec<Range<usize>>);
+
+impl KeepSet {
+    pub(super) fn new(keep_ranges: &[Range<usize>]) -> Self {
+        let mut ranges = keep_ranges
+            .iter()
+            .filter(|range| !range.is_empty())
+            .cloned()
+            .collect::<Vec<_>>();
+        ranges.sort_by_key(|range| range.start);
+
+        let mut merged: Vec<Range<usize>> = Vec::with_capacity(ranges.len());
+        for range in ranges {
+            match merged.last_mut() {
+                Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+                _ => merged.push(range),
+            }
+        }
+        Self(merged)
+    }
+
+    /// Whether every byte of `range` is kept; an empty range always is.
+    fn covers(&self, range: &Range<usize>) -> bool {
+        range.is_empty()
+            || self
+                .0
+                .iter()
+                .any(|kept| kept.start <= range.start && range.end <= kept.end)
+    }
+
+    /// The end of the kept run starting at `start`, `start` itself when that
+    /// byte is hidden.
+    fn run_end(&self, start: usize) -> usize {
+        self.0
+            .iter()
+            .find(|kept| kept.start <= start && start < kept.end)
+            .map_or(start, |kept| kept.end)
+    }
+
+    /// The first kept byte at or after `offset`.
+    fn next_kept(&self, offset: usize) -> Option<usize> {
+        self.0
+            .iter()
+            .find(|kept| offset < kept.end)
+            .map(|kept| kept.start.max(offset))
+    }
+}
+
+/// Headers as the redacted grammar reads them: a header whose name and colon
+/// are kept is listed under its name, with a value only when the rest of its
+/// line is kept too; one with only the value line kept is listed under
+/// [`HIDDEN_KEY`]. A lone `\r` ends the line as well as `\r\n` does.
+pub(super) fn headers(
+    source: &[u8],
+    standard_headers: &HashMap<String, Vec<standard::Header>>,
+    keep: &KeepSet,
+) -> HashMap<String, Vec<Header>> {
+    let mut ordered = standard_headers
+        .iter()
+        .flat_map(|(name, headers)| headers.iter().map(move |header| (name, header)))
+        .collect::<Vec<_>>();
+    ordered.sort_by_key(|(_, header)| header.name.start);
+
+    let mut headers: HashMap<String, Vec<Header>> = HashMap::new();
+    for (name, header) in ordered {
+        let line_end = if source.get(header.raw.end.saturating_sub(2)..header.raw.end)
+            == Some(b"\r\n".as_slice())
+        {
+            header.raw.end - 1
+        } else {
+            header.raw.end
+        };
+        let value_line = header.value.start..line_end;
+        if let Some((key, header)) =
+            header_view(name, &header.name, &header.value, &value_line, keep)
+        {
+            headers.entry(key).or_default().push(header);
+        }
+    }
+    headers
+}
+
+fn header_view(
+    name: &str,
+    name_range: &Range<usize>,
+    value: &Range<usize>,
+    value_line: &Range<usize>,
+    keep: &KeepSet,
+) -> Option<(String, Header)> {
+    let colon = name_range.end..name_range.end + 1;
+    if keep.covers(&name_range.span_to(colon.end)) {
+        let header = Header {
+            name: name_range.clone(),
+            value: keep.covers(value_line).then(|| value.clone()),
+        };
+        Some((name.to_string(), header))
+    } else if keep.covers(&colon) && keep.covers(value_line) {
+        let header = Header {
+            name: colon.start..colon.start,
+            value: Some(value.clone()),
+        };
+        Some((HIDDEN_KEY.to_string(), header))
+    } else {
+        None
+    }
+}
+
+/// A JSON body as the redacted grammar reads it: each field whose quoted key
+/// and colon are kept becomes `.<key>`, and each kept value outside such a
+/// field becomes `.?[n]`, numbered in transcript order. A string counts as
+/// kept when both its quotes are, other values when every byte is. A key or
+/// string value with only its opening quote and a prefix kept is listed as a
+/// [`Body::PartialValue`], under the key when that is kept. Nothing nested
+/// inside a listed field or value is listed again.
+pub(super) fn json_body(
+    source: &[u8],
+    standard_body: &HashMap<String, standard::Body>,
+    keep: &KeepSet,
+) -> HashMap<String, Body> {
+    enum Candidate<'a> {
+        Field {
+            key: &'a Range<usize>,
+            value: Option<&'a Range<usize>>,
+        },
+        Value(&'a Range<usize>),
+        Partial {
+            key: Option<&'a Range<usize>>,
+            prefix: Range<usize>,
+        },
+    }
+
+    let mut candidates = Vec::with_capacity(standard_body.len());
+    for field in standard_body.values() {
+        match field {
+            standard::Body::KeyValue { key, value } => {
+                let value_span = value_span(source, value);
+                let value_kept = value_is_kept(source, value, keep);
+                let value_prefix = partial_prefix(source, value, keep);
+                if keep.covers(&key.with_quotes_and_colon()) {
+                    let key_start = key.start.saturating_sub(1);
+                    if let Some(prefix) = value_prefix {
+                        let span = key_start..prefix.end;
+                        let field = Candidate::Partial {
+                            key: Some(key),
+                            prefix,
+                        };
+                        candidates.push((span, field));
+                        continue;
+                    }
+                    let end = if value_kept {
+                        value_span.end
+                    } else {
+                        key.with_quotes_and_colon().end
+                    };
+                    let field = Candidate::Field {
+                        key,
+                        value: value_kept.then_some(value),
+                    };
+                    candidates.push((key_start..end, field));
+                    continue;
+                }
+                if let Some(prefix) = partial_prefix(source, key, keep) {
+                    let span = key.start - 1..prefix.end;
+                    candidates.push((span, Candidate::Partial { key: None, prefix }));
+                }
+                if value_kept {
+                    candidates.push((value_span, Candidate::Value(value)));
+                } else if let Some(prefix) = value_prefix {
+                    let span = value.start - 1..prefix.end;
+                    candidates.push((span, Candidate::Partial { key: None, prefix }));
+                }
+            }
+            standard::Body::Value(value) if value_is_kept(source, value, keep) => {
+                candidates.push((value_span(source, value), Candidate::Value(value)));
+            }
+            standard::Body::Value(value) => {
+                if let Some(prefix) = partial_prefix(source, value, keep) {
+                    let span = value.start - 1..prefix.end;
+                    candidates.push((span, Candidate::Partial { key: None, prefix }));
+                }
+            }
+        }
+    }
+    candidates.sort_by(|(a, _), (b, _)| a.start.cmp(&b.start).then(b.end.cmp(&a.end)));
+
+    let mut body = HashMap::new();
+    let mut pathstack = PathStack::default();
+    let mut hidden_values = 0;
+    let mut listed_end = 0;
+    for (span, candidate) in candidates {
+        if span.start < listed_end {
+            continue;
+        }
+        listed_end = span.end;
+
+        match candidate {
+            Candidate::Field { key, value } => {
+                let name = String::from_utf8_lossy(source.get(key.clone()).unwrap_or_default());
+                pathstack.push(PathSegment::Key(&name));
+                body.insert(
+                    pathstack.keypath(),
+                    Body::KeyValue {
+                        key: key.clone(),
+                        value: value.cloned(),
+                    },
+                );
+                pathstack.pop();
+            }
+            Candidate::Partial {
+                key: Some(key),
+                prefix,
+            } => {
+                let name = String::from_utf8_lossy(source.get(key.clone()).unwrap_or_default());
+                pathstack.push(PathSegment::Key(&name));
+                let next_revealed = keep.next_kept(prefix.end);
+                body.insert(pathstack.keypath(), Body::partial(prefix, next_revealed));
+                pathstack.pop();
+            }
+            Candidate::Partial { key: None, prefix } => {
+                pathstack.push(PathSegment::Key(HIDDEN_KEY));
+                pathstack.push(PathSegment::Index(hidden_values));
+                let next_revealed = keep.next_kept(prefix.end);
+                body.insert(pathstack.keypath(), Body::partial(prefix, next_revealed));
+                pathstack.pop();
+                pathstack.pop();
+                hidden_values += 1;
+            }
+            Candidate::Value(value) => {
+                pathstack.push(PathSegment::Key(HIDDEN_KEY));
+                pathstack.push(PathSegment::Index(hidden_values));
+                body.insert(pathstack.keypath(), Body::Value(value.clone()));
+                pathstack.pop();
+                pathstack.pop();
+                hidden_values += 1;
+            }
+        }
+    }
+    body
+}
+
+/// A multipart body keeps the standard keypaths; part headers follow the
+/// rules of [`headers`], those with a hidden name listed as
+/// `.parts[i].headers.?[n]`.
+pub(super) fn multipart_body(
+    standard_body: &HashMap<String, standard::Body>,
+    keep: &KeepSet,
+) -> HashMap<String, Body> {
+    let mut body = HashMap::new();
+    for part in 0.. {
+        let mut pathstack = PathStack::default();
+        pathstack.push(PathSegment::Key("parts"));
+        pathstack.push(PathSegment::Index(part));
+        let part_keypath = pathstack.keypath();
+        let Some(standard::Body::Value(range)) = standard_body.get(&part_keypath) else {
+            break;
+        };
+        body.insert(part_keypath.clone(), Body::Value(range.clone()));
+
+        pathstack.push(PathSegment::Key("body"));
+        if let Some(standard::Body::Value(range)) = standard_body.get(&pathstack.keypath()) {
+            body.insert(pathstack.keypath(), Body::Value(range.clone()));
+        }
+        pathstack.pop();
+
+        pathstack.push(PathSegment::Key("headers"));
+        let headers_prefix = format!("{pathstack}.");
+        let mut part_headers = standard_body
+            .iter()
+            .filter_map(|(keypath, field)| match field {
+                standard::Body::KeyValue { key, value } => keypath
+                    .strip_prefix(&headers_prefix)
+                    .map(|name| (name, key, value)),
+                standard::Body::Value(_) => None,
+            })
+            .collect::<Vec<_>>();
+        part_headers.sort_by_key(|(_, key, _)| key.start);
+
+        let mut hidden_headers = 0;
+        for (name, key, value) in part_headers {
+            let value_line = value.start..value.end;
+            let Some((name, header)) = header_view(name, key, value, &value_line, keep) else {
+                continue;
+            };
+            let keypath = if name == HIDDEN_KEY {
+                hidden_headers += 1;
+                format!("{pathstack}.{HIDDEN_KEY}[{}]", hidden_headers - 1)
+            } else {
+                format!("{pathstack}.{name}")
+            };
+            body.insert(
+                keypath,
+                Body::KeyValue {
+                    key: header.name,
+                    value: header.value,
+                },
+            );
+        }
+    }
+    body
+}
+
+/// Whether the standard body of `source` is a multipart one, whose root
+/// opens with a boundary delimiter.
+pub(super) fn is_multipart(source: &[u8], standard_body: &HashMap<String, standard::Body>) -> bool {
+    matches!(
+        standard_body.get(""),
+        Some(standard::Body::Value(root)) if source.get(root.clone()).is_some_and(|root| root.starts_with(b"--"))
+    ) && standard_body.contains_key(".parts[0]")
+}
+
+fn is_string(source: &[u8], value: &Range<usize>) -> bool {
+    value.start > 0
+        && source.get(value.start - 1) == Some(&b'"')
+        && source.get(value.end) == Some(&b'"')
+}
+
+fn value_span(source: &[u8], value: &Range<usize>) -> Range<usize> {
+    if is_string(source, value) {
+        value.with_quotes()
+    } else {
+        value.clone()
+    }
+}
+
+/// The kept prefix of the quoted string `value` when its opening quote is kept
+/// and its closing one is not.
+fn partial_prefix(source: &[u8], value: &Range<usize>, keep: &KeepSet) -> Option<Range<usize>> {
+    (is_string(source, value)
+        && keep.covers(&(value.start - 1..value.start))
+        && !keep.covers(&(value.end..value.end + 1)))
+    .then(|| value.start..keep.run_end(value.start).min(value.end))
+}
+
+fn value_is_kept(source: &[u8], value: &Range<usize>, keep: &KeepSet) -> bool {
+    if is_string(source, value) {
+        keep.covers(&(value.start - 1..value.start)) && keep.covers(&(value.end..value.end + 1))
+    } else {
+        !value.is_empty() && keep.covers(value)
+    }
+}
@@ -4,13 +4,15 @@ use pest::Parser;
 use pest_derive::Parser;
 
 use super::{
-    Body, Header,
-    traversal::{BodyConfig, HeaderConfig},
+    Annotation, Body, Header, annotated,
+    projection::{self, KeepSet},
+    traversal::{BodyConfig, HeaderConfig, MultipartConfig},
 };
 use crate::{
     HttpMessageBuilder,
-    common::{assert_end_of_iterator, assert_rule},
+    common::{assert_end_of_iterator, assert_rule, mask_invalid_utf8, opaque_body_start},
     error::{ParseError, Result},
+    standard,
     traits::{HttpMessage, RangeExtractor, Traverser},
 };
 
@@ -27,6 +29,93 @@ pub struct Request {
     pub body: HashMap<String, Body>,
 }
 
+impl Request {
+    /// A body that is content-encoded, binary, or of a media type other
+    /// than JSON or multipart is not parsed; it is reported as one
+    /// [`Body::Value`] under the root keypath `""`.
+    pub fn from_bytes(input: &[u8]) -> Result<Self> {
+        match opaque_body_start(input) {
+            Some(body_start) => Self::with_opaque_body(
+                RequestBuilder::new().parse(std::str::from_utf8(&input[..body_start])?)?,
+                body_start..input.len(),
+            ),
+            None => RequestBuilder::new().parse(std::str::from_utf8(input)?),
+        }
+    }
+
+    pub fn from_bytes_lossy(input: &[u8]) -> Result<Self> {
+        match opaque_body_start(input) {
+            Some(body_start) => Self::with_opaque_body(
+                RequestBuilder::new().parse(&mask_invalid_utf8(&input[..body_start]))?,
+                body_start..input.len(),
+            ),
+            None => RequestBuilder::new().parse(&mask_invalid_utf8(input)),
+        }
+    }
+
+    /// The redacted view of `request`, parsed from `source`, once every byte
+    /// outside `keep_ranges` is hidden. It is worked out from the original
+    /// parse rather than by parsing the masked bytes, so it exists for any
+    /// mask; where the masked bytes parse it matches [`Self::from_bytes`].
+    #[must_use]
+    pub fn from_standard(
+        source: &[u8],
+        request: &standard::Request,
+        keep_ranges: &[Range<usize>],
+    ) -> Self {
+        let keep = KeepSet::new(keep_ranges);
+        let body = match opaque_body_start(source) {
+            Some(body_start) => {
+                HashMap::from([(String::new(), Body::Value(body_start..source.len()))])
+            }
+            None if projection::is_multipart(source, &request.body) => {
+                projection::multipart_body(&request.body, &keep)
+            }
+            None => projection::json_body(source, &request.body, &keep),
+        };
+        Self {
+            method: request.method.clone(),
+            url: request.url.clone(),
+            protocol_version: request.protocol_version.clone(),
+            headers: projection::headers(source, &request.headers, &keep),
+            body,
+        }
+    }
+
+    fn with_opaque_body(mut request: Self, body: Range<usize>) -> Result<Self> {
+        request.body.insert(String::new(), Body::Value(body));
+        Ok(request)
+    }
+
+    /// Labelled fields of the message, ordered by their offset in `source`,
+    /// the transcript it was parsed from.
+    pub fn annotations(&self, source: &[u8]) -> Vec<Annotation> {
+        annotated::annotations(
+            source,
+            [
+                ("method", &self.method),
+                ("url", &self.url),
+                ("protocol_version", &self.protocol_version),
+            ],
+            &self.headers,
+            &self.body,
+        )
+    }
+
+    /// `source` one line or field per row, with byte offsets on the left,
+    /// redacted bytes drawn as [`super::REDACTED_CHAR`] and field labels in
+    /// a right-hand margin.
+    pub fn to_annotated_string(&self, source: &[u8]) -> String {
+        annotated::annotated_string(source, &self.annotations(source))
+    }
+
+    /// The [`annotations`](Self::annotations) as a JSON array.
+    pub fn to_annotated_json(&self, source: &[u8]) -> String {
+        serde_json::to_string_pretty(&self.annotations(source))
+            .expect("annotations serialize to JSON")
+    }
+}
+
 impl HttpMessage for Request {
     type Header = Header;
     type Body = Body;
@@ -43,6 +132,7 @@ impl HttpMessage for Request {
 pub struct RequestBuilder {
     header_config: HeaderConfig<Rule>,
     body_config: BodyConfig<Rule>,
+    multipart_config: MultipartConfig<Rule>,
 }
 
 impl RequestBuilder {
@@ -52,10 +142,23 @@ impl RequestBuilder {
             header_config: HeaderConfig::new(
                 Rule::headers,
                 Rule::header,
+                Rule::hidden_header,
                 Rule::header_name,
                 Rule::header_value,
             ),
-            body_config: BodyConfig::new(Rule::pair),
+            body_config: BodyConfig::new(Rule::pair, Rule::hidden_pair, Rule::partial_string),
+            multipart_config: MultipartConfig::new(
+                Rule::multipart_body,
+                Rule::part,
+                HeaderConfig::new(
+                    Rule::part_headers,
+                    Rule::header,
+                    Rule::hidden_header,
+                    Rule::header_name,
+                    Rule::header_value,
+                ),
+                Rule::part_body,
+            ),
         }
     }
 
@@ -123,7 +226,7 @@ impl HttpMessageBuilder for RequestBuilder {
     }
 
     fn parse(&self, mut pairs: pest::iterators::Pairs<'_, Self::Rule>) -> Result<Self::Message> {
-        use super::traversal::{BodyTraverser, HeaderTraverser};
+        use super::traversal::{BodyTraverser, HeaderTraverser, MultipartTraverser};
 
         let first_line_pair = pairs
             .next()
@@ -135,8 +238,12 @@ impl HttpMessageBuilder for RequestBuilder {
         let first_line = self.parse_first_line(first_line_pair)?;
         let headers = HeaderTraverser::new(self.header_config, headers_pair)?.traverse()?;
 
-        let body_traverser = BodyTraverser::new(self.body_config);
-        let body = body_traverser.traverse(pairs)?;
+        let body = match pairs.peek() {
+            Some(body_pair) if body_pair.as_rule() == Rule::multipart_body => {
+                MultipartTraverser::new(self.multipart_config, body_pair)?.traverse()?
+            }
+            _ => BodyTraverser::new(self.body_config).traverse(pairs)?,
+        };
 
         Ok(self.build_message(first_line, headers, body))
     }
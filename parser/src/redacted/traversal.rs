@@ -5,7 +5,7 @@ use pest::{
     iterators::{Pair, Pairs},
 };
 
-use super::{Body, Header};
+use super::{Body, HIDDEN_KEY, Header};
 use crate::{
     common::{assert_end_of_iterator, assert_rule},
     error::{ParseError, Result},
@@ -17,15 +17,17 @@ use crate::{
 pub struct HeaderConfig<R> {
     pub headers: R,
     pub header: R,
+    pub hidden_header: R,
     pub header_name: R,
     pub header_value: R,
 }
 
 impl<R: Copy> HeaderConfig<R> {
-    pub fn new(headers: R, header: R, header_name: R, header_value: R) -> Self {
+    pub fn new(headers: R, header: R, hidden_header: R, header_name: R, header_value: R) -> Self {
         Self {
             headers,
             header,
+            hidden_header,
             header_name,
             header_value,
         }
@@ -35,11 +37,17 @@ impl<R: Copy> HeaderConfig<R> {
 #[derive(Debug, Clone, Copy)]
 pub struct BodyConfig<R> {
     pub pair: R,
+    pub hidden_pair: R,
+    pub partial_string: R,
 }
 
 impl<R: Copy> BodyConfig<R> {
-    pub fn new(pair: R) -> Self {
-        Self { pair }
+    pub fn new(pair: R, hidden_pair: R, partial_string: R) -> Self {
+        Self {
+            pair,
+            hidden_pair,
+            partial_string,
+        }
     }
 }
 
@@ -82,6 +90,23 @@ impl<'a, R: RuleType + PartialEq + Copy> HeaderTraverser<'a, R> {
             value,
         })
     }
+
+    fn parse_hidden_header(pair: Pair<'_, R>, config: &HeaderConfig<R>) -> Result<Header> {
+        let start = pair.as_span().start();
+        let mut inner = pair.into_inner();
+
+        let value_pair = inner
+            .next()
+            .ok_or_else(|| ParseError::MissingField("hidden header value".to_string()))?;
+        assert_rule(&value_pair, config.header_value, "header_value")?;
+
+        assert_end_of_iterator(&mut inner, "hidden_header")?;
+
+        Ok(Header {
+            name: start..start,
+            value: Some(value_pair.extract_range()),
+        })
+    }
 }
 
 impl<R: RuleType + PartialEq + Copy> Traverser for HeaderTraverser<'_, R> {
@@ -89,6 +114,15 @@ impl<R: RuleType + PartialEq + Copy> Traverser for HeaderTraverser<'_, R> {
 
     fn traverse(mut self) -> Result<HashMap<String, Self::Output>> {
         for pair in self.pairs.by_ref() {
+            if pair.as_rule() == self.config.hidden_header {
+                let header = Self::parse_hidden_header(pair, &self.config)?;
+                self.headers
+                    .entry(HIDDEN_KEY.to_string())
+                    .or_default()
+                    .push(header);
+                continue;
+            }
+
             assert_rule(&pair, self.config.header, "header")?;
 
             let name_pair =
@@ -108,6 +142,7 @@ pub struct BodyTraverser<'a, R> {
     config: BodyConfig<R>,
     body: HashMap<String, Body>,
     pathstack: PathStack,
+    hidden_values: usize,
     _phantom: std::marker::PhantomData<&'a R>,
 }
 
@@ -117,6 +152,7 @@ impl<'a, R: RuleType + PartialEq + Copy> BodyTraverser<'a, R> {
             config,
             body: HashMap::new(),
             pathstack: PathStack::default(),
+            hidden_values: 0,
             _phantom: std::marker::PhantomData,
         }
     }
@@ -125,12 +161,15 @@ impl<'a, R: RuleType + PartialEq + Copy> BodyTraverser<'a, R> {
         mut self,
         pairs: impl Iterator<Item = Pair<'a, R>>,
     ) -> Result<HashMap<String, Body>> {
+        self.body.reserve(pairs.size_hint().0);
         for pair in pairs {
-            if pair.as_rule() != self.config.pair {
+            if pair.as_rule() == self.config.pair {
+                self.traverse_pair(pair)?;
+            } else if pair.as_rule() == self.config.hidden_pair {
+                self.traverse_hidden_pair(pair)?;
+            } else {
                 break;
             }
-
-            self.traverse_pair(pair)?;
         }
 
         Ok(self.body)
@@ -142,22 +181,165 @@ impl<'a, R: RuleType + PartialEq + Copy> BodyTraverser<'a, R> {
             .next()
             .ok_or_else(|| ParseError::MissingField("key in pair".to_string()))?;
 
-        let value = inner.next().map(|v| v.extract_range());
+        let value = inner.next();
 
         assert_end_of_iterator(&mut inner, "pair")?;
 
-        let key_str = key_pair.as_str().to_string();
-        self.pathstack.push(PathSegment::Key(key_str));
+        self.pathstack.push(PathSegment::Key(key_pair.as_str()));
 
-        self.body.insert(
-            self.pathstack.to_string(),
-            Body::KeyValue {
+        let field = match value {
+            Some(value) if value.as_rule() == self.config.partial_string => {
+                Self::partial_value(value)
+            }
+            value => Body::KeyValue {
                 key: key_pair.extract_range(),
-                value,
+                value: value.map(|v| v.extract_range()),
             },
-        );
+        };
+        self.body.insert(self.pathstack.keypath(), field);
 
         self.pathstack.pop();
         Ok(())
     }
+
+    fn traverse_hidden_pair(&mut self, pair: Pair<'a, R>) -> Result<()> {
+        let mut inner = pair.into_inner();
+        let value = inner
+            .next()
+            .ok_or_else(|| ParseError::MissingField("value in hidden pair".to_string()))?;
+
+        assert_end_of_iterator(&mut inner, "hidden_pair")?;
+
+        let field = if value.as_rule() == self.config.partial_string {
+            Self::partial_value(value)
+        } else {
+            Body::Value(value.extract_range())
+        };
+
+        self.pathstack.push(PathSegment::Key(HIDDEN_KEY));
+        self.pathstack.push(PathSegment::Index(self.hidden_values));
+        self.body.insert(self.pathstack.keypath(), field);
+        self.pathstack.pop();
+        self.pathstack.pop();
+
+        self.hidden_values += 1;
+        Ok(())
+    }
+
+    /// The revealed prefix of a `partial_string`, whose span runs past the
+    /// prefix over the hidden bytes up to the next revealed one.
+    fn partial_value(value: Pair<'a, R>) -> Body {
+        let span = value.as_span();
+        let next_revealed = (span.end() < value.get_input().len()).then_some(span.end());
+        let revealed = value
+            .into_inner()
+            .next()
+            .map_or(span.start() + 1..span.start() + 1, |prefix| {
+                prefix.extract_range()
+            });
+        Body::partial(revealed, next_revealed)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct MultipartConfig<R> {
+    pub multipart: R,
+    pub part: R,
+    pub part_headers: HeaderConfig<R>,
+    pub part_body: R,
+}
+
+impl<R: Copy> MultipartConfig<R> {
+    pub fn new(multipart: R, part: R, part_headers: HeaderConfig<R>, part_body: R) -> Self {
+        Self {
+            multipart,
+            part,
+            part_headers,
+            part_body,
+        }
+    }
+}
+
+/// Maps a multipart body to `.parts[i]`, `.parts[i].headers.<name>` and
+/// `.parts[i].body`. Part headers without a revealed name are listed as
+/// `.parts[i].headers.?[n]`.
+pub struct MultipartTraverser<'a, R> {
+    config: MultipartConfig<R>,
+    root: Pair<'a, R>,
+}
+
+impl<'a, R: RuleType + PartialEq + Copy> MultipartTraverser<'a, R> {
+    pub fn new(config: MultipartConfig<R>, body_pair: Pair<'a, R>) -> Result<Self> {
+        assert_rule(&body_pair, config.multipart, "multipart_body")?;
+        Ok(Self {
+            config,
+            root: body_pair,
+        })
+    }
+
+    fn traverse_part(
+        &self,
+        index: usize,
+        part: Pair<'_, R>,
+        body: &mut HashMap<String, Body>,
+    ) -> Result<()> {
+        assert_rule(&part, self.config.part, "part")?;
+
+        let mut pathstack = PathStack::default();
+        pathstack.push(PathSegment::Key("parts"));
+        pathstack.push(PathSegment::Index(index));
+        body.insert(pathstack.keypath(), Body::Value(part.extract_range()));
+
+        let mut inner = part.into_inner();
+        let headers_pair = inner
+            .next()
+            .ok_or_else(|| ParseError::MissingField("part headers".to_string()))?;
+        let body_pair = inner
+            .next()
+            .ok_or_else(|| ParseError::MissingField("part body".to_string()))?;
+        assert_rule(&body_pair, self.config.part_body, "part_body")?;
+        assert_end_of_iterator(&mut inner, "part")?;
+
+        pathstack.push(PathSegment::Key("headers"));
+        for (name, headers) in
+            HeaderTraverser::new(self.config.part_headers, headers_pair)?.traverse()?
+        {
+            let indexed = name == HIDDEN_KEY;
+            pathstack.push(PathSegment::Key(&name));
+            for (header_index, header) in headers.into_iter().enumerate() {
+                let keypath = if indexed {
+                    format!("{pathstack}[{header_index}]")
+                } else if header_index == 0 {
+                    pathstack.keypath()
+                } else {
+                    break;
+                };
+                body.insert(
+                    keypath,
+                    Body::KeyValue {
+                        key: header.name,
+                        value: header.value,
+                    },
+                );
+            }
+            pathstack.pop();
+        }
+        pathstack.pop();
+
+        pathstack.push(PathSegment::Key("body"));
+        body.insert(pathstack.keypath(), Body::Value(body_pair.extract_range()));
+        Ok(())
+    }
+}
+
+impl<R: RuleType + PartialEq + Copy> Traverser for MultipartTraverser<'_, R> {
+    type Output = Body;
+
+    fn traverse(self) -> Result<HashMap<String, Self::Output>> {
+        let mut body = HashMap::new();
+        for (index, part) in self.root.clone().into_inner().enumerate() {
+            self.traverse_part(index, part, &mut body)?;
+        }
+        Ok(body)
+    }
 }
@@ -2,15 +2,115 @@ mod request;
 mod response;
 mod traversal;
 
-use std::ops::Range;
+use std::{
+    collections::HashMap,
+    ops::{ControlFlow, Range},
+};
 
 pub use request::Request;
 pub use response::Response;
 
+use crate::{
+    common::message_body_start,
+    error::{ParseError, Result},
+};
+
+/// Parses a standalone JSON document into the keypath map used for bodies.
+pub fn parse_json_body(input: &str) -> Result<HashMap<String, Body>> {
+    response::ResponseBuilder::new().parse_json(input)
+}
+
+/// Calls `visit` with the keypath, ranges and kind of each field of a
+/// standalone JSON document, in document order with parents first, starting
+/// with the root `""`. Returning [`ControlFlow::Break`] stops the walk, so a
+/// caller after a few known keypaths skips the rest of a large body.
+pub fn for_each_json_field<F>(input: &str, visit: F) -> Result<()>
+where
+    F: FnMut(&str, &Body, ValueKind) -> ControlFlow<()>,
+{
+    response::ResponseBuilder::new().for_each_json_field(input, visit)
+}
+
+/// The length declared by the `Content-Length` headers, if any. Repeated
+/// headers and comma-separated lists must all agree (RFC 9110 §8.6); one
+/// sent alongside `Transfer-Encoding` is rejected, as recipients disagree
+/// on which of the two frames the body.
+fn declared_content_length(
+    headers: &HashMap<String, Vec<Header>>,
+    source: &str,
+) -> Result<Option<usize>> {
+    let Some(content_lengths) = headers.get("content-length") else {
+        return Ok(None);
+    };
+    if headers.contains_key("transfer-encoding") {
+        return Err(ParseError::InvalidContentLength(
+            "sent alongside Transfer-Encoding".to_string(),
+        ));
+    }
+
+    let mut declared = None;
+    for header in content_lengths {
+        let value = header
+            .normalized_value(source)
+            .ok_or_else(|| ParseError::MissingField("Content-Length value".to_string()))?;
+        for item in value.split(',').map(str::trim) {
+            let length = item
+                .parse::<usize>()
+                .ok()
+                .filter(|_| item.bytes().all(|b| b.is_ascii_digit()))
+                .ok_or_else(|| ParseError::InvalidContentLength(format!("{item:?}")))?;
+            if declared.is_some_and(|declared| declared != length) {
+                return Err(ParseError::InvalidContentLength(format!(
+                    "conflicting values in {value:?}"
+                )));
+            }
+            declared = Some(length);
+        }
+    }
+    Ok(declared)
+}
+
+/// Checks that the body of `source`, everything after the blank line ending
+/// the head, is exactly as long as its `Content-Length` declares. Messages
+/// without the header pass.
+fn check_content_length(headers: &HashMap<String, Vec<Header>>, source: &str) -> Result<()> {
+    let Some(declared) = declared_content_length(headers, source)? else {
+        return Ok(());
+    };
+    let body = message_body_start(source.as_bytes())
+        .map_or(source.len()..source.len(), |start| start..source.len());
+    if body.len() == declared {
+        Ok(())
+    } else {
+        Err(ParseError::ContentLengthMismatch { declared, body })
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Header {
     pub name: Range<usize>,
     pub value: Range<usize>,
+    pub raw: Range<usize>,
+}
+
+impl Header {
+    #[must_use]
+    pub fn normalized_value(&self, source: &str) -> Option<String> {
+        source
+            .get(self.value.clone())
+            .map(crate::normalize_header_value)
+    }
+}
+
+/// The JSON type of a body field's value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueKind {
+    Object,
+    Array,
+    String,
+    Number,
+    Boolean,
+    Null,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
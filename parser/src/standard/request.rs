@@ -1,15 +1,19 @@
-use std::{collections::HashMap, ops::Range, str::FromStr};
+use std::{
+    collections::HashMap,
+    ops::{ControlFlow, Range},
+    str::FromStr,
+};
 
 use pest::Parser;
 use pest_derive::Parser;
 
 use super::{
-    Body, Header,
-    traversal::{BodyConfig, HeaderConfig},
+    Body, Header, ValueKind,
+    traversal::{BodyConfig, HeaderConfig, MultipartConfig},
 };
 use crate::{
     HttpMessageBuilder,
-    common::{assert_end_of_iterator, assert_rule},
+    common::{assert_end_of_iterator, assert_rule, mask_invalid_utf8, opaque_body_start},
     error::{ParseError, Result},
     traits::{HttpMessage, RangeExtractor, Traverser},
 };
@@ -28,6 +32,45 @@ pub struct Request {
 }
 
 impl Request {
+    /// A body that is content-encoded, binary, or of a media type other
+    /// than JSON or multipart is not parsed; it is reported as one
+    /// [`Body::Value`] under the root keypath `""`. Head bytes that are not
+    /// UTF-8 are read as `\0`.
+    pub fn from_bytes(input: &[u8]) -> Result<Self> {
+        if let Some(body_start) = opaque_body_start(input) {
+            let mut request =
+                RequestBuilder::new().parse(&mask_invalid_utf8(&input[..body_start]))?;
+            request
+                .body
+                .insert(String::new(), Body::Value(body_start..input.len()));
+            return Ok(request);
+        }
+        RequestBuilder::new().parse(&mask_invalid_utf8(input))
+    }
+
+    /// Visits the JSON body fields of the request `input` with
+    /// [`super::for_each_json_field`]'s callback, for reveal logic that knows
+    /// its keypaths up front and can stop early. Multipart, opaque and empty
+    /// bodies have no fields to visit.
+    pub fn for_each_body_field<F>(input: &str, visit: F) -> Result<()>
+    where
+        F: FnMut(&str, &Body, ValueKind) -> ControlFlow<()>,
+    {
+        RequestBuilder::new().for_each_body_field(input, visit)
+    }
+
+    /// The body length declared by `Content-Length` in `source`, the input
+    /// this message was parsed from.
+    pub fn declared_content_length(&self, source: &str) -> Result<Option<usize>> {
+        super::declared_content_length(&self.headers, source)
+    }
+
+    /// Fails with [`ParseError::ContentLengthMismatch`] when the body of
+    /// `source` is shorter or longer than declared.
+    pub fn check_content_length(&self, source: &str) -> Result<()> {
+        super::check_content_length(&self.headers, source)
+    }
+
     #[must_use]
     pub fn method_with_space(&self) -> Range<usize> {
         self.method.start..self.method.end + 1
@@ -60,6 +103,7 @@ impl HttpMessage for Request {
 pub struct RequestBuilder {
     header_config: HeaderConfig<Rule>,
     body_config: BodyConfig<Rule>,
+    multipart_config: MultipartConfig<Rule>,
 }
 
 impl RequestBuilder {
@@ -72,7 +116,26 @@ impl RequestBuilder {
                 Rule::header_name,
                 Rule::header_value,
             ),
-            body_config: BodyConfig::new(Rule::object, Rule::pair, Rule::array),
+            body_config: BodyConfig::new(
+                Rule::object,
+                Rule::pair,
+                Rule::array,
+                Rule::string,
+                Rule::number,
+                Rule::boolean,
+                Rule::null,
+            ),
+            multipart_config: MultipartConfig::new(
+                Rule::multipart_body,
+                Rule::part,
+                HeaderConfig::new(
+                    Rule::part_headers,
+                    Rule::header,
+                    Rule::header_name,
+                    Rule::header_value,
+                ),
+                Rule::part_body,
+            ),
         }
     }
 
@@ -82,6 +145,25 @@ impl RequestBuilder {
 
         HttpMessageBuilder::parse(self, pairs)
     }
+
+    pub fn for_each_body_field<F>(&self, input: &str, mut visit: F) -> Result<()>
+    where
+        F: FnMut(&str, &Body, ValueKind) -> ControlFlow<()>,
+    {
+        use super::traversal::BodyTraverser;
+
+        if let Some(body_start) = opaque_body_start(input.as_bytes()) {
+            return self.parse(&input[..body_start]).map(|_| ());
+        }
+        let mut pairs = RequestParser::parse(Rule::request, input)
+            .map_err(|e| ParseError::InvalidSyntax(format!("Failed to parse HTTP request: {e}")))?;
+        if let Some(body_pair) = pairs.nth(2)
+            && (body_pair.as_rule() == Rule::object || body_pair.as_rule() == Rule::array)
+        {
+            return BodyTraverser::new(self.body_config, body_pair)?.visit(&mut visit);
+        }
+        Ok(())
+    }
 }
 
 impl HttpMessageBuilder for RequestBuilder {
@@ -140,7 +222,7 @@ impl HttpMessageBuilder for RequestBuilder {
     }
 
     fn parse(&self, mut pairs: pest::iterators::Pairs<'_, Self::Rule>) -> Result<Self::Message> {
-        use super::traversal::{BodyTraverser, HeaderTraverser};
+        use super::traversal::{BodyTraverser, HeaderTraverser, MultipartTraverser};
 
         let first_line_pair = pairs
             .next()
@@ -152,12 +234,16 @@ impl HttpMessageBuilder for RequestBuilder {
         let first_line = self.parse_first_line(first_line_pair)?;
         let headers = HeaderTraverser::new(self.header_config, headers_pair)?.traverse()?;
 
-        let body = if let Some(body_pair) = pairs.next()
-            && (body_pair.as_rule() == Rule::object || body_pair.as_rule() == Rule::array)
-        {
-            BodyTraverser::new(self.body_config, body_pair)?.traverse()?
-        } else {
-            HashMap::new()
+        let body = match pairs.next() {
+            Some(body_pair)
+                if body_pair.as_rule() == Rule::object || body_pair.as_rule() == Rule::array =>
+            {
+                BodyTraverser::new(self.body_config, body_pair)?.traverse()?
+            }
+            Some(body_pair) if body_pair.as_rule() == Rule::multipart_body => {
+                MultipartTraverser::new(self.multipart_config, body_pair)?.traverse()?
+            }
+            _ => HashMap::new(),
         };
 
         Ok(self.build_message(first_line, headers, body))
@@ -1,15 +1,19 @@
-use std::{collections::HashMap, ops::Range, str::FromStr};
+use std::{
+    collections::HashMap,
+    ops::{ControlFlow, Range},
+    str::FromStr,
+};
 
 use pest::Parser;
 use pest_derive::Parser;
 
 use super::{
-    Body, Header,
-    traversal::{BodyConfig, HeaderConfig},
+    Body, Header, ValueKind,
+    traversal::{BodyConfig, HeaderConfig, MultipartConfig},
 };
 use crate::{
     HttpMessageBuilder,
-    common::{assert_end_of_iterator, assert_rule},
+    common::{assert_end_of_iterator, assert_rule, mask_invalid_utf8, opaque_body_start},
     error::{ParseError, Result},
     traits::{HttpMessage, RangeExtractor, Traverser},
 };
@@ -28,6 +32,44 @@ pub struct Response {
 }
 
 impl Response {
+    /// Reads a body the way [`Request::from_bytes`](super::Request::from_bytes)
+    /// does: one the grammar cannot read is reported as one [`Body::Value`]
+    /// under the root keypath `""`.
+    pub fn from_bytes(input: &[u8]) -> Result<Self> {
+        if let Some(body_start) = opaque_body_start(input) {
+            let mut response =
+                ResponseBuilder::new().parse(&mask_invalid_utf8(&input[..body_start]))?;
+            response
+                .body
+                .insert(String::new(), Body::Value(body_start..input.len()));
+            return Ok(response);
+        }
+        ResponseBuilder::new().parse(&mask_invalid_utf8(input))
+    }
+
+    /// Visits the JSON body fields of the response `input` with
+    /// [`super::for_each_json_field`]'s callback, for reveal logic that knows
+    /// its keypaths up front and can stop early. Multipart, opaque and empty
+    /// bodies have no fields to visit.
+    pub fn for_each_body_field<F>(input: &str, visit: F) -> Result<()>
+    where
+        F: FnMut(&str, &Body, ValueKind) -> ControlFlow<()>,
+    {
+        ResponseBuilder::new().for_each_body_field(input, visit)
+    }
+
+    /// The body length declared by `Content-Length` in `source`, the input
+    /// this message was parsed from.
+    pub fn declared_content_length(&self, source: &str) -> Result<Option<usize>> {
+        super::declared_content_length(&self.headers, source)
+    }
+
+    /// Fails with [`ParseError::ContentLengthMismatch`] when the body of
+    /// `source` is shorter or longer than declared.
+    pub fn check_content_length(&self, source: &str) -> Result<()> {
+        super::check_content_length(&self.headers, source)
+    }
+
     #[must_use]
     pub fn protocol_version_with_space(&self) -> Range<usize> {
         self.protocol_version.start..self.protocol_version.end + 1
@@ -42,6 +84,12 @@ impl Response {
     pub fn status_with_newline(&self) -> Range<usize> {
         self.status.start..self.status.end + 1
     }
+
+    /// The first digit of the status code, which determines its class (`2` for 2xx).
+    #[must_use]
+    pub fn status_class_digit(&self) -> Range<usize> {
+        self.status_code.start..self.status_code.start + 1
+    }
 }
 
 impl HttpMessage for Response {
@@ -60,6 +108,7 @@ impl HttpMessage for Response {
 pub struct ResponseBuilder {
     header_config: HeaderConfig<Rule>,
     body_config: BodyConfig<Rule>,
+    multipart_config: MultipartConfig<Rule>,
 }
 
 impl ResponseBuilder {
@@ -72,7 +121,26 @@ impl ResponseBuilder {
                 Rule::header_name,
                 Rule::header_value,
             ),
-            body_config: BodyConfig::new(Rule::object, Rule::pair, Rule::array),
+            body_config: BodyConfig::new(
+                Rule::object,
+                Rule::pair,
+                Rule::array,
+                Rule::string,
+                Rule::number,
+                Rule::boolean,
+                Rule::null,
+            ),
+            multipart_config: MultipartConfig::new(
+                Rule::multipart_body,
+                Rule::part,
+                HeaderConfig::new(
+                    Rule::part_headers,
+                    Rule::header,
+                    Rule::header_name,
+                    Rule::header_value,
+                ),
+                Rule::part_body,
+            ),
         }
     }
 
@@ -83,6 +151,53 @@ impl ResponseBuilder {
 
         HttpMessageBuilder::parse(self, pairs)
     }
+
+    /// Parses a standalone JSON document into the same keypath map as a
+    /// response body, with ranges relative to `input`.
+    pub fn parse_json(&self, input: &str) -> Result<HashMap<String, Body>> {
+        use super::traversal::BodyTraverser;
+
+        let root = ResponseParser::parse(Rule::json_document, input)
+            .map_err(|e| ParseError::InvalidSyntax(format!("Failed to parse JSON document: {e}")))?
+            .next()
+            .ok_or_else(|| ParseError::MissingField("JSON root".to_string()))?;
+        BodyTraverser::new(self.body_config, root)?.traverse()
+    }
+
+    /// Visits the fields of a standalone JSON document; see
+    /// [`super::for_each_json_field`].
+    pub fn for_each_json_field<F>(&self, input: &str, mut visit: F) -> Result<()>
+    where
+        F: FnMut(&str, &Body, ValueKind) -> ControlFlow<()>,
+    {
+        use super::traversal::BodyTraverser;
+
+        let root = ResponseParser::parse(Rule::json_document, input)
+            .map_err(|e| ParseError::InvalidSyntax(format!("Failed to parse JSON document: {e}")))?
+            .next()
+            .ok_or_else(|| ParseError::MissingField("JSON root".to_string()))?;
+        BodyTraverser::new(self.body_config, root)?.visit(&mut visit)
+    }
+
+    pub fn for_each_body_field<F>(&self, input: &str, mut visit: F) -> Result<()>
+    where
+        F: FnMut(&str, &Body, ValueKind) -> ControlFlow<()>,
+    {
+        use super::traversal::BodyTraverser;
+
+        if let Some(body_start) = opaque_body_start(input.as_bytes()) {
+            return self.parse(&input[..body_start]).map(|_| ());
+        }
+        let mut pairs = ResponseParser::parse(Rule::response, input).map_err(|e| {
+            ParseError::InvalidSyntax(format!("Failed to parse HTTP response: {e}"))
+        })?;
+        if let Some(body_pair) = pairs.nth(2)
+            && (body_pair.as_rule() == Rule::object || body_pair.as_rule() == Rule::array)
+        {
+            return BodyTraverser::new(self.body_config, body_pair)?.visit(&mut visit);
+        }
+        Ok(())
+    }
 }
 
 impl HttpMessageBuilder for ResponseBuilder {
@@ -141,7 +256,7 @@ impl HttpMessageBuilder for ResponseBuilder {
     }
 
     fn parse(&self, mut pairs: pest::iterators::Pairs<'_, Self::Rule>) -> Result<Self::Message> {
-        use super::traversal::{BodyTraverser, HeaderTraverser};
+        use super::traversal::{BodyTraverser, HeaderTraverser, MultipartTraverser};
 
         let first_line_pair = pairs
             .next()
@@ -153,12 +268,16 @@ impl HttpMessageBuilder for ResponseBuilder {
         let first_line = self.parse_first_line(first_line_pair)?;
         let headers = HeaderTraverser::new(self.header_config, headers_pair)?.traverse()?;
 
-        let body = if let Some(body_pair) = pairs.next()
-            && (body_pair.as_rule() == Rule::object || body_pair.as_rule() == Rule::array)
-        {
-            BodyTraverser::new(self.body_config, body_pair)?.traverse()?
-        } else {
-            HashMap::new()
+        let body = match pairs.next() {
+            Some(body_pair)
+                if body_pair.as_rule() == Rule::object || body_pair.as_rule() == Rule::array =>
+            {
+                BodyTraverser::new(self.body_config, body_pair)?.traverse()?
+            }
+            Some(body_pair) if body_pair.as_rule() == Rule::multipart_body => {
+                MultipartTraverser::new(self.multipart_config, body_pair)?.traverse()?
+            }
+            _ => HashMap::new(),
         };
 
         Ok(self.build_message(first_line, headers, body))
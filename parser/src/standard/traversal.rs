@@ -1,11 +1,11 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, ops::ControlFlow};
 
 use pest::{
     RuleType,
     iterators::{Pair, Pairs},
 };
 
-use super::{Body, Header};
+use super::{Body, Header, ValueKind};
 use crate::{
     common::{assert_end_of_iterator, assert_rule},
     error::{ParseError, Result},
@@ -37,14 +37,22 @@ pub struct BodyConfig<R> {
     pub object: R,
     pub pair: R,
     pub array: R,
+    pub string: R,
+    pub number: R,
+    pub boolean: R,
+    pub null: R,
 }
 
 impl<R: Copy> BodyConfig<R> {
-    pub fn new(object: R, pair: R, array: R) -> Self {
+    pub fn new(object: R, pair: R, array: R, string: R, number: R, boolean: R, null: R) -> Self {
         Self {
             object,
             pair,
             array,
+            string,
+            number,
+            boolean,
+            null,
         }
     }
 }
@@ -66,6 +74,7 @@ impl<'a, R: RuleType + PartialEq + Copy> HeaderTraverser<'a, R> {
     }
 
     fn parse_header_inner(pair: Pair<'_, R>, config: &HeaderConfig<R>) -> Result<Header> {
+        let raw = pair.extract_range();
         let mut inner = pair.into_inner();
 
         let name_pair = inner
@@ -83,6 +92,7 @@ impl<'a, R: RuleType + PartialEq + Copy> HeaderTraverser<'a, R> {
         Ok(Header {
             name: name_pair.extract_range(),
             value: value_pair.extract_range(),
+            raw,
         })
     }
 }
@@ -107,10 +117,12 @@ impl<R: RuleType + PartialEq + Copy> Traverser for HeaderTraverser<'_, R> {
     }
 }
 
+/// Walks a JSON body in document order, parents before their children.
+/// [`Self::visit`] hands each field to a callback that may stop the walk;
+/// [`Traverser::traverse`] collects every field into the keypath map.
 pub struct BodyTraverser<'a, R> {
     config: BodyConfig<R>,
     root: Pair<'a, R>,
-    body: HashMap<String, Body>,
     pathstack: PathStack,
 }
 
@@ -124,31 +136,67 @@ impl<'a, R: RuleType + PartialEq + Copy> BodyTraverser<'a, R> {
             )));
         }
 
-        let mut body = HashMap::new();
-        body.insert(String::new(), Body::Value(body_pair.extract_range()));
-
         Ok(Self {
             config,
             root: body_pair,
-            body,
             pathstack: PathStack::default(),
         })
     }
 
-    fn traverse_value(&mut self, value: Pair<'_, R>) -> Result<()> {
+    /// Calls `visit` with the keypath, ranges and kind of each field,
+    /// starting with the root `""`, until it returns [`ControlFlow::Break`];
+    /// nothing after that field is walked.
+    pub fn visit<F>(mut self, visit: &mut F) -> Result<()>
+    where
+        F: FnMut(&str, &Body, ValueKind) -> ControlFlow<()>,
+    {
+        let root = self.root.clone();
+        let kind = self.kind(&root)?;
+        if visit("", &Body::Value(root.extract_range()), kind).is_continue() {
+            // A break only stops the walk.
+            let _ = self.visit_value(root, visit)?;
+        }
+        Ok(())
+    }
+
+    fn kind(&self, value: &Pair<'_, R>) -> Result<ValueKind> {
+        let rule = value.as_rule();
+        let config = &self.config;
+        [
+            (config.object, ValueKind::Object),
+            (config.array, ValueKind::Array),
+            (config.string, ValueKind::String),
+            (config.number, ValueKind::Number),
+            (config.boolean, ValueKind::Boolean),
+            (config.null, ValueKind::Null),
+        ]
+        .into_iter()
+        .find_map(|(kind_rule, kind)| (kind_rule == rule).then_some(kind))
+        .ok_or_else(|| ParseError::UnexpectedRule(format!("expected a JSON value, got {rule:?}")))
+    }
+
+    fn visit_value<F>(&mut self, value: Pair<'_, R>, visit: &mut F) -> Result<ControlFlow<()>>
+    where
+        F: FnMut(&str, &Body, ValueKind) -> ControlFlow<()>,
+    {
         let current_rule = value.as_rule();
 
-        if current_rule == self.config.object {
-            self.traverse_object(value)?;
+        let flow = if current_rule == self.config.object {
+            self.visit_object(value, visit)?
         } else if current_rule == self.config.array {
-            self.traverse_array(value)?;
-        }
+            self.visit_array(value, visit)?
+        } else {
+            ControlFlow::Continue(())
+        };
 
         self.pathstack.pop();
-        Ok(())
+        Ok(flow)
     }
 
-    fn traverse_object(&mut self, value: Pair<'_, R>) -> Result<()> {
+    fn visit_object<F>(&mut self, value: Pair<'_, R>, visit: &mut F) -> Result<ControlFlow<()>>
+    where
+        F: FnMut(&str, &Body, ValueKind) -> ControlFlow<()>,
+    {
         assert_rule(&value, self.config.object, "object")?;
 
         for pair in value.into_inner() {
@@ -164,44 +212,157 @@ impl<'a, R: RuleType + PartialEq + Copy> BodyTraverser<'a, R> {
 
             assert_end_of_iterator(&mut inner, "pair")?;
 
-            let key_str = key_pair.as_str().to_string();
-            self.pathstack.push(PathSegment::Key(key_str));
-
-            self.body.insert(
-                self.pathstack.to_string(),
-                Body::KeyValue {
-                    key: key_pair.extract_range(),
-                    value: value_pair.extract_range(),
-                },
-            );
-
-            self.traverse_value(value_pair)?;
+            self.pathstack.push(PathSegment::Key(key_pair.as_str()));
+
+            let field = Body::KeyValue {
+                key: key_pair.extract_range(),
+                value: value_pair.extract_range(),
+            };
+            let kind = self.kind(&value_pair)?;
+            if visit(self.pathstack.as_str(), &field, kind).is_break()
+                || self.visit_value(value_pair, visit)?.is_break()
+            {
+                return Ok(ControlFlow::Break(()));
+            }
         }
 
-        Ok(())
+        Ok(ControlFlow::Continue(()))
     }
 
-    fn traverse_array(&mut self, value: Pair<'_, R>) -> Result<()> {
+    fn visit_array<F>(&mut self, value: Pair<'_, R>, visit: &mut F) -> Result<ControlFlow<()>>
+    where
+        F: FnMut(&str, &Body, ValueKind) -> ControlFlow<()>,
+    {
         assert_rule(&value, self.config.array, "array")?;
 
         for (i, pair) in value.into_inner().enumerate() {
             self.pathstack.push(PathSegment::Index(i));
-            self.body.insert(
-                self.pathstack.to_string(),
-                Body::Value(pair.extract_range()),
-            );
-            self.traverse_value(pair)?;
+            let field = Body::Value(pair.extract_range());
+            let kind = self.kind(&pair)?;
+            if visit(self.pathstack.as_str(), &field, kind).is_break()
+                || self.visit_value(pair, visit)?.is_break()
+            {
+                return Ok(ControlFlow::Break(()));
+            }
         }
 
-        Ok(())
+        Ok(ControlFlow::Continue(()))
     }
 }
 
 impl<R: RuleType + PartialEq + Copy> Traverser for BodyTraverser<'_, R> {
     type Output = Body;
 
-    fn traverse(mut self) -> Result<HashMap<String, Self::Output>> {
-        self.traverse_value(self.root.clone())?;
-        Ok(self.body)
+    fn traverse(self) -> Result<HashMap<String, Self::Output>> {
+        let mut body = HashMap::with_capacity(node_count_hint(self.root.as_str()));
+        self.visit(&mut |keypath, field, _| {
+            body.insert(keypath.to_string(), field.clone());
+            ControlFlow::Continue(())
+        })?;
+        Ok(body)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct MultipartConfig<R> {
+    pub multipart: R,
+    pub part: R,
+    pub part_headers: HeaderConfig<R>,
+    pub part_body: R,
+}
+
+impl<R: Copy> MultipartConfig<R> {
+    pub fn new(multipart: R, part: R, part_headers: HeaderConfig<R>, part_body: R) -> Self {
+        Self {
+            multipart,
+            part,
+            part_headers,
+            part_body,
+        }
     }
 }
+
+/// Maps a multipart body to `.parts[i]`, `.parts[i].headers.<name>` and
+/// `.parts[i].body`, keeping the first occurrence of a repeated part header.
+pub struct MultipartTraverser<'a, R> {
+    config: MultipartConfig<R>,
+    root: Pair<'a, R>,
+}
+
+impl<'a, R: RuleType + PartialEq + Copy> MultipartTraverser<'a, R> {
+    pub fn new(config: MultipartConfig<R>, body_pair: Pair<'a, R>) -> Result<Self> {
+        assert_rule(&body_pair, config.multipart, "multipart_body")?;
+        Ok(Self {
+            config,
+            root: body_pair,
+        })
+    }
+
+    fn traverse_part(
+        &self,
+        index: usize,
+        part: Pair<'_, R>,
+        body: &mut HashMap<String, Body>,
+    ) -> Result<()> {
+        assert_rule(&part, self.config.part, "part")?;
+
+        let mut pathstack = PathStack::default();
+        pathstack.push(PathSegment::Key("parts"));
+        pathstack.push(PathSegment::Index(index));
+        body.insert(pathstack.keypath(), Body::Value(part.extract_range()));
+
+        let mut inner = part.into_inner();
+        let headers_pair = inner
+            .next()
+            .ok_or_else(|| ParseError::MissingField("part headers".to_string()))?;
+        let body_pair = inner
+            .next()
+            .ok_or_else(|| ParseError::MissingField("part body".to_string()))?;
+        assert_rule(&body_pair, self.config.part_body, "part_body")?;
+        assert_end_of_iterator(&mut inner, "part")?;
+
+        pathstack.push(PathSegment::Key("headers"));
+        for (name, headers) in
+            HeaderTraverser::new(self.config.part_headers, headers_pair)?.traverse()?
+        {
+            if let Some(header) = headers.into_iter().next() {
+                pathstack.push(PathSegment::Key(&name));
+                body.insert(
+                    pathstack.keypath(),
+                    Body::KeyValue {
+                        key: header.name,
+                        value: header.value,
+                    },
+                );
+                pathstack.pop();
+            }
+        }
+        pathstack.pop();
+
+        pathstack.push(PathSegment::Key("body"));
+        body.insert(pathstack.keypath(), Body::Value(body_pair.extract_range()));
+        Ok(())
+    }
+}
+
+impl<R: RuleType + PartialEq + Copy> Traverser for MultipartTraverser<'_, R> {
+    type Output = Body;
+
+    fn traverse(self) -> Result<HashMap<String, Self::Output>> {
+        let mut body = HashMap::new();
+        body.insert(String::new(), Body::Value(self.root.extract_range()));
+        for (index, part) in self.root.clone().into_inner().enumerate() {
+            self.traverse_part(index, part, &mut body)?;
+        }
+        Ok(body)
+    }
+}
+
+/// Upper bound on the keypaths of a JSON body, one per `:` or `,` plus the
+/// root and a first array element, so the map is sized once.
+fn node_count_hint(json: &str) -> usize {
+    json.bytes()
+        .filter(|&b| b == b':' || b == b',' || b == b'[')
+        .count()
+        + 1
+}
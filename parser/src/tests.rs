@@ -1,6 +1,15 @@
-use std::{ops::Range, str::FromStr};
+use std::{
+    ops::{ControlFlow, Range},
+    str::FromStr,
+};
 
-use crate::{JsonFieldRangeExt, redacted, standard};
+use proptest::prelude::*;
+
+use crate::{
+    HttpMessage, JsonFieldRangeExt, ParseError, keypath_matches, last_request_start,
+    last_response_start, mask_invalid_utf8, message_body_start, opaque_body_start, redacted,
+    standard,
+};
 
 fn redact_string(input: &str, keep_ranges: &[Range<usize>]) -> String {
     let mut bytes = input.as_bytes().to_vec();
@@ -35,7 +44,7 @@ User-Agent: TestClient/1.0
 0
 "#;
 
-    let request = standard::Request::from_str(input).unwrap();
+    let request = standard::Request::from_str(input).expect("Request should parse");
 
     assert_eq!(&input[request.method.clone()], "POST");
 
@@ -149,7 +158,7 @@ Date: Mon, 01 Jan 2024 00:00:00 GMT
 0
 "#;
 
-    let response = standard::Response::from_str(input).unwrap();
+    let response = standard::Response::from_str(input).expect("Response should parse");
 
     assert_eq!(&input[response.protocol_version.clone()], "HTTP/1.1");
 
@@ -286,6 +295,78 @@ Date: Mon, 01 Jan 2024 00:00:00 GMT
     }
 }
 
+#[test]
+fn test_body_keypath_wildcards() {
+    let input = "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\r\n{\"data\":{\"users\":[{\"id\":1},{\"id\":2,\"name\":\"bob\"}]}}";
+    let response = standard::Response::from_str(input).expect("Response should parse");
+
+    let keypaths = |pattern: &str| {
+        response
+            .matches(pattern)
+            .into_iter()
+            .map(|(keypath, _)| keypath)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        keypaths(".data.users[*].id"),
+        vec![".data.users[0].id", ".data.users[1].id"]
+    );
+    assert_eq!(keypaths(".data.users[*].name"), vec![".data.users[1].name"]);
+    assert_eq!(keypaths(".*.users"), vec![".data.users"]);
+    assert_eq!(keypaths(".data.users[1]"), vec![".data.users[1]"]);
+    assert_eq!(keypaths(".data.*[0]"), vec![".data.users[0]"]);
+    assert!(response.contains(".data.users[1].name"));
+    assert!(!response.contains(".data.users[0].name"));
+    assert!(!response.contains(".data[*]"));
+
+    // Values revealed without their keys are addressed as `.?[n]`.
+    let body_start = input.find('{').expect("Body should start with '{'");
+    let keep_ranges = response
+        .matches(".data.users[*].id")
+        .into_iter()
+        .filter_map(|(_, body)| match body {
+            standard::Body::KeyValue { value, .. } => Some(value.clone()),
+            standard::Body::Value(_) => None,
+        })
+        .chain(std::iter::once(0..body_start))
+        .collect::<Vec<_>>();
+    let redacted_input = redact_string(input, &keep_ranges);
+    let redacted_response =
+        redacted::Response::from_str(&redacted_input).expect("Redacted response should parse");
+    assert_eq!(redacted_response.matches(".?[*]").len(), 2);
+    assert!(redacted_response.contains(".?[1]"));
+    assert!(!redacted_response.contains(".?[2]"));
+
+    assert!(keypath_matches("", ""));
+    assert!(!keypath_matches(".a[*]", ".a.b"));
+    assert!(!keypath_matches(".a[", ".a["));
+}
+
+#[test]
+fn test_content_encoded_request_body_is_one_value() {
+    let head = b"POST /api/upload HTTP/1.1\r\nHost: api.example.com\r\nContent-Encoding: gzip\r\nContent-Length: 6\r\n\r\n";
+    let input = [head.as_slice(), &[0x1f, 0x8b, 0x08, 0xff, 0x00, 0x7b]].concat();
+
+    assert_eq!(message_body_start(&input), Some(head.len()));
+    let request = standard::Request::from_bytes(&input).expect("Request should parse");
+    assert_eq!(
+        request.body.get(""),
+        Some(&standard::Body::Value(head.len()..input.len()))
+    );
+    assert!(request.headers.contains_key("content-encoding"));
+
+    let redacted_request =
+        redacted::Request::from_bytes_lossy(&input).expect("Redacted request should parse");
+    assert_eq!(
+        redacted_request.body.get(""),
+        Some(&redacted::Body::Value(head.len()..input.len()))
+    );
+
+    let identity = "POST /api HTTP/1.1\r\nContent-Encoding: identity\r\n\r\n{\"a\":1}";
+    let request = standard::Request::from_str(identity).expect("Request should parse");
+    assert!(request.body.contains_key(".a"));
+}
+
 #[test]
 fn test_redacted_request_full_flow() {
     shared::init_test_logging();
@@ -300,7 +381,7 @@ User-Agent: TestClient/1.0
 0
 "#;
 
-    let standard_request = standard::Request::from_str(input).unwrap();
+    let standard_request = standard::Request::from_str(input).expect("Request should parse");
 
     let mut keep_ranges = vec![
         standard_request.method_with_space(),
@@ -308,14 +389,25 @@ User-Agent: TestClient/1.0
         standard_request.protocol_version_with_newline(),
     ];
 
-    let host_header = &standard_request.headers.get("host").unwrap()[0];
+    let host_header = standard_request
+        .headers
+        .get("host")
+        .and_then(|values| values.first())
+        .expect("Host header should exist");
     keep_ranges.push(host_header.name.with_separator());
     keep_ranges.push(host_header.value.with_newline());
 
-    let user_agent_header = &standard_request.headers.get("user-agent").unwrap()[0];
+    let user_agent_header = standard_request
+        .headers
+        .get("user-agent")
+        .and_then(|values| values.first())
+        .expect("User-Agent header should exist");
     keep_ranges.push(user_agent_header.name.with_separator());
 
-    let name_field = standard_request.body.get(".user.name").unwrap();
+    let name_field = standard_request
+        .body
+        .get(".user.name")
+        .expect(".user.name field should exist");
     if let standard::Body::KeyValue { key, value } = name_field {
         keep_ranges.push(key.with_quotes_and_colon());
         keep_ranges.push(value.with_quotes());
@@ -323,7 +415,10 @@ User-Agent: TestClient/1.0
         panic!("name field should be a KeyValue");
     }
 
-    let email_field = standard_request.body.get(".user.email").unwrap();
+    let email_field = standard_request
+        .body
+        .get(".user.email")
+        .expect(".user.email field should exist");
     if let standard::Body::KeyValue { key, value } = email_field {
         keep_ranges.push(key.with_quotes_and_colon());
         keep_ranges.push(value.with_quotes());
@@ -331,7 +426,10 @@ User-Agent: TestClient/1.0
         panic!("email field should be a KeyValue");
     }
 
-    let age_field = standard_request.body.get(".user.age").unwrap();
+    let age_field = standard_request
+        .body
+        .get(".user.age")
+        .expect(".user.age field should exist");
     if let standard::Body::KeyValue { key, .. } = age_field {
         keep_ranges.push(key.with_quotes_and_colon());
     } else {
@@ -340,7 +438,8 @@ User-Agent: TestClient/1.0
 
     let redacted_input = redact_string(input, &keep_ranges);
 
-    let redacted_request = redacted::Request::from_str(&redacted_input).unwrap();
+    let redacted_request =
+        redacted::Request::from_str(&redacted_input).expect("Redacted request should parse");
 
     assert_eq!(&redacted_input[redacted_request.method.clone()], "POST");
     assert_eq!(&redacted_input[redacted_request.url.clone()], "/api/users");
@@ -359,7 +458,10 @@ User-Agent: TestClient/1.0
     let host_header = &host_headers[0];
     assert_eq!(&redacted_input[host_header.name.clone()], "Host");
     assert_eq!(
-        &redacted_input[host_header.value.clone().unwrap()],
+        &redacted_input[host_header
+            .value
+            .clone()
+            .expect("Host header value should be revealed")],
         "api.example.com"
     );
 
@@ -434,7 +536,7 @@ Date: Mon, 01 Jan 2024 00:00:00 GMT
 0
 "#;
 
-    let standard_response = standard::Response::from_str(input).unwrap();
+    let standard_response = standard::Response::from_str(input).expect("Response should parse");
 
     let mut keep_ranges = vec![
         standard_response.protocol_version_with_space(),
@@ -442,14 +544,25 @@ Date: Mon, 01 Jan 2024 00:00:00 GMT
         standard_response.status_with_newline(),
     ];
 
-    let server_header = &standard_response.headers.get("server").unwrap()[0];
+    let server_header = standard_response
+        .headers
+        .get("server")
+        .and_then(|values| values.first())
+        .expect("Server header should exist");
     keep_ranges.push(server_header.name.with_separator());
     keep_ranges.push(server_header.value.with_newline());
 
-    let content_type_header = &standard_response.headers.get("content-type").unwrap()[0];
+    let content_type_header = standard_response
+        .headers
+        .get("content-type")
+        .and_then(|values| values.first())
+        .expect("Content-Type header should exist");
     keep_ranges.push(content_type_header.name.with_separator());
 
-    let status_field = standard_response.body.get(".status").unwrap();
+    let status_field = standard_response
+        .body
+        .get(".status")
+        .expect(".status field should exist");
     if let standard::Body::KeyValue { key, value } = status_field {
         keep_ranges.push(key.with_quotes_and_colon());
         keep_ranges.push(value.with_quotes());
@@ -457,13 +570,20 @@ Date: Mon, 01 Jan 2024 00:00:00 GMT
         panic!("status field should be a KeyValue");
     }
 
-    if let standard::Body::Value(value) = standard_response.body.get(".data.users[0]").unwrap() {
+    if let standard::Body::Value(value) = standard_response
+        .body
+        .get(".data.users[0]")
+        .expect(".data.users[0] field should exist")
+    {
         keep_ranges.push(value.start + 1..value.end - 1);
     } else {
         panic!("data.users[0] should be a Value");
     }
 
-    let data_field = standard_response.body.get(".data").unwrap();
+    let data_field = standard_response
+        .body
+        .get(".data")
+        .expect(".data field should exist");
     if let standard::Body::KeyValue { key, .. } = data_field {
         keep_ranges.push(key.with_quotes_and_colon());
     } else {
@@ -472,7 +592,8 @@ Date: Mon, 01 Jan 2024 00:00:00 GMT
 
     let redacted_input = redact_string(input, &keep_ranges);
 
-    let redacted_response = redacted::Response::from_str(&redacted_input).unwrap();
+    let redacted_response =
+        redacted::Response::from_str(&redacted_input).expect("Redacted response should parse");
 
     assert_eq!(
         &redacted_input[redacted_response.protocol_version.clone()],
@@ -494,7 +615,10 @@ Date: Mon, 01 Jan 2024 00:00:00 GMT
     let server_header = &server_headers[0];
     assert_eq!(&redacted_input[server_header.name.clone()], "Server");
     assert_eq!(
-        &redacted_input[server_header.value.clone().unwrap()],
+        &redacted_input[server_header
+            .value
+            .clone()
+            .expect("Server header value should be revealed")],
         "nginx/1.18.0"
     );
 
@@ -554,3 +678,844 @@ Date: Mon, 01 Jan 2024 00:00:00 GMT
         panic!(".data should be a KeyValue");
     }
 }
+
+#[test]
+fn test_redacted_response_from_bytes_lossy() {
+    shared::init_test_logging();
+
+    let input = "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nServer: nginx\r\n\r\n{\"status\":\"success\",\"balance\":100}";
+
+    let standard_response =
+        standard::Response::from_bytes(input.as_bytes()).expect("Should parse from bytes");
+
+    let mut keep_ranges = vec![
+        standard_response.protocol_version_with_space(),
+        standard_response.status_code_with_space(),
+        standard_response.status_with_newline(),
+    ];
+
+    let server_header = standard_response
+        .headers
+        .get("server")
+        .and_then(|values| values.first())
+        .expect("Server header should exist");
+    keep_ranges.push(server_header.name.with_separator());
+    keep_ranges.push(server_header.value.with_crlf());
+
+    let status_field = standard_response
+        .body
+        .get(".status")
+        .expect(".status field should exist");
+    if let standard::Body::KeyValue { key, value } = status_field {
+        keep_ranges.push(key.with_quotes_and_colon());
+        keep_ranges.push(value.with_quotes());
+    } else {
+        panic!("status field should be a KeyValue");
+    }
+
+    let redacted = redact_string(input, &keep_ranges);
+    let filled: Vec<u8> = redacted
+        .bytes()
+        .map(|byte| if byte == b'\0' { 0xFF } else { byte })
+        .collect();
+
+    assert!(
+        redacted::Response::from_bytes(&filled).is_err(),
+        "Strict byte parsing should reject non-UTF-8 filler"
+    );
+
+    let expected = redacted::Response::from_str(&redacted).expect("Should parse redacted string");
+    let lossy =
+        redacted::Response::from_bytes_lossy(&filled).expect("Should parse non-UTF-8 filler");
+
+    assert_eq!(lossy.status_code, expected.status_code);
+    assert_eq!(lossy.headers, expected.headers);
+    assert_eq!(lossy.body, expected.body);
+    assert_eq!(&input[lossy.status_code.clone()], "200");
+}
+
+#[test]
+fn test_redacted_response_with_hidden_keys() {
+    shared::init_test_logging();
+
+    let input = "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nX-Api-Token: abc123\r\n\r\n{\"username\":\"alice\",\"balance\":100}";
+
+    let standard_response =
+        standard::Response::from_bytes(input.as_bytes()).expect("Should parse from bytes");
+
+    let mut keep_ranges = vec![
+        standard_response.protocol_version_with_space(),
+        standard_response.status_code_with_space(),
+        standard_response.status_with_newline(),
+    ];
+
+    let token_header = standard_response
+        .headers
+        .get("x-api-token")
+        .and_then(|values| values.first())
+        .expect("X-Api-Token header should exist");
+    keep_ranges
+        .push(token_header.name.end..token_header.name.header_full_range(&token_header.value).end);
+
+    let username_field = standard_response
+        .body
+        .get(".username")
+        .expect(".username field should exist");
+    if let standard::Body::KeyValue { value, .. } = username_field {
+        keep_ranges.push(value.with_quotes());
+    } else {
+        panic!("username field should be a KeyValue");
+    }
+
+    let balance_field = standard_response
+        .body
+        .get(".balance")
+        .expect(".balance field should exist");
+    if let standard::Body::KeyValue { value, .. } = balance_field {
+        keep_ranges.push(value.clone());
+    } else {
+        panic!("balance field should be a KeyValue");
+    }
+
+    let redacted_input = redact_string(input, &keep_ranges);
+    let redacted_response =
+        redacted::Response::from_str(&redacted_input).expect("Should parse hidden keys");
+
+    let hidden_headers = redacted_response
+        .headers
+        .get(redacted::HIDDEN_KEY)
+        .expect("Should collect headers with hidden names");
+    assert_eq!(hidden_headers.len(), 1);
+    assert!(hidden_headers[0].name.is_empty());
+    let token_range = hidden_headers[0]
+        .value
+        .clone()
+        .expect("Hidden header value should be revealed");
+    assert_eq!(&redacted_input[token_range], "abc123");
+
+    assert_eq!(redacted_response.body.len(), 2);
+    match redacted_response.body.get(".?[0]") {
+        Some(redacted::Body::Value(range)) => {
+            assert_eq!(&redacted_input[range.clone()], "alice");
+        }
+        other => panic!("first hidden value should be a Value, got {other:?}"),
+    }
+    match redacted_response.body.get(".?[1]") {
+        Some(redacted::Body::Value(range)) => {
+            assert_eq!(&redacted_input[range.clone()], "100");
+        }
+        other => panic!("second hidden value should be a Value, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_headers_with_optional_whitespace_and_folding() {
+    shared::init_test_logging();
+
+    let input = "HTTP/1.1 200 OK\r\nContent-Type:application/json \r\nX-Trace:\t abc  \r\nX-Folded: first\r\n  second\r\n\r\n{\"status\":\"ok\"}";
+
+    let response = standard::Response::from_str(input).expect("Should parse OWS headers");
+
+    let content_type = response
+        .headers
+        .get("content-type")
+        .and_then(|values| values.first())
+        .expect("Content-Type header should exist");
+    assert_eq!(&input[content_type.value.clone()], "application/json");
+    assert_eq!(
+        &input[content_type.raw.clone()],
+        "Content-Type:application/json \r\n"
+    );
+
+    let trace = response
+        .headers
+        .get("x-trace")
+        .and_then(|values| values.first())
+        .expect("X-Trace header should exist");
+    assert_eq!(&input[trace.value.clone()], "abc");
+    assert_eq!(&input[trace.raw.clone()], "X-Trace:\t abc  \r\n");
+
+    let folded = response
+        .headers
+        .get("x-folded")
+        .and_then(|values| values.first())
+        .expect("X-Folded header should exist");
+    assert_eq!(&input[folded.value.clone()], "first\r\n  second");
+    assert_eq!(
+        folded.normalized_value(input).as_deref(),
+        Some("first second")
+    );
+
+    let keep_ranges = vec![
+        response.protocol_version_with_space(),
+        response.status_code_with_space(),
+        response.status_with_newline(),
+        trace.raw.clone(),
+        folded.raw.clone(),
+    ];
+    let redacted_input = redact_string(input, &keep_ranges);
+    let redacted_response =
+        redacted::Response::from_str(&redacted_input).expect("Should parse redacted OWS headers");
+
+    let redacted_folded = redacted_response
+        .headers
+        .get("x-folded")
+        .and_then(|values| values.first())
+        .expect("X-Folded header should exist");
+    assert_eq!(redacted_folded.value, Some(folded.value.clone()));
+    let redacted_trace = redacted_response
+        .headers
+        .get("x-trace")
+        .and_then(|values| values.first())
+        .expect("X-Trace header should exist");
+    assert_eq!(redacted_trace.value, Some(trace.value.clone()));
+}
+
+#[test]
+fn test_redacted_response_with_partially_revealed_value() {
+    shared::init_test_logging();
+
+    let input = "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\r\n{\"account\":\"PL61109010140000071219812874\"}";
+
+    let standard_response =
+        standard::Response::from_bytes(input.as_bytes()).expect("Should parse from bytes");
+
+    let account_field = standard_response
+        .body
+        .get(".account")
+        .expect(".account field should exist");
+    let standard::Body::KeyValue { key, value } = account_field else {
+        panic!("account field should be a KeyValue");
+    };
+    let suffix = value.end - 4..value.end;
+
+    let keep_ranges = vec![
+        standard_response.protocol_version_with_space(),
+        standard_response.status_code_with_space(),
+        standard_response.status_with_newline(),
+        key.with_quotes_and_colon(),
+        value.start - 1..value.start,
+        suffix.clone(),
+        value.end..value.end + 1,
+    ];
+
+    let redacted_input = redact_string(input, &keep_ranges);
+    let redacted_response = redacted::Response::from_str(&redacted_input)
+        .expect("Should parse partially revealed string value");
+
+    match redacted_response.body.get(".account") {
+        Some(redacted::Body::KeyValue {
+            value: Some(range), ..
+        }) => {
+            assert_eq!(range, value);
+            assert_eq!(
+                redacted::revealed_ranges(redacted_input.as_bytes(), range),
+                vec![suffix.clone()]
+            );
+        }
+        other => panic!("account field should be a KeyValue with a value, got {other:?}"),
+    }
+    assert_eq!(&redacted_input[suffix], "2874");
+}
+
+#[test]
+fn test_redacted_response_annotated_rendering() {
+    shared::init_test_logging();
+
+    let input = "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\r\n{\"account\":\"PL61109010140000071219812874\"}";
+
+    let standard_response =
+        standard::Response::from_bytes(input.as_bytes()).expect("Should parse from bytes");
+    let standard::Body::KeyValue { key, value } = standard_response
+        .body
+        .get(".account")
+        .expect(".account field should exist")
+    else {
+        panic!("account field should be a KeyValue");
+    };
+
+    let keep_ranges = vec![
+        standard_response.protocol_version_with_space(),
+        standard_response.status_code_with_space(),
+        standard_response.status_with_newline(),
+        key.with_quotes_and_colon(),
+        value.start - 1..value.start,
+        value.end - 4..value.end + 1,
+    ];
+    let redacted_input = redact_string(input, &keep_ranges);
+    let redacted_response =
+        redacted::Response::from_str(&redacted_input).expect("Redacted response should parse");
+
+    let rendered = redacted_response.to_annotated_string(redacted_input.as_bytes());
+    let rows = rendered.lines().map(str::trim_end).collect::<Vec<_>>();
+    assert!(rows[0].starts_with(" 0 │ HTTP/1.1 "));
+    assert!(rows[0].ends_with("│ protocol_version"));
+    assert!(rows[1].starts_with(" 9 │ 200 "));
+    assert!(rows[1].ends_with("│ status_code"));
+    assert!(rows[2].starts_with("13 │ OK\\r▓▓▓▓"));
+    assert!(rows[2].ends_with("│ status"));
+    assert!(rows[3].starts_with("53 │ account\":\"▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓▓2874\"▓"));
+    assert!(rows[3].ends_with("│ .account"));
+    assert_eq!(rows.len(), 4);
+
+    let json = redacted_response.to_annotated_json(redacted_input.as_bytes());
+    let annotations = redacted_response.annotations(redacted_input.as_bytes());
+    let account = annotations
+        .iter()
+        .find(|annotation| annotation.label == ".account")
+        .expect("Should annotate the account field");
+    assert_eq!(
+        account.revealed_ranges.last(),
+        Some(&(value.end - 4..value.end))
+    );
+    assert!(json.contains("\"label\": \".account\""));
+}
+
+#[test]
+fn test_redacted_response_with_hidden_status() {
+    shared::init_test_logging();
+
+    let input = "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\r\n{\"status\":\"ok\"}";
+
+    let standard_response =
+        standard::Response::from_bytes(input.as_bytes()).expect("Should parse from bytes");
+    assert_eq!(&input[standard_response.status_class_digit()], "2");
+
+    let keep_ranges = vec![
+        standard_response.protocol_version_with_space(),
+        standard_response.status_code.end..standard_response.status.start,
+        standard_response.status.end..standard_response.status_with_newline().end,
+    ];
+    let redacted_input = redact_string(input, &keep_ranges);
+    let redacted_response = redacted::Response::from_str(&redacted_input)
+        .expect("Should parse response with hidden status code and reason");
+
+    assert_eq!(redacted_response.status_code, standard_response.status_code);
+    assert_eq!(redacted_response.status, standard_response.status);
+    assert_eq!(
+        &redacted_input[redacted_response.status_code.clone()],
+        "\0\0\0"
+    );
+}
+
+#[test]
+fn test_multipart_request_parts_are_addressable() {
+    shared::init_test_logging();
+
+    let input = "POST /upload HTTP/1.1\r\nContent-Type: multipart/form-data; boundary=XyZ-42\r\n\r\n--XyZ-42\r\nContent-Disposition: form-data; name=\"note\"\r\n\r\nhello world\r\n--XyZ-42\r\nContent-Disposition: form-data; name=\"file\"; filename=\"a.txt\"\r\nContent-Type: text/plain\r\n\r\nline one\r\nline two\r\n--XyZ-42--\r\n";
+
+    let request = standard::Request::from_str(input).expect("Should parse multipart request");
+    let value_of = |keypath: &str| match request.body.get(keypath) {
+        Some(standard::Body::KeyValue { value, .. }) => &input[value.clone()],
+        Some(standard::Body::Value(range)) => &input[range.clone()],
+        None => panic!("missing {keypath}"),
+    };
+    assert_eq!(
+        value_of(".parts[0].headers.content-disposition"),
+        "form-data; name=\"note\""
+    );
+    assert_eq!(value_of(".parts[0].body"), "hello world");
+    assert_eq!(value_of(".parts[1].headers.content-type"), "text/plain");
+    assert_eq!(value_of(".parts[1].body"), "line one\r\nline two");
+    assert!(!request.body.contains_key(".parts[2]"));
+
+    let part_body = match request.body.get(".parts[1].body") {
+        Some(standard::Body::Value(range)) => range.clone(),
+        other => panic!("unexpected part body {other:?}"),
+    };
+    let note_header = match request.body.get(".parts[0].headers.content-disposition") {
+        Some(standard::Body::KeyValue { value, .. }) => value.clone(),
+        other => panic!("unexpected part header {other:?}"),
+    };
+    let redacted_input = redact_string(
+        input,
+        &[
+            0..note_header.start,
+            note_header.end..part_body.start,
+            part_body.end..input.len(),
+        ],
+    );
+    let redacted_request = redacted::Request::from_str(&redacted_input)
+        .expect("Should parse multipart request with hidden part values");
+    assert_eq!(
+        redacted_request.body.get(".parts[1].body"),
+        Some(&redacted::Body::Value(part_body))
+    );
+    match redacted_request
+        .body
+        .get(".parts[0].headers.content-disposition")
+    {
+        Some(redacted::Body::KeyValue { value: None, .. }) => {}
+        other => panic!("part header value should be hidden, got {other:?}"),
+    }
+    assert!(
+        redacted_request
+            .body
+            .contains_key(".parts[1].headers.content-type")
+    );
+}
+
+#[test]
+fn test_compare_reserialized_json_maps_fields_to_transcript() {
+    shared::init_test_logging();
+
+    let input = "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\r\n{\"username\":\"alice\",\"balance\":100,\"tags\":[],\"active\":true}";
+    let response = standard::Response::from_str(input).expect("Should parse response");
+
+    let reserialized = "{\n  \"active\": true,\n  \"balance\": 100.0,\n  \"tags\": [ ],\n  \"username\": \"alice\"\n}";
+    let comparison = crate::compare_reserialized_json(input, &response.body, reserialized)
+        .expect("Should compare re-serialized JSON");
+    assert!(comparison.is_match(), "{:?}", comparison.mismatches);
+    let username = comparison
+        .matched
+        .iter()
+        .find(|field| field.keypath == ".username")
+        .expect("username should be matched");
+    assert_eq!(&input[username.transcript_range.clone()], "alice");
+
+    let tampered = r#"{"username":"bob","tags":[],"active":true,"extra":null}"#;
+    let comparison = crate::compare_reserialized_json(input, &response.body, tampered)
+        .expect("Should compare tampered JSON");
+    assert_eq!(
+        comparison.mismatches,
+        vec![
+            crate::FieldMismatch::MissingFromReserialized {
+                keypath: ".balance".to_string(),
+                transcript_range: comparison_range(input, "100"),
+            },
+            crate::FieldMismatch::MissingFromTranscript {
+                keypath: ".extra".to_string(),
+            },
+            crate::FieldMismatch::ValueDiffers {
+                keypath: ".username".to_string(),
+                transcript: crate::CanonicalValue::String("alice".to_string()),
+                reserialized: crate::CanonicalValue::String("bob".to_string()),
+                transcript_range: comparison_range(input, "alice"),
+            },
+        ]
+    );
+}
+
+fn comparison_range(input: &str, needle: &str) -> std::ops::Range<usize> {
+    let start = input.find(needle).expect("needle should be in input");
+    start..start + needle.len()
+}
+
+#[test]
+fn test_redacted_view_from_standard_parse_matches_grammar() {
+    shared::init_test_logging();
+
+    fn assert_same_view(input: &str, keep_ranges: &[Range<usize>]) {
+        let redacted_input = redact_string(input, keep_ranges);
+        let parsed =
+            redacted::Response::from_str(&redacted_input).expect("Redacted response should parse");
+        let projected = redacted::Response::from_standard(
+            input.as_bytes(),
+            &standard::Response::from_str(input).expect("Response should parse"),
+            keep_ranges,
+        );
+        assert_eq!(projected.status_code, parsed.status_code);
+        assert_eq!(projected.headers, parsed.headers);
+        assert_eq!(projected.body, parsed.body);
+    }
+
+    let input = "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nX-Api-Token: abc123\r\n\r\n{\"username\":\"alice\",\"data\":{\"users\":[{\"id\":1},{\"id\":2,\"name\":\"bob\"}]}}";
+    let response = standard::Response::from_str(input).expect("Response should parse");
+    let key_value = |keypath: &str| match response.body.get(keypath) {
+        Some(standard::Body::KeyValue { key, value }) => (key.clone(), value.clone()),
+        other => panic!("{keypath} should be a KeyValue, got {other:?}"),
+    };
+    let token = response
+        .headers
+        .get("x-api-token")
+        .and_then(|values| values.first())
+        .expect("X-Api-Token header should exist");
+    let content_type = response
+        .headers
+        .get("content-type")
+        .and_then(|values| values.first())
+        .expect("Content-Type header should exist");
+    let head = response.protocol_version.start..response.status_with_newline().end;
+
+    let (username_key, username) = key_value(".username");
+    let (name_key, _) = key_value(".data.users[1].name");
+    let users_1 = match response.body.get(".data.users[1]") {
+        Some(standard::Body::Value(range)) => range.clone(),
+        other => panic!("users[1] should be a Value, got {other:?}"),
+    };
+    assert_same_view(
+        input,
+        &[
+            head.clone(),
+            content_type.raw.clone(),
+            token.name.end..token.value.end + 1,
+            username_key.with_quotes_and_colon(),
+            username.with_quotes(),
+            name_key.with_quotes_and_colon(),
+        ],
+    );
+    assert_same_view(
+        input,
+        &[
+            head.clone(),
+            content_type.name.with_separator(),
+            username.with_quotes(),
+            key_value(".data.users[0].id").1,
+            users_1,
+        ],
+    );
+
+    // Revealing a string without its quotes leaves bytes the grammar cannot
+    // place; the view from the original parse just leaves the field out.
+    let keep_ranges = [head, username.start + 1..username.end - 1];
+    assert!(redacted::Response::from_str(&redact_string(input, &keep_ranges)).is_err());
+    let projected = redacted::Response::from_standard(input.as_bytes(), &response, &keep_ranges);
+    assert!(projected.headers.is_empty());
+    assert!(projected.body.is_empty());
+
+    let input = "POST /upload HTTP/1.1\r\nContent-Type: multipart/form-data; boundary=XyZ-42\r\n\r\n--XyZ-42\r\nContent-Disposition: form-data; name=\"note\"\r\n\r\nhello world\r\n--XyZ-42--\r\n";
+    let request = standard::Request::from_str(input).expect("Request should parse");
+    let note_header = match request.body.get(".parts[0].headers.content-disposition") {
+        Some(standard::Body::KeyValue { value, .. }) => value.clone(),
+        other => panic!("unexpected part header {other:?}"),
+    };
+    let keep_ranges = [0..note_header.start, note_header.end..input.len()];
+    let parsed = redacted::Request::from_str(&redact_string(input, &keep_ranges))
+        .expect("Redacted request should parse");
+    let projected = redacted::Request::from_standard(input.as_bytes(), &request, &keep_ranges);
+    assert_eq!(projected.url, parsed.url);
+    assert_eq!(projected.headers, parsed.headers);
+    assert_eq!(projected.body, parsed.body);
+}
+
+#[test]
+fn test_strings_cut_by_the_mask_are_partial_values() {
+    shared::init_test_logging();
+
+    fn assert_view(input: &str, keep_ranges: &[Range<usize>]) -> redacted::Response {
+        let redacted_input = redact_string(input, keep_ranges);
+        let parsed = redacted::Response::from_str(&redacted_input)
+            .expect("Should parse a mask that cuts through a string");
+        let projected = redacted::Response::from_standard(
+            input.as_bytes(),
+            &standard::Response::from_str(input).expect("Response should parse"),
+            keep_ranges,
+        );
+        assert_eq!(projected.body, parsed.body);
+        parsed
+    }
+
+    let input =
+        "HTTP/1.1 200 OK\r\n\r\n{\"balance\":100,\"username\":\"alice\",\"currency\":\"PLN\"}";
+    let response = standard::Response::from_str(input).expect("Response should parse");
+    let head = response.protocol_version.start..response.status_with_newline().end;
+    let key_value = |keypath: &str| match response.body.get(keypath) {
+        Some(standard::Body::KeyValue { key, value }) => (key.clone(), value.clone()),
+        other => panic!("{keypath} should be a KeyValue, got {other:?}"),
+    };
+
+    // A value padded to 12 bytes, as the prover pads commitments, runs into
+    // the next key and reveals only its opening quote and a prefix.
+    let (_, balance) = key_value(".balance");
+    let (username_key, _) = key_value(".username");
+    let (currency_key, currency) = key_value(".currency");
+    let padded = balance.start..balance.start + 12;
+    let view = assert_view(
+        input,
+        &[
+            head.clone(),
+            padded,
+            currency_key.with_quotes_and_colon(),
+            currency.with_quotes(),
+        ],
+    );
+    assert_eq!(
+        view.body.get(".?[0]"),
+        Some(&redacted::Body::Value(balance))
+    );
+    let next_revealed = currency_key.start - 1;
+    assert_eq!(
+        view.body.get(".?[1]"),
+        Some(&redacted::Body::PartialValue {
+            revealed: username_key.start..username_key.start + 7,
+            total_hint: Some(next_revealed - username_key.start - 1),
+        })
+    );
+    assert!(matches!(
+        view.body.get(".currency"),
+        Some(redacted::Body::KeyValue { value: Some(_), .. })
+    ));
+
+    // A revealed key keeps its name over a value cut after its prefix, and
+    // nothing revealed after it leaves the length open.
+    let (username_key, username) = key_value(".username");
+    let view = assert_view(
+        input,
+        &[head.clone(), username_key.start - 1..username.start + 3],
+    );
+    assert_eq!(view.body.len(), 1);
+    let Some(redacted::Body::PartialValue {
+        revealed,
+        total_hint: None,
+    }) = view.body.get(".username")
+    else {
+        panic!("username should be a partial value, got {:?}", view.body);
+    };
+    assert_eq!(&input[revealed.clone()], "ali");
+    assert_eq!(
+        view.annotations(input.as_bytes())
+            .iter()
+            .find(|annotation| annotation.label == ".username")
+            .map(|annotation| annotation.range.clone()),
+        Some(revealed.clone())
+    );
+
+    // An opening quote with nothing after it is an empty prefix.
+    let view = assert_view(input, &[head, username.start - 1..username.start]);
+    assert_eq!(
+        view.body.get(".?[0]"),
+        Some(&redacted::Body::PartialValue {
+            revealed: username.start..username.start,
+            total_hint: None,
+        })
+    );
+}
+
+#[test]
+fn test_content_length_is_checked_against_the_body() {
+    let input = "POST /api HTTP/1.1\r\nContent-Length: 7\r\n\r\n{\"a\":1}";
+    let request = standard::Request::from_str(input).expect("Request should parse");
+    assert_eq!(
+        request
+            .declared_content_length(input)
+            .expect("Content-Length should parse"),
+        Some(7)
+    );
+    request
+        .check_content_length(input)
+        .expect("Content-Length should match the body");
+
+    let input = "HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\n{\"a\":1}";
+    let response = standard::Response::from_str(input).expect("Response should parse");
+    let body_start = message_body_start(input.as_bytes()).expect("Head should end before the body");
+    assert!(matches!(
+        response.check_content_length(input),
+        Err(ParseError::ContentLengthMismatch { declared: 5, body }) if body == (body_start..input.len())
+    ));
+
+    let input = "HTTP/1.1 200 OK\r\nContent-Length: 7\r\nContent-Length: 7, 8\r\n\r\n{\"a\":1}";
+    let response = standard::Response::from_str(input).expect("Response should parse");
+    assert!(matches!(
+        response.declared_content_length(input),
+        Err(ParseError::InvalidContentLength(_))
+    ));
+
+    let input = "HTTP/1.1 200 OK\r\nContent-Length: +7\r\n\r\n{\"a\":1}";
+    let response = standard::Response::from_str(input).expect("Response should parse");
+    assert!(response.check_content_length(input).is_err());
+
+    let input = "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\nContent-Length: 7\r\n\r\n7\r\n{\"a\":1}\r\n0\r\n\r\n";
+    let response = standard::Response::from_str(input).expect("Response should parse");
+    assert!(response.check_content_length(input).is_err());
+
+    let input = "GET / HTTP/1.1\r\nHost: example.com\r\n\r\n";
+    let request = standard::Request::from_str(input).expect("Request should parse");
+    assert_eq!(
+        request
+            .declared_content_length(input)
+            .expect("Content-Length should parse"),
+        None
+    );
+    request
+        .check_content_length(input)
+        .expect("Content-Length should match the body");
+}
+
+#[test]
+fn test_binary_and_non_json_bodies_are_one_value() {
+    let head = b"HTTP/1.1 200 OK\r\nContent-Type: image/png\r\nX-Name: caf\xc3\xa9\r\n\r\n";
+    let input = [head.as_slice(), &[0x89, b'P', b'N', b'G', 0xff, 0x00]].concat();
+
+    assert_eq!(opaque_body_start(&input), Some(head.len()));
+    let response = standard::Response::from_bytes(&input).expect("Response should parse");
+    assert_eq!(
+        response.body.get(""),
+        Some(&standard::Body::Value(head.len()..input.len()))
+    );
+    assert_eq!(response.headers["x-name"][0].value.len(), 5);
+    let redacted_response =
+        redacted::Response::from_bytes_lossy(&input).expect("Redacted response should parse");
+    assert_eq!(
+        redacted_response.body.get(""),
+        Some(&redacted::Body::Value(head.len()..input.len()))
+    );
+
+    let text = "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\n\r\nhello";
+    assert!(matches!(
+        standard::Response::from_str(text),
+        Err(ParseError::InvalidSyntax(_))
+    ));
+    let response = standard::Response::from_bytes(text.as_bytes()).expect("Response should parse");
+    assert_eq!(response.body.len(), 1);
+
+    let binary_json = [
+        b"POST /api HTTP/1.1\r\nContent-Type: application/json\r\n\r\n".as_slice(),
+        &[0xc3, 0x28],
+    ]
+    .concat();
+    assert!(standard::Request::from_bytes(&binary_json).is_ok());
+    let latin1 = b"GET / HTTP/1.1\r\nX-Name: caf\xe9\r\n\r\n";
+    let request = standard::Request::from_bytes(latin1).expect("Request should parse");
+    assert_eq!(request.headers["x-name"][0].value.len(), 4);
+
+    let hidden_type = "HTTP/1.1 200 OK\r\nContent-Type: \0\0\0\0\r\n\r\n{\"a\":1}";
+    assert_eq!(opaque_body_start(hidden_type.as_bytes()), None);
+    let json =
+        "HTTP/1.1 200 OK\r\nContent-Type: application/problem+json; charset=utf-8\r\n\r\n{\"a\":1}";
+    assert_eq!(opaque_body_start(json.as_bytes()), None);
+    assert!(
+        standard::Response::from_bytes(json.as_bytes())
+            .expect("Response should parse")
+            .body
+            .contains_key(".a")
+    );
+}
+
+proptest! {
+    #[test]
+    fn test_mask_invalid_utf8_preserves_byte_ranges(
+        segments in proptest::collection::vec(
+            (any::<String>(), proptest::collection::vec(any::<u8>(), 0..4)),
+            0..8,
+        ),
+    ) {
+        let input = segments
+            .iter()
+            .flat_map(|(text, filler)| text.bytes().chain(filler.iter().copied()))
+            .collect::<Vec<_>>();
+        let masked = mask_invalid_utf8(&input);
+
+        prop_assert_eq!(masked.len(), input.len());
+        prop_assert!(
+            masked
+                .bytes()
+                .zip(&input)
+                .all(|(masked, original)| masked == *original || masked == 0)
+        );
+        if let Ok(valid) = std::str::from_utf8(&input) {
+            prop_assert_eq!(masked.as_ref(), valid);
+        }
+    }
+}
+
+#[test]
+fn test_body_fields_are_visited_in_order_with_early_exit() {
+    let input = "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\r\n{\"a\":{\"b\":[1,\"x\"]},\"c\":null,\"d\":true}";
+    let mut visited = Vec::new();
+    standard::Response::for_each_body_field(input, |keypath, _, kind| {
+        visited.push((keypath.to_string(), kind));
+        ControlFlow::Continue(())
+    })
+    .expect("Body should parse");
+    let keypaths = visited
+        .iter()
+        .map(|(keypath, _)| keypath.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        keypaths,
+        ["", ".a", ".a.b", ".a.b[0]", ".a.b[1]", ".c", ".d"]
+    );
+    assert_eq!(visited[3].1, standard::ValueKind::Number);
+    assert_eq!(visited[4].1, standard::ValueKind::String);
+    assert_eq!(visited[5].1, standard::ValueKind::Null);
+
+    let response = standard::Response::from_str(input).expect("Response should parse");
+    assert_eq!(response.body.len(), visited.len());
+
+    let mut found = None;
+    let mut calls = 0;
+    standard::Response::for_each_body_field(input, |keypath, field, _| {
+        calls += 1;
+        if keypath == ".a.b[0]" {
+            found = Some(field.clone());
+            return ControlFlow::Break(());
+        }
+        ControlFlow::Continue(())
+    })
+    .expect("Body should parse");
+    assert_eq!(calls, 4);
+    assert_eq!(found.as_ref(), response.body.get(".a.b[0]"));
+
+    let mut roots = 0;
+    standard::for_each_json_field("[{\"k\":1}]", |_, _, _| {
+        roots += 1;
+        ControlFlow::Break(())
+    })
+    .expect("Body should parse");
+    assert_eq!(roots, 1);
+}
+
+#[test]
+fn test_pipelined_messages_are_split_at_their_declared_lengths() {
+    let first_request = "GET /api/balance/alice HTTP/1.1\r\nHost: localhost\r\n\r\n";
+    let second_request = "POST /api/balance/bob HTTP/1.1\r\nContent-Length: 2\r\n\r\n{}";
+    let sent = format!("{first_request}{second_request}");
+    assert_eq!(last_request_start(sent.as_bytes()), first_request.len());
+    let last = &sent.as_bytes()[last_request_start(sent.as_bytes())..];
+    redacted::Request::from_bytes(last).expect("Last request should parse on its own");
+
+    let first_response = "HTTP/1.1 200 OK\r\ncontent-length: 17\r\n\r\n{\"username\":\"ab\"}";
+    let second_response = "HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\n{}";
+    let received = format!("{first_response}{second_response}");
+    assert_eq!(
+        last_response_start(received.as_bytes()),
+        first_response.len()
+    );
+    let third = format!("{received}{second_response}");
+    assert_eq!(
+        last_response_start(third.as_bytes()),
+        first_response.len() + second_response.len()
+    );
+
+    // A single message, or one whose length cannot be told, starts at zero.
+    assert_eq!(last_request_start(first_request.as_bytes()), 0);
+    assert_eq!(last_response_start(first_response.as_bytes()), 0);
+    let unframed = "HTTP/1.1 200 OK\r\n\r\nbody runs to the close";
+    assert_eq!(last_response_start(unframed.as_bytes()), 0);
+    let redacted_length = format!("{}{second_response}", first_response.replace("17", "\0\0"));
+    assert_eq!(last_response_start(redacted_length.as_bytes()), 0);
+    let chunked =
+        format!("HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n0\r\n\r\n{second_response}");
+    assert_eq!(last_response_start(chunked.as_bytes()), 0);
+    let conflicting = format!(
+        "POST / HTTP/1.1\r\nContent-Length: 2\r\nContent-Length: 3\r\n\r\n{{}}{first_request}"
+    );
+    assert_eq!(last_request_start(conflicting.as_bytes()), 0);
+    // A declared length past the input leaves the message last.
+    let truncated = "POST / HTTP/1.1\r\nContent-Length: 99\r\n\r\n{}";
+    assert_eq!(last_request_start(truncated.as_bytes()), 0);
+}
+
+proptest! {
+    #[test]
+    fn test_last_message_start_is_within_the_input(
+        input in proptest::collection::vec(any::<u8>(), 0..256),
+        lengths in proptest::collection::vec(0usize..64, 1..5),
+    ) {
+        prop_assert!(last_request_start(&input) <= input.len());
+        prop_assert!(last_response_start(&input) <= input.len());
+
+        let messages = lengths
+            .iter()
+            .map(|&len| {
+                format!(
+                    "PUT /item HTTP/1.1\r\nContent-Length: {len}\r\n\r\n{}",
+                    "x".repeat(len)
+                )
+            })
+            .collect::<Vec<_>>();
+        let pipelined = messages.concat();
+        let last_len = messages.last().map_or(0, String::len);
+        prop_assert_eq!(last_request_start(pipelined.as_bytes()), pipelined.len() - last_len);
+    }
+}
@@ -2,7 +2,7 @@ use std::{collections::HashMap, ops::Range};
 
 use pest::{RuleType, iterators::Pair};
 
-use crate::error::Result;
+use crate::{error::Result, path::keypath_matches};
 
 pub trait RangeExtractor {
     fn extract_range(&self) -> Range<usize>;
@@ -27,4 +27,24 @@ pub trait HttpMessage {
     fn headers(&self) -> &HashMap<String, Vec<Self::Header>>;
 
     fn body(&self) -> &HashMap<String, Self::Body>;
+
+    /// Body fields whose keypath matches `pattern`, sorted by keypath. See
+    /// [`keypath_matches`](crate::keypath_matches) for the wildcards.
+    fn matches(&self, pattern: &str) -> Vec<(String, &Self::Body)> {
+        let mut matches = self
+            .body()
+            .iter()
+            .filter(|(keypath, _)| keypath_matches(pattern, keypath))
+            .map(|(keypath, body)| (keypath.clone(), body))
+            .collect::<Vec<_>>();
+        matches.sort_by(|(a, _), (b, _)| a.cmp(b));
+        matches
+    }
+
+    /// Whether any body field matches `pattern`.
+    fn contains(&self, pattern: &str) -> bool {
+        self.body()
+            .keys()
+            .any(|keypath| keypath_matches(pattern, keypath))
+    }
 }
@@ -1,44 +1,83 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+};
 
 use async_compat::CompatExt;
 use axum::{
-    Json, Router,
+    Form, Json, Router,
     extract::{Path, State},
-    http::StatusCode,
+    http::{HeaderMap, StatusCode, header},
     response::{IntoResponse, Response},
-    routing::get,
+    routing::{get, post},
 };
 use serde::{Deserialize, Serialize};
 use smol::lock::RwLock;
 use thiserror::Error;
 
+/// Every known user authenticates to the mock token endpoint with this secret.
+pub const MOCK_CLIENT_SECRET: &str = "mock-client-secret";
+pub const TOKEN_LIFETIME_SECS: u64 = 3600;
+
 #[derive(Error, Debug)]
 pub enum ApiError {
     #[error("User '{0}' not found")]
     UserNotFound(String),
+
+    #[error("Unsupported grant type '{0}'")]
+    UnsupportedGrantType(String),
+
+    #[error("Invalid client credentials")]
+    InvalidClient,
+
+    #[error("Missing or invalid bearer token")]
+    Unauthorized,
 }
 
 impl IntoResponse for ApiError {
     fn into_response(self) -> Response {
-        let (status, message) = match self {
+        match self {
             ApiError::UserNotFound(username) => (
                 StatusCode::NOT_FOUND,
                 format!("User '{}' not found", username),
-            ),
-        };
-        (status, message).into_response()
+            )
+                .into_response(),
+            ApiError::UnsupportedGrantType(_) => (
+                StatusCode::BAD_REQUEST,
+                Json(OAuthErrorResponse::new("unsupported_grant_type")),
+            )
+                .into_response(),
+            ApiError::InvalidClient => (
+                StatusCode::UNAUTHORIZED,
+                Json(OAuthErrorResponse::new("invalid_client")),
+            )
+                .into_response(),
+            ApiError::Unauthorized => (
+                StatusCode::UNAUTHORIZED,
+                [(header::WWW_AUTHENTICATE, "Bearer")],
+                self.to_string(),
+            )
+                .into_response(),
+        }
     }
 }
 
 #[derive(Clone)]
 pub struct AppState {
     balances: Arc<RwLock<HashMap<String, u64>>>,
+    tokens: Arc<RwLock<HashMap<String, String>>>,
+    issued_tokens: Arc<AtomicU64>,
 }
 
 impl AppState {
     pub fn new(balances: HashMap<String, u64>) -> Self {
         Self {
             balances: Arc::new(RwLock::new(balances)),
+            tokens: Arc::default(),
+            issued_tokens: Arc::default(),
         }
     }
 }
@@ -61,10 +100,46 @@ impl BalanceResponse {
     }
 }
 
+/// OAuth2 client credentials grant, where `client_id` is the username.
+#[derive(Serialize, Deserialize)]
+pub struct TokenRequest {
+    pub grant_type: String,
+    pub client_id: String,
+    pub client_secret: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct TokenResponse {
+    pub access_token: String,
+    pub token_type: String,
+    pub expires_in: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct OAuthErrorResponse {
+    pub error: String,
+}
+
+impl OAuthErrorResponse {
+    fn new(error: &str) -> Self {
+        Self {
+            error: error.to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ProfileResponse {
+    username: String,
+    balance: u64,
+}
+
 pub fn get_app(balances: HashMap<String, u64>) -> Router {
     let state = AppState::new(balances);
     Router::new()
         .route("/api/balance/{username}", get(get_balance))
+        .route("/oauth/token", post(issue_token))
+        .route("/api/me", get(get_profile))
         .with_state(state)
 }
 
@@ -80,6 +155,64 @@ async fn get_balance(
     }
 }
 
+async fn issue_token(
+    State(state): State<AppState>,
+    Form(request): Form<TokenRequest>,
+) -> Result<Json<TokenResponse>, ApiError> {
+    if request.grant_type != "client_credentials" {
+        return Err(ApiError::UnsupportedGrantType(request.grant_type));
+    }
+    let known_user = state
+        .balances
+        .read()
+        .compat()
+        .await
+        .contains_key(&request.client_id);
+    if !known_user || request.client_secret != MOCK_CLIENT_SECRET {
+        return Err(ApiError::InvalidClient);
+    }
+
+    let serial = state.issued_tokens.fetch_add(1, Ordering::Relaxed);
+    let access_token = format!("mock-{serial:08x}-{}", request.client_id);
+    state
+        .tokens
+        .write()
+        .compat()
+        .await
+        .insert(access_token.clone(), request.client_id);
+
+    Ok(Json(TokenResponse {
+        access_token,
+        token_type: "Bearer".to_string(),
+        expires_in: TOKEN_LIFETIME_SECS,
+    }))
+}
+
+async fn get_profile(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<ProfileResponse>, ApiError> {
+    let token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or(ApiError::Unauthorized)?;
+    let username = state
+        .tokens
+        .read()
+        .compat()
+        .await
+        .get(token)
+        .cloned()
+        .ok_or(ApiError::Unauthorized)?;
+
+    let balances = state.balances.read().compat().await;
+    match balances.get(&username) {
+        Some(&balance) => Ok(Json(ProfileResponse { username, balance })),
+        None => Err(ApiError::UserNotFound(username)),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use axum::{body::Body, http::Request};
@@ -136,4 +269,73 @@ mod tests {
             assert_eq!(response.status(), StatusCode::NOT_FOUND);
         });
     }
+
+    #[test]
+    fn test_oauth_token_grants_access_to_profile() {
+        smol::block_on(async {
+            let mut balances = HashMap::new();
+            balances.insert("alice".to_string(), 100);
+            let app = get_app(balances);
+
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .method("POST")
+                        .uri("/oauth/token")
+                        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+                        .body(Body::from(format!(
+                            "grant_type=client_credentials&client_id=alice&client_secret={MOCK_CLIENT_SECRET}"
+                        )))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            let token: TokenResponse = serde_json::from_slice(&body).unwrap();
+            assert_eq!(token.token_type, "Bearer");
+
+            let response = app
+                .oneshot(
+                    Request::builder()
+                        .uri("/api/me")
+                        .header(
+                            header::AUTHORIZATION,
+                            format!("Bearer {}", token.access_token),
+                        )
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            let profile: ProfileResponse = serde_json::from_slice(&body).unwrap();
+            assert_eq!(profile.username, "alice");
+            assert_eq!(profile.balance, 100);
+        });
+    }
+
+    #[test]
+    fn test_profile_requires_valid_bearer_token() {
+        smol::block_on(async {
+            let app = get_app(HashMap::new());
+
+            for authorization in [None, Some("Bearer unknown-token")] {
+                let mut request = Request::builder().uri("/api/me");
+                if let Some(value) = authorization {
+                    request = request.header(header::AUTHORIZATION, value);
+                }
+                let response = app
+                    .clone()
+                    .oneshot(request.body(Body::empty()).unwrap())
+                    .await
+                    .unwrap();
+
+                assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+                assert_eq!(response.headers()[header::WWW_AUTHENTICATE], "Bearer");
+            }
+        });
+    }
 }
@@ -0,0 +1,142 @@
+use std::{
+    io,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+use futures::io::{AsyncRead, AsyncWrite};
+
+pub(crate) type CapturedBytes = Arc<Mutex<Vec<u8>>>;
+
+pub(crate) struct CapturingStream<S> {
+    inner: S,
+    captured_read: CapturedBytes,
+    captured_write: CapturedBytes,
+}
+
+impl<S> CapturingStream<S> {
+    pub(crate) fn new(inner: S) -> (Self, CapturedBytes, CapturedBytes) {
+        let captured_read = Arc::new(Mutex::new(Vec::new()));
+        let captured_write = Arc::new(Mutex::new(Vec::new()));
+        (
+            Self {
+                inner,
+                captured_read: captured_read.clone(),
+                captured_write: captured_write.clone(),
+            },
+            captured_read,
+            captured_write,
+        )
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for CapturingStream<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let result = Pin::new(&mut self.inner).poll_read(cx, buf);
+
+        if let Poll::Ready(Ok(n)) = result
+            && let Some(read) = buf.get(..n)
+            && !read.is_empty()
+            && let Ok(mut captured) = self.captured_read.lock()
+        {
+            captured.extend_from_slice(read);
+        }
+
+        result
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for CapturingStream<S> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let result = Pin::new(&mut self.inner).poll_write(cx, buf);
+
+        if let Poll::Ready(Ok(n)) = result
+            && let Some(written) = buf.get(..n)
+            && !written.is_empty()
+            && let Ok(mut captured) = self.captured_write.lock()
+        {
+            captured.extend_from_slice(written);
+        }
+
+        result
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_close(cx)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CapturedTraffic {
+    pub raw_request: Vec<u8>,
+    pub raw_response: Vec<u8>,
+    /// Whether the TLS handshake resumed an earlier session.
+    pub resumed: bool,
+}
+
+pub(crate) fn clone_captured_bytes(captured: &CapturedBytes) -> Option<Vec<u8>> {
+    captured.lock().map(|bytes| bytes.clone()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{AsyncReadExt, AsyncWriteExt};
+    use smol::net::unix::UnixStream;
+
+    use super::{CapturingStream, clone_captured_bytes};
+
+    #[test]
+    fn test_capturing_stream_records_both_directions() {
+        smol::block_on(async {
+            let (inner, mut peer) = UnixStream::pair().expect("Socket pair should be created");
+            let (mut stream, captured_read, captured_write) = CapturingStream::new(inner);
+
+            let request = b"GET /api/balance/alice HTTP/1.1\r\n\r\n";
+            let response = b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n";
+
+            peer.write_all(request)
+                .await
+                .expect("Peer should write the request");
+            let mut received = vec![0; request.len()];
+            stream
+                .read_exact(&mut received)
+                .await
+                .expect("Stream should read the request");
+
+            stream
+                .write_all(response)
+                .await
+                .expect("Stream should write the response");
+            stream.flush().await.expect("Stream should flush");
+            let mut sent = vec![0; response.len()];
+            peer.read_exact(&mut sent)
+                .await
+                .expect("Peer should read the response");
+
+            assert_eq!(received, request);
+            assert_eq!(sent, response);
+            assert_eq!(
+                clone_captured_bytes(&captured_read).expect("Read capture should not be poisoned"),
+                request
+            );
+            assert_eq!(
+                clone_captured_bytes(&captured_write)
+                    .expect("Write capture should not be poisoned"),
+                response
+            );
+        });
+    }
+}
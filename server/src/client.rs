@@ -1,10 +1,4 @@
-use std::{
-    error::Error,
-    io,
-    pin::Pin,
-    sync::{Arc, Mutex},
-    task::{Context, Poll},
-};
+use std::{error::Error, sync::Arc};
 
 use async_compat::Compat;
 use axum::body::Bytes;
@@ -13,78 +7,13 @@ use futures_rustls::TlsConnector;
 use http_body_util::{BodyExt, Full};
 use hyper::Uri;
 use hyper_util::rt::TokioIo;
-use rustls::pki_types::ServerName;
+use rustls::{HandshakeKind, client::Resumption, pki_types::ServerName};
 use thiserror::Error;
 
-type CapturedBytes = Arc<Mutex<Vec<u8>>>;
+use crate::capture::{CapturedTraffic, CapturingStream, clone_captured_bytes};
 
-struct CapturingStream<S> {
-    inner: S,
-    captured_read: CapturedBytes,
-    captured_write: CapturedBytes,
-}
-
-impl<S> CapturingStream<S> {
-    fn new(inner: S) -> (Self, CapturedBytes, CapturedBytes) {
-        let captured_read = Arc::new(Mutex::new(Vec::new()));
-        let captured_write = Arc::new(Mutex::new(Vec::new()));
-        (
-            Self {
-                inner,
-                captured_read: captured_read.clone(),
-                captured_write: captured_write.clone(),
-            },
-            captured_read,
-            captured_write,
-        )
-    }
-}
-
-impl<S: AsyncRead + Unpin> AsyncRead for CapturingStream<S> {
-    fn poll_read(
-        mut self: Pin<&mut Self>,
-        cx: &mut Context<'_>,
-        buf: &mut [u8],
-    ) -> Poll<io::Result<usize>> {
-        let result = Pin::new(&mut self.inner).poll_read(cx, buf);
-
-        if let Poll::Ready(Ok(n)) = result
-            && n > 0
-            && let Ok(mut captured) = self.captured_read.lock()
-        {
-            captured.extend_from_slice(&buf[..n]);
-        }
-
-        result
-    }
-}
-
-impl<S: AsyncWrite + Unpin> AsyncWrite for CapturingStream<S> {
-    fn poll_write(
-        mut self: Pin<&mut Self>,
-        cx: &mut Context<'_>,
-        buf: &[u8],
-    ) -> Poll<io::Result<usize>> {
-        let result = Pin::new(&mut self.inner).poll_write(cx, buf);
-
-        if let Poll::Ready(Ok(n)) = result
-            && n > 0
-            && let Ok(mut captured) = self.captured_write.lock()
-        {
-            captured.extend_from_slice(&buf[..n]);
-        }
-
-        result
-    }
-
-    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
-        Pin::new(&mut self.inner).poll_flush(cx)
-    }
-
-    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
-        Pin::new(&mut self.inner).poll_close(cx)
-    }
-}
+/// Sessions a resuming client remembers per config.
+const RESUMPTION_CACHE_SIZE: usize = 32;
 
 #[derive(Error, Debug)]
 pub enum ClientError {
@@ -104,9 +33,20 @@ pub enum ClientError {
     CapturedTrafficLock(&'static str),
 }
 
-pub struct CapturedTraffic {
-    pub raw_request: Vec<u8>,
-    pub raw_response: Vec<u8>,
+/// A copy of `client_config` that resumes earlier sessions with the same
+/// server when `resume` is set, from a cache of its own, and always does a
+/// full handshake otherwise.
+pub fn with_resumption(
+    client_config: &rustls::ClientConfig,
+    resume: bool,
+) -> Arc<rustls::ClientConfig> {
+    let mut client_config = client_config.clone();
+    client_config.resumption = if resume {
+        Resumption::in_memory_sessions(RESUMPTION_CACHE_SIZE)
+    } else {
+        Resumption::disabled()
+    };
+    Arc::new(client_config)
 }
 
 pub async fn send_request<IO>(
@@ -120,6 +60,7 @@ where
     let server_name = ServerName::try_from("localhost")?;
     let tls_connector = TlsConnector::from(client_config);
     let stream = tls_connector.connect(server_name, cnx).await?;
+    let resumed = stream.get_ref().1.handshake_kind() == Some(HandshakeKind::Resumed);
 
     let (capturing_stream, captured_read_bytes, captured_write_bytes) =
         CapturingStream::new(stream);
@@ -144,8 +85,10 @@ where
     let (conn_result, response) = futures::join!(conn, request_task);
     response?;
 
-    let raw_request = clone_captured_bytes(&captured_write_bytes, "request")?;
-    let raw_response = clone_captured_bytes(&captured_read_bytes, "response")?;
+    let raw_request = clone_captured_bytes(&captured_write_bytes)
+        .ok_or(ClientError::CapturedTrafficLock("request"))?;
+    let raw_response = clone_captured_bytes(&captured_read_bytes)
+        .ok_or(ClientError::CapturedTrafficLock("response"))?;
 
     // BrokenPipe is expected when server closes connection first
     if let Err(e) = conn_result {
@@ -155,6 +98,7 @@ where
             return Ok(CapturedTraffic {
                 raw_request,
                 raw_response,
+                resumed,
             });
         }
         return Err(ClientError::Hyper(e));
@@ -163,15 +107,6 @@ where
     Ok(CapturedTraffic {
         raw_request,
         raw_response,
+        resumed,
     })
 }
-
-fn clone_captured_bytes(
-    captured: &CapturedBytes,
-    label: &'static str,
-) -> Result<Vec<u8>, ClientError> {
-    captured
-        .lock()
-        .map(|bytes| bytes.clone())
-        .map_err(|_| ClientError::CapturedTrafficLock(label))
-}
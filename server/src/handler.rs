@@ -6,9 +6,13 @@ use futures::io::{AsyncRead, AsyncWrite};
 use futures_rustls::TlsAcceptor;
 use hyper::{Request, body::Incoming};
 use hyper_util::rt::TokioIo;
+use rustls::HandshakeKind;
 use shared::SmolExecutor;
 use thiserror::Error;
 use tower::Service;
+use tracing::{Span, debug};
+
+use crate::capture::{CapturedTraffic, CapturingStream, clone_captured_bytes};
 
 #[derive(Error, Debug)]
 pub enum ConnectionError {
@@ -17,6 +21,20 @@ pub enum ConnectionError {
 
     #[error("Failed to serve connection: {0}")]
     ServeConnection(Box<dyn std::error::Error + Send + Sync>),
+
+    #[error("failed to acquire captured traffic lock for {0}")]
+    CapturedTrafficLock(&'static str),
+}
+
+/// A copy of `server_config` that issues stateless TLS 1.3 session tickets,
+/// so clients can resume sessions the way real origins let them. Without
+/// it, rustls resumes only from its in-memory session cache.
+pub fn with_session_tickets(
+    server_config: &rustls::ServerConfig,
+) -> Result<Arc<rustls::ServerConfig>, rustls::Error> {
+    let mut server_config = server_config.clone();
+    server_config.ticketer = rustls::crypto::aws_lc_rs::Ticketer::new()?;
+    Ok(Arc::new(server_config))
 }
 
 pub async fn handle_connection<IO>(
@@ -29,6 +47,45 @@ where
 {
     let tls_acceptor = TlsAcceptor::from(server_config);
     let stream = tls_acceptor.accept(cnx).await?;
+    if let Some(server_name) = stream.get_ref().1.server_name() {
+        shared::record_server_name(&Span::current(), server_name);
+    }
+    debug!(handshake = ?stream.get_ref().1.handshake_kind(), "TLS handshake complete");
+    serve_tls_stream(tower_service, stream).await
+}
+
+pub async fn handle_connection_with_capture<IO>(
+    tower_service: Router,
+    server_config: Arc<rustls::ServerConfig>,
+    cnx: IO,
+) -> Result<CapturedTraffic, ConnectionError>
+where
+    IO: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let tls_acceptor = TlsAcceptor::from(server_config);
+    let stream = tls_acceptor.accept(cnx).await?;
+    if let Some(server_name) = stream.get_ref().1.server_name() {
+        shared::record_server_name(&Span::current(), server_name);
+    }
+    let resumed = stream.get_ref().1.handshake_kind() == Some(HandshakeKind::Resumed);
+    let (capturing_stream, captured_read_bytes, captured_write_bytes) =
+        CapturingStream::new(stream);
+
+    serve_tls_stream(tower_service, capturing_stream).await?;
+
+    Ok(CapturedTraffic {
+        raw_request: clone_captured_bytes(&captured_read_bytes)
+            .ok_or(ConnectionError::CapturedTrafficLock("request"))?,
+        raw_response: clone_captured_bytes(&captured_write_bytes)
+            .ok_or(ConnectionError::CapturedTrafficLock("response"))?,
+        resumed,
+    })
+}
+
+async fn serve_tls_stream<S>(tower_service: Router, stream: S) -> Result<(), ConnectionError>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
     let stream = TokioIo::new(Compat::new(stream));
 
     let hyper_service = hyper::service::service_fn(move |request: Request<Incoming>| {
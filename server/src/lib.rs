@@ -1,9 +1,13 @@
 pub mod app;
+mod capture;
 pub mod client;
 pub mod handler;
 
-pub use client::{CapturedTraffic, ClientError, send_request};
-pub use handler::{ConnectionError, handle_connection};
+pub use capture::CapturedTraffic;
+pub use client::{ClientError, send_request, with_resumption};
+pub use handler::{
+    ConnectionError, handle_connection, handle_connection_with_capture, with_session_tickets,
+};
 pub use shared::SmolExecutor;
 
 #[cfg(test)]
@@ -15,7 +19,10 @@ mod tests {
     use shared::create_test_tls_config;
     use smol::net::unix::UnixStream;
 
-    use crate::{app::get_app, handle_connection, send_request};
+    use crate::{
+        app::get_app, handle_connection, handle_connection_with_capture, send_request,
+        with_resumption, with_session_tickets,
+    };
 
     #[test]
     fn test_https_get_balance_existing_user() {
@@ -26,8 +33,10 @@ mod tests {
             balances.insert("alice".to_string(), 100);
             let app = get_app(balances);
 
-            let test_tls_config = create_test_tls_config().unwrap();
-            let (client_cnx, server_cnx) = UnixStream::pair().unwrap();
+            let test_tls_config =
+                create_test_tls_config().expect("Test TLS config should be created");
+            let (client_cnx, server_cnx) =
+                UnixStream::pair().expect("Socket pair should be created");
 
             let server_task = handle_connection(app, test_tls_config.server_config, server_cnx);
 
@@ -97,8 +106,10 @@ mod tests {
             balances.insert("alice".to_string(), 100);
             let app = get_app(balances);
 
-            let test_tls_config = create_test_tls_config().unwrap();
-            let (client_cnx, server_cnx) = UnixStream::pair().unwrap();
+            let test_tls_config =
+                create_test_tls_config().expect("Test TLS config should be created");
+            let (client_cnx, server_cnx) =
+                UnixStream::pair().expect("Socket pair should be created");
 
             let server_task = handle_connection(app, test_tls_config.server_config, server_cnx);
 
@@ -110,8 +121,8 @@ mod tests {
 
             let (server_result, client_result) = futures::join!(server_task, client_task);
 
-            server_result.unwrap();
-            let traffic = client_result.unwrap();
+            server_result.expect("Server task should complete");
+            let traffic = client_result.expect("Client task should complete");
 
             let raw_request_str = String::from_utf8(traffic.raw_request.clone())
                 .expect("Request should be valid UTF-8");
@@ -209,4 +220,87 @@ mod tests {
             }
         });
     }
+
+    #[test]
+    fn test_server_capture_matches_client_wire_bytes() {
+        shared::init_test_logging();
+
+        smol::block_on(async {
+            let mut balances = HashMap::new();
+            balances.insert("alice".to_string(), 100);
+            let app = get_app(balances);
+
+            let test_tls_config =
+                create_test_tls_config().expect("Test TLS config should be created");
+            let (client_cnx, server_cnx) =
+                UnixStream::pair().expect("Socket pair should be created");
+
+            let server_task =
+                handle_connection_with_capture(app, test_tls_config.server_config, server_cnx);
+
+            let client_task = send_request(
+                Uri::from_static("/api/balance/alice"),
+                test_tls_config.client_config,
+                client_cnx,
+            );
+
+            let (server_result, client_result) = futures::join!(server_task, client_task);
+
+            let server_traffic = server_result.expect("Server task should complete");
+            let client_traffic = client_result.expect("Client task should complete");
+
+            assert_eq!(server_traffic.raw_request, client_traffic.raw_request);
+            assert_eq!(server_traffic.raw_response, client_traffic.raw_response);
+
+            let raw_request_str = String::from_utf8(server_traffic.raw_request)
+                .expect("Request should be valid UTF-8");
+            let parsed_request = parser::standard::Request::from_str(&raw_request_str)
+                .expect("Should parse request");
+            assert_eq!(
+                &raw_request_str[parsed_request.url.clone()],
+                "/api/balance/alice"
+            );
+        });
+    }
+
+    #[test]
+    fn test_session_tickets_resume_second_connection() {
+        shared::init_test_logging();
+
+        smol::block_on(async {
+            let mut balances = HashMap::new();
+            balances.insert("alice".to_string(), 100);
+
+            let test_tls_config =
+                create_test_tls_config().expect("Test TLS config should be created");
+            let server_config = with_session_tickets(&test_tls_config.server_config)
+                .expect("Session tickets should be enabled");
+
+            for (resume, expected) in [(true, [false, true]), (false, [false, false])] {
+                let client_config = with_resumption(&test_tls_config.client_config, resume);
+                for expected_resumed in expected {
+                    let (client_cnx, server_cnx) =
+                        UnixStream::pair().expect("Socket pair should be created");
+                    let server_task = handle_connection_with_capture(
+                        get_app(balances.clone()),
+                        server_config.clone(),
+                        server_cnx,
+                    );
+                    let client_task = send_request(
+                        Uri::from_static("/api/balance/alice"),
+                        client_config.clone(),
+                        client_cnx,
+                    );
+
+                    let (server_result, client_result) = futures::join!(server_task, client_task);
+                    let server_traffic = server_result.expect("Server task should complete");
+                    let client_traffic = client_result.expect("Client task should complete");
+
+                    assert_eq!(client_traffic.resumed, expected_resumed);
+                    assert_eq!(server_traffic.resumed, expected_resumed);
+                    assert_eq!(server_traffic.raw_request, client_traffic.raw_request);
+                }
+            }
+        });
+    }
 }
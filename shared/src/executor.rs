@@ -1,5 +1,9 @@
 use std::future::Future;
 
+use tracing::Instrument;
+
+/// Spawns hyper tasks on smol inside the caller's span, so connection tasks
+/// keep the fields of a [`crate::session_span`].
 #[derive(Clone, Debug, Default)]
 pub struct SmolExecutor {}
 
@@ -9,6 +13,6 @@ where
     F::Output: Send + 'static,
 {
     fn execute(&self, fut: F) {
-        smol::spawn(fut).detach();
+        smol::spawn(fut.in_current_span()).detach();
     }
 }
@@ -7,7 +7,10 @@ mod tls;
 
 pub use errors::{CertificateError, QuicConfigError, SharedError, TlsConfigError};
 pub use executor::SmolExecutor;
-pub use logging::{init_logging, init_test_logging};
+pub use logging::{
+    LOG_FORMAT_ENV, LogFormat, init_logging, init_logging_with_format, init_test_logging,
+    record_server_name, session_span,
+};
 pub use quic::{TestQuicConfig, get_or_create_test_quic_config};
 pub use testing::{TestTlsConfig, create_test_tls_config, get_or_create_test_tls_config};
 pub use tls::{SelfSignedCertificate, generate_self_signed_cert};
@@ -1,22 +1,54 @@
+use std::fmt::Display;
+
+use tracing::{Span, field, info_span};
 use tracing_subscriber::{EnvFilter, fmt::format::FmtSpan};
+use uuid::Uuid;
+
+/// Environment variable selecting the [`LogFormat`] used by [`init_logging`].
+pub const LOG_FORMAT_ENV: &str = "LOG_FORMAT";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    #[default]
+    Text,
+    /// One JSON object per event, including the fields of every enclosing span.
+    Json,
+}
+
+impl LogFormat {
+    /// `json` in [`LOG_FORMAT_ENV`] selects [`LogFormat::Json`], anything else text.
+    pub fn from_env() -> Self {
+        match std::env::var(LOG_FORMAT_ENV) {
+            Ok(format) if format.eq_ignore_ascii_case("json") => Self::Json,
+            _ => Self::Text,
+        }
+    }
+}
 
 pub fn init_logging(default_filter: &str) {
-    let _ = tracing_subscriber::fmt()
-        .with_env_filter(
-            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(default_filter)),
-        )
+    init_logging_with_format(default_filter, LogFormat::from_env());
+}
+
+pub fn init_logging_with_format(default_filter: &str, format: LogFormat) {
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(env_filter(default_filter))
         .with_target(true)
         .with_file(true)
         .with_line_number(true)
-        .with_span_events(FmtSpan::NONE)
-        .try_init();
+        .with_span_events(FmtSpan::NONE);
+    let _ = match format {
+        LogFormat::Text => builder.try_init(),
+        LogFormat::Json => builder
+            .json()
+            .with_current_span(false)
+            .with_span_list(true)
+            .try_init(),
+    };
 }
 
 pub fn init_test_logging() {
     let _ = tracing_subscriber::fmt()
-        .with_env_filter(
-            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")),
-        )
+        .with_env_filter(env_filter("info"))
         .with_target(true)
         .with_file(true)
         .with_line_number(true)
@@ -24,3 +56,25 @@ pub fn init_test_logging() {
         .with_test_writer()
         .try_init();
 }
+
+fn env_filter(default_filter: &str) -> EnvFilter {
+    EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(default_filter))
+}
+
+/// Span for one session with a fresh `session_id` and the remote `peer`.
+/// Events inside it, including those of tasks spawned with
+/// [`tracing::Instrument::in_current_span`], carry both fields.
+/// `server_name` is left empty until [`record_server_name`] fills it.
+pub fn session_span(peer: impl Display) -> Span {
+    info_span!(
+        "session",
+        session_id = %Uuid::new_v4(),
+        peer = %peer,
+        server_name = field::Empty,
+    )
+}
+
+/// Sets `server_name` on `span`; a no-op unless it is a [`session_span`].
+pub fn record_server_name(span: &Span, server_name: &str) {
+    span.record("server_name", server_name);
+}
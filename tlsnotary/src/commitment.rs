@@ -0,0 +1,143 @@
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+use tlsn::transcript::{Direction, PlaintextHash, TranscriptCommitment};
+
+use crate::error::Error;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommitmentLabel {
+    pub label: String,
+    pub direction: Direction,
+    pub range: Range<usize>,
+}
+
+impl CommitmentLabel {
+    fn matches(&self, hash: &PlaintextHash) -> bool {
+        hash.direction == self.direction
+            && hash.idx.min() == Some(self.range.start)
+            && hash.idx.end() == Some(self.range.end)
+    }
+}
+
+pub fn resolve_commitment_labels<'a>(
+    labels: &[CommitmentLabel],
+    transcript_commitments: &'a [TranscriptCommitment],
+) -> Result<Vec<(String, &'a PlaintextHash)>, Error> {
+    labels
+        .iter()
+        .map(|label| {
+            transcript_commitments
+                .iter()
+                .find_map(|commitment| match commitment {
+                    TranscriptCommitment::Hash(hash) if label.matches(hash) => Some(hash),
+                    _ => None,
+                })
+                .map(|hash| (label.label.clone(), hash))
+                .ok_or_else(|| {
+                    Error::InvalidTranscript(format!(
+                        "no {:?} commitment found for label '{}' at {:?}",
+                        label.direction, label.label, label.range
+                    ))
+                })
+        })
+        .collect()
+}
+
+/// Where a hash commitment sits in a session: its direction and its ordinal
+/// among that direction's hash commitments. Identifying commitments by
+/// domain as well as by hash keeps a request commitment from being presented
+/// as a response commitment over the same bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommitmentDomain {
+    pub direction: Direction,
+    pub ordinal: usize,
+}
+
+impl CommitmentDomain {
+    /// `None` when `hash` is not one of `transcript_commitments`.
+    pub fn of(
+        transcript_commitments: &[TranscriptCommitment],
+        hash: &PlaintextHash,
+    ) -> Option<Self> {
+        hash_commitments(transcript_commitments, hash.direction)
+            .position(|candidate| {
+                candidate.idx == hash.idx
+                    && candidate.hash.alg == hash.hash.alg
+                    && candidate.hash.value.as_bytes() == hash.hash.value.as_bytes()
+            })
+            .map(|ordinal| Self {
+                direction: hash.direction,
+                ordinal,
+            })
+    }
+
+    /// `{direction}:{ordinal}:{hex hash}`, e.g. `received:2:9f0c…`.
+    pub fn id(self, hash: &PlaintextHash) -> String {
+        format!(
+            "{}:{}:{}",
+            direction_tag(self.direction),
+            self.ordinal,
+            hex(hash.hash.value.as_bytes())
+        )
+    }
+
+    /// Splits an [`Self::id`] into its domain and hex hash.
+    pub fn parse_id(id: &str) -> Option<(Self, &str)> {
+        let mut parts = id.splitn(3, ':');
+        let direction = match parts.next()? {
+            "sent" => Direction::Sent,
+            "received" => Direction::Received,
+            _ => return None,
+        };
+        let ordinal = parts.next()?.parse().ok()?;
+        Some((Self { direction, ordinal }, parts.next()?))
+    }
+}
+
+/// Finds the commitment named by a [`CommitmentDomain::id`], rejecting ids
+/// from the other direction and ids whose hash differs from the commitment
+/// at that ordinal.
+pub fn resolve_commitment_id<'a>(
+    transcript_commitments: &'a [TranscriptCommitment],
+    id: &str,
+    direction: Direction,
+) -> Result<&'a PlaintextHash, Error> {
+    let (domain, hash) = CommitmentDomain::parse_id(id)
+        .ok_or_else(|| Error::InvalidInput(format!("malformed commitment id '{id}'")))?;
+    if domain.direction != direction {
+        return Err(Error::InvalidTranscript(format!(
+            "commitment {id} is a {:?} commitment, expected {direction:?}",
+            domain.direction
+        )));
+    }
+    hash_commitments(transcript_commitments, direction)
+        .nth(domain.ordinal)
+        .filter(|commitment| hex(commitment.hash.value.as_bytes()) == hash)
+        .ok_or_else(|| {
+            Error::InvalidTranscript(format!("no {direction:?} commitment matches id {id}"))
+        })
+}
+
+pub(crate) fn hash_commitments(
+    transcript_commitments: &[TranscriptCommitment],
+    direction: Direction,
+) -> impl Iterator<Item = &PlaintextHash> {
+    transcript_commitments
+        .iter()
+        .filter_map(move |commitment| match commitment {
+            TranscriptCommitment::Hash(hash) if hash.direction == direction => Some(hash),
+            _ => None,
+        })
+}
+
+fn direction_tag(direction: Direction) -> &'static str {
+    match direction {
+        Direction::Sent => "sent",
+        Direction::Received => "received",
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
@@ -0,0 +1,19 @@
+use std::{future::Future, time::Duration};
+
+use crate::error::Error;
+
+pub(crate) async fn with_deadline<T>(
+    timeout: Option<Duration>,
+    future: impl Future<Output = Result<T, Error>>,
+) -> Result<T, Error> {
+    match timeout {
+        Some(timeout) => {
+            smol::future::or(future, async {
+                smol::Timer::after(timeout).await;
+                Err(Error::Timeout(timeout))
+            })
+            .await
+        }
+        None => future.await,
+    }
+}
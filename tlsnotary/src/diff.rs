@@ -0,0 +1,172 @@
+use std::{collections::BTreeMap, ops::Range};
+
+use parser::redacted::{Body, Header, REDACTED_CHAR};
+use serde::Serialize;
+use tlsn::{hash::HashAlgId, transcript::Direction};
+
+use crate::{
+    commitment::{CommitmentDomain, hash_commitments},
+    verifier::VerifierOutput,
+};
+
+/// What differs between two attestations of the same kind of session, e.g.
+/// the same policy re-attested a week later. Unchanged fields and
+/// commitments are left out.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptDiff {
+    pub fields: Vec<FieldChange>,
+    pub commitments: Vec<CommitmentChange>,
+}
+
+impl TranscriptDiff {
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty() && self.commitments.is_empty()
+    }
+
+    pub fn field(&self, direction: Direction, field: &str) -> Option<&FieldChange> {
+        self.fields
+            .iter()
+            .find(|change| change.direction == direction && change.field == field)
+    }
+}
+
+/// A revealed field whose value changed, appeared or stopped being revealed.
+/// Headers are named `header:<name>`, body fields by keypath; values have
+/// their redacted bytes drawn as [`REDACTED_CHAR`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldChange {
+    pub direction: Direction,
+    pub field: String,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+impl FieldChange {
+    /// Both values as numbers, for thresholds such as "alert when the
+    /// attested balance drops below X".
+    pub fn as_numbers(&self) -> Option<(f64, f64)> {
+        let parse = |value: &Option<String>| value.as_deref()?.trim().parse().ok();
+        Some((parse(&self.before)?, parse(&self.after)?))
+    }
+}
+
+/// A hash commitment, identified by its [`CommitmentDomain`], that covers
+/// different bytes, uses a different algorithm, or exists on one side only.
+/// Commitments are blinded, so their hashes differ across sessions even over
+/// equal plaintext and are not compared.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitmentChange {
+    pub domain: CommitmentDomain,
+    pub before: Option<CommittedRange>,
+    pub after: Option<CommittedRange>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommittedRange {
+    pub range: Range<usize>,
+    pub alg: HashAlgId,
+}
+
+/// Compares the revealed fields and hash commitments of two verifier
+/// outputs. Messages that were not parsed contribute no fields.
+pub fn diff_outputs(before: &VerifierOutput, after: &VerifierOutput) -> TranscriptDiff {
+    let mut fields = Vec::new();
+    for direction in [Direction::Sent, Direction::Received] {
+        let before_fields = revealed_fields(before, direction);
+        let mut after_fields = revealed_fields(after, direction);
+        for (field, before_value) in before_fields {
+            let after_value = after_fields.remove(&field);
+            if after_value.as_ref() != Some(&before_value) {
+                fields.push(FieldChange {
+                    direction,
+                    field,
+                    before: Some(before_value),
+                    after: after_value,
+                });
+            }
+        }
+        fields.extend(
+            after_fields
+                .into_iter()
+                .map(|(field, after_value)| FieldChange {
+                    direction,
+                    field,
+                    before: None,
+                    after: Some(after_value),
+                }),
+        );
+    }
+
+    let mut commitments = Vec::new();
+    for direction in [Direction::Sent, Direction::Received] {
+        let before_ranges = committed_ranges(before, direction);
+        let after_ranges = committed_ranges(after, direction);
+        for ordinal in 0..before_ranges.len().max(after_ranges.len()) {
+            let (before, after) = (before_ranges.get(ordinal), after_ranges.get(ordinal));
+            if before != after {
+                commitments.push(CommitmentChange {
+                    domain: CommitmentDomain { direction, ordinal },
+                    before: before.cloned(),
+                    after: after.cloned(),
+                });
+            }
+        }
+    }
+
+    TranscriptDiff {
+        fields,
+        commitments,
+    }
+}
+
+fn revealed_fields(output: &VerifierOutput, direction: Direction) -> BTreeMap<String, String> {
+    let (headers, body) = match direction {
+        Direction::Sent => match &output.parsed_request {
+            Some(request) => (&request.headers, &request.body),
+            None => return BTreeMap::new(),
+        },
+        Direction::Received => match &output.parsed_response {
+            Some(response) => (&response.headers, &response.body),
+            None => return BTreeMap::new(),
+        },
+    };
+    let source = output.message_bytes(direction);
+
+    let headers = headers.iter().filter_map(|(name, headers)| {
+        let values = headers
+            .iter()
+            .filter_map(|Header { value, .. }| value.as_ref())
+            .map(|range| render(source, range))
+            .collect::<Vec<_>>();
+        (!values.is_empty()).then(|| (format!("header:{name}"), values.join(", ")))
+    });
+    let body = body.iter().filter_map(|(keypath, field)| {
+        let range = match field {
+            Body::KeyValue { value, .. } => value.as_ref()?,
+            Body::Value(range)
+            | Body::PartialValue {
+                revealed: range, ..
+            } => range,
+        };
+        Some((keypath.clone(), render(source, range)))
+    });
+    headers.chain(body).collect()
+}
+
+fn committed_ranges(output: &VerifierOutput, direction: Direction) -> Vec<CommittedRange> {
+    hash_commitments(&output.transcript_commitments, direction)
+        .map(|hash| CommittedRange {
+            range: hash.idx.min().unwrap_or(0)..hash.idx.end().unwrap_or(0),
+            alg: hash.hash.alg,
+        })
+        .collect()
+}
+
+fn render(source: &[u8], range: &Range<usize>) -> String {
+    String::from_utf8_lossy(source.get(range.clone()).unwrap_or_default())
+        .replace('\0', &REDACTED_CHAR.to_string())
+}
@@ -5,6 +5,9 @@ pub enum Error {
     #[error("HTTP request failed with status {0}")]
     HttpRequestFailed(u16),
 
+    #[error("response body exceeds the {limit} byte limit")]
+    ResponseTooLarge { limit: usize },
+
     #[error(transparent)]
     Parser(#[from] parser::ParseError),
 
@@ -20,6 +23,12 @@ pub enum Error {
     #[error("invalid input: {0}")]
     InvalidInput(String),
 
+    #[error("session violates verifier policy: {0}")]
+    PolicyViolation(String),
+
+    #[error("operation timed out after {0:?}")]
+    Timeout(std::time::Duration),
+
     #[error(transparent)]
     Tlsn(#[from] tlsn::Error),
 
@@ -53,3 +62,28 @@ pub enum Error {
     #[error(transparent)]
     Utf8(#[from] std::string::FromUtf8Error),
 }
+
+impl Error {
+    /// Origin failures a fresh connection may not hit: gateway errors, 503
+    /// and connections dropped mid-exchange.
+    #[must_use]
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Self::HttpRequestFailed(status) => matches!(
+                hyper::StatusCode::from_u16(*status),
+                Ok(hyper::StatusCode::BAD_GATEWAY
+                    | hyper::StatusCode::SERVICE_UNAVAILABLE
+                    | hyper::StatusCode::GATEWAY_TIMEOUT)
+            ),
+            Self::Hyper(error) => error.is_incomplete_message() || error.is_closed(),
+            Self::Io(error) => matches!(
+                error.kind(),
+                std::io::ErrorKind::ConnectionReset
+                    | std::io::ErrorKind::ConnectionAborted
+                    | std::io::ErrorKind::BrokenPipe
+                    | std::io::ErrorKind::UnexpectedEof
+            ),
+            _ => false,
+        }
+    }
+}
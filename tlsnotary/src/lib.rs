@@ -1,11 +1,30 @@
+pub mod commitment;
+mod deadline;
+pub mod diff;
 pub mod error;
+pub mod offline;
+mod offset;
+mod policy;
+pub mod preset;
 pub mod prover;
+pub mod stats;
 pub mod verifier;
 
+pub use commitment::{
+    CommitmentDomain, CommitmentLabel, resolve_commitment_id, resolve_commitment_labels,
+};
+pub use diff::{CommitmentChange, CommittedRange, FieldChange, TranscriptDiff, diff_outputs};
 pub use error::Error;
+pub use offline::SessionArtifacts;
+pub use offset::TranscriptOffset;
+pub use preset::{PRESET_COMMITMENT_LENGTH, Preset};
 pub use prover::{
-    BodyFieldConfig, KeyValueCommitConfig, Prover, ProverBuilder, ProverOutput, RevealConfig,
+    BodyFieldConfig, CommitHashAlgs, CommitKeyRevealValueConfig, CommitmentLink, ContentEncoding,
+    FanOutOutput, KeyValueCommitConfig, Prover, ProverBuilder, ProverOutput, RenderedRequest,
+    RequestLineConfig, RequestTemplate, RetryPolicy, RevealConfig, SecretHeader, SubRangeConfig,
+    VerifierChannel,
 };
+pub use stats::{PhaseTiming, SessionStats};
 pub use tlsn::{
     Session,
     config::{
@@ -25,8 +44,9 @@ pub use tlsn::{
     webpki::{CertificateDer, RootCertStore},
 };
 pub use verifier::{
-    ExpectedValue, FieldAssertion, Validator, ValidatorBuilder, Verifier, VerifierBuilder,
-    VerifierOutput,
+    AssertionOutcome, BodyCommitmentOpenings, ExpectedValue, FieldAssertion, HashAlgScope,
+    Predicate, PredicateProofVerifier, ProtocolLimits, ValidationReport, Validator,
+    ValidatorBuilder, Verifier, VerifierBuilder, VerifierOutput, verify_body_commitment,
 };
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -0,0 +1,103 @@
+use serde::{Deserialize, Serialize};
+use tlsn::transcript::{Direction, PartialTranscript, TranscriptCommitment};
+
+use crate::{
+    error::Error, offset::TranscriptOffset, stats::SessionStats, verifier::VerifierOutput,
+};
+
+/// What a verifier keeps of a session to re-check it later. The parsed
+/// messages are not stored; [`verify_session`] rebuilds them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionArtifacts {
+    pub transcript: PartialTranscript,
+    pub transcript_commitments: Vec<TranscriptCommitment>,
+    pub server_name: Option<String>,
+    #[serde(default)]
+    pub request_offset: TranscriptOffset,
+    #[serde(default)]
+    pub response_offset: TranscriptOffset,
+}
+
+impl From<&VerifierOutput> for SessionArtifacts {
+    fn from(output: &VerifierOutput) -> Self {
+        Self {
+            transcript: output.transcript.clone(),
+            transcript_commitments: output.transcript_commitments.clone(),
+            server_name: output.server_name.clone(),
+            request_offset: output.request_offset,
+            response_offset: output.response_offset,
+        }
+    }
+}
+
+/// Re-runs the checks the verifier made after MPC-TLS on stored artifacts,
+/// without the prover or a socket: every commitment lies within the partial
+/// transcript, whose own consistency is checked when it is deserialized, and
+/// both messages parse at their offsets. The output can then be held to a
+/// [`crate::Validator`] as a live one would.
+///
+/// The server identity was proven to the verifier during the session and is
+/// taken as recorded; tlsn hands over no certificate chain to re-check.
+pub fn verify_session(artifacts: &SessionArtifacts) -> Result<VerifierOutput, Error> {
+    let transcript = &artifacts.transcript;
+    for commitment in &artifacts.transcript_commitments {
+        if let TranscriptCommitment::Hash(hash) = commitment {
+            let len = match hash.direction {
+                Direction::Sent => transcript.len_sent(),
+                Direction::Received => transcript.len_received(),
+            };
+            if hash.idx.end().is_some_and(|end| end > len) {
+                return Err(Error::InvalidTranscript(format!(
+                    "{:?} commitment ends past the transcript length {len}",
+                    hash.direction
+                )));
+            }
+        }
+    }
+
+    let parsed_request = parser::redacted::Request::from_bytes_lossy(message_bytes(
+        transcript.sent_unsafe(),
+        artifacts.request_offset,
+        Direction::Sent,
+    )?)
+    .map_err(|error| {
+        Error::InvalidTranscript(format!(
+            "failed to parse redacted request from transcript: {error:?}"
+        ))
+    })?;
+    let parsed_response = parser::redacted::Response::from_bytes_lossy(message_bytes(
+        transcript.received_unsafe(),
+        artifacts.response_offset,
+        Direction::Received,
+    )?)
+    .map_err(|error| {
+        Error::InvalidTranscript(format!(
+            "failed to parse redacted response from transcript: {error:?}"
+        ))
+    })?;
+
+    Ok(VerifierOutput {
+        transcript: transcript.clone(),
+        transcript_commitments: artifacts.transcript_commitments.clone(),
+        server_name: artifacts.server_name.clone(),
+        parsed_request: Some(parsed_request),
+        parsed_response: Some(parsed_response),
+        request_offset: artifacts.request_offset,
+        response_offset: artifacts.response_offset,
+        session_stats: SessionStats::default(),
+    })
+}
+
+pub(crate) fn message_bytes(
+    data: &[u8],
+    offset: TranscriptOffset,
+    direction: Direction,
+) -> Result<&[u8], Error> {
+    data.get(offset.message_start()..).ok_or_else(|| {
+        Error::InvalidTranscript(format!(
+            "{direction:?} message offset {} is past the transcript",
+            offset.message_start()
+        ))
+    })
+}
@@ -0,0 +1,45 @@
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+
+/// Where an HTTP message starts within one direction of the TLS transcript.
+/// The parser reports ranges relative to the message it was given, while
+/// reveals and commitments address the whole transcript; the two only agree
+/// for the first exchange on a connection.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TranscriptOffset(usize);
+
+impl TranscriptOffset {
+    pub const ZERO: Self = Self(0);
+
+    pub fn new(message_start: usize) -> Self {
+        Self(message_start)
+    }
+
+    pub fn message_start(self) -> usize {
+        self.0
+    }
+
+    /// Fails when the shifted range would overflow `usize`.
+    pub fn to_transcript(self, message_range: &Range<usize>) -> Result<Range<usize>, Error> {
+        let shift = |position: usize| {
+            position.checked_add(self.0).ok_or_else(|| {
+                Error::InvalidTranscript(format!(
+                    "message range {message_range:?} overflows at offset {}",
+                    self.0
+                ))
+            })
+        };
+        Ok(shift(message_range.start)?..shift(message_range.end)?)
+    }
+
+    /// `None` when the range starts before the message.
+    pub fn to_message(self, transcript_range: &Range<usize>) -> Option<Range<usize>> {
+        Some(
+            transcript_range.start.checked_sub(self.0)?
+                ..transcript_range.end.checked_sub(self.0)?,
+        )
+    }
+}
@@ -0,0 +1,131 @@
+/// Declares what a session discloses once and expands to the prover's
+/// request and response [`RevealConfig`](crate::RevealConfig)s together with
+/// the verifier's [`Validator`](crate::Validator), so the fields one side
+/// reveals or commits and the fields the other side checks cannot drift
+/// apart. Every assertion is attached to the rule that discloses its field.
+///
+/// ```text
+/// let (request_config, response_config, validator) = policy! {
+///     server_name: "bank.example",
+///     hash_alg: HashAlgId::BLAKE3,
+///     request {
+///         reveal header "host";
+///         commit header "authorization";
+///     }
+///     response {
+///         reveal header "content-type" == "application/json";
+///         reveal body ".username" == "alice";
+///         commit body ".balance" padded 12 where Predicate::GreaterThan(100);
+///         commit value ".account_id";
+///     }
+/// };
+/// ```
+///
+/// `hash_alg` applies to both the commitments and the validator's hash
+/// expectation. Predicates on committed values are only checked on the
+/// response.
+#[macro_export]
+macro_rules! policy {
+    (@rules $direction:ident, $config:ident, $validator:ident;) => {};
+    (
+        @rules $direction:ident, $config:ident, $validator:ident;
+        reveal header $name:literal $(== $value:expr)?; $($rest:tt)*
+    ) => {
+        $config.reveal_headers.push(::std::string::String::from($name));
+        $($validator = $crate::policy!(@header_equals $direction, $validator, $name, $value);)?
+        $crate::policy!(@rules $direction, $config, $validator; $($rest)*);
+    };
+    (
+        @rules $direction:ident, $config:ident, $validator:ident;
+        commit header $name:literal; $($rest:tt)*
+    ) => {
+        $config.commit_headers.push(::std::string::String::from($name));
+        $crate::policy!(@rules $direction, $config, $validator; $($rest)*);
+    };
+    (
+        @rules $direction:ident, $config:ident, $validator:ident;
+        reveal body $keypath:literal $(== $value:expr)?; $($rest:tt)*
+    ) => {
+        $config
+            .reveal_body_fields
+            .push($crate::BodyFieldConfig::Quoted(::std::string::String::from($keypath)));
+        $($validator = $crate::policy!(@body_field_equals $direction, $validator, $keypath, $value);)?
+        $crate::policy!(@rules $direction, $config, $validator; $($rest)*);
+    };
+    (
+        @rules $direction:ident, $config:ident, $validator:ident;
+        commit body $keypath:literal $(padded $len:literal)? $(where $predicate:expr)?;
+        $($rest:tt)*
+    ) => {
+        $config
+            .commit_body_fields
+            .push($crate::policy!(@commit_body_field $keypath $(, $len)?));
+        $($validator = $crate::policy!(@satisfies $direction, $validator, $keypath, $predicate);)?
+        $crate::policy!(@rules $direction, $config, $validator; $($rest)*);
+    };
+    (
+        @rules $direction:ident, $config:ident, $validator:ident;
+        commit value $keypath:literal $(padded $len:literal)?; $($rest:tt)*
+    ) => {
+        $config
+            .reveal_keys_commit_values
+            .push($crate::policy!(@commit_value $keypath $(, $len)?));
+        $crate::policy!(@rules $direction, $config, $validator; $($rest)*);
+    };
+
+    (@commit_body_field $keypath:literal) => {
+        $crate::BodyFieldConfig::Unquoted(::std::string::String::from($keypath))
+    };
+    (@commit_body_field $keypath:literal, $len:literal) => {
+        $crate::BodyFieldConfig::UnquotedPadded(::std::string::String::from($keypath), $len)
+    };
+    (@commit_value $keypath:literal) => {
+        $crate::KeyValueCommitConfig::new(::std::string::String::from($keypath))
+    };
+    (@commit_value $keypath:literal, $len:literal) => {
+        $crate::KeyValueCommitConfig::with_padding(::std::string::String::from($keypath), $len)
+    };
+
+    (@header_equals request, $validator:ident, $name:literal, $value:expr) => {
+        $validator.request_header_equals($name, $value)
+    };
+    (@header_equals response, $validator:ident, $name:literal, $value:expr) => {
+        $validator.response_header_equals($name, $value)
+    };
+    (@body_field_equals request, $validator:ident, $keypath:literal, $value:expr) => {
+        $validator.request_body_field_equals($keypath, $crate::ExpectedValue::from($value))
+    };
+    (@body_field_equals response, $validator:ident, $keypath:literal, $value:expr) => {
+        $validator.response_body_field_equals($keypath, $crate::ExpectedValue::from($value))
+    };
+    (@satisfies request, $($_:tt)*) => {
+        ::std::compile_error!("predicates on committed values are only checked on the response")
+    };
+    (@satisfies response, $validator:ident, $keypath:literal, $predicate:expr) => {
+        $validator.response_committed_field_satisfies($keypath, $predicate)
+    };
+
+    (
+        $(server_name: $server_name:expr,)?
+        $(hash_alg: $hash_alg:expr,)?
+        $(request { $($request:tt)* })?
+        $(response { $($response:tt)* })?
+    ) => {{
+        #[allow(unused_mut)]
+        let mut request_config = $crate::RevealConfig::new();
+        #[allow(unused_mut)]
+        let mut response_config = $crate::RevealConfig::new();
+        #[allow(unused_mut)]
+        let mut validator = $crate::Validator::builder();
+        $(validator = validator.expected_server_name($server_name);)?
+        $(
+            let hash_alg = $hash_alg;
+            request_config.commit_hash_algs = $crate::CommitHashAlgs::new(hash_alg);
+            response_config.commit_hash_algs = $crate::CommitHashAlgs::new(hash_alg);
+            validator = validator.expected_hash_alg(hash_alg);
+        )?
+        $($crate::policy!(@rules request, request_config, validator; $($request)*);)?
+        $($crate::policy!(@rules response, response_config, validator; $($response)*);)?
+        (request_config, response_config, validator.build())
+    }};
+}
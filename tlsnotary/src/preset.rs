@@ -0,0 +1,138 @@
+use tlsn::{hash::HashAlgId, transcript::Direction};
+
+use crate::{
+    prover::{BodyFieldConfig, CommitHashAlgs, KeyValueCommitConfig, RevealConfig},
+    verifier::{ExpectedValue, FieldAssertion, Validator},
+};
+
+/// Padded commitment length used for committed numeric values, wide enough to
+/// hide the number of digits.
+pub const PRESET_COMMITMENT_LENGTH: usize = 12;
+
+/// Common attestation recipes, shared by the prover's response reveal config
+/// and the verifier's validator so both sides agree on the disclosed fields.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Preset {
+    /// Reveals the account holder and commits to a padded balance for a ZK range proof.
+    BalanceCheck {
+        username_path: String,
+        username: String,
+        balance_path: String,
+    },
+    /// Reveals the account holder and commits to the account identifier.
+    AccountOwnership {
+        username_path: String,
+        username: String,
+        account_path: String,
+    },
+    /// Reveals only the KYC status field.
+    KycStatus {
+        status_path: String,
+        expected_status: String,
+    },
+}
+
+impl RevealConfig {
+    #[must_use]
+    pub fn preset(preset: &Preset) -> Self {
+        match preset {
+            Preset::BalanceCheck {
+                username_path,
+                balance_path,
+                ..
+            } => Self {
+                reveal_body_fields: vec![BodyFieldConfig::Quoted(username_path.clone())],
+                commit_body_fields: vec![BodyFieldConfig::UnquotedPadded(
+                    balance_path.clone(),
+                    PRESET_COMMITMENT_LENGTH,
+                )],
+                ..Self::default()
+            },
+            Preset::AccountOwnership {
+                username_path,
+                account_path,
+                ..
+            } => Self {
+                reveal_body_fields: vec![BodyFieldConfig::Quoted(username_path.clone())],
+                reveal_keys_commit_values: vec![KeyValueCommitConfig::new(account_path.clone())],
+                ..Self::default()
+            },
+            Preset::KycStatus { status_path, .. } => Self {
+                reveal_body_fields: vec![BodyFieldConfig::Quoted(status_path.clone())],
+                ..Self::default()
+            },
+        }
+    }
+
+    /// The fewest disclosures that let `validator` check its assertions on
+    /// `direction`, following the rules of [`crate::policy!`]: asserted
+    /// headers and body fields are revealed, fields under a predicate are
+    /// committed padded to [`PRESET_COMMITMENT_LENGTH`], and an expected hash
+    /// algorithm becomes the commitment default. The request and status lines
+    /// are revealed as always. A request without assertions derives to the
+    /// empty config, which reveals all of it.
+    #[must_use]
+    pub fn derive_from(validator: &Validator, direction: Direction) -> Self {
+        let mut config = Self {
+            commit_hash_algs: validator
+                .expected_direction_hash_alg(direction)
+                .map(CommitHashAlgs::new)
+                .unwrap_or_default(),
+            ..Self::default()
+        };
+        for assertion in validator.assertions(direction) {
+            match assertion {
+                FieldAssertion::HeaderEquals { key, .. } => {
+                    push_unique(&mut config.reveal_headers, key.to_lowercase());
+                }
+                FieldAssertion::BodyFieldEquals { key, .. } => push_unique(
+                    &mut config.reveal_body_fields,
+                    BodyFieldConfig::Quoted(key.clone()),
+                ),
+                FieldAssertion::CommittedFieldSatisfies { key, .. } => push_unique(
+                    &mut config.commit_body_fields,
+                    BodyFieldConfig::UnquotedPadded(key.clone(), PRESET_COMMITMENT_LENGTH),
+                ),
+            }
+        }
+        config
+    }
+}
+
+fn push_unique<T: PartialEq>(items: &mut Vec<T>, item: T) {
+    if !items.contains(&item) {
+        items.push(item);
+    }
+}
+
+impl Validator {
+    #[must_use]
+    pub fn preset(preset: &Preset, server_name: impl Into<String>) -> Self {
+        let builder = Validator::builder().expected_server_name(server_name);
+        match preset {
+            Preset::BalanceCheck {
+                username_path,
+                username,
+                ..
+            }
+            | Preset::AccountOwnership {
+                username_path,
+                username,
+                ..
+            } => builder
+                .expected_hash_alg(HashAlgId::BLAKE3)
+                .response_body_field_equals(
+                    username_path.clone(),
+                    ExpectedValue::String(username.clone()),
+                ),
+            Preset::KycStatus {
+                status_path,
+                expected_status,
+            } => builder.response_body_field_equals(
+                status_path.clone(),
+                ExpectedValue::String(expected_status.clone()),
+            ),
+        }
+        .build()
+    }
+}
@@ -0,0 +1,84 @@
+use std::ops::Range;
+
+use serde::Serialize;
+use tlsn::transcript::Direction;
+
+use super::ProverOutput;
+
+/// Two notarizations of the same request with the same reveal config, each
+/// with its own verifier, for consumers that want attestations from two
+/// independent notaries. See [`super::Prover::prove_to_both`].
+#[derive(Debug, Clone)]
+pub struct FanOutOutput {
+    pub first: ProverOutput,
+    pub second: ProverOutput,
+    /// Labels committed in both sessions, in the order of `first`.
+    pub links: Vec<CommitmentLink>,
+}
+
+/// A label both sessions committed. Commitments are blinded, so the two
+/// hashes differ even over equal bytes; when `plaintext_matches`, a proof of
+/// the same statement against each hash convinces both notaries' consumers
+/// of one value.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitmentLink {
+    pub label: String,
+    pub direction: Direction,
+    pub first: Range<usize>,
+    pub second: Range<usize>,
+    pub plaintext_matches: bool,
+}
+
+impl FanOutOutput {
+    /// Links the commitments of two outputs by label. Labels committed in
+    /// one session only, e.g. a field the second response left out, are not
+    /// linked.
+    pub fn new(first: ProverOutput, second: ProverOutput) -> Self {
+        let links = first
+            .commitment_labels
+            .iter()
+            .filter_map(|label| {
+                let other = second.commitment_labels.iter().find(|other| {
+                    other.label == label.label && other.direction == label.direction
+                })?;
+                Some(CommitmentLink {
+                    label: label.label.clone(),
+                    direction: label.direction,
+                    first: label.range.clone(),
+                    second: other.range.clone(),
+                    plaintext_matches: committed_bytes(&first, label.direction, &label.range)
+                        == committed_bytes(&second, other.direction, &other.range),
+                })
+            })
+            .collect();
+        Self {
+            first,
+            second,
+            links,
+        }
+    }
+
+    /// Whether every linked commitment opens to the same bytes in both
+    /// sessions. Values that legitimately change between requests, such as
+    /// timestamps, make this false; check [`Self::link`] for the fields that
+    /// matter instead.
+    pub fn is_consistent(&self) -> bool {
+        self.links.iter().all(|link| link.plaintext_matches)
+    }
+
+    pub fn link(&self, label: &str) -> Option<&CommitmentLink> {
+        self.links.iter().find(|link| link.label == label)
+    }
+}
+
+fn committed_bytes<'a>(
+    output: &'a ProverOutput,
+    direction: Direction,
+    range: &Range<usize>,
+) -> Option<&'a [u8]> {
+    match direction {
+        Direction::Sent => output.sent.get(range.clone()),
+        Direction::Received => output.received.get(range.clone()),
+    }
+}
@@ -1,23 +1,48 @@
+mod fanout;
+mod retry;
 mod reveal;
+mod secret;
+mod template;
+
+use std::time::Duration;
 
 use async_compat::Compat;
+pub use fanout::{CommitmentLink, FanOutOutput};
 use futures::{AsyncRead, AsyncWrite, join};
-use http_body_util::{BodyExt, Empty};
-use hyper::{Request, StatusCode, body::Bytes};
+use http_body_util::{BodyExt, Empty, Full};
+use hyper::{
+    Request, StatusCode,
+    body::{Body, Bytes},
+};
 use hyper_util::rt::TokioIo;
+pub use retry::RetryPolicy;
 pub use reveal::{
-    BodyFieldConfig, KeyValueCommitConfig, RevealConfig, reveal_request, reveal_response,
+    BodyFieldConfig, CommitHashAlgs, CommitKeyRevealValueConfig, KeyValueCommitConfig,
+    RequestLineConfig, RevealConfig, SubRangeConfig, reveal_request, reveal_request_at,
+    reveal_request_with_secrets, reveal_response, reveal_response_at, reveal_response_framed,
 };
+pub use secret::SecretHeader;
+pub use template::{ContentEncoding, RenderedRequest, RequestTemplate};
 use tlsn::{
     Session, SessionHandle,
     config::{
-        prove::ProveConfig, prover::ProverConfig, tls::TlsClientConfig, tls_commit::TlsCommitConfig,
+        prove::ProveConfig,
+        prover::ProverConfig,
+        tls::TlsClientConfig,
+        tls_commit::{TlsCommitConfig, TlsCommitProtocolConfig},
     },
     hash::HashAlgId,
     transcript::{TranscriptCommitConfig, TranscriptCommitmentKind},
 };
-
-use crate::error::Error;
+use tracing::{Instrument, warn};
+
+use crate::{
+    commitment::CommitmentLabel,
+    deadline::with_deadline,
+    error::Error,
+    offset::TranscriptOffset,
+    stats::{SessionStats, StatsRecorder},
+};
 
 #[derive(Debug, Clone)]
 pub struct ProverOutput {
@@ -25,16 +50,29 @@ pub struct ProverOutput {
     pub received: Vec<u8>,
     pub transcript_commitments: Vec<tlsn::transcript::TranscriptCommitment>,
     pub transcript_secrets: Vec<tlsn::transcript::TranscriptSecret>,
+    /// Status line and headers through the blank line, as received.
+    pub response_head: Vec<u8>,
+    /// Where the body starts in `received`, i.e. the length of the head.
+    pub response_body_start: usize,
     pub response_body: Vec<u8>,
+    pub commitment_labels: Vec<CommitmentLabel>,
+    pub session_stats: SessionStats,
+    /// Sessions started to get this output, 1 unless a retry was needed.
+    pub attempts: u32,
 }
 
 pub struct Prover {
     tls_client_config: TlsClientConfig,
     tls_commit_config: TlsCommitConfig,
     request: Request<Empty<Bytes>>,
+    request_body: Bytes,
     request_reveal_config: RevealConfig,
     response_reveal_config: RevealConfig,
+    secret_headers: Vec<SecretHeader>,
     hash_alg: HashAlgId,
+    prove_server_identity: bool,
+    retry_policy: Option<RetryPolicy>,
+    timeout: Option<Duration>,
 }
 
 impl Prover {
@@ -52,43 +90,198 @@ impl Prover {
         T: AsyncRead + AsyncWrite + Send + Unpin + 'static,
         S: AsyncRead + AsyncWrite + Send + Unpin + 'static,
     {
-        let (mpc_tls_connection, prover_fut, session_handle) = Self::setup_and_connect(
-            self.tls_client_config,
-            self.tls_commit_config,
-            verifier_socket,
+        with_deadline(self.timeout, self.run(verifier_socket, server_socket)).await
+    }
+
+    /// Like [`Self::prove`], but hands the verifier socket back once the
+    /// session is closed, for protocols that go on over it, such as
+    /// submitting the proof to a verifier service.
+    pub async fn prove_and_reclaim<T, S>(
+        self,
+        verifier_socket: T,
+        server_socket: S,
+    ) -> Result<(ProverOutput, T), Error>
+    where
+        T: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+        S: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+    {
+        with_deadline(self.timeout, async {
+            let stats = StatsRecorder::new();
+            let (driver, mut handle) = Session::new(stats.wrap(verifier_socket)).split();
+            let driver_task = smol::spawn(driver.in_current_span());
+            let output = self.run_on(&mut handle, &stats, server_socket).await?;
+            handle.close();
+            let verifier_socket = driver_task.await?;
+            Ok((output, verifier_socket.into_inner()))
+        })
+        .await
+    }
+
+    /// Like [`Self::prove`], but opens the sockets through `connect` and, under
+    /// the builder's [`RetryPolicy`], starts over with fresh sockets after a
+    /// transient origin failure. The timeout applies per attempt.
+    pub async fn prove_with_retry<T, S, F, Fut>(self, mut connect: F) -> Result<ProverOutput, Error>
+    where
+        T: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+        S: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+        F: FnMut() -> Fut,
+        Fut: std::future::Future<Output = Result<(T, S), Error>>,
+    {
+        let max_attempts = self
+            .retry_policy
+            .as_ref()
+            .map_or(1, |policy| policy.attempts_for(self.request.method()));
+        let mut attempt = 1;
+        loop {
+            let (verifier_socket, server_socket) = connect().await?;
+            match with_deadline(self.timeout, self.run(verifier_socket, server_socket)).await {
+                Ok(output) => {
+                    return Ok(ProverOutput {
+                        attempts: attempt,
+                        ..output
+                    });
+                }
+                Err(error) if attempt < max_attempts && error.is_transient() => {
+                    let delay = self
+                        .retry_policy
+                        .as_ref()
+                        .map_or(Duration::ZERO, |policy| policy.backoff(attempt));
+                    warn!(attempt, ?delay, error = %error, "Transient origin failure, restarting session");
+                    smol::Timer::after(delay).await;
+                    attempt += 1;
+                }
+                Err(error) => return Err(error),
+            }
+        }
+    }
+
+    /// Like [`Self::prove`], over a [`VerifierChannel`] that stays open for
+    /// further sessions, possibly with other servers.
+    pub async fn prove_on<S>(
+        &self,
+        channel: &mut VerifierChannel,
+        server_socket: S,
+    ) -> Result<ProverOutput, Error>
+    where
+        S: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+    {
+        with_deadline(
+            self.timeout,
+            self.run_on(&mut channel.handle, &channel.stats, server_socket),
+        )
+        .await
+    }
+
+    /// Notarizes the request with two independent verifiers, each given as
+    /// a verifier and origin socket pair. An MPC-TLS session is bound to one
+    /// verifier, so this runs two sessions one after the other with the same
+    /// request and reveal configs, then links their commitments by label.
+    /// The timeout applies per session.
+    pub async fn prove_to_both<T1, S1, T2, S2>(
+        &self,
+        first: (T1, S1),
+        second: (T2, S2),
+    ) -> Result<FanOutOutput, Error>
+    where
+        T1: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+        S1: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+        T2: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+        S2: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+    {
+        let first = with_deadline(self.timeout, self.run(first.0, first.1)).await?;
+        let second = with_deadline(self.timeout, self.run(second.0, second.1)).await?;
+        Ok(FanOutOutput::new(first, second))
+    }
+
+    async fn run<T, S>(&self, verifier_socket: T, server_socket: S) -> Result<ProverOutput, Error>
+    where
+        T: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+        S: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+    {
+        let mut channel = VerifierChannel::open(verifier_socket);
+        let output = self
+            .run_on(&mut channel.handle, &channel.stats, server_socket)
+            .await?;
+        channel.close().await?;
+        Ok(output)
+    }
+
+    async fn run_on<S>(
+        &self,
+        handle: &mut SessionHandle,
+        stats: &StatsRecorder,
+        server_socket: S,
+    ) -> Result<ProverOutput, Error>
+    where
+        S: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+    {
+        let mut stats = stats.resume();
+        let (mpc_tls_connection, prover_fut) = Self::connect(
+            handle,
+            self.tls_client_config.clone(),
+            self.tls_commit_config.clone(),
             server_socket,
         )
         .await?;
+        stats.finish_phase("setup");
 
-        let (mut prover, response_body) =
-            Self::execute_http_exchange(mpc_tls_connection, prover_fut, self.request).await?;
-
-        let prove_config = Self::build_prove_config(
+        let mut request = build_request(&self.request, &self.request_body)?;
+        for secret_header in &self.secret_headers {
+            let (name, value) = secret_header.to_header()?;
+            request.headers_mut().insert(name, value);
+        }
+        let (mut prover, response_body) = Self::execute_http_exchange(
+            mpc_tls_connection,
+            prover_fut,
+            request,
+            response_body_limit(&self.tls_commit_config),
+        )
+        .await?;
+        stats.finish_phase("mpc_tls");
+
+        let response_body_start = parser::message_body_start(prover.transcript().received())
+            .ok_or_else(|| {
+                Error::InvalidTranscript("Response head is not terminated".to_string())
+            })?;
+        let secret_header_names = self
+            .secret_headers
+            .iter()
+            .map(|secret_header| secret_header.name().to_string())
+            .collect::<Vec<_>>();
+        let (prove_config, commitment_labels) = Self::build_prove_config(
             &mut prover,
             self.hash_alg,
+            self.prove_server_identity,
             &self.request_reveal_config,
             &self.response_reveal_config,
+            &secret_header_names,
+            response_body_start,
         )?;
 
         let sent = prover.transcript().sent().to_owned();
         let received = prover.transcript().received().to_owned();
+        let response_head = received[..response_body_start].to_vec();
         let prover_output = Self::generate_and_finalize_proof(prover, &prove_config).await?;
-
-        session_handle.close();
+        stats.finish_phase("prove");
 
         Ok(ProverOutput {
             sent,
             received,
             transcript_commitments: prover_output.transcript_commitments,
             transcript_secrets: prover_output.transcript_secrets,
+            response_head,
+            response_body_start,
             response_body,
+            commitment_labels,
+            session_stats: stats.finish(),
+            attempts: 1,
         })
     }
 
-    async fn setup_and_connect<T, S>(
+    async fn connect<S>(
+        handle: &mut SessionHandle,
         tls_client_config: TlsClientConfig,
         tls_commit_config: TlsCommitConfig,
-        verifier_socket: T,
         server_socket: S,
     ) -> Result<
         (
@@ -99,22 +292,15 @@ impl Prover {
                     tlsn::Error,
                 >,
             > + Send,
-            SessionHandle,
         ),
         Error,
     >
     where
-        T: AsyncRead + AsyncWrite + Send + Unpin + 'static,
         S: AsyncRead + AsyncWrite + Send + Unpin + 'static,
     {
-        let mut session = Session::new(verifier_socket);
-        let prover = session.new_prover(ProverConfig::builder().build()?)?;
-        let (driver, handle) = session.split();
-        smol::spawn(driver).detach();
-
+        let prover = handle.new_prover(ProverConfig::builder().build()?)?;
         let prover = prover.commit(tls_commit_config).await?;
-        let (connection, prover_future) = prover.connect(tls_client_config, server_socket).await?;
-        Ok((connection, prover_future, handle))
+        Ok(prover.connect(tls_client_config, server_socket).await?)
     }
 
     async fn execute_http_exchange<C>(
@@ -125,7 +311,8 @@ impl Prover {
                 tlsn::Error,
             >,
         > + Send,
-        request: Request<Empty<Bytes>>,
+        request: Request<Full<Bytes>>,
+        response_body_limit: usize,
     ) -> Result<
         (
             tlsn::prover::Prover<tlsn::prover::state::Committed>,
@@ -148,48 +335,66 @@ impl Prover {
                 return Err(Error::HttpRequestFailed(status.as_u16()));
             }
 
-            Ok::<Vec<u8>, Error>(response.collect().await?.to_bytes().to_vec())
+            collect_capped(response.into_body(), response_body_limit).await
         };
 
         let (prover, connection_result, request_task_result) =
             join!(prover_fut, connection, request_task);
 
+        // Dropping an oversized body cuts the connection, which would
+        // otherwise surface as a less telling TLS or connection error.
+        let response_body = match request_task_result {
+            Err(error @ Error::ResponseTooLarge { .. }) => return Err(error),
+            result => result,
+        };
         Ok((prover?, {
             connection_result?;
-            request_task_result?
+            response_body?
         }))
     }
 
     fn build_prove_config(
         prover: &mut tlsn::prover::Prover<tlsn::prover::state::Committed>,
         hash_alg: HashAlgId,
+        prove_server_identity: bool,
         request_reveal_config: &RevealConfig,
         response_reveal_config: &RevealConfig,
-    ) -> Result<ProveConfig, Error> {
+        secret_headers: &[String],
+        response_body_start: usize,
+    ) -> Result<(ProveConfig, Vec<CommitmentLabel>), Error> {
         let transcript = prover.transcript().clone();
         let mut prove_config_builder = ProveConfig::builder(&transcript);
-        prove_config_builder.server_identity();
+        if prove_server_identity {
+            prove_config_builder.server_identity();
+        }
 
         let mut transcript_commitment_builder = TranscriptCommitConfig::builder(&transcript);
         transcript_commitment_builder
             .default_kind(TranscriptCommitmentKind::Hash { alg: hash_alg });
 
-        reveal_request(
+        let request_labels = reveal_request_with_secrets(
             transcript.sent(),
+            TranscriptOffset::ZERO,
             &mut prove_config_builder,
             &mut transcript_commitment_builder,
             request_reveal_config,
+            secret_headers,
         )?;
 
-        reveal_response(
+        let response_labels = reveal_response_framed(
             transcript.received(),
+            response_body_start,
+            TranscriptOffset::ZERO,
             &mut prove_config_builder,
             &mut transcript_commitment_builder,
             response_reveal_config,
         )?;
 
         prove_config_builder.transcript_commit(transcript_commitment_builder.build()?);
-        Ok(prove_config_builder.build()?)
+        Ok((
+            prove_config_builder.build()?,
+            [request_labels, response_labels].concat(),
+        ))
     }
 
     async fn generate_and_finalize_proof(
@@ -202,14 +407,58 @@ impl Prover {
     }
 }
 
+/// A connection to the verifier that several [`Prover::prove_on`] calls
+/// share, each notarizing a new TLS session, so the connection and session
+/// multiplexer are set up once. The verifier serves it with
+/// [`crate::Verifier::verify_sessions`].
+pub struct VerifierChannel {
+    handle: SessionHandle,
+    stats: StatsRecorder,
+    driver: smol::Task<Result<(), Error>>,
+}
+
+impl VerifierChannel {
+    pub fn open<T>(verifier_socket: T) -> Self
+    where
+        T: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+    {
+        let stats = StatsRecorder::new();
+        let (driver, handle) = Session::new(stats.wrap(verifier_socket)).split();
+        let driver = smol::spawn(
+            async move {
+                driver.await?;
+                Ok::<_, Error>(())
+            }
+            .in_current_span(),
+        );
+        Self {
+            handle,
+            stats,
+            driver,
+        }
+    }
+
+    /// Closes the connection once the verifier has seen the last session
+    /// out, returning the error the session multiplexer failed with, if any.
+    pub async fn close(self) -> Result<(), Error> {
+        self.handle.close();
+        self.driver.await
+    }
+}
+
 #[derive(Debug)]
 pub struct ProverBuilder {
     tls_client_config: Option<TlsClientConfig>,
     tls_commit_config: Option<TlsCommitConfig>,
     request: Option<Request<Empty<Bytes>>>,
+    request_body: Bytes,
     request_reveal_config: RevealConfig,
     response_reveal_config: RevealConfig,
+    secret_headers: Vec<SecretHeader>,
     hash_alg: HashAlgId,
+    prove_server_identity: bool,
+    retry_policy: Option<RetryPolicy>,
+    timeout: Option<Duration>,
 }
 
 impl ProverBuilder {
@@ -218,9 +467,14 @@ impl ProverBuilder {
             tls_client_config: None,
             tls_commit_config: None,
             request: None,
+            request_body: Bytes::new(),
             request_reveal_config: RevealConfig::default(),
             response_reveal_config: RevealConfig::default(),
+            secret_headers: Vec::new(),
             hash_alg: HashAlgId::BLAKE3,
+            prove_server_identity: true,
+            retry_policy: None,
+            timeout: None,
         }
     }
 
@@ -242,6 +496,14 @@ impl ProverBuilder {
         self
     }
 
+    /// Sent as-is; set `Content-Length` and any `Content-Encoding` on the
+    /// request to match. [`RequestTemplate`] bodies do this for you.
+    #[must_use]
+    pub fn request_body(mut self, body: impl Into<Bytes>) -> Self {
+        self.request_body = body.into();
+        self
+    }
+
     #[must_use]
     pub fn request_reveal_config(mut self, config: RevealConfig) -> Self {
         self.request_reveal_config = config;
@@ -254,13 +516,55 @@ impl ProverBuilder {
         self
     }
 
+    /// Sets the request, its reveal config and its secret headers from a
+    /// rendered [`RequestTemplate`].
+    #[must_use]
+    pub fn request_template(mut self, rendered: RenderedRequest) -> Self {
+        self.request = Some(rendered.request);
+        self.request_body = rendered.body;
+        self.request_reveal_config = rendered.reveal_config;
+        self.secret_headers.extend(rendered.secret_headers);
+        self
+    }
+
+    #[must_use]
+    pub fn secret_header(mut self, secret_header: SecretHeader) -> Self {
+        self.secret_headers.push(secret_header);
+        self
+    }
+
     #[must_use]
     pub fn hash_alg(mut self, alg: HashAlgId) -> Self {
         self.hash_alg = alg;
         self
     }
 
+    /// Whether the verifier learns the server name; on by default.
+    #[must_use]
+    pub fn prove_server_identity(mut self, prove: bool) -> Self {
+        self.prove_server_identity = prove;
+        self
+    }
+
+    /// Retries used by [`Prover::prove_with_retry`]; without one it makes a
+    /// single attempt.
+    #[must_use]
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(policy);
+        self
+    }
+
+    #[must_use]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
     pub fn build(self) -> Result<Prover, Error> {
+        self.secret_headers
+            .iter()
+            .try_for_each(|secret_header| secret_header.to_header().map(drop))?;
+
         Ok(Prover {
             tls_client_config: self
                 .tls_client_config
@@ -271,9 +575,66 @@ impl ProverBuilder {
             request: self
                 .request
                 .ok_or_else(|| Error::InvalidConfig("request is required".into()))?,
+            request_body: self.request_body,
             request_reveal_config: self.request_reveal_config,
             response_reveal_config: self.response_reveal_config,
+            secret_headers: self.secret_headers,
             hash_alg: self.hash_alg,
+            prove_server_identity: self.prove_server_identity,
+            retry_policy: self.retry_policy,
+            timeout: self.timeout,
         })
     }
 }
+
+/// `Request<Empty<Bytes>>` has no `Clone`, so each attempt rebuilds it with
+/// its body.
+fn build_request(
+    request: &Request<Empty<Bytes>>,
+    body: &Bytes,
+) -> Result<Request<Full<Bytes>>, Error> {
+    let mut builder = Request::builder()
+        .method(request.method().clone())
+        .uri(request.uri().clone())
+        .version(request.version());
+    if let Some(headers) = builder.headers_mut() {
+        headers.clone_from(request.headers());
+    }
+    builder
+        .body(Full::new(body.clone()))
+        .map_err(|error| Error::InvalidInput(format!("invalid request: {error}")))
+}
+
+/// The decoded body cannot be longer than the received plaintext, so the
+/// MPC-TLS receive limit bounds it; other protocols are not capped.
+fn response_body_limit(tls_commit_config: &TlsCommitConfig) -> usize {
+    match tls_commit_config.protocol() {
+        TlsCommitProtocolConfig::Mpc(config) => config.max_recv_data(),
+        // The enum is `#[non_exhaustive]`, so this arm is required.
+        _ => usize::MAX,
+    }
+}
+
+/// Reads `body` frame by frame and fails as soon as it grows past `limit`,
+/// rather than buffering whatever the server streams.
+pub(crate) async fn collect_capped<B>(mut body: B, limit: usize) -> Result<Vec<u8>, Error>
+where
+    B: Body<Data = Bytes> + Unpin,
+    Error: From<B::Error>,
+{
+    let mut collected = Vec::new();
+    while let Some(frame) = body.frame().await {
+        let Ok(data) = frame?.into_data() else {
+            continue;
+        };
+        let within_limit = collected
+            .len()
+            .checked_add(data.len())
+            .is_some_and(|len| len <= limit);
+        if !within_limit {
+            return Err(Error::ResponseTooLarge { limit });
+        }
+        collected.extend_from_slice(&data);
+    }
+    Ok(collected)
+}
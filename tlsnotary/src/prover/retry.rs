@@ -0,0 +1,73 @@
+use std::time::Duration;
+
+use hyper::Method;
+
+const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+const DEFAULT_INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(8);
+
+/// How [`super::Prover::prove_with_retry`] handles transient origin failures.
+/// An MPC-TLS session is bound to one server connection, so each retry
+/// starts a fresh session with the verifier as well.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    idempotent_only: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            initial_backoff: DEFAULT_INITIAL_BACKOFF,
+            max_backoff: DEFAULT_MAX_BACKOFF,
+            idempotent_only: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Total attempts, including the first one.
+    #[must_use]
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Delay before the first retry; it doubles per retry up to `max_backoff`.
+    #[must_use]
+    pub fn initial_backoff(mut self, backoff: Duration) -> Self {
+        self.initial_backoff = backoff;
+        self
+    }
+
+    #[must_use]
+    pub fn max_backoff(mut self, backoff: Duration) -> Self {
+        self.max_backoff = backoff;
+        self
+    }
+
+    /// Whether non-idempotent requests are sent only once; on by default.
+    #[must_use]
+    pub fn idempotent_only(mut self, idempotent_only: bool) -> Self {
+        self.idempotent_only = idempotent_only;
+        self
+    }
+
+    pub(crate) fn attempts_for(&self, method: &Method) -> u32 {
+        if self.idempotent_only && !method.is_idempotent() {
+            1
+        } else {
+            self.max_attempts
+        }
+    }
+
+    /// Delay after failed attempt number `attempt`, counting from 1.
+    pub(crate) fn backoff(&self, attempt: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+            .min(self.max_backoff)
+    }
+}
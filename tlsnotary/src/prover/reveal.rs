@@ -1,13 +1,17 @@
 use std::ops::Range;
 
 use parser::{
-    HttpMessage, JsonFieldRangeExt,
+    HttpMessage, JsonFieldRangeExt, message_body_start,
     standard::{Body, Header, Request, Response},
 };
-use tlsn::{config::prove::ProveConfigBuilder, transcript::TranscriptCommitConfigBuilder};
+use tlsn::{
+    config::prove::ProveConfigBuilder,
+    hash::HashAlgId,
+    transcript::{Direction, TranscriptCommitConfigBuilder, TranscriptCommitmentKind},
+};
 use tracing::info;
 
-use crate::error::Error;
+use crate::{commitment::CommitmentLabel, error::Error, offset::TranscriptOffset};
 
 const MAX_LOG_SNIPPET_BYTES: usize = 96;
 
@@ -25,6 +29,13 @@ impl TranscriptDirection {
         }
     }
 
+    fn direction(self) -> Direction {
+        match self {
+            Self::Sent => Direction::Sent,
+            Self::Received => Direction::Received,
+        }
+    }
+
     fn apply_reveal(
         self,
         builder: &mut ProveConfigBuilder<'_>,
@@ -45,12 +56,20 @@ impl TranscriptDirection {
         self,
         builder: &mut TranscriptCommitConfigBuilder,
         range: &Range<usize>,
+        hash_alg: Option<HashAlgId>,
     ) -> Result<(), Error> {
-        match self {
-            Self::Sent => {
+        match (self, hash_alg) {
+            (_, Some(alg)) => {
+                builder.commit_with_kind(
+                    range,
+                    self.direction(),
+                    TranscriptCommitmentKind::Hash { alg },
+                )?;
+            }
+            (Self::Sent, None) => {
                 builder.commit_sent(range)?;
             }
-            Self::Received => {
+            (Self::Received, None) => {
                 builder.commit_recv(range)?;
             }
         }
@@ -108,16 +127,17 @@ fn log_disclosure(
     label: &str,
     range: &Range<usize>,
     source: &[u8],
+    transcript_range: &Range<usize>,
 ) {
     info!(
         direction = direction.label(),
         action = action.label(),
         target = %target,
         label = %label,
-        range_start = range.start,
-        range_end = range.end,
+        range_start = transcript_range.start,
+        range_end = transcript_range.end,
         preview = %preview_range(source, range),
-        "Applied transcript disclosure rule"
+        "Planned transcript disclosure rule"
     );
 }
 
@@ -136,11 +156,129 @@ fn log_unmatched_disclosure(
     );
 }
 
+/// A commitment planned for one message, in transcript coordinates.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PlannedCommit {
+    range: Range<usize>,
+    hash_alg: Option<HashAlgId>,
+}
+
+/// Collects the disclosures of one message; they reach the tlsn builders
+/// only in [`DisclosureBuilders::finish`], after overlap resolution.
 struct DisclosureBuilders<'builder, 'transcript> {
     prove_config: &'builder mut ProveConfigBuilder<'transcript>,
     transcript_commit_config: &'builder mut TranscriptCommitConfigBuilder<'transcript>,
+    commitment_labels: Vec<CommitmentLabel>,
+    reveals: Vec<Range<usize>>,
+    commits: Vec<PlannedCommit>,
+    hash_algs: CommitHashAlgs,
+    /// Where `source` starts in the transcript; rules match on `source`.
+    offset: TranscriptOffset,
+}
+
+impl<'builder, 'transcript> DisclosureBuilders<'builder, 'transcript> {
+    fn new(
+        prove_config: &'builder mut ProveConfigBuilder<'transcript>,
+        transcript_commit_config: &'builder mut TranscriptCommitConfigBuilder<'transcript>,
+        hash_algs: CommitHashAlgs,
+        offset: TranscriptOffset,
+    ) -> Self {
+        Self {
+            prove_config,
+            transcript_commit_config,
+            commitment_labels: Vec::new(),
+            reveals: Vec::new(),
+            commits: Vec::new(),
+            hash_algs,
+            offset,
+        }
+    }
+
+    /// Resolves overlaps between the collected disclosures and applies them.
+    fn finish(self, direction: TranscriptDirection) -> Result<Vec<CommitmentLabel>, Error> {
+        let (reveals, commits) = resolve_disclosures(direction, &self.reveals, &self.commits)?;
+        for range in &reveals {
+            direction.apply_reveal(self.prove_config, range)?;
+        }
+        for commit in &commits {
+            direction.apply_commit(
+                self.transcript_commit_config,
+                &commit.range,
+                commit.hash_alg,
+            )?;
+        }
+        Ok(self.commitment_labels)
+    }
 }
 
+/// Normalizes the disclosures of one message before they reach tlsn:
+///
+/// - identical commitments (same range and hash algorithm) are made once,
+///   and every label naming them is kept;
+/// - a range that is both revealed and committed exactly is contradictory
+///   and rejected;
+/// - a commitment inside a larger revealed range is cut out of it, so the
+///   committed bytes stay hidden;
+/// - any other overlap is kept as configured, e.g. fields revealed inside
+///   a full body commitment or a padded commitment reaching into a revealed
+///   neighbour.
+///
+/// Revealed ranges are then merged and empty ranges dropped.
+fn resolve_disclosures(
+    direction: TranscriptDirection,
+    reveals: &[Range<usize>],
+    commits: &[PlannedCommit],
+) -> Result<(Vec<Range<usize>>, Vec<PlannedCommit>), Error> {
+    let mut unique_commits: Vec<PlannedCommit> = Vec::with_capacity(commits.len());
+    for commit in commits {
+        if !unique_commits.contains(commit) {
+            unique_commits.push(commit.clone());
+        }
+    }
+
+    if let Some(commit) = unique_commits
+        .iter()
+        .find(|commit| !commit.range.is_empty() && reveals.contains(&commit.range))
+    {
+        return Err(Error::InvalidInput(format!(
+            "Range {:?} of the {} is both revealed and committed",
+            commit.range,
+            direction.label()
+        )));
+    }
+
+    let mut pieces = Vec::new();
+    for reveal in reveals {
+        let nested = unique_commits
+            .iter()
+            .map(|commit| commit.range.clone())
+            .filter(|range| {
+                !range.is_empty() && reveal.start <= range.start && range.end <= reveal.end
+            })
+            .collect::<Vec<_>>();
+        pieces.extend(
+            complement_ranges(reveal.end, &nested)
+                .into_iter()
+                .map(|range| range.start.max(reveal.start)..range.end),
+        );
+    }
+    pieces.retain(|range| !range.is_empty());
+    pieces.sort_by_key(|range| (range.start, range.end));
+
+    let mut merged: Vec<Range<usize>> = Vec::with_capacity(pieces.len());
+    for range in pieces {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+
+    unique_commits.retain(|commit| !commit.range.is_empty());
+    Ok((merged, unique_commits))
+}
+
+/// Plans `action` on `range` of the message `source`, shifted to the
+/// transcript by the builders' offset.
 fn apply_disclosure(
     direction: TranscriptDirection,
     action: DisclosureAction,
@@ -150,13 +288,33 @@ fn apply_disclosure(
     source: &[u8],
     builders: &mut DisclosureBuilders<'_, '_>,
 ) -> Result<(), Error> {
+    let transcript_range = builders.offset.to_transcript(range)?;
+    log_disclosure(
+        direction,
+        action,
+        target,
+        label,
+        range,
+        source,
+        &transcript_range,
+    );
     match action {
-        DisclosureAction::Reveal => direction.apply_reveal(builders.prove_config, range)?,
+        DisclosureAction::Reveal => {
+            builders.reveals.push(transcript_range);
+        }
         DisclosureAction::Commit => {
-            direction.apply_commit(builders.transcript_commit_config, range)?
+            let label = format!("{target}:{label}");
+            builders.commits.push(PlannedCommit {
+                range: transcript_range.clone(),
+                hash_alg: builders.hash_algs.for_label(&label),
+            });
+            builders.commitment_labels.push(CommitmentLabel {
+                label,
+                direction: direction.direction(),
+                range: transcript_range,
+            });
         }
     }
-    log_disclosure(direction, action, target, label, range, source);
     Ok(())
 }
 
@@ -227,6 +385,120 @@ impl KeyValueCommitConfig {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubRangeConfig {
+    pub keypath: String,
+    pub relative_range: Range<usize>,
+}
+
+impl SubRangeConfig {
+    #[must_use]
+    pub fn new(keypath: String, relative_range: Range<usize>) -> Self {
+        Self {
+            keypath,
+            relative_range,
+        }
+    }
+
+    fn absolute_range(&self, value: &Range<usize>) -> Result<Range<usize>, Error> {
+        if self.relative_range.start > self.relative_range.end
+            || self.relative_range.end > value.len()
+        {
+            return Err(Error::InvalidInput(format!(
+                "Sub-range {:?} out of bounds for keypath {} with value length {}",
+                self.relative_range,
+                self.keypath,
+                value.len()
+            )));
+        }
+        Ok(value.start + self.relative_range.start..value.start + self.relative_range.end)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommitKeyRevealValueConfig {
+    Header(String),
+    QuotedBodyField(String),
+    UnquotedBodyField(String),
+}
+
+impl CommitKeyRevealValueConfig {
+    fn name(&self) -> &str {
+        match self {
+            Self::Header(s) | Self::QuotedBodyField(s) | Self::UnquotedBodyField(s) => s,
+        }
+    }
+}
+
+/// Hash algorithms for the commitments of one direction. Anything left
+/// unset uses the prover's default `hash_alg`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommitHashAlgs {
+    pub default: Option<HashAlgId>,
+    /// Overrides keyed by commitment label, e.g. `body-field:.balance`.
+    pub by_label: Vec<(String, HashAlgId)>,
+}
+
+impl CommitHashAlgs {
+    #[must_use]
+    pub fn new(default: HashAlgId) -> Self {
+        Self {
+            default: Some(default),
+            by_label: Vec::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn with_label(mut self, label: impl Into<String>, alg: HashAlgId) -> Self {
+        self.by_label.push((label.into(), alg));
+        self
+    }
+
+    fn for_label(&self, label: &str) -> Option<HashAlgId> {
+        self.by_label
+            .iter()
+            .find(|(candidate, _)| candidate == label)
+            .map(|&(_, alg)| alg)
+            .or(self.default)
+    }
+}
+
+/// Which parts of the request line are revealed; the others are committed.
+/// The query includes its leading `?`, and the separators between the parts
+/// are always revealed. Everything is revealed by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RequestLineConfig {
+    pub reveal_method: bool,
+    pub reveal_path: bool,
+    pub reveal_query: bool,
+    pub reveal_version: bool,
+}
+
+impl RequestLineConfig {
+    /// Reveals only the method and protocol version, e.g. to prove which
+    /// host a request went to, through its `host` header, but not the
+    /// endpoint.
+    #[must_use]
+    pub fn hide_target() -> Self {
+        Self {
+            reveal_path: false,
+            reveal_query: false,
+            ..Self::default()
+        }
+    }
+}
+
+impl Default for RequestLineConfig {
+    fn default() -> Self {
+        Self {
+            reveal_method: true,
+            reveal_path: true,
+            reveal_query: true,
+            reveal_version: true,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct RevealConfig {
     pub reveal_headers: Vec<String>,
@@ -234,6 +506,22 @@ pub struct RevealConfig {
     pub reveal_body_fields: Vec<BodyFieldConfig>,
     pub commit_body_fields: Vec<BodyFieldConfig>,
     pub reveal_keys_commit_values: Vec<KeyValueCommitConfig>,
+    pub commit_keys_reveal_values: Vec<CommitKeyRevealValueConfig>,
+    pub reveal_sub_ranges: Vec<SubRangeConfig>,
+    pub commit_sub_ranges: Vec<SubRangeConfig>,
+    pub commit_full_body: bool,
+    /// Commits the status code and reason phrase instead of revealing them,
+    /// so only the status class can be proven later.
+    pub commit_status_class: bool,
+    pub commit_hash_algs: CommitHashAlgs,
+    /// Ranges relative to the request target that are committed instead of
+    /// revealed; the rest of the request line stays revealed.
+    pub commit_target_ranges: Vec<Range<usize>>,
+    /// Applies to requests only, like `commit_target_ranges`.
+    pub request_line: RequestLineConfig,
+    /// When set, the request body is revealed except for these ranges,
+    /// relative to the body start, which are committed instead.
+    pub body_commit_ranges: Option<Vec<Range<usize>>>,
 }
 
 impl RevealConfig {
@@ -255,8 +543,119 @@ impl RevealConfig {
             reveal_body_fields: vec![],
             commit_body_fields: vec![],
             reveal_keys_commit_values: vec![],
+            commit_keys_reveal_values: vec![],
+            reveal_sub_ranges: vec![],
+            commit_sub_ranges: vec![],
+            commit_full_body: false,
+            commit_status_class: false,
+            commit_hash_algs: CommitHashAlgs::default(),
+            commit_target_ranges: vec![],
+            request_line: RequestLineConfig::default(),
+            body_commit_ranges: None,
         }
     }
+
+    fn is_reveal_all(&self) -> bool {
+        self.reveal_headers.is_empty()
+            && self.commit_headers.is_empty()
+            && self.reveal_body_fields.is_empty()
+            && self.commit_body_fields.is_empty()
+            && self.reveal_keys_commit_values.is_empty()
+            && self.commit_keys_reveal_values.is_empty()
+            && self.reveal_sub_ranges.is_empty()
+            && self.commit_sub_ranges.is_empty()
+            && !self.commit_full_body
+            && !self.commit_status_class
+            && self.commit_target_ranges.is_empty()
+            && self.request_line == RequestLineConfig::default()
+            && self.body_commit_ranges.is_none()
+    }
+
+    fn has_body_field_rules(&self) -> bool {
+        !(self.reveal_body_fields.is_empty()
+            && self.commit_body_fields.is_empty()
+            && self.reveal_keys_commit_values.is_empty()
+            && self.commit_keys_reveal_values.is_empty()
+            && self.reveal_sub_ranges.is_empty()
+            && self.commit_sub_ranges.is_empty())
+    }
+
+    fn without_headers(&self, header_names: &[String]) -> Self {
+        let is_excluded = |name: &str| {
+            header_names
+                .iter()
+                .any(|excluded| excluded.eq_ignore_ascii_case(name))
+        };
+        Self {
+            reveal_headers: self
+                .reveal_headers
+                .iter()
+                .filter(|name| !is_excluded(name))
+                .cloned()
+                .collect(),
+            commit_headers: self
+                .commit_headers
+                .iter()
+                .filter(|name| !is_excluded(name))
+                .cloned()
+                .collect(),
+            commit_keys_reveal_values: self
+                .commit_keys_reveal_values
+                .iter()
+                .filter(|rule| match rule {
+                    CommitKeyRevealValueConfig::Header(name) => !is_excluded(name),
+                    CommitKeyRevealValueConfig::QuotedBodyField(_)
+                    | CommitKeyRevealValueConfig::UnquotedBodyField(_) => true,
+                })
+                .cloned()
+                .collect(),
+            ..self.clone()
+        }
+    }
+}
+
+struct SecretHeaderRanges {
+    label: String,
+    name: Range<usize>,
+    value: Range<usize>,
+}
+
+/// Splits each secret header into its name with the colon, which stays
+/// revealed, and the value through the line ending, which is only committed.
+fn secret_header_ranges(request: &Request, secret_headers: &[String]) -> Vec<SecretHeaderRanges> {
+    secret_headers
+        .iter()
+        .flat_map(|header_name| {
+            request
+                .headers
+                .get(&header_name.to_lowercase())
+                .into_iter()
+                .flatten()
+                .enumerate()
+                .map(move |(idx, header)| SecretHeaderRanges {
+                    label: format!("{header_name}[{idx}]"),
+                    name: header.name.start..header.name.end + 1,
+                    value: header.name.end + 1..header.raw.end,
+                })
+        })
+        .collect()
+}
+
+fn complement_ranges(len: usize, excluded: &[Range<usize>]) -> Vec<Range<usize>> {
+    let mut excluded = excluded.to_vec();
+    excluded.sort_by_key(|range| range.start);
+    let mut start = 0;
+    let mut ranges = Vec::new();
+    for range in excluded {
+        if range.start > start {
+            ranges.push(start..range.start);
+        }
+        start = start.max(range.end);
+    }
+    if start < len {
+        ranges.push(start..len);
+    }
+    ranges
 }
 
 fn apply_header_rules<M>(
@@ -275,7 +674,7 @@ where
         match message.headers().get(&key) {
             Some(headers) => {
                 for (idx, header) in headers.iter().enumerate() {
-                    let range = header.name.header_full_range(&header.value);
+                    let range = header.raw.clone();
                     let label = format!("{header_name}[{idx}]");
                     apply_disclosure(
                         direction, action, "header", &label, &range, source, builders,
@@ -367,28 +766,216 @@ where
     Ok(())
 }
 
+fn apply_commit_key_reveal_value_rules<M>(
+    direction: TranscriptDirection,
+    message: &M,
+    source: &[u8],
+    key_value_rules: &[CommitKeyRevealValueConfig],
+    builders: &mut DisclosureBuilders<'_, '_>,
+) -> Result<(), Error>
+where
+    M: HttpMessage<Header = Header, Body = Body>,
+{
+    for key_value_rule in key_value_rules {
+        let name = key_value_rule.name();
+        match key_value_rule {
+            CommitKeyRevealValueConfig::Header(header_name) => {
+                match message.headers().get(&header_name.to_lowercase()) {
+                    Some(headers) => {
+                        for (idx, header) in headers.iter().enumerate() {
+                            let label = format!("{header_name}[{idx}]");
+                            let value_range = header.name.end..header.raw.end;
+                            apply_disclosure(
+                                direction,
+                                DisclosureAction::Commit,
+                                "header-key",
+                                &label,
+                                &header.name,
+                                source,
+                                builders,
+                            )?;
+                            apply_disclosure(
+                                direction,
+                                DisclosureAction::Reveal,
+                                "header-value",
+                                &label,
+                                &value_range,
+                                source,
+                                builders,
+                            )?;
+                        }
+                    }
+                    None => log_unmatched_disclosure(
+                        direction,
+                        "commit+reveal",
+                        "header-key-value",
+                        name,
+                    ),
+                }
+            }
+            CommitKeyRevealValueConfig::QuotedBodyField(_)
+            | CommitKeyRevealValueConfig::UnquotedBodyField(_) => match message.body().get(name) {
+                Some(Body::KeyValue { key, value }) => {
+                    let value_range = match key_value_rule {
+                        CommitKeyRevealValueConfig::QuotedBodyField(_) => value.with_quotes(),
+                        CommitKeyRevealValueConfig::Header(_)
+                        | CommitKeyRevealValueConfig::UnquotedBodyField(_) => value.clone(),
+                    };
+                    apply_disclosure(
+                        direction,
+                        DisclosureAction::Commit,
+                        "body-key",
+                        name,
+                        &key.with_quotes_and_colon(),
+                        source,
+                        builders,
+                    )?;
+                    apply_disclosure(
+                        direction,
+                        DisclosureAction::Reveal,
+                        "body-value",
+                        name,
+                        &value_range,
+                        source,
+                        builders,
+                    )?;
+                }
+                Some(Body::Value(_)) => {
+                    return Err(Error::InvalidInput(format!(
+                        "Expected key-value pair for keypath {name}, got standalone value"
+                    )));
+                }
+                None => {
+                    log_unmatched_disclosure(direction, "commit+reveal", "body-key-value", name)
+                }
+            },
+        }
+    }
+
+    Ok(())
+}
+
+fn apply_sub_range_rules<M>(
+    direction: TranscriptDirection,
+    action: DisclosureAction,
+    message: &M,
+    source: &[u8],
+    sub_ranges: &[SubRangeConfig],
+    builders: &mut DisclosureBuilders<'_, '_>,
+) -> Result<(), Error>
+where
+    M: HttpMessage<Header = Header, Body = Body>,
+{
+    for sub_range in sub_ranges {
+        let keypath = sub_range.keypath.as_str();
+        match message.body().get(keypath) {
+            Some(Body::KeyValue { key, value }) => {
+                let range = sub_range.absolute_range(value)?;
+                if let DisclosureAction::Reveal = action {
+                    let quoted = source.get(value.start.wrapping_sub(1)) == Some(&b'"')
+                        && source.get(value.end) == Some(&b'"');
+                    if !quoted {
+                        return Err(Error::InvalidInput(format!(
+                            "Sub-range reveal for keypath {keypath} requires a quoted string value"
+                        )));
+                    }
+                    apply_disclosure(
+                        direction,
+                        DisclosureAction::Reveal,
+                        "body-key",
+                        keypath,
+                        &key.with_quotes_and_colon(),
+                        source,
+                        builders,
+                    )?;
+                    for quote in [value.start - 1..value.start, value.end..value.end + 1] {
+                        apply_disclosure(
+                            direction,
+                            DisclosureAction::Reveal,
+                            "body-quote",
+                            keypath,
+                            &quote,
+                            source,
+                            builders,
+                        )?;
+                    }
+                }
+                apply_disclosure(
+                    direction,
+                    action,
+                    "body-sub-range",
+                    keypath,
+                    &range,
+                    source,
+                    builders,
+                )?;
+            }
+            Some(Body::Value(_)) => {
+                return Err(Error::InvalidInput(format!(
+                    "Expected key-value pair for keypath {keypath}, got standalone value"
+                )));
+            }
+            None => log_unmatched_disclosure(direction, action.label(), "body-sub-range", keypath),
+        }
+    }
+
+    Ok(())
+}
+
+/// Commits the body, taken as everything after `body_start` when the
+/// head/body boundary is known and as the parsed root value otherwise.
+fn apply_full_body_commit_rule<M>(
+    direction: TranscriptDirection,
+    message: &M,
+    source: &[u8],
+    body_start: Option<usize>,
+    builders: &mut DisclosureBuilders<'_, '_>,
+) -> Result<(), Error>
+where
+    M: HttpMessage<Header = Header, Body = Body>,
+{
+    if let Some(body_start) = body_start.filter(|&start| start < source.len()) {
+        return apply_disclosure(
+            direction,
+            DisclosureAction::Commit,
+            "body",
+            "full",
+            &(body_start..source.len()),
+            source,
+            builders,
+        );
+    }
+    match message.body().get("") {
+        Some(Body::Value(range)) => apply_disclosure(
+            direction,
+            DisclosureAction::Commit,
+            "body",
+            "full",
+            range,
+            source,
+            builders,
+        ),
+        Some(Body::KeyValue { .. }) => Err(Error::InvalidInput(
+            "Expected standalone value at body root, got key-value pair".to_string(),
+        )),
+        None => {
+            log_unmatched_disclosure(direction, DisclosureAction::Commit.label(), "body", "full");
+            Ok(())
+        }
+    }
+}
+
 fn apply_message_reveal_config<M>(
     direction: TranscriptDirection,
     message: &M,
     source: &[u8],
-    start_line_label: &str,
-    start_line_range: Range<usize>,
+    body_start: Option<usize>,
     builders: &mut DisclosureBuilders<'_, '_>,
     config: &RevealConfig,
 ) -> Result<(), Error>
 where
     M: HttpMessage<Header = Header, Body = Body>,
 {
-    apply_disclosure(
-        direction,
-        DisclosureAction::Reveal,
-        "line",
-        start_line_label,
-        &start_line_range,
-        source,
-        builders,
-    )?;
-
     apply_header_rules(
         direction,
         DisclosureAction::Reveal,
@@ -406,6 +993,41 @@ where
         builders,
     )?;
 
+    if let Some(body_start) = parser::opaque_body_start(source) {
+        // A binary, encoded or non-JSON body has no fields to select; it is
+        // committed whole in place of whatever the field rules asked for.
+        if config.has_body_field_rules() && !config.commit_full_body {
+            apply_disclosure(
+                direction,
+                DisclosureAction::Commit,
+                "body",
+                "opaque",
+                &(body_start..source.len()),
+                source,
+                builders,
+            )?;
+        }
+    } else {
+        apply_message_body_rules(direction, message, source, builders, config)?;
+    }
+
+    if config.commit_full_body {
+        apply_full_body_commit_rule(direction, message, source, body_start, builders)?;
+    }
+
+    Ok(())
+}
+
+fn apply_message_body_rules<M>(
+    direction: TranscriptDirection,
+    message: &M,
+    source: &[u8],
+    builders: &mut DisclosureBuilders<'_, '_>,
+    config: &RevealConfig,
+) -> Result<(), Error>
+where
+    M: HttpMessage<Header = Header, Body = Body>,
+{
     apply_body_field_rules(
         direction,
         DisclosureAction::Reveal,
@@ -431,6 +1053,31 @@ where
         builders,
     )?;
 
+    apply_commit_key_reveal_value_rules(
+        direction,
+        message,
+        source,
+        &config.commit_keys_reveal_values,
+        builders,
+    )?;
+
+    apply_sub_range_rules(
+        direction,
+        DisclosureAction::Reveal,
+        message,
+        source,
+        &config.reveal_sub_ranges,
+        builders,
+    )?;
+    apply_sub_range_rules(
+        direction,
+        DisclosureAction::Commit,
+        message,
+        source,
+        &config.commit_sub_ranges,
+        builders,
+    )?;
+
     Ok(())
 }
 
@@ -439,18 +1086,132 @@ pub fn reveal_request<'transcript>(
     prove_config: &mut ProveConfigBuilder<'transcript>,
     transcript_commit_config: &mut TranscriptCommitConfigBuilder<'transcript>,
     config: &RevealConfig,
-) -> Result<(), Error> {
-    let mut builders = DisclosureBuilders {
+) -> Result<Vec<CommitmentLabel>, Error> {
+    reveal_request_with_secrets(
+        request,
+        TranscriptOffset::ZERO,
         prove_config,
         transcript_commit_config,
-    };
+        config,
+        &[],
+    )
+}
 
-    if config.reveal_headers.is_empty()
-        && config.commit_headers.is_empty()
-        && config.reveal_body_fields.is_empty()
-        && config.commit_body_fields.is_empty()
-        && config.reveal_keys_commit_values.is_empty()
-    {
+/// Like [`reveal_request`], for a request starting at `offset` in the sent
+/// transcript, e.g. the second exchange on a kept-alive connection.
+pub fn reveal_request_at<'transcript>(
+    request: &[u8],
+    offset: TranscriptOffset,
+    prove_config: &mut ProveConfigBuilder<'transcript>,
+    transcript_commit_config: &mut TranscriptCommitConfigBuilder<'transcript>,
+    config: &RevealConfig,
+) -> Result<Vec<CommitmentLabel>, Error> {
+    reveal_request_with_secrets(
+        request,
+        offset,
+        prove_config,
+        transcript_commit_config,
+        config,
+        &[],
+    )
+}
+
+/// Like [`reveal_request_at`], but the values of `secret_headers` are never
+/// revealed, whatever `config` says; only their names are revealed and
+/// their values are committed.
+pub fn reveal_request_with_secrets<'transcript>(
+    request: &[u8],
+    offset: TranscriptOffset,
+    prove_config: &mut ProveConfigBuilder<'transcript>,
+    transcript_commit_config: &mut TranscriptCommitConfigBuilder<'transcript>,
+    config: &RevealConfig,
+    secret_headers: &[String],
+) -> Result<Vec<CommitmentLabel>, Error> {
+    let mut builders = DisclosureBuilders::new(
+        prove_config,
+        transcript_commit_config,
+        config.commit_hash_algs.clone(),
+        offset,
+    );
+    plan_request_disclosures(request, config, secret_headers, &mut builders)?;
+    builders.finish(TranscriptDirection::Sent)
+}
+
+fn plan_request_disclosures(
+    request: &[u8],
+    config: &RevealConfig,
+    secret_headers: &[String],
+    builders: &mut DisclosureBuilders<'_, '_>,
+) -> Result<(), Error> {
+    if !secret_headers.is_empty() {
+        let parsed_request = Request::from_bytes(request)?;
+        if let Some(missing) = secret_headers
+            .iter()
+            .find(|name| !parsed_request.headers.contains_key(&name.to_lowercase()))
+        {
+            return Err(Error::InvalidInput(format!(
+                "Secret header {missing} missing from request transcript"
+            )));
+        }
+        let secrets = secret_header_ranges(&parsed_request, secret_headers);
+
+        if config.is_reveal_all() {
+            let secret_values = secrets
+                .iter()
+                .map(|secret| secret.value.clone())
+                .collect::<Vec<_>>();
+            for range in complement_ranges(request.len(), &secret_values) {
+                apply_disclosure(
+                    TranscriptDirection::Sent,
+                    DisclosureAction::Reveal,
+                    "message",
+                    "full",
+                    &range,
+                    request,
+                    builders,
+                )?;
+            }
+        } else {
+            apply_request_line_rule(&parsed_request, request, config, builders)?;
+            if let Some(body_commit_ranges) = &config.body_commit_ranges {
+                apply_request_body_rule(request, body_commit_ranges, builders)?;
+            }
+            apply_message_reveal_config(
+                TranscriptDirection::Sent,
+                &parsed_request,
+                request,
+                None,
+                builders,
+                &config.without_headers(secret_headers),
+            )?;
+            for secret in &secrets {
+                apply_disclosure(
+                    TranscriptDirection::Sent,
+                    DisclosureAction::Reveal,
+                    "secret-header-name",
+                    &secret.label,
+                    &secret.name,
+                    request,
+                    builders,
+                )?;
+            }
+        }
+
+        for secret in &secrets {
+            apply_disclosure(
+                TranscriptDirection::Sent,
+                DisclosureAction::Commit,
+                "secret-header-value",
+                &secret.label,
+                &secret.value,
+                request,
+                builders,
+            )?;
+        }
+        return Ok(());
+    }
+
+    if config.is_reveal_all() {
         let full_range = 0..request.len();
         apply_disclosure(
             TranscriptDirection::Sent,
@@ -459,24 +1220,165 @@ pub fn reveal_request<'transcript>(
             "full",
             &full_range,
             request,
-            &mut builders,
+            builders,
         )?;
         return Ok(());
     }
 
-    let raw_request_str = String::from_utf8(request.to_vec())?;
-    let parsed_request: Request = raw_request_str.parse()?;
-    let request_line_range =
-        parsed_request.method.start..parsed_request.protocol_version.with_newline().end;
+    let parsed_request = Request::from_bytes(request)?;
+    apply_request_line_rule(&parsed_request, request, config, builders)?;
+    if let Some(body_commit_ranges) = &config.body_commit_ranges {
+        apply_request_body_rule(request, body_commit_ranges, builders)?;
+    }
     apply_message_reveal_config(
         TranscriptDirection::Sent,
         &parsed_request,
         request,
-        "request-line",
-        request_line_range,
-        &mut builders,
+        None,
+        builders,
         config,
-    )
+    )?;
+    Ok(())
+}
+
+/// Reveals the request line except for the parts hidden by
+/// `config.request_line` and for `config.commit_target_ranges`, which are
+/// relative to the request target; both are committed instead.
+fn apply_request_line_rule(
+    request: &Request,
+    source: &[u8],
+    config: &RevealConfig,
+    builders: &mut DisclosureBuilders<'_, '_>,
+) -> Result<(), Error> {
+    let direction = TranscriptDirection::Sent;
+    let commit_target_ranges = &config.commit_target_ranges;
+    let line = request.method.start..request.protocol_version.with_newline().end;
+    let query_start = source
+        .get(request.url.clone())
+        .and_then(|target| target.iter().position(|&byte| byte == b'?'))
+        .map_or(request.url.end, |position| request.url.start + position);
+    let toggles = config.request_line;
+    let hidden_parts = [
+        ("method", toggles.reveal_method, request.method.clone()),
+        ("path", toggles.reveal_path, request.url.start..query_start),
+        ("query", toggles.reveal_query, query_start..request.url.end),
+        (
+            "version",
+            toggles.reveal_version,
+            request.protocol_version.clone(),
+        ),
+    ]
+    .into_iter()
+    .filter(|(_, revealed, range)| !revealed && !range.is_empty())
+    .map(|(part, _, range)| (part, range))
+    .collect::<Vec<_>>();
+    let committed = commit_target_ranges
+        .iter()
+        .map(|relative| {
+            if relative.start > relative.end || relative.end > request.url.len() {
+                return Err(Error::InvalidInput(format!(
+                    "Request target range {relative:?} out of bounds for target length {}",
+                    request.url.len()
+                )));
+            }
+            Ok(request.url.start + relative.start..request.url.start + relative.end)
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    let withheld = hidden_parts
+        .iter()
+        .map(|(_, range)| range.clone())
+        .chain(committed.iter().cloned())
+        .collect::<Vec<_>>();
+    for range in complement_ranges(line.end, &withheld)
+        .into_iter()
+        .map(|range| range.start.max(line.start)..range.end)
+        .filter(|range| !range.is_empty())
+    {
+        apply_disclosure(
+            direction,
+            DisclosureAction::Reveal,
+            "line",
+            "request-line",
+            &range,
+            source,
+            builders,
+        )?;
+    }
+    for (part, range) in &hidden_parts {
+        apply_disclosure(
+            direction,
+            DisclosureAction::Commit,
+            "request-line",
+            part,
+            range,
+            source,
+            builders,
+        )?;
+    }
+    for (relative, range) in commit_target_ranges.iter().zip(&committed) {
+        apply_disclosure(
+            direction,
+            DisclosureAction::Commit,
+            "request-target",
+            &format!("{}..{}", relative.start, relative.end),
+            range,
+            source,
+            builders,
+        )?;
+    }
+    Ok(())
+}
+
+/// Reveals the request body except for `body_commit_ranges`, which are
+/// relative to the body start and committed instead.
+fn apply_request_body_rule(
+    source: &[u8],
+    body_commit_ranges: &[Range<usize>],
+    builders: &mut DisclosureBuilders<'_, '_>,
+) -> Result<(), Error> {
+    let direction = TranscriptDirection::Sent;
+    let body_start = message_body_start(source).unwrap_or(source.len());
+    let body_len = source.len() - body_start;
+    let committed = body_commit_ranges
+        .iter()
+        .map(|relative| {
+            if relative.start > relative.end || relative.end > body_len {
+                return Err(Error::InvalidInput(format!(
+                    "Request body range {relative:?} out of bounds for body length {body_len}"
+                )));
+            }
+            Ok(body_start + relative.start..body_start + relative.end)
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    for range in complement_ranges(source.len(), &committed)
+        .into_iter()
+        .map(|range| range.start.max(body_start)..range.end)
+        .filter(|range| !range.is_empty())
+    {
+        apply_disclosure(
+            direction,
+            DisclosureAction::Reveal,
+            "body",
+            "request-body",
+            &range,
+            source,
+            builders,
+        )?;
+    }
+    for (relative, range) in body_commit_ranges.iter().zip(&committed) {
+        apply_disclosure(
+            direction,
+            DisclosureAction::Commit,
+            "request-body",
+            &format!("{}..{}", relative.start, relative.end),
+            range,
+            source,
+            builders,
+        )?;
+    }
+    Ok(())
 }
 
 pub fn reveal_response<'transcript>(
@@ -484,23 +1386,143 @@ pub fn reveal_response<'transcript>(
     prove_config: &mut ProveConfigBuilder<'transcript>,
     transcript_commit_config: &mut TranscriptCommitConfigBuilder<'transcript>,
     config: &RevealConfig,
-) -> Result<(), Error> {
-    let mut builders = DisclosureBuilders {
+) -> Result<Vec<CommitmentLabel>, Error> {
+    reveal_response_at(
+        response,
+        TranscriptOffset::ZERO,
+        prove_config,
+        transcript_commit_config,
+        config,
+    )
+}
+
+/// Like [`reveal_response`], for a response starting at `offset` in the
+/// received transcript.
+pub fn reveal_response_at<'transcript>(
+    response: &[u8],
+    offset: TranscriptOffset,
+    prove_config: &mut ProveConfigBuilder<'transcript>,
+    transcript_commit_config: &mut TranscriptCommitConfigBuilder<'transcript>,
+    config: &RevealConfig,
+) -> Result<Vec<CommitmentLabel>, Error> {
+    reveal_response_parts(
+        response,
+        None,
+        offset,
+        prove_config,
+        transcript_commit_config,
+        config,
+    )
+}
+
+/// Like [`reveal_response_at`], with the head/body boundary already known,
+/// e.g. [`ProverOutput::response_body_start`](crate::ProverOutput). A full
+/// body commitment then covers exactly the bytes after the head, however
+/// the server framed them.
+pub fn reveal_response_framed<'transcript>(
+    response: &[u8],
+    body_start: usize,
+    offset: TranscriptOffset,
+    prove_config: &mut ProveConfigBuilder<'transcript>,
+    transcript_commit_config: &mut TranscriptCommitConfigBuilder<'transcript>,
+    config: &RevealConfig,
+) -> Result<Vec<CommitmentLabel>, Error> {
+    if body_start > response.len() {
+        return Err(Error::InvalidInput(format!(
+            "Response body start {body_start} out of bounds for length {}",
+            response.len()
+        )));
+    }
+    reveal_response_parts(
+        response,
+        Some(body_start),
+        offset,
+        prove_config,
+        transcript_commit_config,
+        config,
+    )
+}
+
+fn reveal_response_parts<'transcript>(
+    response: &[u8],
+    body_start: Option<usize>,
+    offset: TranscriptOffset,
+    prove_config: &mut ProveConfigBuilder<'transcript>,
+    transcript_commit_config: &mut TranscriptCommitConfigBuilder<'transcript>,
+    config: &RevealConfig,
+) -> Result<Vec<CommitmentLabel>, Error> {
+    let mut builders = DisclosureBuilders::new(
         prove_config,
         transcript_commit_config,
-    };
+        config.commit_hash_algs.clone(),
+        offset,
+    );
 
-    let raw_response_str = String::from_utf8(response.to_vec())?;
-    let parsed_response: Response = raw_response_str.parse()?;
-    let status_line_range =
-        parsed_response.protocol_version.start..parsed_response.status.with_newline().end;
+    let parsed_response = Response::from_bytes(response)?;
+    if config.commit_status_class {
+        apply_status_class_rule(&parsed_response, response, &mut builders)?;
+    } else {
+        let status_line_range =
+            parsed_response.protocol_version.start..parsed_response.status.with_newline().end;
+        apply_disclosure(
+            TranscriptDirection::Received,
+            DisclosureAction::Reveal,
+            "line",
+            "status-line",
+            &status_line_range,
+            response,
+            &mut builders,
+        )?;
+    }
     apply_message_reveal_config(
         TranscriptDirection::Received,
         &parsed_response,
         response,
-        "status-line",
-        status_line_range,
+        body_start,
         &mut builders,
         config,
-    )
+    )?;
+    builders.finish(TranscriptDirection::Received)
+}
+
+/// Reveals the protocol version and the separators of the status line, and
+/// commits the status code and reason phrase separately.
+fn apply_status_class_rule(
+    response: &Response,
+    source: &[u8],
+    builders: &mut DisclosureBuilders<'_, '_>,
+) -> Result<(), Error> {
+    let direction = TranscriptDirection::Received;
+    let separator = response.status_code.end..response.status.start;
+    let line_end = response.status.end..response.status.with_newline().end;
+    for (label, range) in [
+        ("protocol-version", response.protocol_version_with_space()),
+        ("separator", separator),
+        ("line-end", line_end),
+    ] {
+        apply_disclosure(
+            direction,
+            DisclosureAction::Reveal,
+            "status-line",
+            label,
+            &range,
+            source,
+            builders,
+        )?;
+    }
+    for (label, range) in [
+        ("code", response.status_code.clone()),
+        ("reason", response.status.clone()),
+    ] {
+        apply_disclosure(
+            direction,
+            DisclosureAction::Commit,
+            "status-line",
+            label,
+            &range,
+            source,
+            builders,
+        )?;
+    }
+    Ok(())
 }
@@ -0,0 +1,53 @@
+use std::fmt;
+
+use hyper::header::{AUTHORIZATION, HeaderName, HeaderValue};
+
+use crate::error::Error;
+
+/// A request header the prover adds at send time and never reveals: the
+/// verifier sees the header name and a commitment to its value.
+#[derive(Clone)]
+pub struct SecretHeader {
+    name: String,
+    value: String,
+}
+
+impl SecretHeader {
+    #[must_use]
+    pub fn new(name: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            value: value.into(),
+        }
+    }
+
+    #[must_use]
+    pub fn bearer(token: impl AsRef<str>) -> Self {
+        Self::new(AUTHORIZATION.as_str(), format!("Bearer {}", token.as_ref()))
+    }
+
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub(crate) fn to_header(&self) -> Result<(HeaderName, HeaderValue), Error> {
+        let name = HeaderName::from_bytes(self.name.as_bytes()).map_err(|_| {
+            Error::InvalidConfig(format!("invalid secret header name {}", self.name))
+        })?;
+        let mut value = HeaderValue::from_str(&self.value).map_err(|_| {
+            Error::InvalidConfig(format!("invalid value for secret header {}", self.name))
+        })?;
+        value.set_sensitive(true);
+        Ok((name, value))
+    }
+}
+
+impl fmt::Debug for SecretHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SecretHeader")
+            .field("name", &self.name)
+            .field("value", &"<redacted>")
+            .finish()
+    }
+}
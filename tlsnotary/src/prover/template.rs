@@ -0,0 +1,204 @@
+use std::{collections::HashMap, io::Write, ops::Range};
+
+use flate2::{
+    Compression,
+    write::{GzEncoder, ZlibEncoder},
+};
+use http_body_util::Empty;
+use hyper::{
+    Method, Request,
+    body::Bytes,
+    header::{CONTENT_ENCODING, CONTENT_LENGTH},
+};
+
+use super::{RevealConfig, SecretHeader};
+use crate::error::Error;
+
+/// An HTTP request with `{placeholder}` substitutions in its path, header
+/// values and body. Static text is revealed; substituted path segments and
+/// body values are committed, and headers with substitutions are sent as
+/// [`SecretHeader`]s. A `{` not followed by a placeholder name is literal,
+/// so JSON bodies need no escaping.
+#[derive(Debug, Clone)]
+pub struct RequestTemplate {
+    method: Method,
+    path: String,
+    headers: Vec<(String, String)>,
+    body: Option<String>,
+    content_encoding: ContentEncoding,
+}
+
+/// How a [`RequestTemplate`] body is encoded on the wire.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ContentEncoding {
+    #[default]
+    Identity,
+    Gzip,
+    Deflate,
+}
+
+impl ContentEncoding {
+    fn header_value(self) -> Option<&'static str> {
+        match self {
+            Self::Identity => None,
+            Self::Gzip => Some("gzip"),
+            Self::Deflate => Some("deflate"),
+        }
+    }
+
+    fn encode(self, body: &[u8]) -> Result<Vec<u8>, Error> {
+        Ok(match self {
+            Self::Identity => body.to_vec(),
+            Self::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(body)?;
+                encoder.finish()?
+            }
+            Self::Deflate => {
+                let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(body)?;
+                encoder.finish()?
+            }
+        })
+    }
+}
+
+/// A rendered [`RequestTemplate`], ready for [`super::ProverBuilder::request_template`].
+#[derive(Debug)]
+pub struct RenderedRequest {
+    pub request: Request<Empty<Bytes>>,
+    /// The encoded body, sent after `request`'s head.
+    pub body: Bytes,
+    pub reveal_config: RevealConfig,
+    pub secret_headers: Vec<SecretHeader>,
+}
+
+impl RequestTemplate {
+    #[must_use]
+    pub fn new(method: Method, path: impl Into<String>) -> Self {
+        Self {
+            method,
+            path: path.into(),
+            headers: Vec::new(),
+            body: None,
+            content_encoding: ContentEncoding::Identity,
+        }
+    }
+
+    #[must_use]
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// `Content-Length` is computed when rendering; one set with
+    /// [`Self::header`] is replaced.
+    #[must_use]
+    pub fn body(mut self, template: impl Into<String>) -> Self {
+        self.body = Some(template.into());
+        self
+    }
+
+    /// Encodes the body and sets `Content-Encoding`. The encoded body is
+    /// revealed when it has no substitutions and committed whole otherwise,
+    /// since compressed placeholder values cannot be told apart.
+    #[must_use]
+    pub fn content_encoding(mut self, encoding: ContentEncoding) -> Self {
+        self.content_encoding = encoding;
+        self
+    }
+
+    pub fn render(&self, values: &HashMap<String, String>) -> Result<RenderedRequest, Error> {
+        let (path, commit_target_ranges) = substitute(&self.path, values)?;
+
+        let mut builder = Request::builder().method(self.method.clone()).uri(path);
+        let mut reveal_headers = Vec::new();
+        let mut secret_headers = Vec::new();
+        for (name, template) in &self.headers {
+            if self.body.is_some()
+                && (name.eq_ignore_ascii_case(CONTENT_LENGTH.as_str())
+                    || name.eq_ignore_ascii_case(CONTENT_ENCODING.as_str()))
+            {
+                continue;
+            }
+            let (value, substitutions) = substitute(template, values)?;
+            if substitutions.is_empty() {
+                builder = builder.header(name, value);
+                reveal_headers.push(name.clone());
+            } else {
+                secret_headers.push(SecretHeader::new(name.clone(), value));
+            }
+        }
+
+        let (body, body_commit_ranges) = match &self.body {
+            Some(template) => {
+                let (body, substitutions) = substitute(template, values)?;
+                let encoded = self.content_encoding.encode(body.as_bytes())?;
+                let body_commit_ranges = match self.content_encoding {
+                    ContentEncoding::Identity => substitutions,
+                    _ if substitutions.is_empty() => Vec::new(),
+                    _ => vec![0..encoded.len()],
+                };
+                if let Some(encoding) = self.content_encoding.header_value() {
+                    builder = builder.header(CONTENT_ENCODING, encoding);
+                    reveal_headers.push(CONTENT_ENCODING.to_string());
+                }
+                builder = builder.header(CONTENT_LENGTH, encoded.len());
+                reveal_headers.push(CONTENT_LENGTH.to_string());
+                (Bytes::from(encoded), Some(body_commit_ranges))
+            }
+            None => (Bytes::new(), None),
+        };
+
+        let request = builder
+            .body(Empty::<Bytes>::new())
+            .map_err(|error| Error::InvalidInput(format!("invalid rendered request: {error}")))?;
+        Ok(RenderedRequest {
+            request,
+            body,
+            reveal_config: RevealConfig {
+                reveal_headers,
+                commit_target_ranges,
+                body_commit_ranges,
+                ..RevealConfig::default()
+            },
+            secret_headers,
+        })
+    }
+}
+
+/// Replaces every `{name}` in `template` and returns the byte ranges of the
+/// substituted values in the output. A `{` that does not start a name, such
+/// as a JSON object's, is copied as-is.
+fn substitute(
+    template: &str,
+    values: &HashMap<String, String>,
+) -> Result<(String, Vec<Range<usize>>), Error> {
+    let mut output = String::with_capacity(template.len());
+    let mut ranges = Vec::new();
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        output.push_str(&rest[..open]);
+        if !rest[open + 1..].starts_with(is_placeholder_char) {
+            output.push('{');
+            rest = &rest[open + 1..];
+            continue;
+        }
+        let close = rest[open..].find('}').ok_or_else(|| {
+            Error::InvalidConfig(format!("unterminated placeholder in template {template}"))
+        })?;
+        let name = &rest[open + 1..open + close];
+        let value = values
+            .get(name)
+            .ok_or_else(|| Error::InvalidInput(format!("no value for placeholder {{{name}}}")))?;
+        ranges.push(output.len()..output.len() + value.len());
+        output.push_str(value);
+        rest = &rest[open + close + 1..];
+    }
+    output.push_str(rest);
+    Ok((output, ranges))
+}
+
+fn is_placeholder_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '-'
+}
@@ -0,0 +1,155 @@
+use std::{
+    io,
+    pin::Pin,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use futures::{AsyncRead, AsyncWrite};
+
+/// Traffic and timing of one MPC-TLS session, as seen from the local party.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SessionStats {
+    /// Bytes written to the counterparty (verifier for the prover, prover for the verifier).
+    pub bytes_sent: u64,
+    /// Bytes read from the counterparty.
+    pub bytes_received: u64,
+    pub phases: Vec<PhaseTiming>,
+}
+
+impl SessionStats {
+    #[must_use]
+    pub fn total_duration(&self) -> Duration {
+        self.phases.iter().map(|phase| phase.duration).sum()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PhaseTiming {
+    pub phase: &'static str,
+    pub duration: Duration,
+}
+
+#[derive(Debug, Default)]
+struct ByteCounters {
+    sent: AtomicU64,
+    received: AtomicU64,
+}
+
+/// Collects byte counts from a [`CountingStream`] and per-phase durations.
+#[derive(Debug)]
+pub(crate) struct StatsRecorder {
+    counters: Arc<ByteCounters>,
+    /// Bytes (sent, received) already counted when this recorder started.
+    baseline: (u64, u64),
+    phases: Vec<PhaseTiming>,
+    phase_start: Instant,
+}
+
+impl StatsRecorder {
+    pub(crate) fn new() -> Self {
+        Self {
+            counters: Arc::default(),
+            baseline: (0, 0),
+            phases: Vec::new(),
+            phase_start: Instant::now(),
+        }
+    }
+
+    /// A recorder for the next session over the same stream, counting only
+    /// the bytes from here on.
+    pub(crate) fn resume(&self) -> Self {
+        Self {
+            counters: Arc::clone(&self.counters),
+            baseline: self.totals(),
+            phases: Vec::new(),
+            phase_start: Instant::now(),
+        }
+    }
+
+    fn totals(&self) -> (u64, u64) {
+        (
+            self.counters.sent.load(Ordering::Relaxed),
+            self.counters.received.load(Ordering::Relaxed),
+        )
+    }
+
+    pub(crate) fn wrap<S>(&self, inner: S) -> CountingStream<S> {
+        CountingStream {
+            inner,
+            counters: Arc::clone(&self.counters),
+        }
+    }
+
+    /// Records the time since the previous phase ended.
+    pub(crate) fn finish_phase(&mut self, phase: &'static str) {
+        let now = Instant::now();
+        self.phases.push(PhaseTiming {
+            phase,
+            duration: now - self.phase_start,
+        });
+        self.phase_start = now;
+    }
+
+    pub(crate) fn finish(self) -> SessionStats {
+        let (sent, received) = self.totals();
+        SessionStats {
+            bytes_sent: sent - self.baseline.0,
+            bytes_received: received - self.baseline.1,
+            phases: self.phases,
+        }
+    }
+}
+
+pub(crate) struct CountingStream<S> {
+    inner: S,
+    counters: Arc<ByteCounters>,
+}
+
+impl<S> CountingStream<S> {
+    pub(crate) fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for CountingStream<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let result = Pin::new(&mut self.inner).poll_read(cx, buf);
+        if let Poll::Ready(Ok(n)) = result {
+            self.counters
+                .received
+                .fetch_add(n as u64, Ordering::Relaxed);
+        }
+        result
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for CountingStream<S> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let result = Pin::new(&mut self.inner).poll_write(cx, buf);
+        if let Poll::Ready(Ok(n)) = result {
+            self.counters.sent.fetch_add(n as u64, Ordering::Relaxed);
+        }
+        result
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_close(cx)
+    }
+}
@@ -17,7 +17,8 @@ use smol::net::unix::UnixStream;
 
 use crate::{
     CertificateDer, ExpectedValue, MpcTlsConfig, ProverOutput, RootCertStore, ServerName,
-    TlsClientConfig, TlsCommitConfig, Validator, VerifierConfig, prover::RevealConfig,
+    TlsClientConfig, TlsCommitConfig, Validator, VerifierConfig,
+    prover::{CommitHashAlgs, RequestLineConfig, RevealConfig},
     verifier::VerifierOutput,
 };
 
@@ -31,8 +32,10 @@ pub struct TestSockets {
 
 /// Creates Unix socket pairs for testing
 pub fn create_test_sockets() -> TestSockets {
-    let (prover_server_socket, server_socket) = UnixStream::pair().unwrap();
-    let (prover_verifier_socket, verifier_socket) = UnixStream::pair().unwrap();
+    let (prover_server_socket, server_socket) =
+        UnixStream::pair().expect("Socket pair should be created");
+    let (prover_verifier_socket, verifier_socket) =
+        UnixStream::pair().expect("Socket pair should be created");
 
     TestSockets {
         prover_server_socket,
@@ -55,7 +58,12 @@ pub fn create_test_request() -> Request<Empty<Bytes>> {
 
 /// Creates prover TLS and commit configurations with test settings
 pub fn create_prover_config(cert_bytes: Vec<u8>) -> (TlsClientConfig, TlsCommitConfig) {
-    let server_name = ServerName::Dns("localhost".to_string().try_into().unwrap());
+    let server_name = ServerName::Dns(
+        "localhost"
+            .to_string()
+            .try_into()
+            .expect("Server name should be valid"),
+    );
 
     let tls_client_config = TlsClientConfig::builder()
         .server_name(server_name)
@@ -63,7 +71,7 @@ pub fn create_prover_config(cert_bytes: Vec<u8>) -> (TlsClientConfig, TlsCommitC
             roots: vec![CertificateDer(cert_bytes)],
         })
         .build()
-        .unwrap();
+        .expect("TLS client config should build");
 
     let tls_commit_config = TlsCommitConfig::builder()
         .protocol(
@@ -71,10 +79,10 @@ pub fn create_prover_config(cert_bytes: Vec<u8>) -> (TlsClientConfig, TlsCommitC
                 .max_sent_data(MAX_SENT_DATA)
                 .max_recv_data(MAX_RECV_DATA)
                 .build()
-                .unwrap(),
+                .expect("MPC-TLS config should build"),
         )
         .build()
-        .unwrap();
+        .expect("TLS commit config should build");
 
     (tls_client_config, tls_commit_config)
 }
@@ -86,7 +94,7 @@ pub fn create_verifier_config(cert_bytes: Vec<u8>) -> VerifierConfig {
             roots: vec![CertificateDer(cert_bytes)],
         })
         .build()
-        .unwrap()
+        .expect("Verifier config should build")
 }
 
 /// Creates reveal configuration for request data
@@ -97,6 +105,15 @@ pub fn create_request_reveal_config() -> RevealConfig {
         reveal_body_fields: vec![],
         commit_body_fields: vec![],
         reveal_keys_commit_values: vec![],
+        commit_keys_reveal_values: vec![],
+        reveal_sub_ranges: vec![],
+        commit_sub_ranges: vec![],
+        commit_full_body: false,
+        commit_status_class: false,
+        commit_hash_algs: CommitHashAlgs::default(),
+        commit_target_ranges: vec![],
+        request_line: RequestLineConfig::default(),
+        body_commit_ranges: None,
     }
 }
 
@@ -110,6 +127,15 @@ pub fn create_response_reveal_config() -> RevealConfig {
         reveal_body_fields: vec![BodyFieldConfig::Quoted(".username".into())],
         commit_body_fields: vec![BodyFieldConfig::UnquotedPadded(".balance".into(), 12)],
         reveal_keys_commit_values: vec![],
+        commit_keys_reveal_values: vec![],
+        reveal_sub_ranges: vec![],
+        commit_sub_ranges: vec![],
+        commit_full_body: false,
+        commit_status_class: false,
+        commit_hash_algs: CommitHashAlgs::default(),
+        commit_target_ranges: vec![],
+        request_line: RequestLineConfig::default(),
+        body_commit_ranges: None,
     }
 }
 
@@ -135,7 +161,8 @@ pub fn verify_prover_output(prover_output: &ProverOutput) {
 /// Verifies basic verifier output properties
 pub fn verify_verifier_output_basic(verifier_output: &VerifierOutput) {
     assert_eq!(
-        verifier_output.server_name, "localhost",
+        verifier_output.server_name.as_deref(),
+        Some("localhost"),
         "Verifier should verify correct server name"
     );
 
@@ -227,7 +254,7 @@ pub fn verify_parsed_response(verifier_output: &VerifierOutput, received_data: &
                 panic!("Username should have a value");
             }
         }
-        parser::redacted::Body::Value(_) => {
+        parser::redacted::Body::Value(_) | parser::redacted::Body::PartialValue { .. } => {
             panic!("Username should be a key-value pair, not just a value");
         }
     }
@@ -235,12 +262,19 @@ pub fn verify_parsed_response(verifier_output: &VerifierOutput, received_data: &
 
 #[cfg(test)]
 mod integration {
-    use futures::join;
+    use futures::{AsyncReadExt, AsyncWriteExt, join};
     use server::{app::get_app, handle_connection};
     use shared::create_test_tls_config;
 
     use super::*;
-    use crate::{HashAlgId, Prover, Verifier};
+    use crate::{
+        BodyFieldConfig, CommitKeyRevealValueConfig, CommitmentDomain, Direction, Error, HashAlgId,
+        Predicate, Preset, ProtocolLimits, ProveConfig, Prover, ProverConfig, RequestTemplate,
+        SecretHeader, Session, SessionArtifacts, SubRangeConfig, TranscriptCommitConfig,
+        TranscriptOffset, Verifier, VerifierChannel, diff_outputs,
+        prover::{reveal_request_at, reveal_response_at},
+        resolve_commitment_id, resolve_commitment_labels, verify_body_commitment,
+    };
 
     #[test]
     fn test_end_to_end_proof_generation_and_verification() {
@@ -248,7 +282,8 @@ mod integration {
 
         smol::block_on(async {
             // Setup
-            let test_tls_config = create_test_tls_config().unwrap();
+            let test_tls_config =
+                create_test_tls_config().expect("Test TLS config should be created");
             let sockets = create_test_sockets();
 
             let (tls_client_config, tls_commit_config) =
@@ -268,13 +303,13 @@ mod integration {
                 .request_reveal_config(create_request_reveal_config())
                 .response_reveal_config(create_response_reveal_config())
                 .build()
-                .unwrap();
+                .expect("Prover should build");
 
             // Build verifier
             let verifier = Verifier::builder()
                 .verifier_config(verifier_config)
                 .build()
-                .unwrap();
+                .expect("Verifier should build");
 
             // Execute protocol
             let prover_task =
@@ -295,6 +330,13 @@ mod integration {
             // Verify verifier output
             verify_verifier_output_basic(&verifier_output);
 
+            // Session statistics cover both directions and every phase
+            for stats in [&prover_output.session_stats, &verifier_output.session_stats] {
+                assert!(stats.bytes_sent > 0, "MPC-TLS should send bytes");
+                assert!(stats.bytes_received > 0, "MPC-TLS should receive bytes");
+                assert_eq!(stats.phases.len(), 3);
+            }
+
             // Verify parsed structures
             let sent_data = String::from_utf8(verifier_output.transcript.sent_unsafe().to_vec())
                 .expect("Sent data should be valid UTF-8");
@@ -304,6 +346,644 @@ mod integration {
 
             verify_parsed_request(&verifier_output, &sent_data);
             verify_parsed_response(&verifier_output, &received_data);
+
+            // A single exchange starts at the beginning of both directions
+            assert_eq!(verifier_output.request_offset, TranscriptOffset::ZERO);
+            assert_eq!(verifier_output.response_offset, TranscriptOffset::ZERO);
+            assert_eq!(
+                verifier_output.message_bytes(Direction::Received),
+                verifier_output.transcript.received_unsafe()
+            );
+
+            // Stored artifacts re-verify offline to the same output
+            let stored = serde_json::to_string(&SessionArtifacts::from(&verifier_output))
+                .expect("Artifacts should serialize");
+            let mut artifacts: SessionArtifacts =
+                serde_json::from_str(&stored).expect("Artifacts should deserialize");
+            let reverified =
+                crate::offline::verify_session(&artifacts).expect("Stored artifacts should verify");
+            assert_eq!(reverified.server_name, verifier_output.server_name);
+            assert_eq!(reverified.transcript, verifier_output.transcript);
+            verify_parsed_response(&reverified, &received_data);
+
+            artifacts.response_offset = TranscriptOffset::new(received_data.len() + 1);
+            assert!(matches!(
+                crate::offline::verify_session(&artifacts),
+                Err(Error::InvalidTranscript(_))
+            ));
+        });
+    }
+
+    #[test]
+    fn test_verifier_channel_carries_several_sessions() {
+        shared::init_test_logging();
+
+        smol::block_on(async {
+            let test_tls_config =
+                create_test_tls_config().expect("Test TLS config should be created");
+            let (prover_verifier_socket, verifier_socket) =
+                UnixStream::pair().expect("Socket pair should be created");
+
+            let (tls_client_config, tls_commit_config) =
+                create_prover_config(test_tls_config.cert_bytes.clone());
+            let verifier = Verifier::builder()
+                .verifier_config(create_verifier_config(test_tls_config.cert_bytes))
+                .build()
+                .expect("Verifier should build");
+            let prover = Prover::builder()
+                .tls_client_config(tls_client_config)
+                .tls_commit_config(tls_commit_config)
+                .request(create_test_request())
+                .request_reveal_config(create_request_reveal_config())
+                .response_reveal_config(create_response_reveal_config())
+                .build()
+                .expect("Prover should build");
+
+            let prover_task = async {
+                let mut channel = VerifierChannel::open(prover_verifier_socket);
+                let mut outputs = Vec::new();
+                for _ in 0..2 {
+                    let (prover_server_socket, server_socket) =
+                        UnixStream::pair().expect("Socket pair should be created");
+                    let server_task = handle_connection(
+                        get_app(create_test_balances()),
+                        test_tls_config.server_config.clone(),
+                        server_socket,
+                    );
+                    let (server_result, prover_result) = join!(
+                        server_task,
+                        prover.prove_on(&mut channel, prover_server_socket)
+                    );
+                    server_result.expect("Server should complete successfully");
+                    outputs.push(prover_result.expect("Prover should complete successfully"));
+                }
+                channel
+                    .close()
+                    .await
+                    .expect("Verifier channel should close cleanly");
+                outputs
+            };
+            let (prover_outputs, verifier_result) =
+                join!(prover_task, verifier.verify_sessions(verifier_socket, 2));
+            let verifier_outputs = verifier_result.expect("Verifier should complete successfully");
+
+            assert_eq!(prover_outputs.len(), 2);
+            assert_eq!(verifier_outputs.len(), 2);
+            for (prover_output, verifier_output) in prover_outputs.iter().zip(&verifier_outputs) {
+                verify_prover_output(prover_output);
+                verify_verifier_output_basic(verifier_output);
+                // Each session counts only its own traffic on the shared connection
+                assert!(prover_output.session_stats.bytes_sent > 0);
+                assert!(verifier_output.session_stats.bytes_received > 0);
+            }
+
+            assert!(diff_outputs(&verifier_outputs[0], &verifier_outputs[0]).is_empty());
+            let diff = diff_outputs(&verifier_outputs[0], &verifier_outputs[1]);
+            assert!(
+                diff.commitments.is_empty(),
+                "Identical sessions should commit to the same ranges: {:?}",
+                diff.commitments
+            );
+            assert!(diff.field(Direction::Received, ".username").is_none());
+        });
+    }
+
+    #[test]
+    fn test_prover_fans_out_to_two_verifiers() {
+        shared::init_test_logging();
+
+        smol::block_on(async {
+            let test_tls_config =
+                create_test_tls_config().expect("Test TLS config should be created");
+            let first = create_test_sockets();
+            let second = create_test_sockets();
+
+            let (tls_client_config, tls_commit_config) =
+                create_prover_config(test_tls_config.cert_bytes.clone());
+            let prover = Prover::builder()
+                .tls_client_config(tls_client_config)
+                .tls_commit_config(tls_commit_config)
+                .request(create_test_request())
+                .request_reveal_config(create_request_reveal_config())
+                .response_reveal_config(create_response_reveal_config())
+                .build()
+                .expect("Prover should build");
+            let verifier = || {
+                Verifier::builder()
+                    .verifier_config(create_verifier_config(test_tls_config.cert_bytes.clone()))
+                    .build()
+                    .expect("Verifier should build")
+            };
+
+            let server_task = |socket| {
+                handle_connection(
+                    get_app(create_test_balances()),
+                    test_tls_config.server_config.clone(),
+                    socket,
+                )
+            };
+            let (first_served, second_served, proved, first_verified, second_verified) = join!(
+                server_task(first.server_socket),
+                server_task(second.server_socket),
+                prover.prove_to_both(
+                    (first.prover_verifier_socket, first.prover_server_socket),
+                    (second.prover_verifier_socket, second.prover_server_socket),
+                ),
+                verifier().verify(first.verifier_socket),
+                verifier().verify(second.verifier_socket),
+            );
+            first_served.expect("First server should complete successfully");
+            second_served.expect("Second server should complete successfully");
+            let fan_out = proved.expect("Prover should complete successfully");
+            let first_output = first_verified.expect("First verifier should complete successfully");
+            let second_output =
+                second_verified.expect("Second verifier should complete successfully");
+
+            verify_prover_output(&fan_out.first);
+            verify_prover_output(&fan_out.second);
+            verify_verifier_output_basic(&first_output);
+            verify_verifier_output_basic(&second_output);
+
+            let balance = fan_out
+                .link("body:.balance")
+                .expect("Balance should be linked");
+            assert!(balance.plaintext_matches);
+            assert_eq!(balance.first, balance.second);
+            assert_eq!(fan_out.links.len(), fan_out.first.commitment_labels.len());
+            assert!(fan_out.is_consistent());
+            // Blinded commitments over the same bytes still differ per session
+            let first_hashes = resolve_commitment_labels(
+                &fan_out.first.commitment_labels,
+                &first_output.transcript_commitments,
+            )
+            .expect("Every commitment label should resolve to a commitment");
+            let second_hashes = resolve_commitment_labels(
+                &fan_out.second.commitment_labels,
+                &second_output.transcript_commitments,
+            )
+            .expect("Every commitment label should resolve to a commitment");
+            assert_ne!(
+                first_hashes[0].1.hash.value.as_bytes(),
+                second_hashes[0].1.hash.value.as_bytes()
+            );
+        });
+    }
+
+    #[test]
+    fn test_transcript_offset_converts_ranges() {
+        let offset = TranscriptOffset::new(120);
+
+        assert_eq!(
+            offset.to_transcript(&(4..10)).expect("range fits"),
+            124..130
+        );
+        assert_eq!(offset.to_message(&(124..130)), Some(4..10));
+        assert_eq!(offset.to_message(&(100..130)), None);
+        assert_eq!(
+            TranscriptOffset::ZERO
+                .to_transcript(&(4..10))
+                .expect("range fits"),
+            4..10
+        );
+    }
+
+    #[test]
+    fn test_transcript_offset_rejects_overflowing_ranges() {
+        let offset = TranscriptOffset::new(usize::MAX);
+
+        assert!(matches!(
+            offset.to_transcript(&(0..1)),
+            Err(Error::InvalidTranscript(_))
+        ));
+        assert_eq!(
+            offset.to_transcript(&(0..0)).expect("range fits"),
+            usize::MAX..usize::MAX
+        );
+    }
+
+    #[test]
+    fn test_verifier_locates_the_last_of_pipelined_exchanges() {
+        shared::init_test_logging();
+
+        smol::block_on(async {
+            let test_tls_config =
+                create_test_tls_config().expect("Test TLS config should be created");
+            let sockets = create_test_sockets();
+
+            let (tls_client_config, tls_commit_config) =
+                create_prover_config(test_tls_config.cert_bytes.clone());
+            let verifier = Verifier::builder()
+                .verifier_config(create_verifier_config(test_tls_config.cert_bytes))
+                .build()
+                .expect("Verifier should build");
+
+            let mut balances = create_test_balances();
+            balances.insert("bob".to_string(), 250);
+            let server_task = handle_connection(
+                get_app(balances),
+                test_tls_config.server_config,
+                sockets.server_socket,
+            );
+
+            let first_request = b"GET /api/balance/bob HTTP/1.1\r\nhost: localhost\r\n\r\n";
+            let second_request = b"GET /api/balance/alice HTTP/1.1\r\ncontent-type: application/json\r\nconnection: close\r\n\r\n";
+            let prover_task = async {
+                let (driver, mut handle) = Session::new(sockets.prover_verifier_socket).split();
+                smol::spawn(driver).detach();
+                let prover = handle
+                    .new_prover(
+                        ProverConfig::builder()
+                            .build()
+                            .expect("Prover config should build"),
+                    )
+                    .expect("Prover should start");
+                let prover = prover
+                    .commit(tls_commit_config)
+                    .await
+                    .expect("Prover should commit to the protocol");
+                let (mut connection, prover_fut) = prover
+                    .connect(tls_client_config, sockets.prover_server_socket)
+                    .await
+                    .expect("Prover should connect to the server");
+
+                // Both requests go out before either response is read.
+                let exchange = async {
+                    connection
+                        .write_all(&[first_request.as_slice(), second_request].concat())
+                        .await
+                        .expect("Requests should be sent");
+                    connection
+                        .flush()
+                        .await
+                        .expect("Requests should be flushed");
+                    let mut received = Vec::new();
+                    connection
+                        .read_to_end(&mut received)
+                        .await
+                        .expect("Responses should be received");
+                    connection.close().await.expect("Connection should close");
+                };
+                let (prover, ()) = join!(prover_fut, exchange);
+                let mut prover = prover.expect("MPC-TLS should complete");
+
+                let transcript = prover.transcript().clone();
+                let response_start = parser::last_response_start(transcript.received());
+                let (first_sent, second_sent) = transcript.sent().split_at(first_request.len());
+                let (first_received, second_received) =
+                    transcript.received().split_at(response_start);
+                let mut prove_config_builder = ProveConfig::builder(&transcript);
+                prove_config_builder.server_identity();
+                let mut transcript_commit_builder = TranscriptCommitConfig::builder(&transcript);
+
+                // The first exchange is revealed whole, so its framing tells
+                // the verifier where the second one starts.
+                prove_config_builder
+                    .reveal_sent(&(0..first_sent.len()))
+                    .expect("First request should be revealed");
+                prove_config_builder
+                    .reveal_recv(&(0..first_received.len()))
+                    .expect("First response should be revealed");
+                reveal_request_at(
+                    second_sent,
+                    TranscriptOffset::new(first_sent.len()),
+                    &mut prove_config_builder,
+                    &mut transcript_commit_builder,
+                    &create_request_reveal_config(),
+                )
+                .expect("Second request should be disclosed");
+                reveal_response_at(
+                    second_received,
+                    TranscriptOffset::new(response_start),
+                    &mut prove_config_builder,
+                    &mut transcript_commit_builder,
+                    &create_response_reveal_config(),
+                )
+                .expect("Second response should be disclosed");
+                prove_config_builder.transcript_commit(
+                    transcript_commit_builder
+                        .build()
+                        .expect("Commit config should build"),
+                );
+                let prove_config = prove_config_builder
+                    .build()
+                    .expect("Prove config should build");
+
+                prover
+                    .prove(&prove_config)
+                    .await
+                    .expect("Prover should prove");
+                prover.close().await.expect("Prover should close");
+                handle.close();
+                (first_sent.len(), response_start)
+            };
+
+            let (server_result, (request_start, response_start), verifier_result) = join!(
+                server_task,
+                prover_task,
+                verifier.verify(sockets.verifier_socket)
+            );
+            server_result.expect("Server should complete successfully");
+            let verifier_output = verifier_result.expect("Verifier should complete successfully");
+
+            assert!(response_start > 0, "Responses should have been pipelined");
+            assert_eq!(
+                verifier_output.request_offset,
+                TranscriptOffset::new(request_start)
+            );
+            assert_eq!(
+                verifier_output.response_offset,
+                TranscriptOffset::new(response_start)
+            );
+            assert!(
+                verifier_output
+                    .message_bytes(Direction::Sent)
+                    .starts_with(b"GET /api/balance/alice")
+            );
+
+            // Parsed ranges index the second exchange, not the transcript.
+            let sent_data = String::from_utf8_lossy(verifier_output.message_bytes(Direction::Sent));
+            let received_data =
+                String::from_utf8_lossy(verifier_output.message_bytes(Direction::Received));
+            verify_parsed_request(&verifier_output, &sent_data);
+            verify_parsed_response(&verifier_output, &received_data);
+
+            let stored = SessionArtifacts::from(&verifier_output);
+            let reverified =
+                crate::offline::verify_session(&stored).expect("Stored artifacts should verify");
+            verify_parsed_response(&reverified, &received_data);
+        });
+    }
+
+    #[test]
+    fn test_response_body_is_capped_while_streaming() {
+        use http_body_util::StreamBody;
+        use hyper::body::Frame;
+
+        use crate::prover::collect_capped;
+
+        let chunk = || Ok::<_, std::io::Error>(Frame::data(Bytes::from(vec![b'a'; 1024])));
+
+        smol::block_on(async {
+            let body = StreamBody::new(futures::stream::iter((0..4).map(|_| chunk())));
+            let collected = collect_capped(body, 4096)
+                .await
+                .expect("A body at the limit should be collected");
+            assert_eq!(collected.len(), 4096);
+
+            let body = StreamBody::new(futures::stream::iter((0..4).map(|_| chunk())));
+            let collected = collect_capped(body, usize::MAX)
+                .await
+                .expect("An uncapped body should be collected");
+            assert_eq!(collected.len(), 4096);
+
+            let empty = StreamBody::new(futures::stream::iter(std::iter::empty::<
+                Result<Frame<Bytes>, std::io::Error>,
+            >()));
+            let collected = collect_capped(empty, 0)
+                .await
+                .expect("An empty body fits a zero limit");
+            assert!(collected.is_empty());
+            let body = StreamBody::new(futures::stream::iter((0..1).map(|_| chunk())));
+            assert!(matches!(
+                collect_capped(body, 0).await,
+                Err(Error::ResponseTooLarge { limit: 0 })
+            ));
+
+            let body = StreamBody::new(futures::stream::iter((0..4).map(|_| chunk())));
+            assert!(matches!(
+                collect_capped(body, 4095).await,
+                Err(Error::ResponseTooLarge { limit: 4095 })
+            ));
+
+            let endless = StreamBody::new(futures::stream::repeat_with(chunk));
+            assert!(
+                matches!(
+                    collect_capped(endless, MAX_RECV_DATA).await,
+                    Err(Error::ResponseTooLarge { .. })
+                ),
+                "An endless body should stop at the limit"
+            );
+        });
+    }
+
+    #[test]
+    fn test_policy_macro_matches_preset() {
+        let preset = Preset::BalanceCheck {
+            username_path: ".username".to_string(),
+            username: "alice".to_string(),
+            balance_path: ".balance".to_string(),
+        };
+
+        let (request_config, response_config, validator) = crate::policy! {
+            server_name: "localhost",
+            hash_alg: HashAlgId::BLAKE3,
+            request {
+                commit header "authorization";
+            }
+            response {
+                reveal body ".username" == "alice";
+                commit body ".balance" padded 12 where Predicate::GreaterThan(100);
+            }
+        };
+
+        assert_eq!(request_config.commit_headers, vec!["authorization"]);
+        assert_eq!(
+            response_config,
+            RevealConfig {
+                commit_hash_algs: CommitHashAlgs::new(HashAlgId::BLAKE3),
+                ..RevealConfig::preset(&preset)
+            }
+        );
+        let expected = Validator::builder()
+            .expected_server_name("localhost")
+            .expected_hash_alg(HashAlgId::BLAKE3)
+            .response_body_field_equals(".username", ExpectedValue::from("alice"))
+            .response_committed_field_satisfies(".balance", Predicate::GreaterThan(100))
+            .build();
+        assert_eq!(format!("{validator:?}"), format!("{expected:?}"));
+    }
+
+    #[test]
+    fn test_reveal_config_derived_from_validator() {
+        let (request_config, response_config, validator) = crate::policy! {
+            hash_alg: HashAlgId::BLAKE3,
+            request {
+                reveal header "host" == "localhost";
+            }
+            response {
+                reveal header "content-type" == "application/json";
+                reveal body ".username" == "alice";
+                commit body ".balance" padded 12 where Predicate::GreaterThan(100);
+            }
+        };
+
+        assert_eq!(
+            RevealConfig::derive_from(&validator, Direction::Sent),
+            request_config
+        );
+        assert_eq!(
+            RevealConfig::derive_from(&validator, Direction::Received),
+            response_config
+        );
+
+        let header_only = Validator::builder()
+            .response_header_equals("Date", "Thu, 01 Jan 2026 00:00:00 GMT")
+            .response_header_equals("date", "Thu, 01 Jan 2026 00:00:00 GMT")
+            .build();
+        let derived = RevealConfig::derive_from(&header_only, Direction::Received);
+        assert_eq!(derived.reveal_headers, vec!["date"]);
+        assert_eq!(derived.commit_hash_algs, CommitHashAlgs::default());
+        assert!(!derived.commit_status_class);
+    }
+
+    #[test]
+    fn test_server_identity_can_be_withheld() {
+        shared::init_test_logging();
+
+        smol::block_on(async {
+            let test_tls_config =
+                create_test_tls_config().expect("Test TLS config should be created");
+            let sockets = create_test_sockets();
+
+            let (tls_client_config, tls_commit_config) =
+                create_prover_config(test_tls_config.cert_bytes.clone());
+            let verifier_config = create_verifier_config(test_tls_config.cert_bytes);
+
+            let app = get_app(create_test_balances());
+            let server_task =
+                handle_connection(app, test_tls_config.server_config, sockets.server_socket);
+
+            let prover = Prover::builder()
+                .tls_client_config(tls_client_config)
+                .tls_commit_config(tls_commit_config)
+                .request(create_test_request())
+                .request_reveal_config(create_request_reveal_config())
+                .response_reveal_config(create_response_reveal_config())
+                .prove_server_identity(false)
+                .build()
+                .expect("Prover should build");
+
+            let verifier = Verifier::builder()
+                .verifier_config(verifier_config)
+                .build()
+                .expect("Verifier should build");
+
+            let prover_task =
+                prover.prove(sockets.prover_verifier_socket, sockets.prover_server_socket);
+            let verifier_task = verifier.verify(sockets.verifier_socket);
+
+            let (_, _, verifier_result) = join!(server_task, prover_task, verifier_task);
+            let verifier_output =
+                verifier_result.expect("Verifier should accept a withheld server identity");
+
+            assert_eq!(verifier_output.server_name, None);
+            assert!(
+                Validator::builder()
+                    .expected_server_name("localhost")
+                    .build()
+                    .validate(&verifier_output)
+                    .is_err(),
+                "Expecting a server name should fail without a proven identity"
+            );
+        });
+    }
+
+    #[test]
+    fn test_prove_and_reclaim_returns_the_verifier_socket() {
+        shared::init_test_logging();
+
+        smol::block_on(async {
+            let test_tls_config =
+                create_test_tls_config().expect("Test TLS config should be created");
+            let sockets = create_test_sockets();
+
+            let (tls_client_config, tls_commit_config) =
+                create_prover_config(test_tls_config.cert_bytes.clone());
+            let verifier_config = create_verifier_config(test_tls_config.cert_bytes);
+
+            let app = get_app(create_test_balances());
+            let server_task =
+                handle_connection(app, test_tls_config.server_config, sockets.server_socket);
+
+            let prover = Prover::builder()
+                .tls_client_config(tls_client_config)
+                .tls_commit_config(tls_commit_config)
+                .request(create_test_request())
+                .request_reveal_config(create_request_reveal_config())
+                .response_reveal_config(create_response_reveal_config())
+                .build()
+                .expect("Prover should build");
+            let prover_task = prover
+                .prove_and_reclaim(sockets.prover_verifier_socket, sockets.prover_server_socket);
+
+            let verifier = Verifier::builder()
+                .verifier_config(verifier_config)
+                .build()
+                .expect("Verifier should build");
+            let verifier_task = verifier.verify(sockets.verifier_socket);
+
+            let (_, prover_result, verifier_result) =
+                join!(server_task, prover_task, verifier_task);
+            let (prover_output, _verifier_socket) =
+                prover_result.expect("Prover should complete successfully");
+            let verifier_output = verifier_result.expect("Verifier should complete successfully");
+
+            assert_eq!(prover_output.attempts, 1);
+            assert_eq!(
+                verifier_output.transcript_commitments.len(),
+                prover_output.transcript_commitments.len()
+            );
+        });
+    }
+
+    #[test]
+    fn test_verifier_rejects_limits_outside_policy() {
+        shared::init_test_logging();
+
+        smol::block_on(async {
+            let test_tls_config =
+                create_test_tls_config().expect("Test TLS config should be created");
+            let sockets = create_test_sockets();
+
+            let (tls_client_config, tls_commit_config) =
+                create_prover_config(test_tls_config.cert_bytes.clone());
+            let verifier_config = create_verifier_config(test_tls_config.cert_bytes);
+
+            let app = get_app(create_test_balances());
+            let server_task =
+                handle_connection(app, test_tls_config.server_config, sockets.server_socket);
+
+            let prover = Prover::builder()
+                .tls_client_config(tls_client_config)
+                .tls_commit_config(tls_commit_config)
+                .request(create_test_request())
+                .request_reveal_config(create_request_reveal_config())
+                .response_reveal_config(create_response_reveal_config())
+                .build()
+                .expect("Prover should build");
+
+            let verifier = Verifier::builder()
+                .verifier_config(verifier_config)
+                .limits(ProtocolLimits::new().max_sent_data(MAX_SENT_DATA / 2))
+                .server_limits("example.com", ProtocolLimits::new())
+                .server_limits(
+                    "localhost",
+                    ProtocolLimits::new().max_sent_data(MAX_SENT_DATA / 2),
+                )
+                .build()
+                .expect("Verifier should build");
+
+            let prover_task =
+                prover.prove(sockets.prover_verifier_socket, sockets.prover_server_socket);
+            let verifier_task = verifier.verify(sockets.verifier_socket);
+
+            // Setup passes on example.com's looser limits; localhost's own
+            // limits are checked once its identity is proven.
+            let (_, _, verifier_result) = join!(server_task, prover_task, verifier_task);
+            assert!(
+                matches!(verifier_result, Err(Error::PolicyViolation(ref reason)) if reason.contains("localhost")),
+                "Verifier should reject limits outside the server's policy, got {verifier_result:?}"
+            );
         });
     }
 
@@ -312,7 +992,8 @@ mod integration {
         shared::init_test_logging();
 
         smol::block_on(async {
-            let test_tls_config = create_test_tls_config().unwrap();
+            let test_tls_config =
+                create_test_tls_config().expect("Test TLS config should be created");
             let sockets = create_test_sockets();
 
             let (tls_client_config, tls_commit_config) =
@@ -330,12 +1011,12 @@ mod integration {
                 .request_reveal_config(create_request_reveal_config())
                 .response_reveal_config(create_response_reveal_config())
                 .build()
-                .unwrap();
+                .expect("Prover should build");
 
             let verifier = Verifier::builder()
                 .verifier_config(verifier_config)
                 .build()
-                .unwrap();
+                .expect("Verifier should build");
 
             let prover_task =
                 prover.prove(sockets.prover_verifier_socket, sockets.prover_server_socket);
@@ -354,6 +1035,41 @@ mod integration {
                 !prover_output.transcript_secrets.is_empty(),
                 "Should have transcript secrets"
             );
+
+            assert!(prover_output.response_head.starts_with(b"HTTP/1.1 200"));
+            assert!(prover_output.response_head.ends_with(b"\r\n\r\n"));
+            assert_eq!(
+                prover_output.received[..prover_output.response_body_start],
+                prover_output.response_head[..]
+            );
+
+            let labelled = resolve_commitment_labels(
+                &prover_output.commitment_labels,
+                &prover_output.transcript_commitments,
+            )
+            .expect("Every commitment label should resolve to a commitment");
+            assert_eq!(labelled.len(), prover_output.commitment_labels.len());
+            assert!(
+                labelled.iter().any(|(label, hash)| label == "body:.balance"
+                    && hash.direction == Direction::Received),
+                "Should label the committed balance field"
+            );
+
+            let commitments = &prover_output.transcript_commitments;
+            for (_, hash) in &labelled {
+                let id = CommitmentDomain::of(commitments, hash)
+                    .expect("Labelled commitments belong to the session")
+                    .id(hash);
+                let resolved = resolve_commitment_id(commitments, &id, hash.direction)
+                    .expect("A commitment id should resolve in its own direction");
+                assert_eq!(resolved.hash.value.as_bytes(), hash.hash.value.as_bytes());
+
+                let other = match hash.direction {
+                    Direction::Sent => Direction::Received,
+                    Direction::Received => Direction::Sent,
+                };
+                assert!(resolve_commitment_id(commitments, &id, other).is_err());
+            }
         });
     }
 
@@ -362,7 +1078,8 @@ mod integration {
         shared::init_test_logging();
 
         smol::block_on(async {
-            let test_tls_config = create_test_tls_config().unwrap();
+            let test_tls_config =
+                create_test_tls_config().expect("Test TLS config should be created");
             let sockets = create_test_sockets();
 
             let (tls_client_config, tls_commit_config) =
@@ -380,12 +1097,12 @@ mod integration {
                 .request_reveal_config(create_request_reveal_config())
                 .response_reveal_config(create_response_reveal_config())
                 .build()
-                .unwrap();
+                .expect("Prover should build");
 
             let verifier = Verifier::builder()
                 .verifier_config(verifier_config)
                 .build()
-                .unwrap();
+                .expect("Verifier should build");
 
             let prover_task =
                 prover.prove(sockets.prover_verifier_socket, sockets.prover_server_socket);
@@ -408,7 +1125,8 @@ mod integration {
         shared::init_test_logging();
 
         smol::block_on(async {
-            let test_tls_config = create_test_tls_config().unwrap();
+            let test_tls_config =
+                create_test_tls_config().expect("Test TLS config should be created");
             let sockets = create_test_sockets();
 
             let (tls_client_config, tls_commit_config) =
@@ -426,12 +1144,12 @@ mod integration {
                 .request_reveal_config(create_request_reveal_config())
                 .response_reveal_config(create_response_reveal_config())
                 .build()
-                .unwrap();
+                .expect("Prover should build");
 
             let verifier = Verifier::builder()
                 .verifier_config(verifier_config)
                 .build()
-                .unwrap();
+                .expect("Verifier should build");
 
             let prover_task =
                 prover.prove(sockets.prover_verifier_socket, sockets.prover_server_socket);
@@ -455,7 +1173,8 @@ mod integration {
         shared::init_test_logging();
 
         smol::block_on(async {
-            let test_tls_config = create_test_tls_config().unwrap();
+            let test_tls_config =
+                create_test_tls_config().expect("Test TLS config should be created");
             let sockets = create_test_sockets();
 
             let (tls_client_config, tls_commit_config) =
@@ -475,12 +1194,12 @@ mod integration {
                 .request_reveal_config(create_request_reveal_config())
                 .response_reveal_config(create_response_reveal_config())
                 .build()
-                .unwrap();
+                .expect("Prover should build");
 
             let verifier = Verifier::builder()
                 .verifier_config(verifier_config)
                 .build()
-                .unwrap();
+                .expect("Verifier should build");
 
             let prover_task =
                 prover.prove(sockets.prover_verifier_socket, sockets.prover_server_socket);
@@ -502,6 +1221,17 @@ mod integration {
                 .validate(&verifier_output)
                 .expect("Validation should pass with correct properties");
 
+            // Test validator with a per-direction hash algorithm override
+            let wrong_hash_validator = Validator::builder()
+                .expected_hash_alg(HashAlgId::BLAKE3)
+                .expected_direction_hash_alg(Direction::Received, HashAlgId::SHA256)
+                .build();
+
+            assert!(
+                wrong_hash_validator.validate(&verifier_output).is_err(),
+                "Validation should fail when received commitments use another hash algorithm"
+            );
+
             // Test validator with wrong server name
             let wrong_validator = Validator::builder()
                 .expected_server_name("wronghost")
@@ -531,6 +1261,1009 @@ mod integration {
                 wrong_body_validator.validate(&verifier_output).is_err(),
                 "Validation should fail with wrong body field value"
             );
+
+            // Committed-field predicates need proofs to pass
+            let predicate_validator = Validator::builder()
+                .response_committed_field_satisfies(".balance", Predicate::GreaterThan(50))
+                .build();
+
+            assert!(
+                predicate_validator.validate(&verifier_output).is_err(),
+                "Validation should fail when no predicate proofs are supplied"
+            );
+
+            // Merged validators report every failing assertion
+            let report = wrong_header_validator
+                .merge(wrong_body_validator)
+                .expect("Validators without conflicting expectations should merge")
+                .merge(validator)
+                .expect("Validators without conflicting expectations should merge")
+                .validate_all(&verifier_output);
+
+            assert!(!report.passed(), "Merged report should contain failures");
+            assert_eq!(report.failures().count(), 2);
+            assert!(
+                report
+                    .outcomes
+                    .iter()
+                    .any(|outcome| outcome.actual.as_deref() == Some("alice") && !outcome.passed()),
+                "Report should include the actual username value"
+            );
+
+            // Balance-check preset matches the hand-assembled configuration
+            let balance_preset = Preset::BalanceCheck {
+                username_path: ".username".into(),
+                username: "alice".into(),
+                balance_path: ".balance".into(),
+            };
+            assert_eq!(
+                RevealConfig::preset(&balance_preset),
+                create_response_reveal_config()
+            );
+            Validator::preset(&balance_preset, "localhost")
+                .validate(&verifier_output)
+                .expect("Balance-check preset validation should pass");
+
+            let conflict = Validator::builder()
+                .expected_server_name("localhost")
+                .build()
+                .merge(wrong_validator);
+            assert!(
+                conflict.is_err(),
+                "Merging conflicting server names should fail"
+            );
         });
     }
+
+    #[test]
+    fn test_full_body_commitment_with_field_openings() {
+        shared::init_test_logging();
+
+        smol::block_on(async {
+            let test_tls_config =
+                create_test_tls_config().expect("Test TLS config should be created");
+            let sockets = create_test_sockets();
+
+            let (tls_client_config, tls_commit_config) =
+                create_prover_config(test_tls_config.cert_bytes.clone());
+            let verifier_config = create_verifier_config(test_tls_config.cert_bytes);
+
+            let app = get_app(create_test_balances());
+            let server_task =
+                handle_connection(app, test_tls_config.server_config, sockets.server_socket);
+
+            let response_reveal_config = RevealConfig {
+                commit_full_body: true,
+                ..create_response_reveal_config()
+            };
+
+            let prover = Prover::builder()
+                .tls_client_config(tls_client_config)
+                .tls_commit_config(tls_commit_config)
+                .request(create_test_request())
+                .request_reveal_config(create_request_reveal_config())
+                .response_reveal_config(response_reveal_config)
+                .build()
+                .expect("Prover should build");
+
+            let verifier = Verifier::builder()
+                .verifier_config(verifier_config)
+                .build()
+                .expect("Verifier should build");
+
+            let prover_task =
+                prover.prove(sockets.prover_verifier_socket, sockets.prover_server_socket);
+            let verifier_task = verifier.verify(sockets.verifier_socket);
+
+            let (_, _, verifier_result) = join!(server_task, prover_task, verifier_task);
+
+            let verifier_output = verifier_result.expect("Verifier should complete successfully");
+
+            let body_commitment = verify_body_commitment(&verifier_output, Direction::Received)
+                .expect("Field commitments should be consistent with the body commitment");
+
+            let response = verifier_output.message_bytes(Direction::Received);
+            let body_start = parser::message_body_start(response)
+                .expect("Response head should end in the transcript");
+            let response_start = verifier_output.response_offset.message_start();
+            assert_eq!(
+                body_commitment.body_range,
+                response_start + body_start..response_start + response.len(),
+                "Body commitment should cover exactly the response body"
+            );
+            assert_eq!(
+                body_commitment.body.idx.min(),
+                Some(body_commitment.body_range.start)
+            );
+            assert_eq!(
+                body_commitment.body.idx.end(),
+                Some(body_commitment.body_range.end)
+            );
+            assert_eq!(
+                body_commitment.openings.len(),
+                1,
+                "Balance commitment should be an opening of the body commitment"
+            );
+        });
+    }
+
+    #[test]
+    fn test_commit_key_reveal_value() {
+        shared::init_test_logging();
+
+        smol::block_on(async {
+            let test_tls_config =
+                create_test_tls_config().expect("Test TLS config should be created");
+            let sockets = create_test_sockets();
+
+            let (tls_client_config, tls_commit_config) =
+                create_prover_config(test_tls_config.cert_bytes.clone());
+            let verifier_config = create_verifier_config(test_tls_config.cert_bytes);
+
+            let app = get_app(create_test_balances());
+            let server_task =
+                handle_connection(app, test_tls_config.server_config, sockets.server_socket);
+
+            let response_reveal_config = RevealConfig {
+                reveal_body_fields: vec![],
+                commit_keys_reveal_values: vec![CommitKeyRevealValueConfig::QuotedBodyField(
+                    ".username".into(),
+                )],
+                ..create_response_reveal_config()
+            };
+
+            let prover = Prover::builder()
+                .tls_client_config(tls_client_config)
+                .tls_commit_config(tls_commit_config)
+                .request(create_test_request())
+                .request_reveal_config(create_request_reveal_config())
+                .response_reveal_config(response_reveal_config)
+                .build()
+                .expect("Prover should build");
+
+            let verifier = Verifier::builder()
+                .verifier_config(verifier_config)
+                .build()
+                .expect("Verifier should build");
+
+            let prover_task =
+                prover.prove(sockets.prover_verifier_socket, sockets.prover_server_socket);
+            let verifier_task = verifier.verify(sockets.verifier_socket);
+
+            let (_, _, verifier_result) = join!(server_task, prover_task, verifier_task);
+
+            let verifier_output = verifier_result.expect("Verifier should complete successfully");
+            let parsed_response = verifier_output
+                .parsed_response
+                .as_ref()
+                .expect("Should have parsed response");
+            let received_data =
+                String::from_utf8_lossy(verifier_output.transcript.received_unsafe());
+
+            assert!(
+                !parsed_response.body.contains_key(".username"),
+                "Username key should stay hidden"
+            );
+            match parsed_response.body.get(".?[0]") {
+                Some(parser::redacted::Body::Value(range)) => {
+                    assert_eq!(&received_data[range.clone()], "alice");
+                }
+                other => panic!("Hidden-key value should be revealed, got {other:?}"),
+            }
+        });
+    }
+
+    #[test]
+    fn test_commit_nested_in_reveal_stays_hidden() {
+        shared::init_test_logging();
+
+        smol::block_on(async {
+            let test_tls_config =
+                create_test_tls_config().expect("Test TLS config should be created");
+            let sockets = create_test_sockets();
+
+            let (tls_client_config, tls_commit_config) =
+                create_prover_config(test_tls_config.cert_bytes.clone());
+            let verifier_config = create_verifier_config(test_tls_config.cert_bytes);
+
+            let app = get_app(create_test_balances());
+            let server_task =
+                handle_connection(app, test_tls_config.server_config, sockets.server_socket);
+
+            let response_reveal_config = RevealConfig {
+                commit_body_fields: vec![
+                    BodyFieldConfig::UnquotedPadded(".balance".into(), 12),
+                    BodyFieldConfig::UnquotedPadded(".balance".into(), 12),
+                ],
+                commit_sub_ranges: vec![SubRangeConfig::new(".username".into(), 0..5)],
+                ..create_response_reveal_config()
+            };
+
+            let prover = Prover::builder()
+                .tls_client_config(tls_client_config)
+                .tls_commit_config(tls_commit_config)
+                .request(create_test_request())
+                .request_reveal_config(create_request_reveal_config())
+                .response_reveal_config(response_reveal_config)
+                .build()
+                .expect("Prover should build");
+
+            let verifier = Verifier::builder()
+                .verifier_config(verifier_config)
+                .build()
+                .expect("Verifier should build");
+
+            let prover_task =
+                prover.prove(sockets.prover_verifier_socket, sockets.prover_server_socket);
+            let verifier_task = verifier.verify(sockets.verifier_socket);
+
+            let (_, prover_result, verifier_result) =
+                join!(server_task, prover_task, verifier_task);
+
+            let prover_output = prover_result.expect("Prover should complete successfully");
+            let verifier_output = verifier_result.expect("Verifier should complete successfully");
+
+            let labelled = resolve_commitment_labels(
+                &prover_output.commitment_labels,
+                &prover_output.transcript_commitments,
+            )
+            .expect("Every commitment label should resolve to a commitment");
+            let balance_hashes = labelled
+                .iter()
+                .filter(|(label, _)| label == "body:.balance")
+                .map(|(_, hash)| hash.hash.value.as_bytes())
+                .collect::<Vec<_>>();
+            assert_eq!(
+                balance_hashes.len(),
+                2,
+                "Both balance labels should be kept"
+            );
+            assert_eq!(
+                balance_hashes[0], balance_hashes[1],
+                "Duplicate balance commitments should be made once"
+            );
+            assert!(
+                labelled
+                    .iter()
+                    .any(|(label, _)| label == "body-sub-range:.username"),
+                "Nested username value should be committed"
+            );
+
+            let received_data =
+                String::from_utf8_lossy(verifier_output.transcript.received_unsafe());
+            assert!(
+                received_data.contains("username"),
+                "Username key should stay revealed"
+            );
+            assert!(
+                !received_data.contains("alice"),
+                "Committed username value should be cut out of the reveal"
+            );
+        });
+    }
+
+    #[test]
+    fn test_identical_reveal_and_commit_is_rejected() {
+        let response =
+            b"HTTP/1.1 200 OK\r\ncontent-type: application/json\r\n\r\n{\"username\":\"alice\"}";
+        let transcript = tlsn::transcript::Transcript::new(b"".to_vec(), response.to_vec());
+        let mut prove_config_builder = ProveConfig::builder(&transcript);
+        let mut transcript_commit_builder = TranscriptCommitConfig::builder(&transcript);
+
+        let config = RevealConfig {
+            reveal_body_fields: vec![BodyFieldConfig::Quoted(".username".into())],
+            commit_body_fields: vec![BodyFieldConfig::Quoted(".username".into())],
+            ..RevealConfig::new()
+        };
+
+        let result = crate::prover::reveal_response(
+            response,
+            &mut prove_config_builder,
+            &mut transcript_commit_builder,
+            &config,
+        );
+        assert!(
+            matches!(result, Err(Error::InvalidInput(ref reason)) if reason.contains("both revealed and committed")),
+            "Identical reveal and commit should be rejected, got {result:?}"
+        );
+    }
+
+    #[test]
+    fn test_secret_header_is_committed_not_revealed() {
+        shared::init_test_logging();
+
+        smol::block_on(async {
+            let test_tls_config =
+                create_test_tls_config().expect("Test TLS config should be created");
+            let sockets = create_test_sockets();
+
+            let (tls_client_config, tls_commit_config) =
+                create_prover_config(test_tls_config.cert_bytes.clone());
+            let verifier_config = create_verifier_config(test_tls_config.cert_bytes);
+
+            let app = get_app(create_test_balances());
+            let server_task =
+                handle_connection(app, test_tls_config.server_config, sockets.server_socket);
+
+            let prover = Prover::builder()
+                .tls_client_config(tls_client_config)
+                .tls_commit_config(tls_commit_config)
+                .request(create_test_request())
+                .request_reveal_config(create_request_reveal_config())
+                .response_reveal_config(create_response_reveal_config())
+                .secret_header(SecretHeader::bearer("s3cr3t-token"))
+                .build()
+                .expect("Prover should build");
+
+            let verifier = Verifier::builder()
+                .verifier_config(verifier_config)
+                .build()
+                .expect("Verifier should build");
+
+            let prover_task =
+                prover.prove(sockets.prover_verifier_socket, sockets.prover_server_socket);
+            let verifier_task = verifier.verify(sockets.verifier_socket);
+
+            let (_, prover_result, verifier_result) =
+                join!(server_task, prover_task, verifier_task);
+
+            let prover_output = prover_result.expect("Prover should complete successfully");
+            let verifier_output = verifier_result.expect("Verifier should complete successfully");
+
+            assert!(
+                prover_output
+                    .commitment_labels
+                    .iter()
+                    .any(|label| label.label == "secret-header-value:authorization[0]"),
+                "Secret header value should be committed"
+            );
+
+            let sent_data = String::from_utf8_lossy(verifier_output.transcript.sent_unsafe());
+            assert!(
+                !sent_data.contains("s3cr3t-token"),
+                "Secret header value should not be revealed"
+            );
+            let parsed_request = verifier_output
+                .parsed_request
+                .as_ref()
+                .expect("Should have parsed request");
+            let authorization = &parsed_request
+                .headers
+                .get("authorization")
+                .expect("Secret header name should be revealed")[0];
+            assert_eq!(authorization.value, None);
+        });
+    }
+
+    #[test]
+    fn test_request_line_hides_target_but_reveals_method_and_version() {
+        shared::init_test_logging();
+
+        smol::block_on(async {
+            let test_tls_config =
+                create_test_tls_config().expect("Test TLS config should be created");
+            let sockets = create_test_sockets();
+
+            let (tls_client_config, tls_commit_config) =
+                create_prover_config(test_tls_config.cert_bytes.clone());
+            let verifier_config = create_verifier_config(test_tls_config.cert_bytes);
+
+            let app = get_app(create_test_balances());
+            let server_task =
+                handle_connection(app, test_tls_config.server_config, sockets.server_socket);
+
+            let request_reveal_config = RevealConfig {
+                request_line: RequestLineConfig::hide_target(),
+                ..create_request_reveal_config()
+            };
+
+            let prover = Prover::builder()
+                .tls_client_config(tls_client_config)
+                .tls_commit_config(tls_commit_config)
+                .request(create_test_request())
+                .request_reveal_config(request_reveal_config)
+                .response_reveal_config(create_response_reveal_config())
+                .build()
+                .expect("Prover should build");
+
+            let verifier = Verifier::builder()
+                .verifier_config(verifier_config)
+                .build()
+                .expect("Verifier should build");
+
+            let prover_task =
+                prover.prove(sockets.prover_verifier_socket, sockets.prover_server_socket);
+            let verifier_task = verifier.verify(sockets.verifier_socket);
+
+            let (_, prover_result, verifier_result) =
+                join!(server_task, prover_task, verifier_task);
+
+            let prover_output = prover_result.expect("Prover should complete successfully");
+            let verifier_output = verifier_result.expect("Verifier should complete successfully");
+
+            let labels = prover_output
+                .commitment_labels
+                .iter()
+                .map(|label| label.label.as_str())
+                .collect::<Vec<_>>();
+            assert!(labels.contains(&"request-line:path"));
+            assert!(
+                !labels.contains(&"request-line:query"),
+                "An absent query should not be committed"
+            );
+            assert!(!labels.contains(&"request-line:method"));
+
+            let sent_data = String::from_utf8_lossy(verifier_output.transcript.sent_unsafe());
+            let hidden_path = "\0".repeat("/api/balance/alice".len());
+            assert!(sent_data.starts_with(&format!("GET {hidden_path} HTTP/1.1")));
+            assert!(sent_data.contains("content-type: application/json"));
+        });
+    }
+
+    #[test]
+    fn test_request_template_commits_substituted_path_segment() {
+        shared::init_test_logging();
+
+        smol::block_on(async {
+            let test_tls_config =
+                create_test_tls_config().expect("Test TLS config should be created");
+            let sockets = create_test_sockets();
+
+            let (tls_client_config, tls_commit_config) =
+                create_prover_config(test_tls_config.cert_bytes.clone());
+            let verifier_config = create_verifier_config(test_tls_config.cert_bytes);
+
+            let app = get_app(create_test_balances());
+            let server_task =
+                handle_connection(app, test_tls_config.server_config, sockets.server_socket);
+
+            let values = HashMap::from([
+                ("user".to_string(), "alice".to_string()),
+                ("key".to_string(), "k3y".to_string()),
+            ]);
+            let rendered = RequestTemplate::new(hyper::Method::GET, "/api/balance/{user}")
+                .header("content-type", "application/json")
+                .header("Connection", "close")
+                .header("x-api-key", "{key}")
+                .render(&values)
+                .expect("Template should render");
+            let prover = Prover::builder()
+                .tls_client_config(tls_client_config)
+                .tls_commit_config(tls_commit_config)
+                .request_template(rendered)
+                .response_reveal_config(create_response_reveal_config())
+                .build()
+                .expect("Prover should build");
+
+            let verifier = Verifier::builder()
+                .verifier_config(verifier_config)
+                .build()
+                .expect("Verifier should build");
+
+            let prover_task =
+                prover.prove(sockets.prover_verifier_socket, sockets.prover_server_socket);
+            let verifier_task = verifier.verify(sockets.verifier_socket);
+
+            let (_, prover_result, verifier_result) =
+                join!(server_task, prover_task, verifier_task);
+
+            let prover_output = prover_result.expect("Prover should complete successfully");
+            let verifier_output = verifier_result.expect("Verifier should complete successfully");
+
+            let labels = prover_output
+                .commitment_labels
+                .iter()
+                .map(|label| label.label.as_str())
+                .collect::<Vec<_>>();
+            assert!(labels.contains(&"request-target:13..18"));
+            assert!(labels.contains(&"secret-header-value:x-api-key[0]"));
+
+            let sent_data = String::from_utf8_lossy(verifier_output.transcript.sent_unsafe());
+            assert!(sent_data.starts_with("GET /api/balance/\0\0\0\0\0 HTTP/1.1"));
+            assert!(!sent_data.contains("k3y"));
+            assert!(sent_data.contains("content-type: application/json"));
+        });
+    }
+
+    #[test]
+    fn test_request_template_body_framing_and_encoding() {
+        use std::io::Read;
+
+        use crate::ContentEncoding;
+
+        let values = HashMap::from([("amount".to_string(), "250".to_string())]);
+        let template = RequestTemplate::new(hyper::Method::POST, "/api/transfer")
+            .header("content-type", "application/json")
+            .header("content-length", "0")
+            .body(r#"{"to":"bob","amount":{amount}}"#);
+
+        let rendered = template.render(&values).expect("Template should render");
+        assert_eq!(rendered.body.as_ref(), br#"{"to":"bob","amount":250}"#);
+        assert_eq!(
+            rendered
+                .request
+                .headers()
+                .get_all("content-length")
+                .iter()
+                .count(),
+            1
+        );
+        assert_eq!(rendered.request.headers()["content-length"], "25");
+        assert_eq!(
+            rendered.reveal_config.body_commit_ranges,
+            Some(vec![21..24])
+        );
+
+        let rendered = template
+            .content_encoding(ContentEncoding::Gzip)
+            .render(&values)
+            .expect("Template should render");
+        let mut decoded = String::new();
+        flate2::read::GzDecoder::new(rendered.body.as_ref())
+            .read_to_string(&mut decoded)
+            .expect("Rendered body should be valid gzip");
+        assert_eq!(decoded, r#"{"to":"bob","amount":250}"#);
+        assert_eq!(rendered.request.headers()["content-encoding"], "gzip");
+        assert_eq!(
+            rendered.request.headers()["content-length"],
+            rendered.body.len().to_string().as_str()
+        );
+        assert_eq!(
+            rendered.reveal_config.body_commit_ranges,
+            Some(vec![0..rendered.body.len()])
+        );
+    }
+
+    #[test]
+    fn test_oauth_protected_profile_with_secret_bearer_token() {
+        use http_body_util::BodyExt;
+        use server::app::{MOCK_CLIENT_SECRET, TokenResponse};
+        use tower::ServiceExt;
+
+        shared::init_test_logging();
+
+        smol::block_on(async {
+            let test_tls_config =
+                create_test_tls_config().expect("Test TLS config should be created");
+            let sockets = create_test_sockets();
+
+            let (tls_client_config, tls_commit_config) =
+                create_prover_config(test_tls_config.cert_bytes.clone());
+            let verifier_config = create_verifier_config(test_tls_config.cert_bytes);
+
+            let app = get_app(create_test_balances());
+            let token_response = app
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .method("POST")
+                        .uri("/oauth/token")
+                        .header("content-type", "application/x-www-form-urlencoded")
+                        .body(axum::body::Body::from(format!(
+                            "grant_type=client_credentials&client_id=alice&client_secret={MOCK_CLIENT_SECRET}"
+                        )))
+                        .expect("Failed to build token request"),
+                )
+                .await
+                .expect("Token request should succeed");
+            let token_body = token_response
+                .into_body()
+                .collect()
+                .await
+                .expect("Token body should be readable")
+                .to_bytes();
+            let token: TokenResponse =
+                serde_json::from_slice(&token_body).expect("Token response should be JSON");
+
+            let server_task =
+                handle_connection(app, test_tls_config.server_config, sockets.server_socket);
+
+            let request = Request::builder()
+                .method("GET")
+                .uri("/api/me")
+                .header("content-type", "application/json")
+                .header("Connection", "close")
+                .body(Empty::<Bytes>::new())
+                .expect("Failed to build request");
+            let prover = Prover::builder()
+                .tls_client_config(tls_client_config)
+                .tls_commit_config(tls_commit_config)
+                .request(request)
+                .request_reveal_config(create_request_reveal_config())
+                .response_reveal_config(RevealConfig {
+                    reveal_body_fields: vec![BodyFieldConfig::Quoted(".username".into())],
+                    ..RevealConfig::default()
+                })
+                .secret_header(SecretHeader::bearer(token.access_token.clone()))
+                .build()
+                .expect("Prover should build");
+
+            let verifier = Verifier::builder()
+                .verifier_config(verifier_config)
+                .build()
+                .expect("Verifier should build");
+
+            let prover_task =
+                prover.prove(sockets.prover_verifier_socket, sockets.prover_server_socket);
+            let verifier_task = verifier.verify(sockets.verifier_socket);
+
+            let (server_result, prover_result, verifier_result) =
+                join!(server_task, prover_task, verifier_task);
+
+            server_result.expect("Server should complete successfully");
+            prover_result.expect("Prover should complete successfully");
+            let verifier_output = verifier_result.expect("Verifier should complete successfully");
+
+            let sent_data = String::from_utf8_lossy(verifier_output.transcript.sent_unsafe());
+            assert!(
+                !sent_data.contains(&token.access_token),
+                "Access token should not be revealed"
+            );
+
+            Validator::builder()
+                .response_body_field_equals(".username", ExpectedValue::String("alice".into()))
+                .build()
+                .validate(&verifier_output)
+                .expect("Profile username should be revealed");
+        });
+    }
+}
+
+#[cfg(test)]
+mod fault_injection {
+    use std::{
+        io,
+        pin::Pin,
+        task::{Context, Poll},
+        time::Duration,
+    };
+
+    use axum::{Router, http::StatusCode};
+    use futures::{AsyncRead, AsyncWrite, FutureExt, join};
+    use server::{app::get_app, handle_connection};
+    use shared::create_test_tls_config;
+
+    use super::*;
+    use crate::{Error, Prover, RetryPolicy, Verifier};
+
+    const DEADLINE: Duration = Duration::from_secs(30);
+
+    #[derive(Debug, Clone, Copy)]
+    enum Fault {
+        FlipBit { offset: usize },
+        Truncate { after: usize },
+        Stall { after: usize },
+        SwapWrites { index: usize },
+    }
+
+    #[derive(Debug, Clone, Copy)]
+    enum Side {
+        Prover,
+        Verifier,
+    }
+
+    struct FaultyStream<S> {
+        inner: S,
+        fault: Fault,
+        written: usize,
+        writes: usize,
+        held: Vec<u8>,
+        outgoing: Vec<u8>,
+        closed: bool,
+    }
+
+    impl<S> FaultyStream<S> {
+        fn new(inner: S, fault: Fault) -> Self {
+            Self {
+                inner,
+                fault,
+                written: 0,
+                writes: 0,
+                held: Vec::new(),
+                outgoing: Vec::new(),
+                closed: false,
+            }
+        }
+    }
+
+    impl<S: AsyncWrite + Unpin> FaultyStream<S> {
+        fn poll_drain(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            while !self.outgoing.is_empty() {
+                let n = match Pin::new(&mut self.inner).poll_write(cx, &self.outgoing) {
+                    Poll::Ready(Ok(n)) => n,
+                    Poll::Ready(Err(error)) => return Poll::Ready(Err(error)),
+                    Poll::Pending => return Poll::Pending,
+                };
+                self.outgoing.drain(..n);
+            }
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    impl<S: AsyncRead + Unpin> AsyncRead for FaultyStream<S> {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<io::Result<usize>> {
+            Pin::new(&mut self.inner).poll_read(cx, buf)
+        }
+    }
+
+    impl<S: AsyncWrite + Unpin> AsyncWrite for FaultyStream<S> {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            let this = &mut *self;
+            if this.closed {
+                return Poll::Ready(Err(io::ErrorKind::BrokenPipe.into()));
+            }
+            match this.poll_drain(cx) {
+                Poll::Ready(Ok(())) => {}
+                other => return other.map(|result| result.map(|()| 0)),
+            }
+
+            let start = this.written;
+            let write_index = this.writes;
+            match this.fault {
+                Fault::FlipBit { offset } if (start..start + buf.len()).contains(&offset) => {
+                    let mut corrupted = buf.to_vec();
+                    corrupted[offset - start] ^= 0x01;
+                    this.outgoing = corrupted;
+                }
+                Fault::Truncate { after } if start + buf.len() >= after => {
+                    this.outgoing = buf[..after.saturating_sub(start)].to_vec();
+                    if this.poll_drain(cx).is_pending() {
+                        return Poll::Pending;
+                    }
+                    this.closed = true;
+                    return Pin::new(&mut this.inner)
+                        .poll_close(cx)
+                        .map(|_| Err(io::ErrorKind::BrokenPipe.into()));
+                }
+                Fault::Stall { after } if start >= after => return Poll::Pending,
+                Fault::SwapWrites { index } if write_index == index => {
+                    this.held = buf.to_vec();
+                }
+                Fault::SwapWrites { index } if write_index == index + 1 => {
+                    this.outgoing = [buf, this.held.as_slice()].concat();
+                    this.held.clear();
+                }
+                Fault::FlipBit { .. }
+                | Fault::Truncate { .. }
+                | Fault::Stall { .. }
+                | Fault::SwapWrites { .. } => {
+                    this.outgoing = buf.to_vec();
+                }
+            }
+
+            this.written += buf.len();
+            this.writes += 1;
+            // Bytes are accepted once buffered; draining failures surface on the next call.
+            let _ = this.poll_drain(cx);
+            Poll::Ready(Ok(buf.len()))
+        }
+
+        fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            let this = &mut *self;
+            match this.poll_drain(cx) {
+                Poll::Ready(Ok(())) => Pin::new(&mut this.inner).poll_flush(cx),
+                other => other,
+            }
+        }
+
+        fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            let this = &mut *self;
+            match this.poll_drain(cx) {
+                Poll::Ready(Ok(())) => Pin::new(&mut this.inner).poll_close(cx),
+                other => other,
+            }
+        }
+    }
+
+    fn run_with_fault(
+        side: Side,
+        fault: Fault,
+    ) -> (Result<ProverOutput, Error>, Result<VerifierOutput, Error>) {
+        smol::block_on(async {
+            let test_tls_config =
+                create_test_tls_config().expect("Test TLS config should be created");
+            let sockets = create_test_sockets();
+
+            let (tls_client_config, tls_commit_config) =
+                create_prover_config(test_tls_config.cert_bytes.clone());
+            let verifier_config = create_verifier_config(test_tls_config.cert_bytes);
+
+            let app = get_app(create_test_balances());
+            let server_task = smol::future::or(
+                handle_connection(app, test_tls_config.server_config, sockets.server_socket)
+                    .map(|_| ()),
+                smol::Timer::after(DEADLINE).map(|_| ()),
+            );
+
+            let prover = Prover::builder()
+                .tls_client_config(tls_client_config)
+                .tls_commit_config(tls_commit_config)
+                .request(create_test_request())
+                .request_reveal_config(create_request_reveal_config())
+                .response_reveal_config(create_response_reveal_config())
+                .timeout(DEADLINE)
+                .build()
+                .expect("Prover should build");
+
+            let verifier = Verifier::builder()
+                .verifier_config(verifier_config)
+                .timeout(DEADLINE)
+                .build()
+                .expect("Verifier should build");
+
+            match side {
+                Side::Prover => {
+                    let prover_task = prover.prove(
+                        FaultyStream::new(sockets.prover_verifier_socket, fault),
+                        sockets.prover_server_socket,
+                    );
+                    let verifier_task = verifier.verify(sockets.verifier_socket);
+                    let (_, prover_result, verifier_result) =
+                        join!(server_task, prover_task, verifier_task);
+                    (prover_result, verifier_result)
+                }
+                Side::Verifier => {
+                    let prover_task =
+                        prover.prove(sockets.prover_verifier_socket, sockets.prover_server_socket);
+                    let verifier_task =
+                        verifier.verify(FaultyStream::new(sockets.verifier_socket, fault));
+                    let (_, prover_result, verifier_result) =
+                        join!(server_task, prover_task, verifier_task);
+                    (prover_result, verifier_result)
+                }
+            }
+        })
+    }
+
+    fn assert_both_fail(side: Side, fault: Fault) {
+        shared::init_test_logging();
+
+        let (prover_result, verifier_result) = run_with_fault(side, fault);
+        assert!(
+            prover_result.is_err(),
+            "Prover should fail with {fault:?} on {side:?} side"
+        );
+        assert!(
+            verifier_result.is_err(),
+            "Verifier should fail with {fault:?} on {side:?} side"
+        );
+    }
+
+    fn assert_both_time_out(side: Side, fault: Fault) {
+        shared::init_test_logging();
+
+        let (prover_result, verifier_result) = run_with_fault(side, fault);
+        assert!(
+            matches!(prover_result, Err(Error::Timeout(_))),
+            "Prover should hit its deadline with {fault:?} on {side:?} side, got {prover_result:?}"
+        );
+        assert!(
+            matches!(verifier_result, Err(Error::Timeout(_))),
+            "Verifier should hit its deadline with {fault:?} on {side:?} side, got {:?}",
+            verifier_result.map(|_| ())
+        );
+    }
+
+    #[test]
+    fn test_prover_bit_flip_in_handshake() {
+        assert_both_fail(Side::Prover, Fault::FlipBit { offset: 0 });
+    }
+
+    #[test]
+    fn test_prover_bit_flip_mid_session() {
+        assert_both_fail(Side::Prover, Fault::FlipBit { offset: 4096 });
+    }
+
+    #[test]
+    fn test_verifier_bit_flip_in_handshake() {
+        assert_both_fail(Side::Verifier, Fault::FlipBit { offset: 0 });
+    }
+
+    #[test]
+    fn test_verifier_bit_flip_mid_session() {
+        assert_both_fail(Side::Verifier, Fault::FlipBit { offset: 4096 });
+    }
+
+    #[test]
+    fn test_prover_truncated_at_start() {
+        assert_both_fail(Side::Prover, Fault::Truncate { after: 1 });
+    }
+
+    #[test]
+    fn test_prover_truncated_mid_session() {
+        assert_both_fail(Side::Prover, Fault::Truncate { after: 8192 });
+    }
+
+    #[test]
+    fn test_verifier_truncated_mid_session() {
+        assert_both_fail(Side::Verifier, Fault::Truncate { after: 8192 });
+    }
+
+    #[test]
+    fn test_prover_writes_reordered() {
+        assert_both_fail(Side::Prover, Fault::SwapWrites { index: 1 });
+    }
+
+    #[test]
+    fn test_verifier_writes_reordered() {
+        assert_both_fail(Side::Verifier, Fault::SwapWrites { index: 1 });
+    }
+
+    #[test]
+    fn test_prover_stalls() {
+        assert_both_time_out(Side::Prover, Fault::Stall { after: 4096 });
+    }
+
+    #[test]
+    fn test_verifier_stalls() {
+        assert_both_time_out(Side::Verifier, Fault::Stall { after: 4096 });
+    }
+
+    #[test]
+    fn test_prove_with_retry_restarts_after_service_unavailable() {
+        shared::init_test_logging();
+
+        let result = smol::block_on(async {
+            let test_tls_config =
+                create_test_tls_config().expect("Test TLS config should be created");
+            let (tls_client_config, tls_commit_config) =
+                create_prover_config(test_tls_config.cert_bytes.clone());
+
+            let prover = Prover::builder()
+                .tls_client_config(tls_client_config)
+                .tls_commit_config(tls_commit_config)
+                .request(create_test_request())
+                .request_reveal_config(create_request_reveal_config())
+                .response_reveal_config(create_response_reveal_config())
+                .retry_policy(RetryPolicy::default().initial_backoff(Duration::ZERO))
+                .timeout(DEADLINE)
+                .build()
+                .expect("Prover should build");
+
+            let mut attempts = 0;
+            prover
+                .prove_with_retry(|| {
+                    attempts += 1;
+                    let sockets = create_test_sockets();
+                    let app = if attempts == 1 {
+                        Router::new().fallback(|| async { StatusCode::SERVICE_UNAVAILABLE })
+                    } else {
+                        get_app(create_test_balances())
+                    };
+                    smol::spawn(handle_connection(
+                        app,
+                        test_tls_config.server_config.clone(),
+                        sockets.server_socket,
+                    ))
+                    .detach();
+                    let verifier = Verifier::builder()
+                        .verifier_config(create_verifier_config(
+                            test_tls_config.cert_bytes.clone(),
+                        ))
+                        .timeout(DEADLINE)
+                        .build()
+                        .expect("Verifier should build");
+                    smol::spawn(verifier.verify(sockets.verifier_socket)).detach();
+                    async move {
+                        Ok((
+                            sockets.prover_verifier_socket,
+                            sockets.prover_server_socket,
+                        ))
+                    }
+                })
+                .await
+        });
+
+        let prover_output = result.expect("The second session should succeed");
+        assert_eq!(prover_output.attempts, 2);
+        verify_prover_output(&prover_output);
+    }
 }
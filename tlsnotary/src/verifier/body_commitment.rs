@@ -0,0 +1,142 @@
+use std::{collections::HashMap, ops::Range};
+
+use parser::redacted::Body;
+use tlsn::transcript::{Direction, PlaintextHash, TranscriptCommitment};
+
+use super::VerifierOutput;
+use crate::error::Error;
+
+#[derive(Debug, Clone)]
+pub struct BodyCommitmentOpenings {
+    pub body: PlaintextHash,
+    /// Transcript range of the parsed body, which `body` commits to exactly.
+    pub body_range: Range<usize>,
+    pub openings: Vec<PlaintextHash>,
+}
+
+pub fn verify_body_commitment(
+    output: &VerifierOutput,
+    direction: Direction,
+) -> Result<BodyCommitmentOpenings, Error> {
+    let hashes = output
+        .transcript_commitments
+        .iter()
+        .filter_map(|commitment| match commitment {
+            TranscriptCommitment::Hash(hash) if hash.direction == direction => Some(hash),
+            _ => None,
+        })
+        .map(|hash| commitment_range(hash).map(|range| (range, hash)))
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    let offset = output.message_offset(direction);
+    let body_range = offset.to_transcript(&parsed_body_range(output, direction)?)?;
+    let body = hashes
+        .iter()
+        .find(|(range, _)| *range == body_range)
+        .map(|(_, hash)| *hash)
+        .ok_or_else(|| {
+            Error::InvalidTranscript(format!(
+                "no {direction:?} hash commitment covers exactly the body {body_range:?}"
+            ))
+        })?;
+
+    if let Some(header_end) = parsed_headers(output, direction)
+        .into_iter()
+        .flat_map(|headers| headers.values().flatten())
+        .map(|header| {
+            let end = header
+                .value
+                .as_ref()
+                .map_or(header.name.end, |value| value.end);
+            end + offset.message_start()
+        })
+        .max()
+        && header_end > body_range.start
+    {
+        return Err(Error::InvalidTranscript(format!(
+            "{direction:?} body commitment starts at {} inside header section ending at {header_end}",
+            body_range.start
+        )));
+    }
+
+    let openings = hashes
+        .iter()
+        .filter(|(range, hash)| range.start >= body_range.start && !std::ptr::eq(*hash, body))
+        .map(|(range, hash)| {
+            if range.end > body_range.end {
+                return Err(Error::InvalidTranscript(format!(
+                    "{direction:?} field commitment {range:?} exceeds body commitment {body_range:?}"
+                )));
+            }
+            if hash.hash.alg != body.hash.alg {
+                return Err(Error::InvalidTranscript(format!(
+                    "{direction:?} field commitment uses {:?}, body commitment uses {:?}",
+                    hash.hash.alg, body.hash.alg
+                )));
+            }
+            Ok((*hash).clone())
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    Ok(BodyCommitmentOpenings {
+        body: body.clone(),
+        body_range,
+        openings,
+    })
+}
+
+/// The body the prover commits in full: everything after the head when the
+/// boundary is revealed, otherwise the parsed root value. Relative to the
+/// message.
+fn parsed_body_range(output: &VerifierOutput, direction: Direction) -> Result<Range<usize>, Error> {
+    let message = output.message_bytes(direction);
+    if let Some(body_start) =
+        parser::message_body_start(message).filter(|&start| start < message.len())
+    {
+        return Ok(body_start..message.len());
+    }
+    let root = match direction {
+        Direction::Sent => output
+            .parsed_request
+            .as_ref()
+            .and_then(|request| request.body.get("")),
+        Direction::Received => output
+            .parsed_response
+            .as_ref()
+            .and_then(|response| response.body.get("")),
+    };
+    match root {
+        Some(Body::Value(range)) => Ok(range.clone()),
+        Some(Body::KeyValue { .. } | Body::PartialValue { .. }) | None => Err(
+            Error::InvalidTranscript(format!("{direction:?} message has no body to commit to")),
+        ),
+    }
+}
+
+fn commitment_range(hash: &PlaintextHash) -> Result<Range<usize>, Error> {
+    let start = hash
+        .idx
+        .min()
+        .ok_or(Error::MissingField("commitment range start"))?;
+    let end = hash
+        .idx
+        .end()
+        .ok_or(Error::MissingField("commitment range end"))?;
+    Ok(start..end)
+}
+
+fn parsed_headers(
+    output: &VerifierOutput,
+    direction: Direction,
+) -> Option<&HashMap<String, Vec<parser::redacted::Header>>> {
+    match direction {
+        Direction::Sent => output
+            .parsed_request
+            .as_ref()
+            .map(|request| &request.headers),
+        Direction::Received => output
+            .parsed_response
+            .as_ref()
+            .map(|response| &response.headers),
+    }
+}
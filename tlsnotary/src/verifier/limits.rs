@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+
+use tlsn::config::tls_commit::TlsCommitProtocolConfig;
+
+use crate::error::Error;
+
+/// Upper bounds on what a prover may ask the verifier to commit to. Unset
+/// bounds allow anything.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ProtocolLimits {
+    pub max_sent_data: Option<usize>,
+    pub max_recv_data: Option<usize>,
+}
+
+impl ProtocolLimits {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn max_sent_data(mut self, max_sent_data: usize) -> Self {
+        self.max_sent_data = Some(max_sent_data);
+        self
+    }
+
+    #[must_use]
+    pub fn max_recv_data(mut self, max_recv_data: usize) -> Self {
+        self.max_recv_data = Some(max_recv_data);
+        self
+    }
+
+    /// Why `protocol` falls outside these limits, if it does. Only MPC-TLS
+    /// is accepted; tlsn does not expose cipher suites or TLS versions at
+    /// this stage, so only data limits are checked.
+    pub fn violation(&self, protocol: &TlsCommitProtocolConfig) -> Option<String> {
+        match protocol {
+            TlsCommitProtocolConfig::Mpc(config) => {
+                self.data_violation(config.max_sent_data(), config.max_recv_data())
+            }
+            _ => Some("expected MPC-TLS protocol".to_string()),
+        }
+    }
+
+    fn data_violation(&self, max_sent_data: usize, max_recv_data: usize) -> Option<String> {
+        let exceeds = |requested: usize, limit: Option<usize>| limit.filter(|l| requested > *l);
+        if let Some(limit) = exceeds(max_sent_data, self.max_sent_data) {
+            return Some(format!(
+                "max_sent_data {max_sent_data} exceeds the limit of {limit}"
+            ));
+        }
+        exceeds(max_recv_data, self.max_recv_data)
+            .map(|limit| format!("max_recv_data {max_recv_data} exceeds the limit of {limit}"))
+    }
+
+    /// The looser of the two bounds for each field.
+    fn loosest(self, other: Self) -> Self {
+        let loosest = |a: Option<usize>, b: Option<usize>| a.zip(b).map(|(a, b)| a.max(b));
+        Self {
+            max_sent_data: loosest(self.max_sent_data, other.max_sent_data),
+            max_recv_data: loosest(self.max_recv_data, other.max_recv_data),
+        }
+    }
+}
+
+/// Data limits a prover proposed at session setup.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct RequestedData {
+    sent: usize,
+    recv: usize,
+}
+
+/// Default limits plus overrides keyed by lowercase server name. The server
+/// is only known once its identity is proven, so session setup is checked
+/// against the loosest limits that could apply and the proven server's own
+/// limits are checked after.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct LimitPolicy {
+    pub(crate) default: ProtocolLimits,
+    pub(crate) by_server_name: HashMap<String, ProtocolLimits>,
+}
+
+impl LimitPolicy {
+    pub(crate) fn check_setup(
+        &self,
+        protocol: &TlsCommitProtocolConfig,
+    ) -> Result<RequestedData, Error> {
+        let at_setup = self
+            .by_server_name
+            .values()
+            .fold(self.default, |limits, server| limits.loosest(*server));
+        if let Some(reason) = at_setup.violation(protocol) {
+            return Err(Error::PolicyViolation(reason));
+        }
+        match protocol {
+            TlsCommitProtocolConfig::Mpc(config) => Ok(RequestedData {
+                sent: config.max_sent_data(),
+                recv: config.max_recv_data(),
+            }),
+            _ => Err(Error::PolicyViolation(
+                "expected MPC-TLS protocol".to_string(),
+            )),
+        }
+    }
+
+    pub(crate) fn check_server(
+        &self,
+        server_name: Option<&str>,
+        requested: RequestedData,
+    ) -> Result<(), Error> {
+        let limits = server_name
+            .and_then(|name| self.by_server_name.get(&name.to_ascii_lowercase()))
+            .unwrap_or(&self.default);
+        match limits.data_violation(requested.sent, requested.recv) {
+            Some(reason) => Err(Error::PolicyViolation(match server_name {
+                Some(name) => format!("{reason} for {name}"),
+                None => reason,
+            })),
+            None => Ok(()),
+        }
+    }
+}
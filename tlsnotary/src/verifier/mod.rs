@@ -1,22 +1,74 @@
+mod body_commitment;
+mod limits;
 mod validator;
 
+use std::{collections::HashMap, time::Duration};
+
+pub use body_commitment::{BodyCommitmentOpenings, verify_body_commitment};
 use futures::{AsyncRead, AsyncWrite};
-use tlsn::{Session, config::verifier::VerifierConfig, transcript::PartialTranscript};
-pub use validator::{ExpectedValue, FieldAssertion, Validator, ValidatorBuilder};
+use limits::LimitPolicy;
+pub use limits::ProtocolLimits;
+use tlsn::{
+    Session, SessionHandle,
+    config::verifier::VerifierConfig,
+    transcript::{Direction, PartialTranscript},
+};
+use tracing::{Instrument, Span, info, warn};
+pub use validator::{
+    AssertionOutcome, ExpectedValue, FieldAssertion, HashAlgScope, Predicate,
+    PredicateProofVerifier, ValidationReport, Validator, ValidatorBuilder,
+};
 
-use crate::error::Error;
+use crate::{
+    deadline::with_deadline,
+    error::Error,
+    offline::message_bytes,
+    offset::TranscriptOffset,
+    stats::{SessionStats, StatsRecorder},
+};
 
 #[derive(Debug)]
 pub struct VerifierOutput {
     pub transcript: PartialTranscript,
     pub transcript_commitments: Vec<tlsn::transcript::TranscriptCommitment>,
-    pub server_name: String,
+    /// `None` when the prover chose not to prove the server identity.
+    pub server_name: Option<String>,
+    /// The last request on the connection; earlier pipelined ones are
+    /// skipped by their revealed framing.
     pub parsed_request: Option<parser::redacted::Request>,
     pub parsed_response: Option<parser::redacted::Response>,
+    /// Where `parsed_request` starts in the sent transcript. Parsed ranges
+    /// are relative to their message, commitments to the transcript.
+    pub request_offset: TranscriptOffset,
+    /// Where `parsed_response` starts in the received transcript.
+    pub response_offset: TranscriptOffset,
+    pub session_stats: SessionStats,
+}
+
+impl VerifierOutput {
+    pub fn message_offset(&self, direction: Direction) -> TranscriptOffset {
+        match direction {
+            Direction::Sent => self.request_offset,
+            Direction::Received => self.response_offset,
+        }
+    }
+
+    /// The transcript bytes of the parsed message in `direction`, which its
+    /// parsed ranges index into.
+    pub fn message_bytes(&self, direction: Direction) -> &[u8] {
+        let data = match direction {
+            Direction::Sent => self.transcript.sent_unsafe(),
+            Direction::Received => self.transcript.received_unsafe(),
+        };
+        data.get(self.message_offset(direction).message_start()..)
+            .unwrap_or_default()
+    }
 }
 
 pub struct Verifier {
     verifier_config: VerifierConfig,
+    limits: LimitPolicy,
+    timeout: Option<Duration>,
 }
 
 impl Verifier {
@@ -29,44 +81,109 @@ impl Verifier {
     where
         T: AsyncRead + AsyncWrite + Send + Unpin + 'static,
     {
-        let mut session = Session::new(socket);
-        let verifier = session.new_verifier(self.verifier_config)?;
-        let (driver, handle) = session.split();
-        smol::spawn(driver).detach();
+        self.verify_sessions(socket, 1)
+            .await?
+            .pop()
+            .ok_or(Error::MissingField("verifier output"))
+    }
 
+    /// Verifies `sessions` sessions in turn over one connection, as a
+    /// prover's [`crate::VerifierChannel`] sends them. The timeout
+    /// applies per session.
+    pub async fn verify_sessions<T>(
+        self,
+        socket: T,
+        sessions: usize,
+    ) -> Result<Vec<VerifierOutput>, Error>
+    where
+        T: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+    {
+        let stats = StatsRecorder::new();
+        let (driver, mut handle) = Session::new(stats.wrap(socket)).split();
+        smol::spawn(driver.in_current_span()).detach();
+
+        let mut outputs = Vec::with_capacity(sessions);
+        for _ in 0..sessions {
+            let result = with_deadline(self.timeout, self.run(&mut handle, stats.resume())).await;
+            match result {
+                Ok(output) => outputs.push(output),
+                Err(error) => {
+                    handle.close();
+                    return Err(error);
+                }
+            }
+        }
+        handle.close();
+        Ok(outputs)
+    }
+
+    async fn run(
+        &self,
+        handle: &mut SessionHandle,
+        mut stats: StatsRecorder,
+    ) -> Result<VerifierOutput, Error> {
+        let verifier = handle.new_verifier(self.verifier_config.clone())?;
         let verifier = verifier.commit().await?;
+        let requested = match self.limits.check_setup(verifier.request().protocol()) {
+            Ok(requested) => requested,
+            Err(error) => {
+                warn!(error = %error, "Rejecting prover protocol configuration");
+                verifier.reject(Some(error.to_string().as_str())).await?;
+                return Err(error);
+            }
+        };
         let verifier = verifier.accept().await?;
+        stats.finish_phase("setup");
         let verifier = verifier.run().await?;
+        stats.finish_phase("mpc_tls");
         let verifier = verifier.verify().await?;
         let (output, verifier) = verifier.accept().await?;
         verifier.close().await?;
-        handle.close();
+        stats.finish_phase("verify");
 
-        let server_name = output
-            .server_name
-            .ok_or(Error::MissingField("server name"))?;
+        let server_name = output.server_name.map(|name| name.to_string());
+        match &server_name {
+            Some(server_name) => shared::record_server_name(&Span::current(), server_name),
+            None => info!("Prover did not prove the server identity"),
+        }
+        self.limits
+            .check_server(server_name.as_deref(), requested)?;
         let transcript = output.transcript.ok_or(Error::MissingField("transcript"))?;
 
-        let sent_data = String::from_utf8(transcript.sent_unsafe().to_vec())?;
-        let received_data = String::from_utf8(transcript.received_unsafe().to_vec())?;
-        let parsed_request: parser::redacted::Request = sent_data.parse().map_err(|error| {
+        let request_offset =
+            TranscriptOffset::new(parser::last_request_start(transcript.sent_unsafe()));
+        let response_offset =
+            TranscriptOffset::new(parser::last_response_start(transcript.received_unsafe()));
+        let parsed_request = parser::redacted::Request::from_bytes_lossy(message_bytes(
+            transcript.sent_unsafe(),
+            request_offset,
+            Direction::Sent,
+        )?)
+        .map_err(|error| {
             Error::InvalidTranscript(format!(
                 "failed to parse redacted request from transcript: {error:?}"
             ))
         })?;
-        let parsed_response: parser::redacted::Response =
-            received_data.parse().map_err(|error| {
-                Error::InvalidTranscript(format!(
-                    "failed to parse redacted response from transcript: {error:?}"
-                ))
-            })?;
+        let parsed_response = parser::redacted::Response::from_bytes_lossy(message_bytes(
+            transcript.received_unsafe(),
+            response_offset,
+            Direction::Received,
+        )?)
+        .map_err(|error| {
+            Error::InvalidTranscript(format!(
+                "failed to parse redacted response from transcript: {error:?}"
+            ))
+        })?;
 
         Ok(VerifierOutput {
             transcript,
             transcript_commitments: output.transcript_commitments,
-            server_name: server_name.to_string(),
+            server_name,
             parsed_request: Some(parsed_request),
             parsed_response: Some(parsed_response),
+            request_offset,
+            response_offset,
+            session_stats: stats.finish(),
         })
     }
 }
@@ -74,12 +191,18 @@ impl Verifier {
 #[derive(Debug)]
 pub struct VerifierBuilder {
     verifier_config: Option<VerifierConfig>,
+    limits: ProtocolLimits,
+    server_limits: HashMap<String, ProtocolLimits>,
+    timeout: Option<Duration>,
 }
 
 impl VerifierBuilder {
     fn new() -> Self {
         Self {
             verifier_config: None,
+            limits: ProtocolLimits::default(),
+            server_limits: HashMap::new(),
+            timeout: None,
         }
     }
 
@@ -89,11 +212,40 @@ impl VerifierBuilder {
         self
     }
 
+    /// Limits for sessions with servers that have none of their own.
+    /// Proposals over them are rejected at session setup with
+    /// [`Error::PolicyViolation`].
+    #[must_use]
+    pub fn limits(mut self, limits: ProtocolLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Limits for sessions whose proven server is `server_name`, replacing
+    /// the default ones.
+    #[must_use]
+    pub fn server_limits(mut self, server_name: &str, limits: ProtocolLimits) -> Self {
+        self.server_limits
+            .insert(server_name.to_ascii_lowercase(), limits);
+        self
+    }
+
+    #[must_use]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
     pub fn build(self) -> Result<Verifier, Error> {
         Ok(Verifier {
             verifier_config: self
                 .verifier_config
                 .ok_or_else(|| Error::InvalidConfig("verifier_config is required".into()))?,
+            limits: LimitPolicy {
+                default: self.limits,
+                by_server_name: self.server_limits,
+            },
+            timeout: self.timeout,
         })
     }
 }
@@ -1,14 +1,43 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, ops::Range};
 
-use tlsn::hash::HashAlgId;
+use serde::{Deserialize, Serialize};
+use tlsn::{
+    hash::HashAlgId,
+    transcript::{Direction, PlaintextHash, TranscriptCommitment},
+};
 
 use super::VerifierOutput;
-use crate::error::Error;
+use crate::{commitment::CommitmentLabel, error::Error, offset::TranscriptOffset};
+
+/// Bytes allowed between the end of a JSON key and the start of its
+/// committed value (the closing quote and the colon).
+const MAX_KEY_VALUE_GAP: usize = 2;
 
 #[derive(Debug, Clone)]
 pub enum FieldAssertion {
     HeaderEquals { key: String, value: String },
     BodyFieldEquals { key: String, value: ExpectedValue },
+    CommittedFieldSatisfies { key: String, predicate: Predicate },
+}
+
+/// A claim about a committed value, established by a zero-knowledge proof
+/// instead of by reading the plaintext.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Predicate {
+    GreaterThan(u64),
+    LessThan(u64),
+}
+
+/// Checks the proof supplied for the committed value at `keypath`. The
+/// validator has already bound `commitment` to that keypath.
+pub trait PredicateProofVerifier {
+    fn verify_predicate(
+        &self,
+        keypath: &str,
+        commitment: &PlaintextHash,
+        predicate: Predicate,
+    ) -> Result<(), Error>;
 }
 
 #[derive(Debug, Clone)]
@@ -19,10 +48,129 @@ pub enum ExpectedValue {
     String(String),
 }
 
+impl From<&str> for ExpectedValue {
+    fn from(value: &str) -> Self {
+        Self::String(value.to_string())
+    }
+}
+
+impl From<String> for ExpectedValue {
+    fn from(value: String) -> Self {
+        Self::String(value)
+    }
+}
+
+impl From<bool> for ExpectedValue {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+impl From<f64> for ExpectedValue {
+    fn from(value: f64) -> Self {
+        Self::Number(value)
+    }
+}
+
+/// The commitments a hash algorithm expectation applies to. When several
+/// scopes match a commitment, the most specific one is checked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HashAlgScope {
+    All,
+    Direction(Direction),
+    Range {
+        direction: Direction,
+        range: Range<usize>,
+    },
+}
+
+impl HashAlgScope {
+    fn specificity(&self, hash: &PlaintextHash) -> Option<u8> {
+        match self {
+            Self::All => Some(0),
+            Self::Direction(direction) if *direction == hash.direction => Some(1),
+            Self::Range { direction, range }
+                if *direction == hash.direction
+                    && hash.idx.min() == Some(range.start)
+                    && hash.idx.end() == Some(range.end) =>
+            {
+                Some(2)
+            }
+            Self::Direction(_) | Self::Range { .. } => None,
+        }
+    }
+}
+
+impl From<&CommitmentLabel> for HashAlgScope {
+    fn from(label: &CommitmentLabel) -> Self {
+        Self::Range {
+            direction: label.direction,
+            range: label.range.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AssertionOutcome {
+    pub assertion: String,
+    pub actual: Option<String>,
+    pub failure: Option<String>,
+}
+
+impl AssertionOutcome {
+    #[must_use]
+    pub fn passed(&self) -> bool {
+        self.failure.is_none()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ValidationReport {
+    pub outcomes: Vec<AssertionOutcome>,
+}
+
+impl ValidationReport {
+    #[must_use]
+    pub fn passed(&self) -> bool {
+        self.outcomes.iter().all(AssertionOutcome::passed)
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = &AssertionOutcome> {
+        self.outcomes.iter().filter(|outcome| !outcome.passed())
+    }
+}
+
+struct Check {
+    assertion: String,
+    actual: Option<String>,
+    result: Result<(), Error>,
+}
+
+impl Check {
+    fn new(
+        assertion: String,
+        actual: Result<String, Error>,
+        compare: impl FnOnce(&str) -> Result<(), Error>,
+    ) -> Self {
+        match actual {
+            Ok(actual) => Self {
+                assertion,
+                result: compare(&actual),
+                actual: Some(actual),
+            },
+            Err(error) => Self {
+                assertion,
+                actual: None,
+                result: Err(error),
+            },
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Validator {
     expected_server_name: Option<String>,
-    expected_hash_alg: Option<HashAlgId>,
+    expected_hash_algs: Vec<(HashAlgScope, HashAlgId)>,
     request_assertions: Vec<FieldAssertion>,
     response_assertions: Vec<FieldAssertion>,
 }
@@ -33,124 +181,357 @@ impl Validator {
         ValidatorBuilder::new()
     }
 
-    pub fn validate(&self, output: &VerifierOutput) -> Result<(), Error> {
-        if let Some(expected_name) = &self.expected_server_name
-            && output.server_name != *expected_name
-        {
-            return Err(Error::InvalidTranscript(format!(
-                "Expected server name '{}', got '{}'",
-                expected_name, output.server_name
-            )));
-        }
+    pub fn merge(self, other: Validator) -> Result<Validator, Error> {
+        Ok(Validator {
+            expected_server_name: merge_expectation(
+                "server name",
+                self.expected_server_name,
+                other.expected_server_name,
+            )?,
+            expected_hash_algs: other.expected_hash_algs.into_iter().try_fold(
+                self.expected_hash_algs,
+                |mut merged, (scope, alg)| {
+                    match merged.iter().find(|(existing, _)| *existing == scope) {
+                        Some(&(_, existing_alg)) => {
+                            merge_expectation("hash algorithm", Some(existing_alg), Some(alg))?;
+                        }
+                        None => merged.push((scope, alg)),
+                    }
+                    Ok::<_, Error>(merged)
+                },
+            )?,
+            request_assertions: [self.request_assertions, other.request_assertions].concat(),
+            response_assertions: [self.response_assertions, other.response_assertions].concat(),
+        })
+    }
 
-        if let Some(expected_alg) = self.expected_hash_alg {
-            for commitment in &output.transcript_commitments {
-                if let tlsn::transcript::TranscriptCommitment::Hash(hash) = commitment
-                    && hash.hash.alg != expected_alg
-                {
-                    return Err(Error::InvalidTranscript(format!(
-                        "Expected {:?} hash algorithm in {:?} direction, got {:?}",
-                        expected_alg, hash.direction, hash.hash.alg
-                    )));
-                }
-            }
+    pub(crate) fn assertions(&self, direction: Direction) -> &[FieldAssertion] {
+        match direction {
+            Direction::Sent => &self.request_assertions,
+            Direction::Received => &self.response_assertions,
         }
+    }
 
-        if !self.request_assertions.is_empty() {
-            let request = output
-                .parsed_request
-                .as_ref()
-                .ok_or(Error::MissingField("parsed request"))?;
-
-            let request_data = output.transcript.sent_unsafe();
-
-            for assertion in &self.request_assertions {
-                Self::validate_assertion(
-                    assertion,
-                    &request.headers,
-                    &request.body,
-                    request_data,
-                    "request",
-                )?;
-            }
-        }
+    /// The algorithm expected of `direction`'s commitments as a whole, if any.
+    pub(crate) fn expected_direction_hash_alg(&self, direction: Direction) -> Option<HashAlgId> {
+        self.expected_hash_algs
+            .iter()
+            .filter_map(|(scope, alg)| match scope {
+                HashAlgScope::All => Some((0, *alg)),
+                HashAlgScope::Direction(scoped) if *scoped == direction => Some((1, *alg)),
+                HashAlgScope::Direction(_) | HashAlgScope::Range { .. } => None,
+            })
+            .max_by_key(|&(rank, _)| rank)
+            .map(|(_, alg)| alg)
+    }
 
-        if !self.response_assertions.is_empty() {
-            let response = output
-                .parsed_response
-                .as_ref()
-                .ok_or(Error::MissingField("parsed response"))?;
-
-            let response_data = output.transcript.received_unsafe();
-
-            for assertion in &self.response_assertions {
-                Self::validate_assertion(
-                    assertion,
-                    &response.headers,
-                    &response.body,
-                    response_data,
-                    "response",
-                )?;
-            }
+    /// Predicate assertions on committed fields fail here; use
+    /// [`Self::validate_with_proofs`] to check them.
+    pub fn validate(&self, output: &VerifierOutput) -> Result<(), Error> {
+        self.checks(output, None)
+            .into_iter()
+            .try_for_each(|check| check.result)
+    }
+
+    pub fn validate_with_proofs(
+        &self,
+        output: &VerifierOutput,
+        proofs: &dyn PredicateProofVerifier,
+    ) -> Result<(), Error> {
+        self.checks(output, Some(proofs))
+            .into_iter()
+            .try_for_each(|check| check.result)
+    }
+
+    #[must_use]
+    pub fn validate_all(&self, output: &VerifierOutput) -> ValidationReport {
+        Self::report(self.checks(output, None))
+    }
+
+    #[must_use]
+    pub fn validate_all_with_proofs(
+        &self,
+        output: &VerifierOutput,
+        proofs: &dyn PredicateProofVerifier,
+    ) -> ValidationReport {
+        Self::report(self.checks(output, Some(proofs)))
+    }
+
+    fn report(checks: Vec<Check>) -> ValidationReport {
+        ValidationReport {
+            outcomes: checks
+                .into_iter()
+                .map(|check| AssertionOutcome {
+                    assertion: check.assertion,
+                    actual: check.actual,
+                    failure: check.result.err().map(|error| error.to_string()),
+                })
+                .collect(),
         }
+    }
+
+    fn checks(
+        &self,
+        output: &VerifierOutput,
+        proofs: Option<&dyn PredicateProofVerifier>,
+    ) -> Vec<Check> {
+        let server_name = self.expected_server_name.iter().map(|expected_name| {
+            Check::new(
+                format!("server name equals '{expected_name}'"),
+                output
+                    .server_name
+                    .clone()
+                    .ok_or(Error::MissingField("server identity")),
+                |actual| {
+                    if actual == expected_name {
+                        Ok(())
+                    } else {
+                        Err(Error::InvalidTranscript(format!(
+                            "Expected server name '{expected_name}', got '{actual}'"
+                        )))
+                    }
+                },
+            )
+        });
+
+        let hash_alg = output
+            .transcript_commitments
+            .iter()
+            .filter_map(|commitment| match commitment {
+                TranscriptCommitment::Hash(hash) => Some(hash),
+                _ => None,
+            })
+            .filter_map(|hash| {
+                self.expected_hash_algs
+                    .iter()
+                    .filter_map(|(scope, alg)| scope.specificity(hash).map(|rank| (rank, alg)))
+                    .max_by_key(|&(rank, _)| rank)
+                    .map(|(_, expected_alg)| Check {
+                        assertion: format!(
+                            "{:?} commitment {:?} uses {expected_alg:?} hash algorithm",
+                            hash.direction, hash.idx
+                        ),
+                        actual: Some(format!("{:?}", hash.hash.alg)),
+                        result: if hash.hash.alg == *expected_alg {
+                            Ok(())
+                        } else {
+                            Err(Error::InvalidTranscript(format!(
+                                "Expected {:?} hash algorithm for {:?} commitment {:?}, got {:?}",
+                                expected_alg, hash.direction, hash.idx, hash.hash.alg
+                            )))
+                        },
+                    })
+            });
+
+        let request = output
+            .parsed_request
+            .as_ref()
+            .map(|request| (&request.headers, &request.body));
+        let request_checks = self.request_assertions.iter().map(|assertion| {
+            Self::check_assertion(
+                assertion,
+                request.ok_or(Error::MissingField("parsed request")),
+                output.message_bytes(Direction::Sent),
+                "request",
+                Committed {
+                    direction: Direction::Sent,
+                    commitments: &output.transcript_commitments,
+                    offset: output.request_offset,
+                    proofs,
+                },
+            )
+        });
+
+        let response = output
+            .parsed_response
+            .as_ref()
+            .map(|response| (&response.headers, &response.body));
+        let response_checks = self.response_assertions.iter().map(|assertion| {
+            Self::check_assertion(
+                assertion,
+                response.ok_or(Error::MissingField("parsed response")),
+                output.message_bytes(Direction::Received),
+                "response",
+                Committed {
+                    direction: Direction::Received,
+                    commitments: &output.transcript_commitments,
+                    offset: output.response_offset,
+                    proofs,
+                },
+            )
+        });
 
-        Ok(())
+        server_name
+            .chain(hash_alg)
+            .chain(request_checks)
+            .chain(response_checks)
+            .collect()
     }
 
-    fn validate_assertion(
+    fn check_assertion(
         assertion: &FieldAssertion,
-        headers: &HashMap<String, Vec<parser::redacted::Header>>,
-        body: &HashMap<String, parser::redacted::Body>,
+        message: Result<ParsedFields<'_>, Error>,
         data: &[u8],
         ctx: &str,
-    ) -> Result<(), Error> {
+        committed: Committed<'_>,
+    ) -> Check {
+        let (headers, body) = match message {
+            Ok(fields) => fields,
+            Err(error) => {
+                return Check {
+                    assertion: Self::describe(assertion, ctx),
+                    actual: None,
+                    result: Err(error),
+                };
+            }
+        };
+
         match assertion {
-            FieldAssertion::HeaderEquals { key, value } => {
-                let header = headers
-                    .get(&key.to_lowercase())
-                    .and_then(|h| h.first())
-                    .ok_or_else(|| {
-                        Error::InvalidTranscript(format!("Missing {ctx} header '{key}'"))
-                    })?;
-                let range = header.value.as_ref().ok_or_else(|| {
-                    Error::InvalidTranscript(format!("{ctx} header '{key}' has no value"))
-                })?;
-                let actual = std::str::from_utf8(&data[range.clone()])
-                    .map_err(|_| Error::InvalidTranscript("Invalid UTF-8".into()))?;
-                if actual != value {
-                    return Err(Error::InvalidTranscript(format!(
-                        "{ctx} header '{key}': expected '{value}', got '{actual}'"
-                    )));
+            FieldAssertion::HeaderEquals { key, value } => Check::new(
+                Self::describe(assertion, ctx),
+                Self::header_value(headers, data, ctx, key),
+                |actual| {
+                    if actual == parser::normalize_header_value(value) {
+                        Ok(())
+                    } else {
+                        Err(Error::InvalidTranscript(format!(
+                            "{ctx} header '{key}': expected '{value}', got '{actual}'"
+                        )))
+                    }
+                },
+            ),
+            FieldAssertion::BodyFieldEquals { key, value } => Check::new(
+                Self::describe(assertion, ctx),
+                Self::body_value(body, data, ctx, key),
+                |actual| Self::validate_value(value, actual, ctx, key),
+            ),
+            FieldAssertion::CommittedFieldSatisfies { key, predicate } => {
+                match Self::committed_field(body, &committed, ctx, key) {
+                    Ok(hash) => Check {
+                        assertion: Self::describe(assertion, ctx),
+                        actual: Some(format!("committed {:?}", hash.idx)),
+                        result: committed
+                            .proofs
+                            .ok_or_else(|| {
+                                Error::InvalidConfig(format!(
+                                    "no predicate proofs supplied for {ctx} field '{key}'"
+                                ))
+                            })
+                            .and_then(|proofs| proofs.verify_predicate(key, hash, *predicate)),
+                    },
+                    Err(error) => Check {
+                        assertion: Self::describe(assertion, ctx),
+                        actual: None,
+                        result: Err(error),
+                    },
                 }
             }
+        }
+    }
+
+    fn describe(assertion: &FieldAssertion, ctx: &str) -> String {
+        match assertion {
+            FieldAssertion::HeaderEquals { key, value } => {
+                format!("{ctx} header '{key}' equals '{value}'")
+            }
             FieldAssertion::BodyFieldEquals { key, value } => {
-                let field = body.get(key).ok_or_else(|| {
-                    Error::InvalidTranscript(format!("Missing {ctx} body field '{key}'"))
-                })?;
-                Self::validate_value(value, field, data, ctx, key)?;
+                format!("{ctx} body field '{key}' equals {value:?}")
+            }
+            FieldAssertion::CommittedFieldSatisfies { key, predicate } => {
+                format!("{ctx} committed body field '{key}' satisfies {predicate:?}")
             }
         }
-        Ok(())
     }
 
-    fn validate_value(
-        expected: &ExpectedValue,
-        field: &parser::redacted::Body,
+    /// The commitment to the hidden value that follows `key`.
+    fn committed_field<'a>(
+        body: &HashMap<String, parser::redacted::Body>,
+        committed: &Committed<'a>,
+        ctx: &str,
+        key: &str,
+    ) -> Result<&'a PlaintextHash, Error> {
+        let key_range = match body.get(key) {
+            Some(parser::redacted::Body::KeyValue { key, value: None }) => key,
+            Some(_) => {
+                return Err(Error::InvalidTranscript(format!(
+                    "{ctx} field '{key}' is revealed, not committed"
+                )));
+            }
+            None => {
+                return Err(Error::InvalidTranscript(format!(
+                    "Missing {ctx} body field '{key}'"
+                )));
+            }
+        };
+        let key_end = committed.offset.to_transcript(key_range)?.end;
+        committed
+            .commitments
+            .iter()
+            .filter_map(|commitment| match commitment {
+                TranscriptCommitment::Hash(hash) if hash.direction == committed.direction => {
+                    Some(hash)
+                }
+                _ => None,
+            })
+            .find(|hash| {
+                hash.idx
+                    .min()
+                    .is_some_and(|start| start >= key_end && start - key_end <= MAX_KEY_VALUE_GAP)
+            })
+            .ok_or_else(|| {
+                Error::InvalidTranscript(format!("No commitment bound to {ctx} field '{key}'"))
+            })
+    }
+
+    fn header_value(
+        headers: &HashMap<String, Vec<parser::redacted::Header>>,
         data: &[u8],
         ctx: &str,
         key: &str,
-    ) -> Result<(), Error> {
+    ) -> Result<String, Error> {
+        let header = headers
+            .get(&key.to_lowercase())
+            .and_then(|h| h.first())
+            .ok_or_else(|| Error::InvalidTranscript(format!("Missing {ctx} header '{key}'")))?;
+        let range = header.value.as_ref().ok_or_else(|| {
+            Error::InvalidTranscript(format!("{ctx} header '{key}' has no value"))
+        })?;
+        Self::slice_utf8(data, range).map(|value| parser::normalize_header_value(&value))
+    }
+
+    fn body_value(
+        body: &HashMap<String, parser::redacted::Body>,
+        data: &[u8],
+        ctx: &str,
+        key: &str,
+    ) -> Result<String, Error> {
+        let field = body
+            .get(key)
+            .ok_or_else(|| Error::InvalidTranscript(format!("Missing {ctx} body field '{key}'")))?;
         let range = match field {
             parser::redacted::Body::KeyValue { value, .. } => value.as_ref(),
             parser::redacted::Body::Value(r) => Some(r),
+            parser::redacted::Body::PartialValue { .. } => None,
         }
         .ok_or_else(|| {
             Error::InvalidTranscript(format!("Missing value for {ctx} field '{key}'"))
         })?;
+        Self::slice_utf8(data, range)
+    }
 
-        let actual = std::str::from_utf8(&data[range.clone()])
-            .map_err(|_| Error::InvalidTranscript("Invalid UTF-8".into()))?;
+    fn slice_utf8(data: &[u8], range: &std::ops::Range<usize>) -> Result<String, Error> {
+        data.get(range.clone())
+            .and_then(|bytes| std::str::from_utf8(bytes).ok())
+            .map(str::to_string)
+            .ok_or_else(|| Error::InvalidTranscript("Invalid UTF-8".into()))
+    }
 
+    fn validate_value(
+        expected: &ExpectedValue,
+        actual: &str,
+        ctx: &str,
+        key: &str,
+    ) -> Result<(), Error> {
         let mismatch = |exp: &dyn std::fmt::Display, act: &dyn std::fmt::Display| {
             Error::InvalidTranscript(format!("{ctx} field '{key}': expected {exp}, got {act}"))
         };
@@ -186,10 +567,37 @@ impl Validator {
     }
 }
 
+struct Committed<'a> {
+    direction: Direction,
+    commitments: &'a [TranscriptCommitment],
+    /// Where the parsed message starts in the transcript.
+    offset: TranscriptOffset,
+    proofs: Option<&'a dyn PredicateProofVerifier>,
+}
+
+type ParsedFields<'a> = (
+    &'a HashMap<String, Vec<parser::redacted::Header>>,
+    &'a HashMap<String, parser::redacted::Body>,
+);
+
+fn merge_expectation<T: PartialEq + std::fmt::Debug>(
+    name: &str,
+    left: Option<T>,
+    right: Option<T>,
+) -> Result<Option<T>, Error> {
+    match (left, right) {
+        (Some(left), Some(right)) if left != right => Err(Error::InvalidConfig(format!(
+            "conflicting expected {name}: {left:?} vs {right:?}"
+        ))),
+        (Some(value), _) | (None, Some(value)) => Ok(Some(value)),
+        (None, None) => Ok(None),
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct ValidatorBuilder {
     expected_server_name: Option<String>,
-    expected_hash_alg: Option<HashAlgId>,
+    expected_hash_algs: Vec<(HashAlgScope, HashAlgId)>,
     request_assertions: Vec<FieldAssertion>,
     response_assertions: Vec<FieldAssertion>,
 }
@@ -206,8 +614,26 @@ impl ValidatorBuilder {
     }
 
     #[must_use]
-    pub fn expected_hash_alg(mut self, alg: HashAlgId) -> Self {
-        self.expected_hash_alg = Some(alg);
+    pub fn expected_hash_alg(self, alg: HashAlgId) -> Self {
+        self.expected_scoped_hash_alg(HashAlgScope::All, alg)
+    }
+
+    #[must_use]
+    pub fn expected_direction_hash_alg(self, direction: Direction, alg: HashAlgId) -> Self {
+        self.expected_scoped_hash_alg(HashAlgScope::Direction(direction), alg)
+    }
+
+    /// Expects the commitment the prover recorded under `label` to use `alg`.
+    #[must_use]
+    pub fn expected_label_hash_alg(self, label: &CommitmentLabel, alg: HashAlgId) -> Self {
+        self.expected_scoped_hash_alg(HashAlgScope::from(label), alg)
+    }
+
+    #[must_use]
+    pub fn expected_scoped_hash_alg(mut self, scope: HashAlgScope, alg: HashAlgId) -> Self {
+        self.expected_hash_algs
+            .retain(|(existing, _)| *existing != scope);
+        self.expected_hash_algs.push((scope, alg));
         self
     }
 
@@ -265,11 +691,27 @@ impl ValidatorBuilder {
         self
     }
 
+    /// Requires a proof that the hidden value committed for `key` satisfies
+    /// `predicate`, checked by [`Validator::validate_with_proofs`].
+    #[must_use]
+    pub fn response_committed_field_satisfies(
+        mut self,
+        key: impl Into<String>,
+        predicate: Predicate,
+    ) -> Self {
+        self.response_assertions
+            .push(FieldAssertion::CommittedFieldSatisfies {
+                key: key.into(),
+                predicate,
+            });
+        self
+    }
+
     #[must_use]
     pub fn build(self) -> Validator {
         Validator {
             expected_server_name: self.expected_server_name,
-            expected_hash_alg: self.expected_hash_alg,
+            expected_hash_algs: self.expected_hash_algs,
             request_assertions: self.request_assertions,
             response_assertions: self.response_assertions,
         }
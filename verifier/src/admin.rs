@@ -0,0 +1,401 @@
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    path::PathBuf,
+    sync::{Arc, Mutex, PoisonError, RwLock, Weak},
+    time::Instant,
+};
+
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode, header},
+    routing::{delete, get, post},
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use smol::channel::{self, Receiver, Sender};
+use subtle::ConstantTimeEq;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::{
+    AdmissionControl, LoadShedder, LoadStatus, NotaryConfig, PolicySet, ServiceOptions,
+    TrafficLimits, TrafficMeter, TrafficTotals, errors::ProtocolError,
+};
+
+/// Shortest bearer token, in bytes, that [`AdminConfig::validate`] accepts.
+pub const MIN_ADMIN_TOKEN_LEN: usize = 32;
+
+/// Where the admin routes listen and the bearer token they require.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct AdminConfig {
+    pub addr: SocketAddr,
+    pub token: String,
+}
+
+impl AdminConfig {
+    /// Rejects empty tokens and those shorter than [`MIN_ADMIN_TOKEN_LEN`]
+    /// bytes once surrounding whitespace is trimmed.
+    pub fn validate(&self) -> Result<(), ProtocolError> {
+        if self.token.trim().len() < MIN_ADMIN_TOKEN_LEN {
+            return Err(ProtocolError::InvalidConfig(format!(
+                "the admin token must be at least {MIN_ADMIN_TOKEN_LEN} bytes"
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SessionPhase {
+    /// Waiting for the prover's opening frame.
+    #[default]
+    Opening,
+    Notarizing,
+    AwaitingProof,
+    Verifying,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionSummary {
+    pub id: Uuid,
+    pub phase: SessionPhase,
+    pub policy: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub age_secs: u64,
+    /// MPC traffic so far; zero until notarization starts.
+    pub traffic: TrafficTotals,
+}
+
+/// What `POST /admin/reload` replaces. Sessions read them once, when they
+/// start, so a reload never changes the limits of one already running.
+#[derive(Debug, Clone)]
+struct ReloadableSettings {
+    policies: Option<Arc<PolicySet>>,
+    traffic_limits: Option<TrafficLimits>,
+    require_session_nonce: bool,
+}
+
+#[derive(Debug, Default)]
+struct SessionState {
+    phase: SessionPhase,
+    policy: Option<String>,
+    meter: Option<TrafficMeter>,
+}
+
+#[derive(Debug)]
+struct SessionEntry {
+    started: Instant,
+    started_at: DateTime<Utc>,
+    state: Mutex<SessionState>,
+    cancel: Sender<()>,
+}
+
+/// Operator view of a running service: lists and cancels live sessions and
+/// reloads limits and policies from the config file without a restart.
+/// Every route requires `Authorization: Bearer <token>`.
+#[derive(Debug)]
+pub struct Admin {
+    token: blake3::Hash,
+    config_path: Option<PathBuf>,
+    admission_control: Option<Arc<AdmissionControl>>,
+    load_shedder: Option<Arc<LoadShedder>>,
+    settings: RwLock<ReloadableSettings>,
+    sessions: Mutex<HashMap<Uuid, Arc<SessionEntry>>>,
+}
+
+impl Admin {
+    /// Checks `config` with [`AdminConfig::validate`] and requires its token
+    /// without surrounding whitespace. The reloadable settings start out as
+    /// those of `options`; `config_path` is what a reload re-reads.
+    pub fn new(
+        config: &AdminConfig,
+        config_path: Option<PathBuf>,
+        options: &ServiceOptions,
+    ) -> Result<Arc<Self>, ProtocolError> {
+        config.validate()?;
+        Ok(Arc::new(Self {
+            token: blake3::hash(config.token.trim().as_bytes()),
+            config_path,
+            admission_control: options.admission_control.clone(),
+            load_shedder: options.load_shedder.clone(),
+            settings: RwLock::new(ReloadableSettings {
+                policies: options.policies.clone(),
+                traffic_limits: options.traffic_limits,
+                require_session_nonce: options.require_session_nonce,
+            }),
+            sessions: Mutex::default(),
+        }))
+    }
+
+    /// Live sessions, oldest first.
+    pub fn sessions(&self) -> Vec<SessionSummary> {
+        let sessions = self.sessions.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut summaries = sessions
+            .iter()
+            .map(|(&id, entry)| {
+                let state = entry.state.lock().unwrap_or_else(PoisonError::into_inner);
+                SessionSummary {
+                    id,
+                    phase: state.phase,
+                    policy: state.policy.clone(),
+                    started_at: entry.started_at,
+                    age_secs: entry.started.elapsed().as_secs(),
+                    traffic: state
+                        .meter
+                        .as_ref()
+                        .map(TrafficMeter::totals)
+                        .unwrap_or_default(),
+                }
+            })
+            .collect::<Vec<_>>();
+        summaries.sort_by_key(|summary| summary.started_at);
+        summaries
+    }
+
+    /// Aborts a live session, dropping its stream; `false` when `id` is not
+    /// live.
+    pub fn cancel(&self, id: Uuid) -> bool {
+        let sessions = self.sessions.lock().unwrap_or_else(PoisonError::into_inner);
+        let Some(entry) = sessions.get(&id) else {
+            return false;
+        };
+        let _ = entry.cancel.try_send(());
+        true
+    }
+
+    /// Re-reads the config file the service was started with and applies it
+    /// with [`Self::apply`].
+    pub fn reload(&self) -> Result<(), ProtocolError> {
+        let path = self.config_path.as_ref().ok_or_else(|| {
+            ProtocolError::InvalidConfig("the service was started without a config file".into())
+        })?;
+        self.apply(&NotaryConfig::from_toml_file(path)?)
+    }
+
+    /// Applies the policies, traffic limits, nonce requirement and admission
+    /// budget of `config` to sessions started from now on. Listeners, the
+    /// certificate and the artifact store need a restart.
+    pub fn apply(&self, config: &NotaryConfig) -> Result<(), ProtocolError> {
+        let policies = config
+            .policies
+            .as_ref()
+            .map(PolicySet::from_json_file)
+            .transpose()?;
+        let settings = ReloadableSettings {
+            policies: policies.map(Arc::new),
+            traffic_limits: config.traffic_limits,
+            require_session_nonce: config.require_session_nonce,
+        };
+        *self
+            .settings
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = settings;
+        if let Some(admission_control) = &self.admission_control {
+            admission_control.set_budget(
+                config
+                    .admission_budget
+                    .unwrap_or_else(AdmissionControl::default_budget),
+            );
+        }
+        info!(
+            traffic_limits = ?config.traffic_limits,
+            admission_budget = ?config.admission_budget,
+            "Reloaded verifier settings"
+        );
+        Ok(())
+    }
+
+    pub fn router(self: Arc<Self>) -> Router {
+        Router::new()
+            .route("/admin/sessions", get(list_sessions))
+            .route("/admin/sessions/{id}", delete(cancel_session))
+            .route("/admin/reload", post(reload))
+            .route("/admin/load", get(load_status))
+            .with_state(self)
+    }
+
+    /// Registers a session and overrides the reloadable settings in
+    /// `options` with the current ones.
+    pub(crate) fn start_session(self: &Arc<Self>, options: &mut ServiceOptions) -> LiveSession {
+        let settings = self
+            .settings
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+        options.policies = settings.policies;
+        options.traffic_limits = settings.traffic_limits;
+        options.require_session_nonce = settings.require_session_nonce;
+
+        let (cancel, cancelled) = channel::bounded(1);
+        let id = Uuid::new_v4();
+        let entry = Arc::new(SessionEntry {
+            started: Instant::now(),
+            started_at: Utc::now(),
+            state: Mutex::default(),
+            cancel,
+        });
+        self.sessions
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(id, Arc::clone(&entry));
+        LiveSession {
+            tracked: Some(TrackedSession {
+                admin: Arc::downgrade(self),
+                id,
+                entry,
+                cancelled,
+            }),
+        }
+    }
+
+    fn authorize(&self, headers: &HeaderMap) -> Result<(), StatusCode> {
+        headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            // Hashing first gives both sides the same length, so the
+            // comparison time does not depend on the presented token.
+            .filter(|token| {
+                bool::from(
+                    blake3::hash(token.as_bytes())
+                        .as_bytes()
+                        .ct_eq(self.token.as_bytes()),
+                )
+            })
+            .map(|_| ())
+            .ok_or(StatusCode::UNAUTHORIZED)
+    }
+}
+
+/// Serves [`Admin::router`] over plain HTTP until the listener fails.
+pub async fn serve_admin(addr: SocketAddr, admin: Arc<Admin>) -> std::io::Result<()> {
+    let listener = smol::net::TcpListener::bind(addr).await?;
+    info!(%addr, "Admin API listening");
+    crate::serve_router(listener, admin.router()).await
+}
+
+#[derive(Debug)]
+struct TrackedSession {
+    admin: Weak<Admin>,
+    id: Uuid,
+    entry: Arc<SessionEntry>,
+    cancelled: Receiver<()>,
+}
+
+/// A session's entry in [`Admin::sessions`], removed once dropped. Without
+/// admin routes sessions are untracked and every call is a no-op.
+#[derive(Debug)]
+pub(crate) struct LiveSession {
+    tracked: Option<TrackedSession>,
+}
+
+impl LiveSession {
+    pub(crate) fn untracked() -> Self {
+        Self { tracked: None }
+    }
+
+    pub(crate) fn set_phase(&self, phase: SessionPhase) {
+        self.update(|state| state.phase = phase);
+    }
+
+    pub(crate) fn set_policy(&self, policy: Option<&str>) {
+        self.update(|state| state.policy = policy.map(str::to_string));
+    }
+
+    pub(crate) fn attach_meter(&self, meter: &TrafficMeter) {
+        self.update(|state| state.meter = Some(meter.clone()));
+    }
+
+    /// Resolves once an operator cancels the session; never when untracked.
+    pub(crate) async fn cancelled(&self) {
+        match &self.tracked {
+            Some(tracked) => {
+                let _ = tracked.cancelled.recv().await;
+            }
+            None => smol::future::pending().await,
+        }
+    }
+
+    fn update(&self, update: impl FnOnce(&mut SessionState)) {
+        if let Some(tracked) = &self.tracked {
+            update(
+                &mut tracked
+                    .entry
+                    .state
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner),
+            );
+        }
+    }
+}
+
+impl Drop for LiveSession {
+    fn drop(&mut self) {
+        if let Some(tracked) = &self.tracked
+            && let Some(admin) = tracked.admin.upgrade()
+        {
+            admin
+                .sessions
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .remove(&tracked.id);
+        }
+    }
+}
+
+async fn list_sessions(
+    State(admin): State<Arc<Admin>>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<SessionSummary>>, StatusCode> {
+    admin.authorize(&headers)?;
+    Ok(Json(admin.sessions()))
+}
+
+async fn cancel_session(
+    State(admin): State<Arc<Admin>>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> StatusCode {
+    if let Err(status) = admin.authorize(&headers) {
+        return status;
+    }
+    if admin.cancel(id) {
+        warn!(session_id = %id, "Session cancelled through the admin API");
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::NOT_FOUND
+    }
+}
+
+async fn load_status(
+    State(admin): State<Arc<Admin>>,
+    headers: HeaderMap,
+) -> Result<Json<LoadStatus>, StatusCode> {
+    admin.authorize(&headers)?;
+    admin
+        .load_shedder
+        .as_ref()
+        .map(|load_shedder| Json(load_shedder.status()))
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+async fn reload(
+    State(admin): State<Arc<Admin>>,
+    headers: HeaderMap,
+) -> Result<StatusCode, (StatusCode, String)> {
+    admin
+        .authorize(&headers)
+        .map_err(|status| (status, String::new()))?;
+    smol::unblock(move || admin.reload())
+        .await
+        .map(|()| StatusCode::NO_CONTENT)
+        .map_err(|error| {
+            warn!(error = %error, "Config reload failed");
+            (StatusCode::UNPROCESSABLE_ENTITY, error.to_string())
+        })
+}
@@ -0,0 +1,126 @@
+use std::sync::{
+    Arc,
+    atomic::{AtomicUsize, Ordering},
+};
+
+use axum::{Json, Router, extract::State, routing::get};
+use serde::{Deserialize, Serialize};
+
+use crate::{MAX_RECV_DATA, MAX_SENT_DATA, errors::ProtocolError};
+
+/// Number of maximum-size sessions the default budget admits concurrently.
+const DEFAULT_CONCURRENT_SESSIONS: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AdmissionStatus {
+    pub budget: usize,
+    pub in_use: usize,
+    pub available: usize,
+}
+
+/// Body of `GET /health`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthStatus {
+    pub admission: AdmissionStatus,
+}
+
+/// Admits notarization sessions against a shared cost budget instead of a
+/// flat session count, so a few large sessions cannot starve the verifier.
+#[derive(Debug)]
+pub struct AdmissionControl {
+    budget: AtomicUsize,
+    in_use: AtomicUsize,
+}
+
+impl AdmissionControl {
+    pub fn new(budget: usize) -> Self {
+        Self {
+            budget: AtomicUsize::new(budget),
+            in_use: AtomicUsize::new(0),
+        }
+    }
+
+    /// Room for a few maximum-size sessions at once.
+    pub fn default_budget() -> usize {
+        DEFAULT_CONCURRENT_SESSIONS * Self::session_cost(MAX_SENT_DATA, MAX_RECV_DATA)
+    }
+
+    /// Changes the budget for later admissions. Sessions already admitted
+    /// keep their cost until they finish, even past a lowered budget.
+    pub fn set_budget(&self, budget: usize) {
+        self.budget.store(budget, Ordering::Release);
+    }
+
+    /// MPC bandwidth and CPU scale with the plaintext limits the prover asks
+    /// for. A sum past `usize::MAX` saturates, and no finite budget admits it.
+    pub fn session_cost(max_sent_data: usize, max_recv_data: usize) -> usize {
+        max_sent_data.saturating_add(max_recv_data)
+    }
+
+    pub fn try_admit(self: &Arc<Self>, cost: usize) -> Result<AdmissionPermit, ProtocolError> {
+        let budget = self.budget.load(Ordering::Acquire);
+        self.in_use
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |in_use| {
+                in_use.checked_add(cost).filter(|total| *total <= budget)
+            })
+            .map_err(|in_use| ProtocolError::AdmissionRejected {
+                cost,
+                available: budget.saturating_sub(in_use),
+            })?;
+
+        Ok(AdmissionPermit {
+            control: Arc::clone(self),
+            cost,
+        })
+    }
+
+    pub fn status(&self) -> AdmissionStatus {
+        let budget = self.budget.load(Ordering::Acquire);
+        let in_use = self.in_use.load(Ordering::Acquire);
+        AdmissionStatus {
+            budget,
+            in_use,
+            available: budget.saturating_sub(in_use),
+        }
+    }
+
+    /// Serves `GET /health` with the budget left for new sessions.
+    pub fn router(self: Arc<Self>) -> Router {
+        Router::new()
+            .route("/health", get(get_health))
+            .with_state(self)
+    }
+}
+
+async fn get_health(State(control): State<Arc<AdmissionControl>>) -> Json<HealthStatus> {
+    Json(HealthStatus {
+        admission: control.status(),
+    })
+}
+
+impl Default for AdmissionControl {
+    fn default() -> Self {
+        Self::new(Self::default_budget())
+    }
+}
+
+/// Holds admitted cost until dropped.
+#[derive(Debug)]
+pub struct AdmissionPermit {
+    control: Arc<AdmissionControl>,
+    cost: usize,
+}
+
+impl AdmissionPermit {
+    pub fn cost(&self) -> usize {
+        self.cost
+    }
+}
+
+impl Drop for AdmissionPermit {
+    fn drop(&mut self) {
+        self.control.in_use.fetch_sub(self.cost, Ordering::AcqRel);
+    }
+}
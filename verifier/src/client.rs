@@ -0,0 +1,217 @@
+use std::{
+    io,
+    net::SocketAddr,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use async_compat::Compat;
+use futures::{AsyncRead, AsyncWrite, AsyncWriteExt};
+use http_body_util::{BodyExt, Empty};
+use hyper::{Request, StatusCode, body::Bytes, header};
+use hyper_util::rt::TokioIo;
+use quinn::{Connection, Endpoint, RecvStream, SendStream};
+use serde_json::Value;
+use smol::net::TcpStream;
+use tokio::io::Join;
+use uuid::Uuid;
+use zktlsn::Proof;
+
+use crate::{
+    errors::{ClientError, ProtocolError},
+    jobs::JobStatus,
+    protocol::{ProofMessage, SessionInit, VerificationOutcome, read_json_frame, write_json_frame},
+    rpc::{
+        METHOD_SESSION_NOTARIZE, METHOD_SESSION_VERIFY, RpcRequest, RpcResponse, SubmitParams,
+        SubmitResult, VERIFICATION_FAILED_CODE, init_session, submit_session,
+    },
+};
+
+/// A bidirectional QUIC stream as the futures-io types the sessions run over.
+pub type QuicStream = Compat<Join<RecvStream, SendStream>>;
+
+/// A prover's connection to the verifier service. Each session runs on its
+/// own stream, so one connection serves any number of them.
+#[derive(Debug, Clone)]
+pub struct VerifierClient {
+    connection: Connection,
+}
+
+impl VerifierClient {
+    pub fn new(connection: Connection) -> Self {
+        Self { connection }
+    }
+
+    /// Connects `endpoint`, which needs a default client config, to the
+    /// verifier at `addr`.
+    pub async fn create_session(
+        endpoint: &Endpoint,
+        addr: SocketAddr,
+        server_name: &str,
+    ) -> Result<Self, ClientError> {
+        let connection = endpoint.connect(addr, server_name)?.await?;
+        Ok(Self::new(connection))
+    }
+
+    pub fn connection(&self) -> &Connection {
+        &self.connection
+    }
+
+    /// Opens a stream and sends `session.init` on it; the MPC-TLS session
+    /// runs over the returned stream next.
+    pub async fn open_notarize_stream(
+        &self,
+        session_init: &SessionInit,
+    ) -> Result<NotarizeStream<QuicStream>, ClientError> {
+        let (send, recv) = self.connection.open_bi().await?;
+        NotarizeStream::init(Compat::new(tokio::io::join(recv, send)), session_init).await
+    }
+}
+
+/// A session stream past `session.init`. It reads and writes as the raw
+/// stream for the MPC-TLS phase; once that is done the proof is sent with
+/// [`Self::submit_verification`] or [`Self::queue_verification`].
+#[derive(Debug)]
+pub struct NotarizeStream<IO> {
+    io: IO,
+    next_id: u64,
+    session_nonce: Option<String>,
+}
+
+impl<IO> NotarizeStream<IO>
+where
+    IO: AsyncRead + AsyncWrite + Unpin + Send,
+{
+    /// Sends `session.init` over any stream, e.g. a connection to a TCP
+    /// listener.
+    pub async fn init(mut io: IO, session_init: &SessionInit) -> Result<Self, ClientError> {
+        let result = init_session(&mut io, 0, session_init).await?;
+        if result.next_method != METHOD_SESSION_NOTARIZE {
+            return Err(ClientError::UnexpectedMethod(result.next_method));
+        }
+        Ok(Self {
+            io,
+            next_id: 1,
+            session_nonce: result.session_nonce,
+        })
+    }
+
+    /// The nonce to send, revealed, in the
+    /// [`SESSION_NONCE_HEADER`](crate::SESSION_NONCE_HEADER) of the notarized
+    /// request.
+    pub fn session_nonce(&self) -> Option<&str> {
+        self.session_nonce.as_deref()
+    }
+
+    /// Sends `session.verify` and closes the stream. A failed verification
+    /// is returned as an unsuccessful outcome rather than an error.
+    pub async fn submit_verification(
+        self,
+        proof: Proof,
+    ) -> Result<VerificationOutcome, ClientError> {
+        self.submit_proof_message(ProofMessage::new(proof)).await
+    }
+
+    /// [`Self::submit_verification`] with the threshold claims of `message`.
+    pub async fn submit_proof_message(
+        mut self,
+        message: ProofMessage,
+    ) -> Result<VerificationOutcome, ClientError> {
+        let request = RpcRequest::new(self.take_id(), METHOD_SESSION_VERIFY, message);
+        write_json_frame(&mut self.io, &request).await?;
+        let response: RpcResponse<VerificationOutcome> = read_json_frame(&mut self.io).await?;
+        self.io.close().await?;
+        if response.id != request.id {
+            return Err(ProtocolError::InvalidRpc(format!(
+                "response id {} does not match request id {}",
+                response.id, request.id
+            ))
+            .into());
+        }
+
+        match (response.result, response.error) {
+            (_, Some(error)) if error.code == VERIFICATION_FAILED_CODE => {
+                let data = error.data.ok_or(ClientError::MissingResult)?;
+                Ok(serde_json::from_value(data)?)
+            }
+            (_, Some(error)) => Err(ClientError::Rpc {
+                code: error.code,
+                message: error.message,
+            }),
+            (Some(outcome), None) => Ok(outcome),
+            (None, None) => Err(ClientError::MissingResult),
+        }
+    }
+
+    /// Sends `session.submit` and closes the stream; poll the outcome with
+    /// [`job_status`] or wait for the webhook.
+    pub async fn queue_verification(
+        mut self,
+        params: &SubmitParams,
+    ) -> Result<SubmitResult, ClientError> {
+        let id = self.take_id();
+        let result = submit_session(&mut self.io, id, params).await;
+        self.io.close().await?;
+        Ok(result?)
+    }
+
+    fn take_id(&mut self) -> Value {
+        let id = self.next_id;
+        self.next_id += 1;
+        Value::from(id)
+    }
+}
+
+impl<IO: AsyncRead + Unpin> AsyncRead for NotarizeStream<IO> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.io).poll_read(cx, buf)
+    }
+}
+
+impl<IO: AsyncWrite + Unpin> AsyncWrite for NotarizeStream<IO> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.io).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.io).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.io).poll_close(cx)
+    }
+}
+
+/// Fetches `GET /jobs/{id}` from the job routes at `jobs_addr`; `None` when
+/// the verifier does not know the job.
+pub async fn job_status(
+    jobs_addr: SocketAddr,
+    job_id: Uuid,
+) -> Result<Option<JobStatus>, ClientError> {
+    let stream = TcpStream::connect(jobs_addr).await?;
+    let (mut sender, connection) =
+        hyper::client::conn::http1::handshake(TokioIo::new(Compat::new(stream))).await?;
+    smol::spawn(connection).detach();
+
+    let request = Request::get(format!("/jobs/{job_id}"))
+        .header(header::HOST, jobs_addr.to_string())
+        .header(header::CONNECTION, "close")
+        .body(Empty::<Bytes>::new())?;
+    let response = sender.send_request(request).await?;
+    match response.status() {
+        StatusCode::NOT_FOUND => Ok(None),
+        status if status.is_success() => {
+            let body = response.into_body().collect().await?.to_bytes();
+            Ok(Some(serde_json::from_slice(&body)?))
+        }
+        status => Err(ClientError::Status(status)),
+    }
+}
@@ -0,0 +1,208 @@
+use std::{
+    fs,
+    net::{Ipv6Addr, SocketAddr},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use serde::Deserialize;
+use shared::{TestQuicConfig, get_or_create_test_quic_config};
+
+use crate::{
+    AdminConfig, AdmissionControl, ArtifactStore, DEFAULT_SHUTDOWN_GRACE_PERIOD,
+    FilesystemArtifactStore, JobQueue, JobQueueConfig, ListenerConfig, LoadShedder,
+    LoadSheddingConfig, NotaryKey, PolicySet, RoughtimeServer, ServiceOptions, StoreKeys,
+    TimeSource, TrafficLimits, TransparencyLog, errors::ProtocolError, timestamp::decode_hex,
+};
+
+const DEFAULT_PORT: u16 = 5000;
+
+/// Deployment settings for the `verifier notary serve` command, read from
+/// TOML. Every field is optional; the defaults match the examples.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+pub struct NotaryConfig {
+    /// QUIC certificate and key as PEM; a self-signed pair is generated when
+    /// either file is missing.
+    pub cert_path: PathBuf,
+    pub key_path: PathBuf,
+    pub listeners: Vec<ListenerConfig>,
+    /// Directory for attestation artifacts; none are kept when unset.
+    pub artifact_dir: Option<PathBuf>,
+    /// JSON [`StoreKeys`] file; artifacts are encrypted at rest when set.
+    pub artifact_key_file: Option<PathBuf>,
+    /// JSON [`PolicySet`] file.
+    pub policies: Option<PathBuf>,
+    /// Bytes of `maxSentData + maxRecvData` that concurrent sessions may
+    /// ask for in total; see [`AdmissionControl`].
+    pub admission_budget: Option<usize>,
+    pub load_shedding: Option<LoadSheddingConfig>,
+    pub traffic_limits: Option<TrafficLimits>,
+    pub shutdown_grace_period_secs: u64,
+    /// See [`ServiceOptions::require_session_nonce`]. Bare-frame sessions
+    /// are issued no nonce, so setting this leaves them unusable.
+    pub require_session_nonce: bool,
+    /// Serves the [`Admin`](crate::Admin) routes; the policies, limits and
+    /// admission budget above can then be reloaded without a restart.
+    pub admin: Option<AdminConfig>,
+    /// Has attestations timestamped by this server as well as the local
+    /// clock; recording an attestation fails while it is unreachable.
+    pub roughtime: Option<RoughtimeServer>,
+    /// File holding the 64 hex digit seed of the [`NotaryKey`] attestations,
+    /// their timestamps and the transparency log's tree heads are signed
+    /// with; they go unsigned when unset.
+    pub signing_key_file: Option<PathBuf>,
+    /// File of the [`TransparencyLog`] every attestation is appended to.
+    pub transparency_log: Option<PathBuf>,
+    /// Queues `session.submit` verifications; needs `webhookKeyFile`.
+    pub job_queue: Option<JobQueueConfig>,
+    /// File holding the 64 hex digit key job webhooks are signed with.
+    pub webhook_key_file: Option<PathBuf>,
+    /// Serves the job status routes and, with a transparency log, its root
+    /// and inclusion proofs over plain HTTP.
+    pub status_addr: Option<SocketAddr>,
+    /// Runs [`run_self_test`](crate::run_self_test) before listening and
+    /// refuses to start when it fails.
+    pub self_test: bool,
+    /// See [`ServiceOptions::simulate`].
+    #[cfg(feature = "simulation")]
+    pub simulate: bool,
+}
+
+impl Default for NotaryConfig {
+    fn default() -> Self {
+        Self {
+            cert_path: PathBuf::from("cert.pem"),
+            key_path: PathBuf::from("key.pem"),
+            listeners: vec![ListenerConfig::quic(SocketAddr::from((
+                Ipv6Addr::UNSPECIFIED,
+                DEFAULT_PORT,
+            )))],
+            artifact_dir: None,
+            artifact_key_file: None,
+            policies: None,
+            admission_budget: None,
+            load_shedding: None,
+            traffic_limits: None,
+            shutdown_grace_period_secs: DEFAULT_SHUTDOWN_GRACE_PERIOD.as_secs(),
+            require_session_nonce: false,
+            admin: None,
+            roughtime: None,
+            signing_key_file: None,
+            transparency_log: None,
+            job_queue: None,
+            webhook_key_file: None,
+            status_addr: None,
+            self_test: false,
+            #[cfg(feature = "simulation")]
+            simulate: false,
+        }
+    }
+}
+
+impl NotaryConfig {
+    /// Parses `path` and validates the [`AdminConfig`] token.
+    pub fn from_toml_file(path: impl AsRef<Path>) -> Result<Self, ProtocolError> {
+        let path = path.as_ref();
+        let config: Self = toml::from_str(&fs::read_to_string(path)?).map_err(|error| {
+            ProtocolError::InvalidConfig(format!("{}: {error}", path.display()))
+        })?;
+        if let Some(admin) = &config.admin {
+            admin.validate()?;
+        }
+        Ok(config)
+    }
+
+    /// Loads the certificate and key, generating them on first start.
+    pub async fn quic_server_config(&self) -> Result<quinn::ServerConfig, ProtocolError> {
+        let TestQuicConfig { server_config, .. } =
+            get_or_create_test_quic_config(&self.cert_path, &self.key_path)
+                .await
+                .map_err(|error| ProtocolError::InvalidConfig(error.to_string()))?;
+        Ok(server_config)
+    }
+
+    pub fn artifact_store(&self) -> Result<Option<FilesystemArtifactStore>, ProtocolError> {
+        let Some(artifact_dir) = &self.artifact_dir else {
+            return Ok(None);
+        };
+        let store = FilesystemArtifactStore::new(artifact_dir)?;
+        Ok(Some(match &self.artifact_key_file {
+            Some(path) => store.with_keys(StoreKeys::from_json_file(path)?),
+            None => store,
+        }))
+    }
+
+    /// Opens the log file, creating it on first start.
+    pub fn open_transparency_log(&self) -> Result<Option<TransparencyLog>, ProtocolError> {
+        self.transparency_log
+            .as_ref()
+            .map(TransparencyLog::open)
+            .transpose()
+            .map_err(|error| ProtocolError::InvalidConfig(format!("transparency log: {error}")))
+    }
+
+    /// Starts the queue's workers with the webhook key.
+    pub fn start_job_queue(&self) -> Result<Option<Arc<JobQueue>>, ProtocolError> {
+        let Some(config) = &self.job_queue else {
+            return Ok(None);
+        };
+        let path = self.webhook_key_file.as_ref().ok_or_else(|| {
+            ProtocolError::InvalidConfig("`jobQueue` needs a `webhookKeyFile`".into())
+        })?;
+        let webhook_secret = decode_hex(fs::read_to_string(path)?.trim())
+            .and_then(|key| <[u8; 32]>::try_from(key).ok())
+            .ok_or_else(|| {
+                ProtocolError::InvalidConfig(format!(
+                    "{}: webhook key is not 32 hex-encoded bytes",
+                    path.display()
+                ))
+            })?;
+        Ok(Some(JobQueue::start(config.clone(), webhook_secret)))
+    }
+
+    pub fn service_options(&self) -> Result<ServiceOptions, ProtocolError> {
+        let artifact_store = self.artifact_store()?;
+        let notary_key = self
+            .signing_key_file
+            .as_ref()
+            .map(NotaryKey::from_hex_file)
+            .transpose()?
+            .map(Arc::new);
+        let transparency_log = self.open_transparency_log()?.map(|log| match &notary_key {
+            Some(notary_key) => log.with_notary_key(Arc::clone(notary_key)),
+            None => log,
+        });
+        let policies = self
+            .policies
+            .as_ref()
+            .map(PolicySet::from_json_file)
+            .transpose()?;
+        let admission_control = self
+            .admission_budget
+            .map_or_else(AdmissionControl::default, AdmissionControl::new);
+
+        Ok(ServiceOptions {
+            artifact_store: artifact_store.map(|store| Arc::new(store) as Arc<dyn ArtifactStore>),
+            admission_control: Some(Arc::new(admission_control)),
+            load_shedder: self
+                .load_shedding
+                .map(|config| Arc::new(LoadShedder::new(config))),
+            policies: policies.map(Arc::new),
+            traffic_limits: self.traffic_limits,
+            job_queue: self.start_job_queue()?,
+            transparency_log: transparency_log.map(Arc::new),
+            time_source: self
+                .roughtime
+                .clone()
+                .map_or_else(TimeSource::local, TimeSource::roughtime),
+            notary_key,
+            shutdown_grace_period: Duration::from_secs(self.shutdown_grace_period_secs),
+            require_session_nonce: self.require_session_nonce,
+            #[cfg(feature = "simulation")]
+            simulate: self.simulate,
+            ..ServiceOptions::default()
+        })
+    }
+}
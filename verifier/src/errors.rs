@@ -11,6 +11,36 @@ pub enum ProtocolError {
     #[error("invalid protocol configuration: {0}")]
     InvalidConfig(String),
 
+    #[error("invalid JSON-RPC message: {0}")]
+    InvalidRpc(String),
+
+    #[error("invalid trace context: {0}")]
+    InvalidTraceContext(String),
+
+    #[error("invalid webhook URL {0}")]
+    InvalidWebhook(String),
+
+    #[error("verification job queue is full ({0} jobs pending)")]
+    JobQueueFull(usize),
+
+    #[error("idempotency key '{0}' was already used for a different submission")]
+    IdempotencyConflict(String),
+
+    #[error("unknown notarization policy '{0}'")]
+    UnknownPolicy(String),
+
+    #[error("session violates policy '{policy}': {reason}")]
+    PolicyViolation { policy: String, reason: String },
+
+    #[error("session {direction} traffic exceeded the {limit} byte limit")]
+    TrafficLimitExceeded { direction: &'static str, limit: u64 },
+
+    #[error("session cost {cost} exceeds available admission budget {available}")]
+    AdmissionRejected { cost: usize, available: usize },
+
+    #[error("verifier is shedding load: {0}")]
+    Overloaded(String),
+
     #[error("invalid proving request: {0}")]
     InvalidProvingRequest(String),
 
@@ -29,6 +59,15 @@ pub enum ProtocolError {
     #[error("response parsing failed: {0}")]
     ResponseParse(String),
 
+    #[error("session nonce check failed: {0}")]
+    SessionNonce(String),
+
+    #[error("session cancelled by an operator")]
+    SessionCancelled,
+
+    #[error("startup self-test failed while {stage}: {reason}")]
+    SelfTest { stage: &'static str, reason: String },
+
     #[error(transparent)]
     Io(#[from] std::io::Error),
 
@@ -40,4 +79,147 @@ pub enum ProtocolError {
 
     #[error(transparent)]
     TlsNotary(#[from] tlsnotary::Error),
+
+    #[error(transparent)]
+    Store(#[from] StoreError),
+
+    #[error(transparent)]
+    Timestamp(#[from] TimestampError),
+}
+
+#[derive(Debug, Error)]
+pub enum ClientError {
+    #[error("verifier expected '{0}' after session.init instead of notarization")]
+    UnexpectedMethod(String),
+
+    #[error("verifier returned JSON-RPC error {code}: {message}")]
+    Rpc { code: i64, message: String },
+
+    #[error("verifier response has neither an outcome nor an error")]
+    MissingResult,
+
+    #[error("job route responded with status {0}")]
+    Status(hyper::StatusCode),
+
+    #[error(transparent)]
+    Connect(#[from] quinn::ConnectError),
+
+    #[error(transparent)]
+    Connection(#[from] quinn::ConnectionError),
+
+    #[error(transparent)]
+    Protocol(#[from] ProtocolError),
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[error(transparent)]
+    Hyper(#[from] hyper::Error),
+
+    #[error(transparent)]
+    Http(#[from] hyper::http::Error),
+
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Error)]
+pub enum StoreError {
+    #[error("attestation artifact {0} not found")]
+    NotFound(uuid::Uuid),
+
+    #[error("attestation artifact {id} failed its {part} integrity check")]
+    Integrity { id: uuid::Uuid, part: &'static str },
+
+    #[error("attestation artifact {0} is encrypted and no store keys are configured")]
+    Sealed(uuid::Uuid),
+
+    #[error("attestation artifact {0} is stored unencrypted although store keys are configured")]
+    Unsealed(uuid::Uuid),
+
+    #[error("attestation artifact {0} could not be decrypted")]
+    Decrypt(uuid::Uuid),
+
+    #[error("unknown store key {0}")]
+    UnknownKey(String),
+
+    #[error("store key {0} is not 32 hex-encoded bytes")]
+    InvalidKey(String),
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Error)]
+pub enum TimestampError {
+    #[error("invalid Roughtime public key {0}")]
+    InvalidKey(String),
+
+    #[error("malformed Roughtime response: {0}")]
+    Malformed(&'static str),
+
+    #[error("Roughtime response has no {} tag", String::from_utf8_lossy(.0))]
+    MissingTag([u8; 4]),
+
+    #[error("Roughtime {0} signature does not verify")]
+    BadSignature(&'static str),
+
+    #[error("Roughtime midpoint lies outside the delegation's validity")]
+    OutsideDelegation,
+
+    #[error("Roughtime response does not sign the attestation nonce")]
+    NonceMismatch,
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+#[derive(Debug, Error)]
+pub enum WebhookError {
+    #[error("unsupported webhook URL {0}, expected http://host[:port]/path")]
+    UnsupportedUrl(String),
+
+    #[error("webhook host {host} resolves to non-public address {addr}")]
+    ForbiddenAddress {
+        host: String,
+        addr: std::net::IpAddr,
+    },
+
+    #[error("webhook responded with status {0}")]
+    Status(hyper::StatusCode),
+
+    #[error("webhook did not respond within {0:?}")]
+    Timeout(std::time::Duration),
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[error(transparent)]
+    Hyper(#[from] hyper::Error),
+
+    #[error(transparent)]
+    Http(#[from] hyper::http::Error),
+
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+}
+
+impl WebhookError {
+    /// Client errors other than timeouts and rate limits will not change on retry.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::Status(status) if status.is_client_error() => matches!(
+                *status,
+                hyper::StatusCode::REQUEST_TIMEOUT | hyper::StatusCode::TOO_MANY_REQUESTS
+            ),
+            Self::Status(_) | Self::Timeout(_) | Self::Io(_) | Self::Hyper(_) => true,
+            Self::UnsupportedUrl(_)
+            | Self::ForbiddenAddress { .. }
+            | Self::Http(_)
+            | Self::Json(_) => false,
+        }
+    }
 }
@@ -1,8 +1,15 @@
+use std::{net::SocketAddr, sync::Arc};
+
+use async_compat::Compat;
+use smol::net::TcpStream;
 use thiserror::Error;
 use tokio::io::join;
-use tracing::{error, info};
+use tracing::{Instrument, error, info, warn};
 
-use crate::protocol::run_notarize_and_verify_stream;
+use crate::{
+    ServiceOptions, listener::ListenerMetrics, protocol::run_notarize_and_verify_stream,
+    shutdown::SessionTracker,
+};
 
 #[derive(Debug, Error)]
 pub enum HandlerError {
@@ -10,9 +17,15 @@ pub enum HandlerError {
     Accept(#[from] quinn::ConnectionError),
 }
 
-pub async fn handle(incoming: quinn::Incoming) -> Result<(), HandlerError> {
+pub(crate) async fn handle(
+    incoming: quinn::Incoming,
+    options: ServiceOptions,
+    tracker: SessionTracker,
+    metrics: Arc<ListenerMetrics>,
+) -> Result<(), HandlerError> {
     let connection = incoming.await?;
     let remote_addr = connection.remote_address();
+    metrics.connection_accepted();
     info!(%remote_addr, "Accepted QUIC connection");
 
     loop {
@@ -23,18 +36,60 @@ pub async fn handle(incoming: quinn::Incoming) -> Result<(), HandlerError> {
         };
 
         let stream_id = send.id();
+        let Some(session) = tracker.start() else {
+            metrics.session_refused();
+            warn!(%stream_id, "Refusing new stream during shutdown");
+            continue;
+        };
+        metrics.session_started();
         let stream = join(recv, send);
-        smol::spawn(async move {
-            info!(%stream_id, "Starting notarize+verify pipeline on stream");
-            if let Err(error) = run_notarize_and_verify_stream(stream).await {
-                error!(%stream_id, error = %error, "Pipeline failed");
-            } else {
-                info!(%stream_id, "Pipeline completed");
+        let options = options.clone();
+        let metrics = Arc::clone(&metrics);
+        smol::spawn(
+            async move {
+                info!(%stream_id, "Starting notarize+verify pipeline on stream");
+                if let Err(error) = run_notarize_and_verify_stream(stream, options).await {
+                    metrics.session_failed();
+                    error!(%stream_id, error = %error, "Pipeline failed");
+                } else {
+                    info!(%stream_id, "Pipeline completed");
+                }
+                drop(session);
             }
-        })
+            .instrument(shared::session_span(remote_addr)),
+        )
         .detach();
     }
 
     info!(%remote_addr, "Connection closed");
     Ok(())
 }
+
+/// Runs one session over a TCP connection.
+pub(crate) async fn handle_tcp(
+    stream: TcpStream,
+    remote_addr: SocketAddr,
+    options: ServiceOptions,
+    tracker: SessionTracker,
+    metrics: Arc<ListenerMetrics>,
+) {
+    let Some(session) = tracker.start() else {
+        metrics.session_refused();
+        warn!(%remote_addr, "Refusing new TCP connection during shutdown");
+        return;
+    };
+    metrics.connection_accepted();
+    metrics.session_started();
+    async {
+        info!("Starting notarize+verify pipeline on TCP connection");
+        if let Err(error) = run_notarize_and_verify_stream(Compat::new(stream), options).await {
+            metrics.session_failed();
+            error!(error = %error, "Pipeline failed");
+        } else {
+            info!("Pipeline completed");
+        }
+    }
+    .instrument(shared::session_span(remote_addr))
+    .await;
+    drop(session);
+}
@@ -0,0 +1,449 @@
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    sync::{Arc, Weak},
+    time::{Duration, Instant},
+};
+
+use async_compat::Compat;
+use axum::{
+    Json, Router,
+    body::Bytes,
+    extract::{Path, State},
+    http::StatusCode,
+    routing::get,
+};
+use http_body_util::Full;
+use hyper::{Request, Uri, body::Incoming, header};
+use hyper_util::rt::TokioIo;
+use serde::{Deserialize, Serialize};
+use shared::SmolExecutor;
+use smol::{
+    Timer,
+    channel::{self, Receiver, Sender, TrySendError},
+    lock::Mutex,
+    net::{TcpListener, TcpStream},
+};
+use tower::Service;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::{
+    errors::{ProtocolError, WebhookError},
+    protocol::VerificationOutcome,
+};
+
+pub const DEFAULT_QUEUE_CAPACITY: usize = 64;
+pub const DEFAULT_JOB_WORKERS: usize = 2;
+pub const DEFAULT_JOB_TTL: Duration = Duration::from_secs(60 * 60);
+pub const DEFAULT_WEBHOOK_ATTEMPTS: usize = 5;
+pub const DEFAULT_WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+pub const SIGNATURE_HEADER: &str = "x-verifier-signature";
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+const INITIAL_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+type VerifyFn = Box<dyn FnOnce() -> VerificationOutcome + Send>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+pub struct JobQueueConfig {
+    pub workers: usize,
+    /// Finished jobs, and the idempotency keys naming them, are dropped this
+    /// long after they complete.
+    pub job_ttl_secs: u64,
+    pub webhook_attempts: usize,
+    /// Limit on each delivery attempt, from connecting to the response.
+    pub webhook_timeout_secs: u64,
+    /// Hosts webhooks may reach although they resolve to loopback, private or
+    /// link-local addresses, which are refused for any other host.
+    pub webhook_allowed_hosts: Vec<String>,
+}
+
+impl Default for JobQueueConfig {
+    fn default() -> Self {
+        Self {
+            workers: DEFAULT_JOB_WORKERS,
+            job_ttl_secs: DEFAULT_JOB_TTL.as_secs(),
+            webhook_attempts: DEFAULT_WEBHOOK_ATTEMPTS,
+            webhook_timeout_secs: DEFAULT_WEBHOOK_TIMEOUT.as_secs(),
+            webhook_allowed_hosts: Vec::new(),
+        }
+    }
+}
+
+/// A prover's idempotency key bound to a digest of its submission, so the key
+/// only ever returns the job of that same submission.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdempotencyKey {
+    key: String,
+    payload: blake3::Hash,
+}
+
+impl IdempotencyKey {
+    pub fn new(key: impl Into<String>, payload: &[u8]) -> Self {
+        Self {
+            key: key.into(),
+            payload: blake3::hash(payload),
+        }
+    }
+}
+
+struct QueuedJob {
+    id: Uuid,
+    webhook_url: Option<Uri>,
+    verify: VerifyFn,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "camelCase")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Completed { outcome: VerificationOutcome },
+}
+
+/// Body POSTed to a job's webhook once verification finishes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobResult {
+    pub job_id: Uuid,
+    pub outcome: VerificationOutcome,
+}
+
+struct TrackedJob {
+    status: JobStatus,
+    finished_at: Option<Instant>,
+}
+
+#[derive(Default)]
+struct JobTable {
+    jobs: HashMap<Uuid, TrackedJob>,
+    idempotency_keys: HashMap<String, (Uuid, blake3::Hash)>,
+}
+
+impl JobTable {
+    fn evict_finished(&mut self, ttl: Duration) {
+        self.jobs.retain(|_, job| {
+            job.finished_at
+                .is_none_or(|finished_at| finished_at.elapsed() < ttl)
+        });
+        let jobs = &self.jobs;
+        self.idempotency_keys
+            .retain(|_, (id, _)| jobs.contains_key(id));
+    }
+}
+
+/// Runs proof verification off the session stream. Results are kept for
+/// `GET /jobs/{id}` and, when a webhook is given, POSTed to it signed with
+/// [`webhook_signature`] and retried with backoff. Each delivery runs on its
+/// own task, so a slow webhook never holds up a worker. Deliveries carry the job id
+/// as their idempotency key, and resubmitting the same submission under an
+/// idempotency key returns the existing job instead of verifying again.
+pub struct JobQueue {
+    table: Mutex<JobTable>,
+    sender: Sender<QueuedJob>,
+    webhook_secret: [u8; 32],
+    webhook_attempts: usize,
+    webhook_timeout: Duration,
+    webhook_allowed_hosts: Vec<String>,
+    job_ttl: Duration,
+}
+
+impl JobQueue {
+    /// Creates the queue and spawns its verification workers, which stop once
+    /// the queue is dropped.
+    pub fn start(config: JobQueueConfig, webhook_secret: [u8; 32]) -> Arc<Self> {
+        let (sender, receiver) = channel::bounded(DEFAULT_QUEUE_CAPACITY);
+        let queue = Arc::new(Self {
+            table: Mutex::default(),
+            sender,
+            webhook_secret,
+            webhook_attempts: config.webhook_attempts,
+            webhook_timeout: Duration::from_secs(config.webhook_timeout_secs),
+            webhook_allowed_hosts: config.webhook_allowed_hosts,
+            job_ttl: Duration::from_secs(config.job_ttl_secs),
+        });
+        for _ in 0..config.workers.max(1) {
+            smol::spawn(run_worker(Arc::downgrade(&queue), receiver.clone())).detach();
+        }
+        queue
+    }
+
+    /// Queues `verify`, or returns the job already queued for the same
+    /// submission under `idempotency_key`. The webhook host is resolved up
+    /// front, so a URL the queue would refuse to deliver to fails here.
+    pub async fn enqueue(
+        &self,
+        webhook_url: Option<&str>,
+        idempotency_key: Option<IdempotencyKey>,
+        verify: impl FnOnce() -> VerificationOutcome + Send + 'static,
+    ) -> Result<Uuid, ProtocolError> {
+        let webhook_url = match webhook_url {
+            Some(url) => Some(self.checked_webhook(url).await?),
+            None => None,
+        };
+
+        let mut table = self.table.lock().await;
+        table.evict_finished(self.job_ttl);
+        if let Some(IdempotencyKey { key, payload }) = &idempotency_key
+            && let Some((id, existing)) = table.idempotency_keys.get(key)
+        {
+            if existing != payload {
+                return Err(ProtocolError::IdempotencyConflict(key.clone()));
+            }
+            info!(job_id = %id, "Returning existing job for idempotency key");
+            return Ok(*id);
+        }
+
+        let id = Uuid::new_v4();
+        let job = QueuedJob {
+            id,
+            webhook_url,
+            verify: Box::new(verify),
+        };
+        self.sender.try_send(job).map_err(|error| match error {
+            TrySendError::Full(_) => ProtocolError::JobQueueFull(DEFAULT_QUEUE_CAPACITY),
+            TrySendError::Closed(_) => {
+                ProtocolError::InvalidConfig("verification job queue is closed".to_string())
+            }
+        })?;
+        table.jobs.insert(
+            id,
+            TrackedJob {
+                status: JobStatus::Queued,
+                finished_at: None,
+            },
+        );
+        if let Some(IdempotencyKey { key, payload }) = idempotency_key {
+            table.idempotency_keys.insert(key, (id, payload));
+        }
+        Ok(id)
+    }
+
+    pub async fn status(&self, id: Uuid) -> Option<JobStatus> {
+        let mut table = self.table.lock().await;
+        table.evict_finished(self.job_ttl);
+        table.jobs.get(&id).map(|job| job.status.clone())
+    }
+
+    async fn checked_webhook(&self, url: &str) -> Result<Uri, ProtocolError> {
+        let invalid = |error: &dyn std::fmt::Display| {
+            ProtocolError::InvalidWebhook(format!("{url}: {error}"))
+        };
+        let uri = url.parse::<Uri>().map_err(|error| invalid(&error))?;
+        webhook_addrs(&uri, &self.webhook_allowed_hosts)
+            .await
+            .map_err(|error| invalid(&error))?;
+        Ok(uri)
+    }
+
+    async fn set_status(&self, id: Uuid, status: JobStatus) {
+        let finished_at = matches!(status, JobStatus::Completed { .. }).then(Instant::now);
+        self.table.lock().await.jobs.insert(
+            id,
+            TrackedJob {
+                status,
+                finished_at,
+            },
+        );
+    }
+
+    pub fn router(self: Arc<Self>) -> Router {
+        Router::new()
+            .route("/jobs/{id}", get(get_job))
+            .with_state(self)
+    }
+
+    async fn run(self: &Arc<Self>, job: QueuedJob) {
+        self.set_status(job.id, JobStatus::Running).await;
+        let outcome = smol::unblock(job.verify).await;
+        info!(job_id = %job.id, success = outcome.success, "Verification job finished");
+        self.set_status(
+            job.id,
+            JobStatus::Completed {
+                outcome: outcome.clone(),
+            },
+        )
+        .await;
+
+        if let Some(webhook_url) = job.webhook_url {
+            let result = JobResult {
+                job_id: job.id,
+                outcome,
+            };
+            let queue = Arc::clone(self);
+            smol::spawn(async move {
+                if let Err(error) = queue.deliver(&webhook_url, &result).await {
+                    error!(job_id = %result.job_id, %webhook_url, error = %error, "Webhook delivery failed");
+                }
+            })
+            .detach();
+        }
+    }
+
+    async fn deliver(&self, webhook_url: &Uri, result: &JobResult) -> Result<(), WebhookError> {
+        let body = Bytes::from(serde_json::to_vec(result)?);
+        let signature = webhook_signature(&self.webhook_secret, &body);
+        let mut delay = INITIAL_RETRY_DELAY;
+        let mut attempt = 1;
+        loop {
+            let attempt_result = smol::future::or(
+                post_webhook(
+                    webhook_url,
+                    &self.webhook_allowed_hosts,
+                    result.job_id,
+                    &signature,
+                    body.clone(),
+                ),
+                async {
+                    Timer::after(self.webhook_timeout).await;
+                    Err(WebhookError::Timeout(self.webhook_timeout))
+                },
+            )
+            .await;
+            match attempt_result {
+                Ok(()) => return Ok(()),
+                Err(error) if attempt < self.webhook_attempts && error.is_retryable() => {
+                    warn!(job_id = %result.job_id, attempt, error = %error, "Retrying webhook delivery");
+                    Timer::after(delay).await;
+                    delay = delay.saturating_mul(2);
+                    attempt += 1;
+                }
+                Err(error) => return Err(error),
+            }
+        }
+    }
+}
+
+/// Hex blake3 keyed hash of a webhook body, sent in [`SIGNATURE_HEADER`].
+pub fn webhook_signature(secret: &[u8; 32], body: &[u8]) -> String {
+    blake3::keyed_hash(secret, body).to_hex().to_string()
+}
+
+/// Serves [`JobQueue::router`] over plain HTTP until the listener fails.
+pub async fn serve_jobs(listener: TcpListener, queue: Arc<JobQueue>) -> std::io::Result<()> {
+    serve_router(listener, queue.router()).await
+}
+
+/// Serves `router` over plain HTTP until the listener fails, e.g. the job
+/// and transparency log routes merged into one.
+pub async fn serve_router(listener: TcpListener, router: Router) -> std::io::Result<()> {
+    loop {
+        let (stream, remote_addr) = listener.accept().await?;
+        let router = router.clone();
+        smol::spawn(async move {
+            let hyper_service = hyper::service::service_fn(move |request: Request<Incoming>| {
+                router.clone().call(request)
+            });
+            if let Err(error) =
+                hyper_util::server::conn::auto::Builder::new(SmolExecutor::default())
+                    .serve_connection(TokioIo::new(Compat::new(stream)), hyper_service)
+                    .await
+            {
+                warn!(%remote_addr, error = %error, "HTTP status connection failed");
+            }
+        })
+        .detach();
+    }
+}
+
+async fn run_worker(queue: Weak<JobQueue>, receiver: Receiver<QueuedJob>) {
+    while let Ok(job) = receiver.recv().await {
+        let Some(queue) = queue.upgrade() else {
+            break;
+        };
+        queue.run(job).await;
+    }
+}
+
+async fn get_job(
+    State(queue): State<Arc<JobQueue>>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<JobStatus>, StatusCode> {
+    queue
+        .status(id)
+        .await
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Resolves the webhook host. Every address must be public unless the host
+/// is allowed, and delivery connects only to the addresses checked here, so
+/// the host cannot be rebound to an internal one in between.
+async fn webhook_addrs(
+    url: &Uri,
+    allowed_hosts: &[String],
+) -> Result<Vec<SocketAddr>, WebhookError> {
+    let (Some("http"), Some(host)) = (url.scheme_str(), url.host()) else {
+        return Err(WebhookError::UnsupportedUrl(url.to_string()));
+    };
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    let addrs = smol::net::resolve((host, url.port_u16().unwrap_or(80))).await?;
+    let allowed = allowed_hosts
+        .iter()
+        .any(|allowed| allowed.eq_ignore_ascii_case(host));
+    match addrs.iter().find(|addr| !allowed && !is_public(addr.ip())) {
+        Some(addr) => Err(WebhookError::ForbiddenAddress {
+            host: host.to_string(),
+            addr: addr.ip(),
+        }),
+        None => Ok(addrs),
+    }
+}
+
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [first, second, ..] = ip.octets();
+            let shared = first == 100 && second & 0xc0 == 64;
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || ip.is_documentation()
+                || shared)
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public(IpAddr::V4(ip)),
+            None => {
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    || ip.is_multicast()
+                    || ip.is_unique_local()
+                    || ip.is_unicast_link_local())
+            }
+        },
+    }
+}
+
+async fn post_webhook(
+    url: &Uri,
+    allowed_hosts: &[String],
+    job_id: Uuid,
+    signature: &str,
+    body: Bytes,
+) -> Result<(), WebhookError> {
+    let addrs = webhook_addrs(url, allowed_hosts).await?;
+    let Some(authority) = url.authority() else {
+        return Err(WebhookError::UnsupportedUrl(url.to_string()));
+    };
+    let stream = TcpStream::connect(addrs.as_slice()).await?;
+    let (mut sender, connection) =
+        hyper::client::conn::http1::handshake(TokioIo::new(Compat::new(stream))).await?;
+    smol::spawn(connection).detach();
+
+    let request = Request::post(url.path_and_query().map_or("/", |path| path.as_str()))
+        .header(header::HOST, authority.as_str())
+        .header(header::CONNECTION, "close")
+        .header(header::CONTENT_TYPE, "application/json")
+        .header(IDEMPOTENCY_KEY_HEADER, job_id.to_string())
+        .header(SIGNATURE_HEADER, signature)
+        .body(Full::new(body))?;
+    let status = sender.send_request(request).await?.status();
+    if !status.is_success() {
+        return Err(WebhookError::Status(status));
+    }
+    Ok(())
+}
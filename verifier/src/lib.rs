@@ -1,27 +1,237 @@
+use std::{future::Future, sync::Arc, time::Duration};
+
+use futures::future::join_all;
 use quinn::Endpoint;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
-use crate::handler::handle;
+use crate::{jobs::JobQueue, shutdown::SessionTracker};
 
+pub mod admin;
+pub mod admission;
+pub mod client;
+pub mod config;
 pub mod errors;
 pub mod handler;
+pub mod jobs;
+pub mod listener;
+pub mod load;
+pub mod policy;
 pub mod protocol;
+pub mod rpc;
+pub mod sealing;
+pub mod selftest;
+pub mod shutdown;
+pub mod signing;
+#[cfg(feature = "simulation")]
+pub mod simulation;
+pub mod store;
+pub mod stream;
+pub mod timestamp;
+pub mod transcript;
+pub mod transparency;
 
 pub const MAX_SENT_DATA: usize = 1 << 12;
 pub const MAX_RECV_DATA: usize = 1 << 14;
 
-pub use errors::ProtocolError;
-pub use protocol::{ProofMessage, VerificationOutcome};
+pub use admin::{
+    Admin, AdminConfig, MIN_ADMIN_TOKEN_LEN, SessionPhase, SessionSummary, serve_admin,
+};
+pub use admission::{AdmissionControl, AdmissionPermit, AdmissionStatus, HealthStatus};
+pub use client::{NotarizeStream, QuicStream, VerifierClient, job_status};
+pub use config::NotaryConfig;
+pub use errors::{ClientError, ProtocolError, StoreError, TimestampError, WebhookError};
+pub use jobs::{
+    IdempotencyKey, JobQueue, JobQueueConfig, JobResult, JobStatus, serve_jobs, serve_router,
+    webhook_signature,
+};
+pub use listener::{Listener, ListenerConfig, ListenerMetrics, ListenerStats, ListenerTransport};
+pub use load::{LoadShedder, LoadSheddingConfig, LoadStatus};
+pub use policy::{NotarizationPolicy, PolicySet};
+pub use protocol::{
+    FieldDisclosure, ProofMessage, SessionInit, TraceContext, VerificationOutcome,
+    verify_thresholds,
+};
+pub use rpc::{
+    RpcError, RpcRequest, RpcResponse, SESSION_NONCE_HEADER, SessionInitResult, SubmitParams,
+    SubmitResult, init_session, submit_session, verify_session,
+};
+pub use sealing::{SealedArtifact, StoreKeys};
+pub use selftest::run_self_test;
+pub use shutdown::{DEFAULT_SHUTDOWN_GRACE_PERIOD, ShutdownSummary};
+pub use signing::NotaryKey;
+#[cfg(feature = "simulation")]
+pub use simulation::RecordedSession;
+pub use store::{
+    ArtifactBuilder, ArtifactIntegrity, ArtifactStore, AttestationArtifact, FilesystemArtifactStore,
+};
+pub use stream::{MeteredStream, TrafficLimits, TrafficMeter, TrafficTotals};
+pub use timestamp::{NotaryTimestamp, RoughtimeAttestation, RoughtimeServer, TimeSource};
+pub use transcript::SharedTranscript;
+pub use transparency::{InclusionProof, LogRoot, TransparencyLog};
+
+#[derive(Clone)]
+pub struct ServiceOptions {
+    pub artifact_store: Option<Arc<dyn ArtifactStore>>,
+    pub admission_control: Option<Arc<AdmissionControl>>,
+    /// Refuses new sessions while memory or CPU run short; sampled while
+    /// the service runs.
+    pub load_shedder: Option<Arc<LoadShedder>>,
+    /// Enables `session.submit`; without it only inline verification is served.
+    pub job_queue: Option<Arc<JobQueue>>,
+    /// Named policies provers select in their opening frame.
+    pub policies: Option<Arc<PolicySet>>,
+    /// Caps on MPC traffic per session; traffic is counted either way.
+    pub traffic_limits: Option<TrafficLimits>,
+    /// Appends every successful attestation and reports its leaf index in
+    /// the outcome.
+    pub transparency_log: Option<Arc<TransparencyLog>>,
+    /// Stamps attestations as they are recorded.
+    pub time_source: TimeSource,
+    /// Signs attestations as they are recorded, covering the timestamp.
+    pub notary_key: Option<Arc<NotaryKey>>,
+    pub shutdown_grace_period: Duration,
+    /// Rejects sessions whose notarized request does not reveal the nonce
+    /// issued at `session.init`. Only JSON-RPC sessions are issued one, so
+    /// this refuses bare-frame sessions as soon as they open. A revealed
+    /// nonce is checked either way.
+    pub require_session_nonce: bool,
+    /// Tracks sessions for the admin routes and supplies the reloadable
+    /// settings, which then take precedence over the ones above.
+    pub admin: Option<Arc<Admin>>,
+    /// Replays a [`RecordedSession`] sent by the prover instead of running
+    /// MPC-TLS. Nothing is notarized; for tests and load tests only.
+    #[cfg(feature = "simulation")]
+    pub simulate: bool,
+}
+
+impl Default for ServiceOptions {
+    fn default() -> Self {
+        Self {
+            artifact_store: None,
+            admission_control: None,
+            load_shedder: None,
+            job_queue: None,
+            policies: None,
+            traffic_limits: None,
+            transparency_log: None,
+            time_source: TimeSource::default(),
+            notary_key: None,
+            shutdown_grace_period: DEFAULT_SHUTDOWN_GRACE_PERIOD,
+            require_session_nonce: false,
+            admin: None,
+            #[cfg(feature = "simulation")]
+            simulate: false,
+        }
+    }
+}
 
 pub async fn serve(endpoint: Endpoint) {
-    info!("Verifier service ready, waiting for QUIC connections");
+    serve_with_options(endpoint, ServiceOptions::default()).await;
+}
+
+pub async fn serve_with_artifact_store(
+    endpoint: Endpoint,
+    artifact_store: Option<Arc<dyn ArtifactStore>>,
+) {
+    serve_with_options(
+        endpoint,
+        ServiceOptions {
+            artifact_store,
+            ..ServiceOptions::default()
+        },
+    )
+    .await;
+}
+
+pub async fn serve_with_options(endpoint: Endpoint, options: ServiceOptions) {
+    serve_until(endpoint, options, smol::future::pending()).await;
+}
+
+/// Serves until `shutdown` resolves, then stops accepting sessions, drains
+/// in-flight ones for up to the grace period, flushes the artifact store and
+/// closes the endpoint.
+pub async fn serve_until(
+    endpoint: Endpoint,
+    options: ServiceOptions,
+    shutdown: impl Future<Output = ()>,
+) -> ShutdownSummary {
+    serve_listeners_until(vec![Listener::quic(endpoint)], options, shutdown).await
+}
+
+/// [`serve_until`] over several listeners, e.g. QUIC on IPv4 and IPv6 plus a
+/// TCP fallback, all sharing `options` and one shutdown drain.
+pub async fn serve_listeners_until(
+    mut listeners: Vec<Listener>,
+    options: ServiceOptions,
+    shutdown: impl Future<Output = ()>,
+) -> ShutdownSummary {
+    if let Some(admission_control) = &options.admission_control {
+        info!(status = ?admission_control.status(), "Admission control enabled");
+    }
+    let load_monitor = options
+        .load_shedder
+        .clone()
+        .map(|load_shedder| smol::spawn(load_shedder.monitor()));
+    #[cfg(feature = "simulation")]
+    if options.simulate {
+        warn!("Simulation mode: sessions replay recorded transcripts without MPC-TLS");
+    }
+    for listener in &listeners {
+        match listener.local_addr() {
+            Ok(addr) => info!(transport = %listener.transport(), %addr, "Verifier listening"),
+            Err(error) => warn!(error = %error, "Listener has no local address"),
+        }
+    }
+
+    let (tracker, finished) = SessionTracker::new();
+    let accept_loops = join_all(
+        listeners
+            .iter()
+            .map(|listener| listener.accept_loop(&options, &tracker)),
+    );
+    smol::future::or(
+        async {
+            accept_loops.await;
+        },
+        shutdown,
+    )
+    .await;
 
-    while let Some(incoming) = endpoint.accept().await {
-        smol::spawn(async move {
-            if let Err(error) = handle(incoming).await {
-                error!(error = %error, "Connection task failed");
-            }
-        })
-        .detach();
+    info!(
+        grace_period = ?options.shutdown_grace_period,
+        "Shutdown requested, draining in-flight sessions"
+    );
+    for listener in &mut listeners {
+        listener.stop_accepting();
     }
+    let summary = tracker
+        .drain(&finished, options.shutdown_grace_period)
+        .await;
+    if summary.unfinished > 0 {
+        warn!(
+            unfinished = summary.unfinished,
+            "Grace period elapsed with sessions in flight"
+        );
+    }
+    drop(load_monitor);
+
+    if let Some(artifact_store) = options.artifact_store
+        && let Err(error) = smol::unblock(move || artifact_store.flush()).await
+    {
+        error!(error = %error, "Failed to flush artifact store");
+    }
+
+    for listener in listeners {
+        info!(
+            transport = %listener.transport(),
+            stats = ?listener.metrics().stats(),
+            "Closing listener"
+        );
+        listener.close().await;
+    }
+    info!(?summary, "Verifier service stopped");
+    summary
 }
+
+#[cfg(test)]
+mod tests;
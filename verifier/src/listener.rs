@@ -0,0 +1,284 @@
+use std::{
+    fmt, fs, io,
+    net::SocketAddr,
+    path::Path,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use quinn::{Endpoint, EndpointConfig, ServerConfig, VarInt};
+use serde::{Deserialize, Serialize};
+use smol::{Timer, net::TcpListener};
+use socket2::{Domain, Protocol, Socket, Type};
+use tracing::error;
+
+use crate::{
+    ServiceOptions,
+    errors::ProtocolError,
+    handler::{handle, handle_tcp},
+    shutdown::SessionTracker,
+};
+
+const TCP_BACKLOG: i32 = 1024;
+/// Pause after a failed TCP accept, e.g. when out of file descriptors.
+const ACCEPT_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ListenerTransport {
+    Quic,
+    /// One session per plain, unencrypted connection, for networks that drop
+    /// UDP. Put it behind a TLS terminator when the network is not trusted.
+    Tcp,
+}
+
+impl fmt::Display for ListenerTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Quic => "quic",
+            Self::Tcp => "tcp",
+        })
+    }
+}
+
+/// One address the verifier accepts sessions on. An IPv6 wildcard address
+/// also takes IPv4 traffic unless `ipv6_only` is set.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct ListenerConfig {
+    pub transport: ListenerTransport,
+    pub addr: SocketAddr,
+    #[serde(default)]
+    pub ipv6_only: bool,
+}
+
+impl ListenerConfig {
+    pub fn quic(addr: SocketAddr) -> Self {
+        Self {
+            transport: ListenerTransport::Quic,
+            addr,
+            ipv6_only: false,
+        }
+    }
+
+    pub fn tcp(addr: SocketAddr) -> Self {
+        Self {
+            transport: ListenerTransport::Tcp,
+            addr,
+            ipv6_only: false,
+        }
+    }
+
+    #[must_use]
+    pub fn ipv6_only(mut self, ipv6_only: bool) -> Self {
+        self.ipv6_only = ipv6_only;
+        self
+    }
+
+    pub fn from_json_file(path: impl AsRef<Path>) -> Result<Vec<Self>, ProtocolError> {
+        let configs: Vec<Self> = serde_json::from_slice(&fs::read(path)?)?;
+        if configs.is_empty() {
+            return Err(ProtocolError::InvalidConfig(
+                "no listeners configured".to_string(),
+            ));
+        }
+        Ok(configs)
+    }
+
+    /// Binds the listener; QUIC listeners serve with `server_config`.
+    pub fn bind(&self, server_config: &ServerConfig) -> Result<Listener, ProtocolError> {
+        let (socket_type, protocol) = match self.transport {
+            ListenerTransport::Quic => (Type::DGRAM, Protocol::UDP),
+            ListenerTransport::Tcp => (Type::STREAM, Protocol::TCP),
+        };
+        let socket = Socket::new(Domain::for_address(self.addr), socket_type, Some(protocol))?;
+        if self.addr.is_ipv6() {
+            socket.set_only_v6(self.ipv6_only)?;
+        }
+
+        match self.transport {
+            ListenerTransport::Quic => {
+                socket.bind(&self.addr.into())?;
+                let runtime = quinn::default_runtime()
+                    .ok_or_else(|| io::Error::other("no async runtime for QUIC"))?;
+                let endpoint = Endpoint::new(
+                    EndpointConfig::default(),
+                    Some(server_config.clone()),
+                    socket.into(),
+                    runtime,
+                )?;
+                Ok(Listener::quic(endpoint))
+            }
+            ListenerTransport::Tcp => {
+                socket.set_reuse_address(true)?;
+                socket.bind(&self.addr.into())?;
+                socket.listen(TCP_BACKLOG)?;
+                let listener = TcpListener::try_from(std::net::TcpListener::from(socket))?;
+                Ok(Listener::tcp(listener))
+            }
+        }
+    }
+
+    pub fn bind_all(
+        configs: &[Self],
+        server_config: &ServerConfig,
+    ) -> Result<Vec<Listener>, ProtocolError> {
+        configs
+            .iter()
+            .map(|config| config.bind(server_config))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListenerStats {
+    pub connections: u64,
+    pub sessions: u64,
+    pub failed_sessions: u64,
+    /// Connections or streams turned away because shutdown had begun.
+    pub refused_sessions: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct ListenerMetrics {
+    connections: AtomicU64,
+    sessions: AtomicU64,
+    failed_sessions: AtomicU64,
+    refused_sessions: AtomicU64,
+}
+
+impl ListenerMetrics {
+    pub fn stats(&self) -> ListenerStats {
+        ListenerStats {
+            connections: self.connections.load(Ordering::Relaxed),
+            sessions: self.sessions.load(Ordering::Relaxed),
+            failed_sessions: self.failed_sessions.load(Ordering::Relaxed),
+            refused_sessions: self.refused_sessions.load(Ordering::Relaxed),
+        }
+    }
+
+    pub(crate) fn connection_accepted(&self) {
+        self.connections.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn session_started(&self) {
+        self.sessions.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn session_failed(&self) {
+        self.failed_sessions.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn session_refused(&self) {
+        self.refused_sessions.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+enum Bound {
+    Quic(Endpoint),
+    /// `None` once the listener stopped accepting and closed its socket.
+    Tcp(Option<TcpListener>),
+}
+
+/// A bound QUIC endpoint or TCP listener, with the metrics of the sessions
+/// it accepted.
+pub struct Listener {
+    socket: Bound,
+    metrics: Arc<ListenerMetrics>,
+}
+
+impl Listener {
+    pub fn quic(endpoint: Endpoint) -> Self {
+        Self {
+            socket: Bound::Quic(endpoint),
+            metrics: Arc::default(),
+        }
+    }
+
+    pub fn tcp(listener: TcpListener) -> Self {
+        Self {
+            socket: Bound::Tcp(Some(listener)),
+            metrics: Arc::default(),
+        }
+    }
+
+    pub fn transport(&self) -> ListenerTransport {
+        match self.socket {
+            Bound::Quic(_) => ListenerTransport::Quic,
+            Bound::Tcp(_) => ListenerTransport::Tcp,
+        }
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        match &self.socket {
+            Bound::Quic(endpoint) => endpoint.local_addr(),
+            Bound::Tcp(Some(listener)) => listener.local_addr(),
+            Bound::Tcp(None) => Err(io::Error::new(
+                io::ErrorKind::NotConnected,
+                "TCP listener stopped accepting",
+            )),
+        }
+    }
+
+    pub fn metrics(&self) -> Arc<ListenerMetrics> {
+        Arc::clone(&self.metrics)
+    }
+
+    pub(crate) async fn accept_loop(&self, options: &ServiceOptions, tracker: &SessionTracker) {
+        match &self.socket {
+            Bound::Quic(endpoint) => {
+                while let Some(incoming) = endpoint.accept().await {
+                    let options = options.clone();
+                    let tracker = tracker.clone();
+                    let metrics = self.metrics();
+                    smol::spawn(async move {
+                        if let Err(error) = handle(incoming, options, tracker, metrics).await {
+                            error!(error = %error, "Connection task failed");
+                        }
+                    })
+                    .detach();
+                }
+            }
+            Bound::Tcp(None) => {}
+            Bound::Tcp(Some(listener)) => loop {
+                match listener.accept().await {
+                    Ok((stream, remote_addr)) => {
+                        let options = options.clone();
+                        let tracker = tracker.clone();
+                        let metrics = self.metrics();
+                        smol::spawn(handle_tcp(stream, remote_addr, options, tracker, metrics))
+                            .detach();
+                    }
+                    Err(error) => {
+                        error!(error = %error, "Failed to accept TCP connection");
+                        Timer::after(ACCEPT_RETRY_DELAY).await;
+                    }
+                }
+            },
+        }
+    }
+
+    /// Refuses new connections while in-flight sessions drain. A QUIC
+    /// endpoint keeps its socket for the open connections; a TCP listener
+    /// closes its socket, so new connections are refused by the OS.
+    pub(crate) fn stop_accepting(&mut self) {
+        match &mut self.socket {
+            Bound::Quic(endpoint) => endpoint.set_server_config(None),
+            Bound::Tcp(listener) => drop(listener.take()),
+        }
+    }
+
+    pub(crate) async fn close(self) {
+        match self.socket {
+            Bound::Quic(endpoint) => {
+                endpoint.close(VarInt::from_u32(0), b"verifier shutting down");
+                endpoint.wait_idle().await;
+            }
+            Bound::Tcp(_) => {}
+        }
+    }
+}
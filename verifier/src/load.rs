@@ -0,0 +1,161 @@
+use std::{
+    fs,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+use smol::Timer;
+use tracing::{info, warn};
+
+use crate::errors::ProtocolError;
+
+const DEFAULT_RESUME_PERCENT: u8 = 80;
+const DEFAULT_SAMPLE_INTERVAL_MS: u64 = 500;
+
+/// Thresholds past which new sessions are refused. Shedding starts when a
+/// sample crosses a threshold and stops once every sample is back under
+/// `resume_percent` of its threshold, so the service does not flap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+pub struct LoadSheddingConfig {
+    /// Resident memory of the process.
+    pub max_memory_bytes: Option<u64>,
+    /// How late a timer fires on the executor, which grows as MPC and
+    /// proof verification saturate the CPU.
+    pub max_loop_lag_ms: Option<u64>,
+    pub resume_percent: u8,
+    pub sample_interval_ms: u64,
+}
+
+impl Default for LoadSheddingConfig {
+    fn default() -> Self {
+        Self {
+            max_memory_bytes: None,
+            max_loop_lag_ms: None,
+            resume_percent: DEFAULT_RESUME_PERCENT,
+            sample_interval_ms: DEFAULT_SAMPLE_INTERVAL_MS,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoadStatus {
+    pub shedding: bool,
+    /// `None` where the platform does not report it.
+    pub memory_bytes: Option<u64>,
+    pub loop_lag_ms: u64,
+    /// Sessions refused since the service started.
+    pub shed_sessions: u64,
+}
+
+/// Refuses new sessions while the notary is short of memory or CPU, on top
+/// of [`AdmissionControl`](crate::AdmissionControl), which only accounts
+/// for the sessions it admitted.
+#[derive(Debug)]
+pub struct LoadShedder {
+    config: LoadSheddingConfig,
+    shedding: AtomicBool,
+    memory_bytes: AtomicU64,
+    loop_lag_ms: AtomicU64,
+    shed_sessions: AtomicU64,
+}
+
+impl LoadShedder {
+    pub fn new(config: LoadSheddingConfig) -> Self {
+        Self {
+            config,
+            shedding: AtomicBool::new(false),
+            memory_bytes: AtomicU64::new(0),
+            loop_lag_ms: AtomicU64::new(0),
+            shed_sessions: AtomicU64::new(0),
+        }
+    }
+
+    /// Samples memory and executor lag every `sample_interval_ms` until
+    /// dropped.
+    pub async fn monitor(self: Arc<Self>) {
+        let interval = Duration::from_millis(self.config.sample_interval_ms.max(1));
+        loop {
+            let started = Instant::now();
+            Timer::after(interval).await;
+            let loop_lag = started.elapsed().saturating_sub(interval);
+            self.record(resident_memory(), loop_lag);
+        }
+    }
+
+    /// Updates the shedding state from one sample.
+    pub fn record(&self, memory_bytes: Option<u64>, loop_lag: Duration) {
+        let loop_lag_ms = u64::try_from(loop_lag.as_millis()).unwrap_or(u64::MAX);
+        self.memory_bytes
+            .store(memory_bytes.unwrap_or(0), Ordering::Relaxed);
+        self.loop_lag_ms.store(loop_lag_ms, Ordering::Relaxed);
+
+        let samples = [
+            (memory_bytes, self.config.max_memory_bytes),
+            (Some(loop_lag_ms), self.config.max_loop_lag_ms),
+        ];
+        let over = samples
+            .iter()
+            .any(|&(value, max)| value.zip(max).is_some_and(|(value, max)| value > max));
+        let resume_percent = u128::from(self.config.resume_percent.min(100));
+        // Widened so neither product saturates and the comparison is exact.
+        let recovered = samples.iter().all(|&(value, max)| {
+            value.zip(max).is_none_or(|(value, max)| {
+                u128::from(value).saturating_mul(100)
+                    <= u128::from(max).saturating_mul(resume_percent)
+            })
+        });
+
+        let was_shedding = self.shedding.load(Ordering::Acquire);
+        if !was_shedding && over {
+            self.shedding.store(true, Ordering::Release);
+            warn!(status = ?self.status(), "Shedding load, refusing new sessions");
+        } else if was_shedding && recovered {
+            self.shedding.store(false, Ordering::Release);
+            info!(status = ?self.status(), "Load recovered, accepting sessions again");
+        }
+    }
+
+    /// Counts and refuses a new session while shedding.
+    pub fn admit(&self) -> Result<(), ProtocolError> {
+        if !self.shedding.load(Ordering::Acquire) {
+            return Ok(());
+        }
+        self.shed_sessions.fetch_add(1, Ordering::Relaxed);
+        let status = self.status();
+        Err(ProtocolError::Overloaded(format!(
+            "memory {} bytes, event loop lag {} ms",
+            status.memory_bytes.unwrap_or(0),
+            status.loop_lag_ms
+        )))
+    }
+
+    pub fn status(&self) -> LoadStatus {
+        let memory_bytes = self.memory_bytes.load(Ordering::Relaxed);
+        LoadStatus {
+            shedding: self.shedding.load(Ordering::Acquire),
+            memory_bytes: (memory_bytes > 0).then_some(memory_bytes),
+            loop_lag_ms: self.loop_lag_ms.load(Ordering::Relaxed),
+            shed_sessions: self.shed_sessions.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// `VmRSS` from `/proc/self/status`; `None` off Linux.
+fn resident_memory() -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    let kilobytes = status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))?
+        .trim()
+        .strip_suffix("kB")?
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+    Some(kilobytes.saturating_mul(1024))
+}
@@ -0,0 +1,222 @@
+use std::{
+    env,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    sync::Arc,
+};
+
+use anyhow::{Context, bail};
+use async_signal::{Signal, Signals};
+use axum::Router;
+use futures::StreamExt;
+use tracing::{error, info};
+use verifier::{
+    Admin, ListenerConfig, NotaryConfig, run_self_test, serve_admin, serve_listeners_until,
+    serve_router,
+};
+
+const DEFAULT_CONFIG_PATH: &str = "notary.toml";
+const USAGE: &str = "usage: verifier notary <serve|rewrap-keys> [--config <path>]";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Command {
+    Serve,
+    /// Moves stored artifacts onto the current store key.
+    RewrapKeys,
+}
+
+fn main() -> ExitCode {
+    let (command, config_path) = match parse_args(env::args().skip(1)) {
+        Ok(parsed) => parsed,
+        Err(message) => {
+            eprintln!("{message}\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    shared::init_logging("info");
+    if command == Command::RewrapKeys {
+        return match rewrap_keys(config_path) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(error) => {
+                error!(error = %format!("{error:#}"), "Re-wrapping artifact keys failed");
+                ExitCode::FAILURE
+            }
+        };
+    }
+    if let Err(error) = zktlsn::setup_barretenberg_srs() {
+        error!(error = %error, "Failed to set up Barretenberg SRS");
+        return ExitCode::FAILURE;
+    }
+
+    smol::block_on(async {
+        match serve(config_path).await {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(error) => {
+                error!(error = %format!("{error:#}"), "Notary failed");
+                ExitCode::FAILURE
+            }
+        }
+    })
+}
+
+/// The command and config path, `None` when defaulted and absent so
+/// built-in defaults apply.
+fn parse_args(
+    mut args: impl Iterator<Item = String>,
+) -> Result<(Command, Option<PathBuf>), String> {
+    let command = match (args.next().as_deref(), args.next().as_deref()) {
+        (Some("notary"), Some("serve")) => Command::Serve,
+        (Some("notary"), Some("rewrap-keys")) => Command::RewrapKeys,
+        _ => return Err("expected the `notary serve` or `notary rewrap-keys` command".to_string()),
+    };
+    match (args.next().as_deref(), args.next(), args.next()) {
+        (None, ..) => {
+            let default = PathBuf::from(DEFAULT_CONFIG_PATH);
+            Ok((command, default.exists().then_some(default)))
+        }
+        (Some("--config"), Some(path), None) => Ok((command, Some(PathBuf::from(path)))),
+        (Some(flag), ..) => Err(format!("unexpected argument `{flag}`")),
+    }
+}
+
+fn load_config(config_path: Option<&Path>) -> anyhow::Result<NotaryConfig> {
+    Ok(match config_path {
+        Some(path) => NotaryConfig::from_toml_file(path)?,
+        None => NotaryConfig::default(),
+    })
+}
+
+fn rewrap_keys(config_path: Option<PathBuf>) -> anyhow::Result<()> {
+    let config = load_config(config_path.as_deref())?;
+    if config.artifact_key_file.is_none() {
+        bail!("`artifactKeyFile` is not set");
+    }
+    let Some(store) = config.artifact_store()? else {
+        bail!("`artifactDir` is not set");
+    };
+    let rewritten = store.rewrap()?;
+    info!(rewritten, "Re-wrapped artifact keys");
+    Ok(())
+}
+
+async fn serve(config_path: Option<PathBuf>) -> anyhow::Result<()> {
+    let config = load_config(config_path.as_deref())?;
+    info!(config = ?config_path, "Starting notary");
+
+    let server_config = config.quic_server_config().await?;
+    let mut options = config.service_options()?;
+    if let Some(notary_key) = &options.notary_key {
+        info!(public_key = notary_key.public_key(), "Signing attestations");
+    }
+    if config.self_test {
+        info!("Running startup self-test");
+        run_self_test(&options)
+            .await
+            .context("startup self-test failed")?;
+    }
+    let listeners = ListenerConfig::bind_all(&config.listeners, &server_config)?;
+    if let Some(addr) = config.status_addr {
+        let job_routes = options.job_queue.clone().map(|queue| queue.router());
+        let log_routes = options.transparency_log.clone().map(|log| log.router());
+        let health_routes = options
+            .admission_control
+            .clone()
+            .map(|control| control.router());
+        let router = job_routes
+            .into_iter()
+            .chain(log_routes)
+            .chain(health_routes)
+            .fold(Router::new(), Router::merge);
+        let listener = smol::net::TcpListener::bind(addr)
+            .await
+            .with_context(|| format!("binding the status routes to {addr}"))?;
+        info!(%addr, "Status routes listening");
+        smol::spawn(async move {
+            if let Err(error) = serve_router(listener, router).await {
+                error!(error = %error, "Status routes stopped");
+            }
+        })
+        .detach();
+    }
+    if let Some(admin_config) = &config.admin {
+        let admin = Admin::new(admin_config, config_path, &options)?;
+        options.admin = Some(Arc::clone(&admin));
+        let addr = admin_config.addr;
+        smol::spawn(async move {
+            if let Err(error) = serve_admin(addr, admin).await {
+                error!(error = %error, "Admin API stopped");
+            }
+        })
+        .detach();
+    }
+
+    let mut signals = Signals::new([Signal::Int, Signal::Term])?;
+    let shutdown = async move {
+        signals.next().await;
+    };
+    let summary = serve_listeners_until(listeners, options, shutdown).await;
+    info!(?summary, "Notary shut down");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<(Command, Option<PathBuf>), String> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_parse_args_defaults_the_config_path() {
+        let default = Path::new(DEFAULT_CONFIG_PATH)
+            .exists()
+            .then(|| PathBuf::from(DEFAULT_CONFIG_PATH));
+        assert_eq!(
+            parse(&["notary", "serve"]),
+            Ok((Command::Serve, default.clone()))
+        );
+        assert_eq!(
+            parse(&["notary", "rewrap-keys"]),
+            Ok((Command::RewrapKeys, default))
+        );
+    }
+
+    #[test]
+    fn test_parse_args_rejects_unknown_commands_and_flags() {
+        let cases: [&[&str]; 8] = [
+            &[],
+            &["notary"],
+            &["serve"],
+            &["notary", "start"],
+            &["prover", "serve"],
+            &["notary", "serve", "--verbose"],
+            &["notary", "serve", "--config"],
+            &["notary", "serve", "--config", "a.toml", "b.toml"],
+        ];
+        for args in cases {
+            assert!(parse(args).is_err(), "{args:?} should be rejected");
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn test_parse_args_reads_command_and_config_path(
+            path in "[a-z/]{1,24}\\.toml",
+            rewrap in any::<bool>(),
+        ) {
+            let (command, name) = if rewrap {
+                (Command::RewrapKeys, "rewrap-keys")
+            } else {
+                (Command::Serve, "serve")
+            };
+            prop_assert_eq!(
+                parse(&["notary", name, "--config", &path]),
+                Ok((command, Some(PathBuf::from(&path))))
+            );
+        }
+    }
+}
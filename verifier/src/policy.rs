@@ -0,0 +1,256 @@
+use std::{collections::HashMap, fs, path::Path, time::Duration};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use tlsnotary::{ProtocolLimits, TlsCommitProtocolConfig};
+use zktlsn::BoundCommitment;
+
+use crate::{errors::ProtocolError, timestamp::NotaryTimestamp};
+
+/// What a session notarized under a named policy must look like. Empty lists
+/// and unset limits place no constraint beyond the service defaults.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct NotarizationPolicy {
+    pub name: String,
+    pub allowed_hosts: Vec<String>,
+    pub max_sent_data: Option<usize>,
+    pub max_recv_data: Option<usize>,
+    pub revealed_request_headers: Vec<String>,
+    pub revealed_response_fields: Vec<String>,
+    pub committed_response_fields: Vec<String>,
+    /// Committed fields a ZK proof has to be verified against.
+    pub proven_fields: Vec<String>,
+    /// Most seconds between MPC-TLS finishing and the attestation's
+    /// timestamp, either way. Checked when the attestation is recorded.
+    pub session_time_window_secs: Option<u64>,
+}
+
+impl NotarizationPolicy {
+    #[must_use]
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ..Self::default()
+        }
+    }
+
+    #[must_use]
+    pub fn allowed_host(mut self, host: impl Into<String>) -> Self {
+        self.allowed_hosts.push(host.into());
+        self
+    }
+
+    #[must_use]
+    pub fn max_sent_data(mut self, max_sent_data: usize) -> Self {
+        self.max_sent_data = Some(max_sent_data);
+        self
+    }
+
+    #[must_use]
+    pub fn max_recv_data(mut self, max_recv_data: usize) -> Self {
+        self.max_recv_data = Some(max_recv_data);
+        self
+    }
+
+    #[must_use]
+    pub fn revealed_request_header(mut self, name: impl Into<String>) -> Self {
+        self.revealed_request_headers.push(name.into());
+        self
+    }
+
+    #[must_use]
+    pub fn revealed_response_field(mut self, keypath: impl Into<String>) -> Self {
+        self.revealed_response_fields.push(keypath.into());
+        self
+    }
+
+    #[must_use]
+    pub fn committed_response_field(mut self, keypath: impl Into<String>) -> Self {
+        self.committed_response_fields.push(keypath.into());
+        self
+    }
+
+    #[must_use]
+    pub fn proven_field(mut self, keypath: impl Into<String>) -> Self {
+        self.proven_fields.push(keypath.into());
+        self
+    }
+
+    #[must_use]
+    pub fn session_time_window(mut self, window: Duration) -> Self {
+        self.session_time_window_secs = Some(window.as_secs());
+        self
+    }
+
+    /// The limits a `tlsnotary::Verifier` enforces for this policy.
+    pub fn protocol_limits(&self) -> ProtocolLimits {
+        ProtocolLimits {
+            max_sent_data: self.max_sent_data,
+            max_recv_data: self.max_recv_data,
+        }
+    }
+
+    pub(crate) fn check_protocol(
+        &self,
+        protocol: &TlsCommitProtocolConfig,
+    ) -> Result<(), ProtocolError> {
+        match self.protocol_limits().violation(protocol) {
+            Some(reason) => Err(self.violation(reason)),
+            None => Ok(()),
+        }
+    }
+
+    /// `server_name` is `None` when the prover did not prove the server
+    /// identity, which only a policy without allowed hosts accepts.
+    pub(crate) fn check_server_name(&self, server_name: Option<&str>) -> Result<(), ProtocolError> {
+        if self.allowed_hosts.is_empty() {
+            return Ok(());
+        }
+        match server_name {
+            Some(server_name)
+                if self
+                    .allowed_hosts
+                    .iter()
+                    .any(|host| host.eq_ignore_ascii_case(server_name)) =>
+            {
+                Ok(())
+            }
+            Some(server_name) => {
+                Err(self.violation(format!("server {server_name} is not an allowed host")))
+            }
+            None => Err(self.violation("server identity is not proven".to_string())),
+        }
+    }
+
+    pub(crate) fn check_disclosures(
+        &self,
+        parsed_request: &parser::redacted::Request,
+        parsed_response: &parser::redacted::Response,
+        bound_commitments: &HashMap<String, BoundCommitment>,
+    ) -> Result<(), ProtocolError> {
+        for name in &self.revealed_request_headers {
+            let revealed = parsed_request
+                .headers
+                .get(&name.to_ascii_lowercase())
+                .is_some_and(|headers| headers.iter().any(|header| header.value.is_some()));
+            if !revealed {
+                return Err(self.violation(format!("request header {name} is not revealed")));
+            }
+        }
+        for keypath in &self.revealed_response_fields {
+            let revealed = match parsed_response.body.get(keypath) {
+                Some(parser::redacted::Body::KeyValue { value, .. }) => value.is_some(),
+                Some(parser::redacted::Body::Value(_)) => true,
+                Some(parser::redacted::Body::PartialValue { .. }) | None => false,
+            };
+            if !revealed {
+                return Err(self.violation(format!("response field {keypath} is not revealed")));
+            }
+        }
+        for keypath in &self.committed_response_fields {
+            if !bound_commitments.contains_key(keypath) {
+                return Err(self.violation(format!("response field {keypath} is not committed")));
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn check_proven_fields(
+        &self,
+        verified_fields: &[String],
+    ) -> Result<(), ProtocolError> {
+        match self
+            .proven_fields
+            .iter()
+            .find(|field| !verified_fields.contains(field))
+        {
+            Some(field) => Err(self.violation(format!("no proof verified for field {field}"))),
+            None => Ok(()),
+        }
+    }
+
+    pub(crate) fn check_session_time(
+        &self,
+        session_time: DateTime<Utc>,
+        timestamp: &NotaryTimestamp,
+    ) -> Result<(), ProtocolError> {
+        let Some(window) = self.session_time_window_secs else {
+            return Ok(());
+        };
+        let gap = (timestamp.time() - session_time).abs();
+        let within = i64::try_from(window)
+            .ok()
+            .and_then(TimeDelta::try_seconds)
+            .is_none_or(|window| gap <= window);
+        if !within {
+            return Err(self.violation(format!(
+                "session finished {}s from the attestation timestamp, more than {window}s",
+                gap.num_seconds()
+            )));
+        }
+        Ok(())
+    }
+
+    fn violation(&self, reason: String) -> ProtocolError {
+        ProtocolError::PolicyViolation {
+            policy: self.name.clone(),
+            reason,
+        }
+    }
+}
+
+/// Policies a prover can name in its opening frame, loaded from the verifier
+/// config. Sessions naming none fall back to `default_policy` when it is set.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PolicySet {
+    pub default_policy: Option<String>,
+    pub policies: Vec<NotarizationPolicy>,
+}
+
+impl PolicySet {
+    pub fn from_json_file(path: impl AsRef<Path>) -> Result<Self, ProtocolError> {
+        let policies: Self = serde_json::from_slice(&fs::read(path)?)?;
+        if let Some(name) = &policies.default_policy
+            && policies.get(name).is_none()
+        {
+            return Err(ProtocolError::InvalidConfig(format!(
+                "default policy '{name}' is not defined"
+            )));
+        }
+        Ok(policies)
+    }
+
+    #[must_use]
+    pub fn with_policy(mut self, policy: NotarizationPolicy) -> Self {
+        self.policies
+            .retain(|existing| existing.name != policy.name);
+        self.policies.push(policy);
+        self
+    }
+
+    #[must_use]
+    pub fn with_default_policy(mut self, name: impl Into<String>) -> Self {
+        self.default_policy = Some(name.into());
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&NotarizationPolicy> {
+        self.policies.iter().find(|policy| policy.name == name)
+    }
+
+    /// The policy a session runs under, if any.
+    pub fn resolve(
+        &self,
+        requested: Option<&str>,
+    ) -> Result<Option<&NotarizationPolicy>, ProtocolError> {
+        match requested.or(self.default_policy.as_deref()) {
+            Some(name) => self
+                .get(name)
+                .map(Some)
+                .ok_or_else(|| ProtocolError::UnknownPolicy(name.to_string())),
+            None => Ok(None),
+        }
+    }
+}
@@ -1,21 +1,54 @@
-use std::{collections::HashMap, ops::Range, path::Path, str::FromStr};
+use std::{
+    collections::{BTreeMap, HashMap},
+    ops::Range,
+    path::Path,
+    sync::Arc,
+};
 
 use async_compat::Compat;
+use chrono::{DateTime, Utc};
 use futures::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use shared::{TestTlsConfig, get_or_create_test_tls_config};
 use tlsnotary::{
-    CertificateDer, Direction, RootCertStore, Session, TlsCommitProtocolConfig,
+    CertificateDer, Direction, ProtocolLimits, RootCertStore, Session, TlsCommitProtocolConfig,
     TranscriptCommitment, VerifierConfig,
 };
-use tracing::{debug, info, instrument, warn};
+use tracing::{Instrument, Span, debug, info, info_span, instrument, warn};
+use uuid::Uuid;
 use zktlsn::{
-    Proof, bind_commitments_to_keys, extract_committed_hash_from_proof, verify_proof_against_hash,
+    BoundCommitment, Proof, ThresholdClaim, bind_commitments_to_keys,
+    bind_commitments_with_thresholds, extract_committed_hash_from_proof, verify_proof_against_hash,
 };
 
-use crate::{MAX_RECV_DATA, MAX_SENT_DATA, errors::ProtocolError};
+#[cfg(feature = "simulation")]
+use crate::simulation::RecordedSession;
+use crate::{
+    MAX_RECV_DATA, MAX_SENT_DATA, ServiceOptions,
+    admin::{LiveSession, SessionPhase},
+    admission::{AdmissionControl, AdmissionPermit},
+    errors::ProtocolError,
+    jobs::IdempotencyKey,
+    policy::{NotarizationPolicy, PolicySet},
+    rpc::{
+        Framing, METHOD_SESSION_SUBMIT, ProofRequest, SESSION_NONCE_HEADER, SubmitParams,
+        SubmitResult,
+    },
+    signing::NotaryKey,
+    store::{ArtifactBuilder, ArtifactStore, AttestationArtifact},
+    stream::{MeteredStream, TrafficMeter, TrafficTotals},
+    timestamp::TimeSource,
+    transcript::SharedTranscript,
+    transparency::TransparencyLog,
+};
 
 const MAX_FRAME_BYTES: usize = 1 << 20;
+const TRACEPARENT_VERSION: &str = "00";
+const PROOF_VERIFIED_MESSAGE: &str = "ZK proof verified successfully";
+/// The certificate origins are trusted by, and the key the self-test origin
+/// serves it with; both are generated when missing.
+pub(crate) const ORIGIN_CERT_PATH: &str = "test_cert.pem";
+pub(crate) const ORIGIN_KEY_PATH: &str = "test_key.pem";
 
 struct StepProgress {
     current: usize,
@@ -48,15 +81,137 @@ impl StepProgress {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceContext {
+    pub traceparent: String,
+}
+
+impl TraceContext {
+    pub fn new() -> Self {
+        let trace_id = Uuid::new_v4().simple().to_string();
+        // The high half of a v4 UUID carries its version bits, so it is never zero.
+        let (span_id, _) = Uuid::new_v4().as_u64_pair();
+        Self {
+            traceparent: format!("{TRACEPARENT_VERSION}-{trace_id}-{span_id:016x}-01"),
+        }
+    }
+
+    pub fn trace_id(&self) -> Result<&str, ProtocolError> {
+        self.fields().map(|(trace_id, _)| trace_id)
+    }
+
+    pub fn parent_span_id(&self) -> Result<&str, ProtocolError> {
+        self.fields().map(|(_, span_id)| span_id)
+    }
+
+    fn fields(&self) -> Result<(&str, &str), ProtocolError> {
+        let invalid = || ProtocolError::InvalidTraceContext(self.traceparent.clone());
+        let parts = self.traceparent.split('-').collect::<Vec<_>>();
+        let [version, trace_id, span_id, flags] = parts.as_slice() else {
+            return Err(invalid());
+        };
+        let is_hex = |value: &str, len: usize| {
+            value.len() == len && value.bytes().all(|byte| byte.is_ascii_hexdigit())
+        };
+        let is_zero = |value: &str| value.bytes().all(|byte| byte == b'0');
+        if *version != TRACEPARENT_VERSION
+            || !is_hex(trace_id, 32)
+            || !is_hex(span_id, 16)
+            || !is_hex(flags, 2)
+            || is_zero(trace_id)
+            || is_zero(span_id)
+        {
+            return Err(invalid());
+        }
+        Ok((trace_id, span_id))
+    }
+
+    pub async fn read_from<IO>(io: &mut IO) -> Result<Self, ProtocolError>
+    where
+        IO: AsyncRead + Unpin + Send,
+    {
+        let trace_context: Self = read_json_frame(io).await?;
+        trace_context.fields()?;
+        Ok(trace_context)
+    }
+
+    pub async fn write_to<IO>(&self, io: &mut IO) -> Result<(), ProtocolError>
+    where
+        IO: AsyncWrite + Unpin + Send,
+    {
+        write_json_frame(io, self).await
+    }
+}
+
+impl Default for TraceContext {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Opening frame of a session: the trace context and, optionally, the name
+/// of the notarization policy the prover asks to be held to.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionInit {
+    #[serde(flatten)]
+    pub trace_context: TraceContext,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub policy: Option<String>,
+}
+
+impl SessionInit {
+    pub fn new(trace_context: TraceContext) -> Self {
+        Self {
+            trace_context,
+            policy: None,
+        }
+    }
+
+    #[must_use]
+    pub fn with_policy(mut self, policy: impl Into<String>) -> Self {
+        self.policy = Some(policy.into());
+        self
+    }
+
+    pub async fn write_to<IO>(&self, io: &mut IO) -> Result<(), ProtocolError>
+    where
+        IO: AsyncWrite + Unpin + Send,
+    {
+        write_json_frame(io, self).await
+    }
+}
+
+impl From<TraceContext> for SessionInit {
+    fn from(trace_context: TraceContext) -> Self {
+        Self::new(trace_context)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ProofMessage {
     pub proof: Proof,
+    /// Predicate proofs over committed response fields, by keypath. Each is
+    /// checked against the commitment bound to its keypath, and the
+    /// keypath is then reported as verified.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub thresholds: BTreeMap<String, ThresholdClaim>,
 }
 
 impl ProofMessage {
     pub fn new(proof: Proof) -> Self {
-        Self { proof }
+        Self {
+            proof,
+            thresholds: BTreeMap::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn with_threshold(mut self, keypath: impl Into<String>, claim: ThresholdClaim) -> Self {
+        self.thresholds.insert(keypath.into(), claim);
+        self
     }
 
     pub async fn read_from<IO>(io: &mut IO) -> Result<Self, ProtocolError>
@@ -78,9 +233,26 @@ impl ProofMessage {
 #[serde(rename_all = "camelCase")]
 pub struct VerificationOutcome {
     pub success: bool,
+    /// Empty when the prover did not prove the server identity.
     pub server_name: String,
     pub verified_fields: Vec<String>,
     pub message: String,
+    /// Leaf index of the attestation in the verifier's transparency log.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_index: Option<u64>,
+    /// How each response body field reached the verifier, by the keypaths
+    /// commitments are bound to, e.g. `.balance`. Empty on failure.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub field_disclosures: BTreeMap<String, FieldDisclosure>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FieldDisclosure {
+    Revealed,
+    /// Hidden, but bound to a hash commitment the proof opens.
+    Committed,
+    Redacted,
 }
 
 impl VerificationOutcome {
@@ -90,6 +262,8 @@ impl VerificationOutcome {
             server_name,
             verified_fields,
             message,
+            log_index: None,
+            field_disclosures: BTreeMap::new(),
         }
     }
 
@@ -99,6 +273,8 @@ impl VerificationOutcome {
             server_name,
             verified_fields: Vec::new(),
             message,
+            log_index: None,
+            field_disclosures: BTreeMap::new(),
         }
     }
 
@@ -119,29 +295,253 @@ impl VerificationOutcome {
 
 #[derive(Debug, Clone)]
 struct NotarizedTranscript {
-    server_name: String,
-    request: String,
-    response: String,
+    /// `None` when the prover did not prove the server identity.
+    server_name: Option<String>,
+    transcript: SharedTranscript,
     transcript_commitments: Vec<TranscriptCommitment>,
+    parsed_request: parser::redacted::Request,
+    parsed_response: parser::redacted::Response,
+    bound_commitments: HashMap<String, BoundCommitment>,
+    policy: Option<NotarizationPolicy>,
+    traffic: TrafficTotals,
+    session_time: DateTime<Utc>,
+}
+
+impl NotarizedTranscript {
+    fn success_outcome(&self, verified_fields: Vec<String>) -> VerificationOutcome {
+        VerificationOutcome {
+            field_disclosures: response_field_disclosures(
+                &self.parsed_response,
+                &self.bound_commitments,
+            ),
+            ..VerificationOutcome::success(
+                self.outcome_server_name(),
+                verified_fields,
+                PROOF_VERIFIED_MESSAGE.to_string(),
+            )
+        }
+    }
+
+    /// The server name as outcomes and artifacts report it.
+    fn outcome_server_name(&self) -> String {
+        self.server_name.clone().unwrap_or_default()
+    }
 }
 
-#[instrument(skip(stream), fields(phase = "notarize+verify"))]
-pub async fn run_notarize_and_verify_stream<IO>(stream: IO) -> Result<(), ProtocolError>
+/// The disclosure of every body field of `parsed_response`, keyed like
+/// `bound_commitments`.
+pub(crate) fn response_field_disclosures<V>(
+    parsed_response: &parser::redacted::Response,
+    bound_commitments: &HashMap<String, V>,
+) -> BTreeMap<String, FieldDisclosure> {
+    parsed_response
+        .body
+        .iter()
+        .map(|(keypath, field)| {
+            let revealed = match field {
+                parser::redacted::Body::KeyValue { value, .. } => value.is_some(),
+                parser::redacted::Body::Value(_) => true,
+                parser::redacted::Body::PartialValue { .. } => false,
+            };
+            let disclosure = match (revealed, bound_commitments.contains_key(keypath)) {
+                (true, _) => FieldDisclosure::Revealed,
+                (false, true) => FieldDisclosure::Committed,
+                (false, false) => FieldDisclosure::Redacted,
+            };
+            (keypath.clone(), disclosure)
+        })
+        .collect()
+}
+
+pub async fn run_notarize_and_verify_stream<IO>(
+    stream: IO,
+    mut options: ServiceOptions,
+) -> Result<(), ProtocolError>
+where
+    IO: tokio::io::AsyncRead + tokio::io::AsyncWrite + Send + Unpin + 'static,
+{
+    let session = match options.admin.clone() {
+        Some(admin) => admin.start_session(&mut options),
+        None => LiveSession::untracked(),
+    };
+    smol::future::or(run_session(stream, options, &session), async {
+        session.cancelled().await;
+        warn!("Session cancelled by an operator");
+        Err(ProtocolError::SessionCancelled)
+    })
+    .await
+}
+
+async fn run_session<IO>(
+    stream: IO,
+    options: ServiceOptions,
+    session: &LiveSession,
+) -> Result<(), ProtocolError>
+where
+    IO: tokio::io::AsyncRead + tokio::io::AsyncWrite + Send + Unpin + 'static,
+{
+    let session_span = Span::current();
+    let mut io = Compat::new(stream);
+    let (framing, session_init, session_nonce) =
+        Framing::accept_session(&mut io, options.load_shedder.as_deref()).await?;
+    let trace_context = &session_init.trace_context;
+    let span = info_span!(
+        "attestation",
+        trace_id = %trace_context.trace_id()?,
+        parent_span_id = %trace_context.parent_span_id()?,
+        policy = session_init.policy.as_deref().unwrap_or("none"),
+    );
+    session.set_policy(session_init.policy.as_deref());
+
+    let nonce_binding = NonceBinding {
+        issued: session_nonce,
+        required: options.require_session_nonce,
+    };
+    let policy = match nonce_binding
+        .check_issued()
+        .and_then(|()| resolve_policy(options.policies.as_deref(), session_init.policy.as_deref()))
+    {
+        Ok(policy) => policy,
+        Err(error) => {
+            span.in_scope(|| warn!(error = %error, "Rejected session at init"));
+            send_verification_outcome_and_close(
+                &mut io,
+                &framing,
+                &VerificationOutcome::failure(String::new(), error.to_string()),
+            )
+            .await?;
+            return Err(error);
+        }
+    };
+    run_pipeline(
+        io.into_inner(),
+        options,
+        framing,
+        policy,
+        nonce_binding,
+        session_span,
+        session,
+    )
+    .instrument(span)
+    .await
+}
+
+fn resolve_policy(
+    policies: Option<&PolicySet>,
+    requested: Option<&str>,
+) -> Result<Option<NotarizationPolicy>, ProtocolError> {
+    match policies {
+        Some(policies) => Ok(policies.resolve(requested)?.cloned()),
+        None => requested.map_or(Ok(None), |name| {
+            Err(ProtocolError::UnknownPolicy(name.to_string()))
+        }),
+    }
+}
+
+/// The nonce a session was issued at `session.init`. The notarized request
+/// must reveal it in [`SESSION_NONCE_HEADER`], so a transcript notarized in
+/// an earlier session cannot be passed off as this one's.
+#[derive(Debug, Clone)]
+pub(crate) struct NonceBinding {
+    pub(crate) issued: Option<String>,
+    pub(crate) required: bool,
+}
+
+impl NonceBinding {
+    /// Refuses a session that must reveal a nonce but was issued none, as
+    /// bare-frame sessions are, before it runs MPC-TLS for nothing.
+    pub(crate) fn check_issued(&self) -> Result<(), ProtocolError> {
+        if self.required && self.issued.is_none() {
+            return Err(ProtocolError::SessionNonce(format!(
+                "bare-frame sessions are issued no nonce to reveal in {SESSION_NONCE_HEADER}; \
+                 open the session with a JSON-RPC session.init"
+            )));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn check(
+        &self,
+        parsed_request: &parser::redacted::Request,
+        sent: &str,
+    ) -> Result<(), ProtocolError> {
+        let revealed = parsed_request
+            .headers
+            .get(SESSION_NONCE_HEADER)
+            .into_iter()
+            .flatten()
+            .filter_map(|header| sent.get(header.value.clone()?))
+            .collect::<Vec<_>>();
+
+        match (&self.issued, revealed.as_slice()) {
+            (_, []) if self.required => Err(ProtocolError::SessionNonce(format!(
+                "request does not reveal {SESSION_NONCE_HEADER}"
+            ))),
+            (_, []) => Ok(()),
+            (Some(issued), revealed) if revealed.iter().all(|nonce| nonce == issued) => Ok(()),
+            (Some(_), _) => Err(ProtocolError::SessionNonce(
+                "request carries a nonce issued to another session".to_string(),
+            )),
+            (None, _) => Err(ProtocolError::SessionNonce(
+                "request carries a nonce but none was issued to this session".to_string(),
+            )),
+        }
+    }
+}
+
+#[instrument(
+    skip(stream, options, framing, policy, nonce_binding, session_span, session),
+    fields(phase = "notarize+verify")
+)]
+async fn run_pipeline<IO>(
+    stream: IO,
+    options: ServiceOptions,
+    mut framing: Framing,
+    policy: Option<NotarizationPolicy>,
+    nonce_binding: NonceBinding,
+    session_span: Span,
+    session: &LiveSession,
+) -> Result<(), ProtocolError>
 where
     IO: tokio::io::AsyncRead + tokio::io::AsyncWrite + Send + Unpin + 'static,
 {
     let mut progress = StepProgress::new(6);
     progress.tick("starting pipeline");
-    let (mut io, notarized_transcript) = run_notarization(stream).await?;
+    let artifact = ArtifactBuilder::new();
+    debug!(artifact_id = %artifact.id(), "Assigned session artifact id");
+    session.set_phase(SessionPhase::Notarizing);
+    let (mut io, notarized_transcript) =
+        run_notarization(stream, &options, policy, &nonce_binding, session).await?;
     progress.tick("notarization finished");
-    log_notarized_transcript(&notarized_transcript)?;
+    session.set_phase(SessionPhase::AwaitingProof);
+    if let Some(server_name) = &notarized_transcript.server_name {
+        shared::record_server_name(&session_span, server_name);
+    }
+    log_notarized_transcript(&notarized_transcript);
     info!(
-        server_name = %notarized_transcript.server_name,
+        server_name = notarized_transcript.server_name.as_deref(),
         commitments = notarized_transcript.transcript_commitments.len(),
         "Notarization complete"
     );
 
-    let proof_message = ProofMessage::read_from(&mut io).await?;
+    let proof_message = match framing.read_proof_request(&mut io).await? {
+        ProofRequest::Verify(proof_message) => {
+            session.set_phase(SessionPhase::Verifying);
+            proof_message
+        }
+        ProofRequest::Submit(params) => {
+            progress.tick("received proof submission");
+            return submit_verification_job(
+                io,
+                &framing,
+                options,
+                notarized_transcript,
+                artifact,
+                params,
+            )
+            .await;
+        }
+    };
     progress.tick("received proof payload");
     info!(
         proof_len = proof_message.proof.proof.len(),
@@ -156,6 +556,7 @@ where
         "Received full proof payload bytes"
     );
 
+    let proof = proof_message.proof.clone();
     let verified_fields = match verify_proof_message(&notarized_transcript, proof_message) {
         Ok(verified_fields) => verified_fields,
         Err(error) => {
@@ -163,8 +564,9 @@ where
             progress.tick("proof verification finished");
             send_verification_outcome_and_close(
                 &mut io,
+                &framing,
                 &VerificationOutcome::failure(
-                    notarized_transcript.server_name.clone(),
+                    notarized_transcript.outcome_server_name(),
                     error.to_string(),
                 ),
             )
@@ -176,27 +578,338 @@ where
     };
     progress.tick("proof verification finished");
 
-    let verification_outcome = VerificationOutcome::success(
-        notarized_transcript.server_name.clone(),
-        verified_fields,
-        "ZK proof verified successfully".to_string(),
-    );
-    send_verification_outcome_and_close(&mut io, &verification_outcome).await?;
+    let mut verification_outcome = notarized_transcript.success_outcome(verified_fields);
+    if options.artifact_store.is_some() || options.transparency_log.is_some() {
+        let server_name = notarized_transcript.outcome_server_name();
+        let outcome = verification_outcome.clone();
+        let time_source = options.time_source.clone();
+        let notary_key = options.notary_key.clone();
+        let sealed = smol::unblock(move || {
+            attestation_artifact(
+                &notarized_transcript,
+                artifact,
+                proof,
+                outcome,
+                &time_source,
+                notary_key.as_deref(),
+            )
+        })
+        .await;
+        let artifact = match sealed {
+            Ok(artifact) => artifact,
+            Err(error) => {
+                warn!(error = %error, "Failed to seal attestation");
+                send_verification_outcome_and_close(
+                    &mut io,
+                    &framing,
+                    &VerificationOutcome::failure(server_name, error.to_string()),
+                )
+                .await?;
+                progress.tick("sent verification result");
+                progress.tick("stream closed");
+                return Err(error);
+            }
+        };
+        let (artifact_store, transparency_log) = (options.artifact_store, options.transparency_log);
+        let recorded = smol::unblock(move || {
+            record_attestation(
+                artifact_store.as_deref(),
+                transparency_log.as_deref(),
+                artifact,
+            )
+        })
+        .await;
+        match recorded {
+            Ok(log_index) => verification_outcome.log_index = log_index,
+            Err(error) => {
+                warn!(error = %error, "Failed to record attestation");
+                send_verification_outcome_and_close(
+                    &mut io,
+                    &framing,
+                    &VerificationOutcome::failure(server_name, error.to_string()),
+                )
+                .await?;
+                progress.tick("sent verification result");
+                progress.tick("stream closed");
+                return Err(error);
+            }
+        }
+    }
+    send_verification_outcome_and_close(&mut io, &framing, &verification_outcome).await?;
     progress.tick("sent verification result");
     progress.tick("stream closed");
     Ok(())
 }
 
-#[instrument(skip(stream), fields(phase = "notarize"))]
+/// Hands proof verification to the job queue and answers with the job id, so
+/// the stream closes without waiting for the verifier.
+async fn submit_verification_job<IO>(
+    mut io: Compat<IO>,
+    framing: &Framing,
+    options: ServiceOptions,
+    notarized_transcript: NotarizedTranscript,
+    artifact: ArtifactBuilder,
+    params: SubmitParams,
+) -> Result<(), ProtocolError>
+where
+    IO: tokio::io::AsyncRead + tokio::io::AsyncWrite + Send + Unpin + 'static,
+{
+    let server_name = notarized_transcript.outcome_server_name();
+    let idempotency_key = params
+        .idempotency_key
+        .as_deref()
+        .map(|key| submission_key(key, &notarized_transcript, &params))
+        .transpose();
+    let queued = match (options.job_queue, idempotency_key) {
+        (Some(job_queue), Ok(idempotency_key)) => {
+            let artifact_store = options.artifact_store;
+            let transparency_log = options.transparency_log;
+            let time_source = options.time_source;
+            let notary_key = options.notary_key;
+            let proof_message = ProofMessage {
+                proof: params.proof,
+                thresholds: params.thresholds,
+            };
+            let span = Span::current();
+            job_queue
+                .enqueue(params.webhook_url.as_deref(), idempotency_key, move || {
+                    span.in_scope(|| {
+                        verify_and_persist(
+                            &notarized_transcript,
+                            artifact,
+                            proof_message,
+                            artifact_store.as_deref(),
+                            transparency_log.as_deref(),
+                            &time_source,
+                            notary_key.as_deref(),
+                        )
+                    })
+                })
+                .await
+        }
+        (Some(_), Err(error)) => Err(error),
+        (None, _) => Err(ProtocolError::InvalidRpc(format!(
+            "{METHOD_SESSION_SUBMIT} requires a verification job queue"
+        ))),
+    };
+
+    match queued {
+        Ok(job_id) => {
+            info!(%job_id, "Queued proof verification job");
+            framing
+                .write_submitted(&mut io, &SubmitResult { job_id })
+                .await?;
+            io.close().await?;
+            Ok(())
+        }
+        Err(error) => {
+            warn!(error = %error, "Failed to queue proof verification");
+            send_verification_outcome_and_close(
+                &mut io,
+                framing,
+                &VerificationOutcome::failure(server_name, error.to_string()),
+            )
+            .await?;
+            Err(error)
+        }
+    }
+}
+
+/// Binds the prover's key to the session's commitments as well as the
+/// proof, so a key reused by another session cannot name this one's job.
+fn submission_key(
+    key: &str,
+    notarized_transcript: &NotarizedTranscript,
+    params: &SubmitParams,
+) -> Result<IdempotencyKey, ProtocolError> {
+    let payload = serde_json::to_vec(&(
+        &notarized_transcript.transcript_commitments,
+        &params.proof,
+        &params.thresholds,
+        &params.webhook_url,
+    ))?;
+    Ok(IdempotencyKey::new(key, &payload))
+}
+
+/// Verification as run by a queued job, where failures become the outcome
+/// rather than an error on a stream that is already closed.
+fn verify_and_persist(
+    notarized_transcript: &NotarizedTranscript,
+    artifact: ArtifactBuilder,
+    proof_message: ProofMessage,
+    artifact_store: Option<&dyn ArtifactStore>,
+    transparency_log: Option<&TransparencyLog>,
+    time_source: &TimeSource,
+    notary_key: Option<&NotaryKey>,
+) -> VerificationOutcome {
+    let proof = proof_message.proof.clone();
+    let mut outcome = match verify_proof_message(notarized_transcript, proof_message) {
+        Ok(verified_fields) => notarized_transcript.success_outcome(verified_fields),
+        Err(error) => {
+            warn!(error = %error, "Proof verification failed");
+            return VerificationOutcome::failure(
+                notarized_transcript.outcome_server_name(),
+                error.to_string(),
+            );
+        }
+    };
+
+    if artifact_store.is_some() || transparency_log.is_some() {
+        let artifact = match attestation_artifact(
+            notarized_transcript,
+            artifact,
+            proof,
+            outcome.clone(),
+            time_source,
+            notary_key,
+        ) {
+            Ok(artifact) => artifact,
+            Err(error) => {
+                warn!(error = %error, "Failed to seal attestation");
+                return VerificationOutcome::failure(
+                    notarized_transcript.outcome_server_name(),
+                    error.to_string(),
+                );
+            }
+        };
+        match record_attestation(artifact_store, transparency_log, artifact) {
+            Ok(log_index) => outcome.log_index = log_index,
+            Err(error) => {
+                warn!(error = %error, "Failed to record attestation");
+                return VerificationOutcome::failure(
+                    notarized_transcript.outcome_server_name(),
+                    error.to_string(),
+                );
+            }
+        }
+    }
+    outcome
+}
+
+/// Appends the artifact to the transparency log, then persists it with the
+/// log index in its outcome.
+fn record_attestation(
+    artifact_store: Option<&dyn ArtifactStore>,
+    transparency_log: Option<&TransparencyLog>,
+    mut artifact: AttestationArtifact,
+) -> Result<Option<u64>, ProtocolError> {
+    let artifact_id = artifact.id;
+    if let Some(transparency_log) = transparency_log {
+        let log_index = transparency_log.append(artifact_id, artifact.digest()?)?;
+        info!(%artifact_id, log_index, "Appended attestation to transparency log");
+        artifact.outcome.log_index = Some(log_index);
+    }
+    if let Some(artifact_store) = artifact_store {
+        artifact_store.put(&artifact)?;
+        info!(%artifact_id, "Persisted attestation artifact");
+    }
+    Ok(artifact.outcome.log_index)
+}
+
+/// Seals the session into its artifact and timestamps it, holding the
+/// timestamp to the session's policy, then signs both with the notary key
+/// when one is configured. Blocks while a Roughtime server answers.
+fn attestation_artifact(
+    notarized_transcript: &NotarizedTranscript,
+    artifact: ArtifactBuilder,
+    proof: Proof,
+    outcome: VerificationOutcome,
+    time_source: &TimeSource,
+    notary_key: Option<&NotaryKey>,
+) -> Result<AttestationArtifact, ProtocolError> {
+    let mut artifact = artifact
+        .notarized(
+            notarized_transcript.outcome_server_name(),
+            notarized_transcript.transcript.sent_text(),
+            notarized_transcript.transcript.received_text(),
+            notarized_transcript.transcript_commitments.clone(),
+        )
+        .traffic(notarized_transcript.traffic)
+        .session_time(notarized_transcript.session_time)
+        .finish(proof, outcome)?;
+    let timestamp = time_source.stamp(&artifact.digest()?)?;
+    if let Some(policy) = &notarized_transcript.policy {
+        policy.check_session_time(notarized_transcript.session_time, &timestamp)?;
+    }
+    info!(
+        time = %timestamp.time(),
+        roughtime = timestamp.roughtime.is_some(),
+        "Timestamped attestation"
+    );
+    artifact.timestamp = Some(timestamp);
+    if let Some(notary_key) = notary_key {
+        artifact.sign(notary_key)?;
+    }
+    Ok(artifact)
+}
+
+#[instrument(
+    skip(stream, options, policy, nonce_binding, session),
+    fields(phase = "notarize")
+)]
 async fn run_notarization<IO>(
     stream: IO,
-) -> Result<(Compat<IO>, NotarizedTranscript), ProtocolError>
+    options: &ServiceOptions,
+    policy: Option<NotarizationPolicy>,
+    nonce_binding: &NonceBinding,
+    session: &LiveSession,
+) -> Result<(Compat<MeteredStream<IO>>, NotarizedTranscript), ProtocolError>
+where
+    IO: tokio::io::AsyncRead + tokio::io::AsyncWrite + Send + Unpin + 'static,
+{
+    let meter = TrafficMeter::new(options.traffic_limits);
+    session.attach_meter(&meter);
+    #[cfg(feature = "simulation")]
+    let result = if options.simulate {
+        replay_recorded_session(
+            meter.wrap(stream),
+            &meter,
+            options.admission_control.as_ref(),
+            policy,
+            nonce_binding,
+        )
+        .await
+    } else {
+        notarize(
+            meter.wrap(stream),
+            &meter,
+            options.admission_control.as_ref(),
+            policy,
+            nonce_binding,
+        )
+        .await
+    };
+    #[cfg(not(feature = "simulation"))]
+    let result = notarize(
+        meter.wrap(stream),
+        &meter,
+        options.admission_control.as_ref(),
+        policy,
+        nonce_binding,
+    )
+    .await;
+    let traffic = meter.totals();
+    info!(
+        bytes_received = traffic.bytes_received,
+        bytes_sent = traffic.bytes_sent,
+        "Notarization traffic"
+    );
+    result.map_err(|error| meter.exceeded().unwrap_or(error))
+}
+
+async fn notarize<IO>(
+    stream: MeteredStream<IO>,
+    meter: &TrafficMeter,
+    admission_control: Option<&Arc<AdmissionControl>>,
+    policy: Option<NotarizationPolicy>,
+    nonce_binding: &NonceBinding,
+) -> Result<(Compat<MeteredStream<IO>>, NotarizedTranscript), ProtocolError>
 where
     IO: tokio::io::AsyncRead + tokio::io::AsyncWrite + Send + Unpin + 'static,
 {
     let session = Session::new(Compat::new(stream));
     let (driver, mut handle) = session.split();
-    let driver_task = smol::spawn(driver);
+    let driver_task = smol::spawn(driver.in_current_span());
 
     let verifier_config = create_verifier_config()?;
     info!("Created verifier configuration");
@@ -207,14 +920,28 @@ where
     let verifier = verifier.commit().await.map_err(tlsnotary::Error::from)?;
     info!("Verifier committed protocol proposal");
 
-    if let Some(reason) = protocol_rejection_reason(verifier.request().protocol()) {
+    if let Err(error) = check_protocol(verifier.request().protocol(), policy.as_ref()) {
+        let reason = error.to_string();
         verifier
             .reject(Some(reason.as_str()))
             .await
             .map_err(tlsnotary::Error::from)?;
         warn!(reason = %reason, "Rejected prover protocol configuration");
-        return Err(ProtocolError::InvalidConfig(reason));
+        return Err(error);
     }
+
+    let _admission_permit = match admit_session(verifier.request().protocol(), admission_control) {
+        Ok(permit) => permit,
+        Err(error) => {
+            let reason = error.to_string();
+            verifier
+                .reject(Some(reason.as_str()))
+                .await
+                .map_err(tlsnotary::Error::from)?;
+            warn!(reason = %reason, "Rejected session over admission budget");
+            return Err(error);
+        }
+    };
     info!("Accepted prover protocol configuration");
 
     let verifier = verifier
@@ -224,14 +951,12 @@ where
         .run()
         .await
         .map_err(tlsnotary::Error::from)?;
+    let session_time = Utc::now();
     info!("Finished MPC-TLS run");
     let verifier = verifier.verify().await.map_err(tlsnotary::Error::from)?;
     info!("Started verification phase");
 
-    if let Some(reason) = proving_request_rejection_reason(
-        verifier.request().server_identity(),
-        verifier.request().reveal().is_some(),
-    ) {
+    if let Some(reason) = proving_request_rejection_reason(verifier.request().reveal().is_some()) {
         let verifier = verifier
             .reject(Some(reason.as_str()))
             .await
@@ -248,38 +973,119 @@ where
     handle.close();
     let io = driver_task.await.map_err(tlsnotary::Error::from)?;
 
-    let server_name = output
-        .server_name
-        .ok_or(ProtocolError::MissingField("server_name"))?
-        .to_string();
-    let transcript = output
-        .transcript
-        .ok_or(ProtocolError::MissingField("transcript"))?;
-    let request = String::from_utf8(transcript.sent_unsafe().to_vec())?;
-    let response = String::from_utf8(transcript.received_unsafe().to_vec())?;
-
-    Ok((
-        io,
-        NotarizedTranscript {
-            server_name,
-            request,
-            response,
-            transcript_commitments: output.transcript_commitments,
-        },
-    ))
+    let server_name = output.server_name.map(|name| name.to_string());
+    let transcript = {
+        let partial = output
+            .transcript
+            .ok_or(ProtocolError::MissingField("transcript"))?;
+        SharedTranscript::new(partial.sent_unsafe(), partial.received_unsafe())
+    };
+    let notarized_transcript = check_notarized_transcript(
+        server_name,
+        transcript,
+        output.transcript_commitments,
+        policy,
+        nonce_binding,
+        meter.totals(),
+        session_time,
+    )?;
+    Ok((io, notarized_transcript))
+}
+
+/// Stands in for [`notarize`] in simulation mode: the prover sends a
+/// [`RecordedSession`] instead of running MPC-TLS, and everything after the
+/// MPC phase runs as usual.
+#[cfg(feature = "simulation")]
+async fn replay_recorded_session<IO>(
+    stream: MeteredStream<IO>,
+    meter: &TrafficMeter,
+    admission_control: Option<&Arc<AdmissionControl>>,
+    policy: Option<NotarizationPolicy>,
+    nonce_binding: &NonceBinding,
+) -> Result<(Compat<MeteredStream<IO>>, NotarizedTranscript), ProtocolError>
+where
+    IO: tokio::io::AsyncRead + tokio::io::AsyncWrite + Send + Unpin + 'static,
+{
+    let mut io = Compat::new(stream);
+    let recorded = RecordedSession::read_from(&mut io).await?;
+    info!(
+        server_name = recorded.server_name.as_deref(),
+        sent_len = recorded.sent.len(),
+        received_len = recorded.received.len(),
+        "Replaying recorded session"
+    );
+
+    let protocol = recorded.protocol()?;
+    check_protocol(&protocol, policy.as_ref())?;
+    let _admission_permit = admit_session(&protocol, admission_control)?;
+
+    let transcript = SharedTranscript::new(&recorded.sent, &recorded.received);
+    let notarized_transcript = check_notarized_transcript(
+        recorded.server_name,
+        transcript,
+        recorded.transcript_commitments,
+        policy,
+        nonce_binding,
+        meter.totals(),
+        Utc::now(),
+    )?;
+    Ok((io, notarized_transcript))
+}
+
+/// Parses the notarized transcript, checks its session nonce, binds its
+/// commitments to response fields and holds it to the session's policy.
+fn check_notarized_transcript(
+    server_name: Option<String>,
+    transcript: SharedTranscript,
+    transcript_commitments: Vec<TranscriptCommitment>,
+    policy: Option<NotarizationPolicy>,
+    nonce_binding: &NonceBinding,
+    traffic: TrafficTotals,
+    session_time: DateTime<Utc>,
+) -> Result<NotarizedTranscript, ProtocolError> {
+    let (parsed_request, parsed_response) = parse_transcript(&transcript)?;
+    nonce_binding.check(&parsed_request, &transcript.sent_text())?;
+    let bound_commitments = bind_commitments_to_keys(&parsed_response, &transcript_commitments)
+        .map_err(|error| ProtocolError::CommitmentBindingFailed(error.to_string()))?;
+    if server_name.is_none() {
+        info!("Prover did not prove the server identity");
+    }
+    if let Some(policy) = &policy {
+        policy.check_server_name(server_name.as_deref())?;
+        policy.check_disclosures(&parsed_request, &parsed_response, &bound_commitments)?;
+        info!(policy = %policy.name, "Transcript satisfies notarization policy");
+    }
+
+    Ok(NotarizedTranscript {
+        server_name,
+        transcript,
+        transcript_commitments,
+        parsed_request,
+        parsed_response,
+        bound_commitments,
+        policy,
+        traffic,
+        session_time,
+    })
+}
+
+/// Redaction filler need not be UTF-8, so both directions are read with the
+/// lossy parsers.
+pub(crate) fn parse_transcript(
+    transcript: &SharedTranscript,
+) -> Result<(parser::redacted::Request, parser::redacted::Response), ProtocolError> {
+    let parsed_request = parser::redacted::Request::from_bytes_lossy(transcript.sent())
+        .map_err(|error| ProtocolError::RequestParse(format!("{error:?}")))?;
+    let parsed_response = parser::redacted::Response::from_bytes_lossy(transcript.received())
+        .map_err(|error| ProtocolError::ResponseParse(format!("{error:?}")))?;
+    Ok((parsed_request, parsed_response))
 }
 
 fn verify_proof_message(
     notarized_transcript: &NotarizedTranscript,
     proof_message: ProofMessage,
 ) -> Result<Vec<String>, ProtocolError> {
-    let parsed_response = parser::redacted::Response::from_str(&notarized_transcript.response)
-        .map_err(|error| ProtocolError::ResponseParse(format!("{error:?}")))?;
-    let bindings = bind_commitments_to_keys(
-        &parsed_response,
-        &notarized_transcript.transcript_commitments,
-    )
-    .map_err(|error| ProtocolError::CommitmentBindingFailed(error.to_string()))?;
+    let bindings = &notarized_transcript.bound_commitments;
 
     if bindings.is_empty() {
         return Err(ProtocolError::NoCommitmentsFound);
@@ -292,8 +1098,7 @@ fn verify_proof_message(
         "Extracted public committed hash from proof"
     );
 
-    info!(parsed_response = ?parsed_response, "Parsed notarized response");
-    for (field, binding) in &bindings {
+    for (field, binding) in bindings {
         info!(
             field = %field,
             key_range_start = binding.key_range.start,
@@ -307,7 +1112,7 @@ fn verify_proof_message(
     }
 
     let (matched_field, expected_hash) =
-        select_unique_bound_field_for_hash(&bindings, &proof_committed_hash)?;
+        select_unique_bound_field_for_hash(bindings, &proof_committed_hash)?;
     verify_proof_against_hash(&proof_message.proof, &expected_hash)
         .map_err(|error| ProtocolError::ProofVerificationFailed(error.to_string()))?;
     info!(
@@ -315,12 +1120,60 @@ fn verify_proof_message(
         "Proof cryptographically bound to transcript commitment"
     );
 
-    Ok(vec![matched_field])
+    let mut verified_fields = vec![matched_field];
+    for field in verify_thresholds(
+        &notarized_transcript.parsed_response,
+        &notarized_transcript.transcript_commitments,
+        proof_message.thresholds,
+    )? {
+        if !verified_fields.contains(&field) {
+            verified_fields.push(field);
+        }
+    }
+    if let Some(policy) = &notarized_transcript.policy {
+        policy.check_proven_fields(&verified_fields)?;
+    }
+    Ok(verified_fields)
 }
 
-fn create_verifier_config() -> Result<VerifierConfig, ProtocolError> {
+/// Binds each threshold claim to the commitment at its keypath and checks
+/// its predicate proof against that commitment, returning the keypaths in
+/// order.
+pub fn verify_thresholds(
+    parsed_response: &parser::redacted::Response,
+    transcript_commitments: &[TranscriptCommitment],
+    thresholds: BTreeMap<String, ThresholdClaim>,
+) -> Result<Vec<String>, ProtocolError> {
+    if thresholds.is_empty() {
+        return Ok(Vec::new());
+    }
+    let bindings = bind_commitments_with_thresholds(
+        parsed_response,
+        transcript_commitments,
+        thresholds.into_iter().collect(),
+    )
+    .map_err(|error| ProtocolError::ProofVerificationFailed(error.to_string()))?;
+    bindings
+        .into_iter()
+        .filter_map(|(field, binding)| {
+            let predicate = binding.threshold.as_ref()?.predicate;
+            Some((field, (binding, predicate)))
+        })
+        .collect::<BTreeMap<_, _>>()
+        .into_iter()
+        .map(|(field, (binding, predicate))| {
+            binding.verify().map_err(|error| {
+                ProtocolError::ProofVerificationFailed(format!("{field}: {error}"))
+            })?;
+            info!(field = %field, ?predicate, "Threshold proof bound to transcript commitment");
+            Ok(field)
+        })
+        .collect()
+}
+
+pub(crate) fn create_verifier_config() -> Result<VerifierConfig, ProtocolError> {
     let TestTlsConfig { cert_bytes, .. } =
-        get_or_create_test_tls_config(Path::new("test_cert.pem"), Path::new("test_key.pem"))
+        get_or_create_test_tls_config(Path::new(ORIGIN_CERT_PATH), Path::new(ORIGIN_KEY_PATH))
             .map_err(|error| ProtocolError::InvalidConfig(error.to_string()))?;
 
     Ok(VerifierConfig::builder()
@@ -331,7 +1184,7 @@ fn create_verifier_config() -> Result<VerifierConfig, ProtocolError> {
         .map_err(tlsnotary::Error::from)?)
 }
 
-async fn read_json_frame<IO, T>(io: &mut IO) -> Result<T, ProtocolError>
+pub(crate) async fn read_json_frame<IO, T>(io: &mut IO) -> Result<T, ProtocolError>
 where
     IO: AsyncRead + Unpin + Send,
     T: DeserializeOwned,
@@ -348,7 +1201,7 @@ where
     Ok(serde_json::from_slice(&payload)?)
 }
 
-async fn write_json_frame<IO, T>(io: &mut IO, value: &T) -> Result<(), ProtocolError>
+pub(crate) async fn write_json_frame<IO, T>(io: &mut IO, value: &T) -> Result<(), ProtocolError>
 where
     IO: AsyncWrite + Unpin + Send,
     T: Serialize,
@@ -366,49 +1219,58 @@ where
 
 async fn send_verification_outcome_and_close<IO>(
     io: &mut IO,
+    framing: &Framing,
     outcome: &VerificationOutcome,
 ) -> Result<(), ProtocolError>
 where
     IO: AsyncWrite + Unpin + Send,
 {
-    outcome.write_to(io).await?;
+    framing.write_outcome(io, outcome).await?;
     io.close().await?;
     Ok(())
 }
 
-fn protocol_rejection_reason(protocol: &TlsCommitProtocolConfig) -> Option<String> {
-    match protocol {
-        TlsCommitProtocolConfig::Mpc(mpc_tls_config) => {
-            if mpc_tls_config.max_sent_data() > MAX_SENT_DATA {
-                return Some(format!(
-                    "max_sent_data too large: {} > {}",
-                    mpc_tls_config.max_sent_data(),
-                    MAX_SENT_DATA
-                ));
-            }
-
-            if mpc_tls_config.max_recv_data() > MAX_RECV_DATA {
-                return Some(format!(
-                    "max_recv_data too large: {} > {}",
-                    mpc_tls_config.max_recv_data(),
-                    MAX_RECV_DATA
-                ));
-            }
-
-            None
-        }
-        _ => Some("expected MPC-TLS protocol".to_string()),
+/// Checks the proposal against the service's own limits, then against the
+/// session's policy, whose violations are reported as such.
+fn check_protocol(
+    protocol: &TlsCommitProtocolConfig,
+    policy: Option<&NotarizationPolicy>,
+) -> Result<(), ProtocolError> {
+    let service_limits = ProtocolLimits::new()
+        .max_sent_data(MAX_SENT_DATA)
+        .max_recv_data(MAX_RECV_DATA);
+    if let Some(reason) = service_limits.violation(protocol) {
+        return Err(ProtocolError::InvalidConfig(reason));
     }
+    policy.map_or(Ok(()), |policy| policy.check_protocol(protocol))
 }
 
-fn proving_request_rejection_reason(
-    server_identity_revealed: bool,
-    reveal_payload_present: bool,
-) -> Option<String> {
-    if !server_identity_revealed {
-        return Some("missing required server identity reveal".to_string());
-    }
+fn admit_session(
+    protocol: &TlsCommitProtocolConfig,
+    admission_control: Option<&Arc<AdmissionControl>>,
+) -> Result<Option<AdmissionPermit>, ProtocolError> {
+    let (Some(admission_control), TlsCommitProtocolConfig::Mpc(mpc_tls_config)) =
+        (admission_control, protocol)
+    else {
+        return Ok(None);
+    };
 
+    let cost = AdmissionControl::session_cost(
+        mpc_tls_config.max_sent_data(),
+        mpc_tls_config.max_recv_data(),
+    );
+    let permit = admission_control.try_admit(cost)?;
+    info!(
+        cost,
+        status = ?admission_control.status(),
+        "Admitted session within admission budget"
+    );
+    Ok(Some(permit))
+}
+
+/// A missing server identity is tolerated, as by [`tlsnotary::Verifier`]:
+/// the outcome then carries no server name and host policies refuse it.
+fn proving_request_rejection_reason(reveal_payload_present: bool) -> Option<String> {
     if !reveal_payload_present {
         return Some("missing required transcript reveal payload".to_string());
     }
@@ -417,7 +1279,7 @@ fn proving_request_rejection_reason(
 }
 
 fn select_unique_bound_field_for_hash(
-    bindings: &HashMap<String, zktlsn::BoundCommitment>,
+    bindings: &HashMap<String, BoundCommitment>,
     proof_committed_hash: &[u8],
 ) -> Result<(String, [u8; 32]), ProtocolError> {
     let mut matched_field: Option<String> = None;
@@ -452,29 +1314,31 @@ fn select_unique_bound_field_for_hash(
     )
 }
 
-fn log_notarized_transcript(
-    notarized_transcript: &NotarizedTranscript,
-) -> Result<(), ProtocolError> {
+fn log_notarized_transcript(notarized_transcript: &NotarizedTranscript) {
     let request_commit_mask = build_commitment_mask(
         &notarized_transcript.transcript_commitments,
         Direction::Sent,
-        notarized_transcript.request.len(),
+        notarized_transcript.transcript.sent().len(),
     );
     let response_commit_mask = build_commitment_mask(
         &notarized_transcript.transcript_commitments,
         Direction::Received,
-        notarized_transcript.response.len(),
+        notarized_transcript.transcript.received().len(),
     );
 
     info!(
-        server_name = %notarized_transcript.server_name,
-        request_len = notarized_transcript.request.len(),
-        response_len = notarized_transcript.response.len(),
+        server_name = notarized_transcript.server_name.as_deref(),
+        request_len = notarized_transcript.transcript.sent().len(),
+        response_len = notarized_transcript.transcript.received().len(),
         commitment_count = notarized_transcript.transcript_commitments.len(),
         "Received notarization transcript from prover"
     );
-    let request_view = render_verifier_view(&notarized_transcript.request, &request_commit_mask);
-    let response_view = render_verifier_view(&notarized_transcript.response, &response_commit_mask);
+    let request_view =
+        render_verifier_view(notarized_transcript.transcript.sent(), &request_commit_mask);
+    let response_view = render_verifier_view(
+        notarized_transcript.transcript.received(),
+        &response_commit_mask,
+    );
     info!(
         "Verifier full request view (legend: 🙈 redacted byte, 🔐 committed byte):\n{}",
         request_view
@@ -484,15 +1348,24 @@ fn log_notarized_transcript(
         response_view
     );
 
-    let parsed_request = parser::redacted::Request::from_str(&notarized_transcript.request)
-        .map_err(|error| ProtocolError::RequestParse(format!("{error:?}")))?;
-    info!(parsed_request = ?parsed_request, "Parsed notarized request");
-    log_redacted_request_details(&parsed_request, &notarized_transcript.request);
+    info!(parsed_request = ?notarized_transcript.parsed_request, "Parsed notarized request");
+    log_redacted_request_details(
+        &notarized_transcript.parsed_request,
+        &notarized_transcript.transcript.sent_text(),
+    );
 
-    let parsed_response = parser::redacted::Response::from_str(&notarized_transcript.response)
-        .map_err(|error| ProtocolError::ResponseParse(format!("{error:?}")))?;
-    info!(parsed_response = ?parsed_response, "Parsed notarized response");
-    log_redacted_response_details(&parsed_response, &notarized_transcript.response);
+    info!(parsed_response = ?notarized_transcript.parsed_response, "Parsed notarized response");
+    log_redacted_response_details(
+        &notarized_transcript.parsed_response,
+        &notarized_transcript.transcript.received_text(),
+    );
+    info!(
+        disclosures = ?response_field_disclosures(
+            &notarized_transcript.parsed_response,
+            &notarized_transcript.bound_commitments,
+        ),
+        "Resolved response field disclosures"
+    );
 
     for (index, commitment) in notarized_transcript
         .transcript_commitments
@@ -519,8 +1392,6 @@ fn log_notarized_transcript(
             ),
         }
     }
-
-    Ok(())
 }
 
 fn build_commitment_mask(
@@ -551,8 +1422,7 @@ fn build_commitment_mask(
     mask
 }
 
-fn render_verifier_view(transcript: &str, commit_mask: &[bool]) -> String {
-    let bytes = transcript.as_bytes();
+fn render_verifier_view(bytes: &[u8], commit_mask: &[bool]) -> String {
     let max_len = bytes.len().min(commit_mask.len());
     let mut out = String::new();
 
@@ -685,6 +1555,21 @@ fn log_redacted_request_details(parsed_request: &parser::redacted::Request, requ
                     "Parsed transcript request field"
                 );
             }
+            parser::redacted::Body::PartialValue {
+                revealed,
+                total_hint,
+            } => {
+                info!(
+                    direction = "request",
+                    item = "body-partial-value",
+                    keypath = %keypath,
+                    revealed_range_start = revealed.start,
+                    revealed_range_end = revealed.end,
+                    total_hint = ?total_hint,
+                    value_preview = %preview_text_range(request, revealed),
+                    "Parsed transcript request field"
+                );
+            }
         }
     }
 }
@@ -745,6 +1630,21 @@ fn log_redacted_response_details(parsed_response: &parser::redacted::Response, r
                     "Parsed transcript response field"
                 );
             }
+            parser::redacted::Body::PartialValue {
+                revealed,
+                total_hint,
+            } => {
+                info!(
+                    direction = "response",
+                    item = "body-partial-value",
+                    keypath = %keypath,
+                    revealed_range_start = revealed.start,
+                    revealed_range_end = revealed.end,
+                    total_hint = ?total_hint,
+                    value_preview = %preview_text_range(response, revealed),
+                    "Parsed transcript response field"
+                );
+            }
         }
     }
 }
@@ -0,0 +1,337 @@
+use std::collections::BTreeMap;
+
+use futures::{AsyncRead, AsyncWrite};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::Value;
+use uuid::Uuid;
+use zktlsn::{Proof, ThresholdClaim};
+
+use crate::{
+    errors::ProtocolError,
+    load::LoadShedder,
+    protocol::{ProofMessage, SessionInit, VerificationOutcome, read_json_frame, write_json_frame},
+};
+
+pub const JSONRPC_VERSION: &str = "2.0";
+pub const METHOD_SESSION_INIT: &str = "session.init";
+/// Marks the switch to raw MPC-TLS traffic; it has no JSON-RPC frame of its own.
+pub const METHOD_SESSION_NOTARIZE: &str = "session.notarize";
+pub const METHOD_SESSION_VERIFY: &str = "session.verify";
+/// Queues verification and answers with a job id instead of the outcome.
+pub const METHOD_SESSION_SUBMIT: &str = "session.submit";
+pub const VERIFICATION_FAILED_CODE: i64 = -32001;
+/// Answers `session.init` while the verifier sheds load; try again later.
+pub const SERVER_BUSY_CODE: i64 = -32002;
+/// Request header carrying the nonce issued at `session.init`; it must be
+/// revealed in the notarized request.
+pub const SESSION_NONCE_HEADER: &str = "x-verifier-nonce";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcRequest<P> {
+    pub jsonrpc: String,
+    pub id: Value,
+    pub method: String,
+    pub params: P,
+}
+
+impl<P> RpcRequest<P> {
+    pub fn new(id: impl Into<Value>, method: &str, params: P) -> Self {
+        Self {
+            jsonrpc: JSONRPC_VERSION.to_string(),
+            id: id.into(),
+            method: method.to_string(),
+            params,
+        }
+    }
+
+    fn expect_version(self) -> Result<Self, ProtocolError> {
+        if self.jsonrpc != JSONRPC_VERSION {
+            return Err(ProtocolError::InvalidRpc(format!(
+                "unsupported jsonrpc version '{}'",
+                self.jsonrpc
+            )));
+        }
+        Ok(self)
+    }
+
+    fn expect_method(self, method: &str) -> Result<Self, ProtocolError> {
+        let request = self.expect_version()?;
+        if request.method != method {
+            return Err(ProtocolError::InvalidRpc(format!(
+                "expected method '{method}', got '{}'",
+                request.method
+            )));
+        }
+        Ok(request)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcResponse<R> {
+    pub jsonrpc: String,
+    pub id: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<R>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<RpcError>,
+}
+
+impl<R> RpcResponse<R> {
+    fn result(id: Value, result: R) -> Self {
+        Self {
+            jsonrpc: JSONRPC_VERSION.to_string(),
+            id,
+            result: Some(result),
+            error: None,
+        }
+    }
+
+    fn error(id: Value, error: RpcError) -> Self {
+        Self {
+            jsonrpc: JSONRPC_VERSION.to_string(),
+            id,
+            result: None,
+            error: Some(error),
+        }
+    }
+
+    fn into_result(self) -> Result<R, ProtocolError> {
+        match (self.result, self.error) {
+            (_, Some(error)) => Err(ProtocolError::ProofVerificationFailed(error.message)),
+            (Some(result), None) => Ok(result),
+            (None, None) => Err(ProtocolError::InvalidRpc(
+                "response has neither result nor error".to_string(),
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionInitResult {
+    pub next_method: String,
+    /// Sent back in [`SESSION_NONCE_HEADER`] of the notarized request.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_nonce: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubmitParams {
+    pub proof: Proof,
+    /// See [`ProofMessage::thresholds`].
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub thresholds: BTreeMap<String, ThresholdClaim>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhook_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idempotency_key: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubmitResult {
+    pub job_id: Uuid,
+}
+
+/// What the prover asks for once notarization is done.
+#[derive(Debug, Clone)]
+pub(crate) enum ProofRequest {
+    Verify(ProofMessage),
+    Submit(SubmitParams),
+}
+
+/// Wire format chosen by the prover's first frame; the MPC-TLS phase is raw
+/// bytes in both cases.
+#[derive(Debug, Clone)]
+pub(crate) enum Framing {
+    Plain,
+    JsonRpc { id: Value },
+}
+
+impl Framing {
+    /// Reads the opening frame, either a bare [`SessionInit`] or a
+    /// `session.init` request carrying one, and acknowledges the latter with
+    /// a fresh session nonce. A bare frame has no reply to carry one.
+    ///
+    /// While `load_shedder` refuses sessions, a `session.init` request is
+    /// answered with [`SERVER_BUSY_CODE`] and a bare frame by closing.
+    pub(crate) async fn accept_session<IO>(
+        io: &mut IO,
+        load_shedder: Option<&LoadShedder>,
+    ) -> Result<(Self, SessionInit, Option<String>), ProtocolError>
+    where
+        IO: AsyncRead + AsyncWrite + Unpin + Send,
+    {
+        let frame: Value = read_json_frame(io).await?;
+        let admission = load_shedder.map_or(Ok(()), LoadShedder::admit);
+        if frame.get("jsonrpc").is_none() {
+            admission?;
+            let session_init: SessionInit = serde_json::from_value(frame)?;
+            session_init.trace_context.trace_id()?;
+            return Ok((Self::Plain, session_init, None));
+        }
+
+        let request = serde_json::from_value::<RpcRequest<SessionInit>>(frame)?
+            .expect_method(METHOD_SESSION_INIT)?;
+        if let Err(error) = admission {
+            let busy = RpcError {
+                code: SERVER_BUSY_CODE,
+                message: error.to_string(),
+                data: None,
+            };
+            write_json_frame(io, &RpcResponse::<()>::error(request.id, busy)).await?;
+            return Err(error);
+        }
+        request.params.trace_context.trace_id()?;
+        let session_nonce = Uuid::new_v4().simple().to_string();
+        let response = RpcResponse::result(
+            request.id.clone(),
+            SessionInitResult {
+                next_method: METHOD_SESSION_NOTARIZE.to_string(),
+                session_nonce: Some(session_nonce.clone()),
+            },
+        );
+        write_json_frame(io, &response).await?;
+        Ok((
+            Self::JsonRpc { id: request.id },
+            request.params,
+            Some(session_nonce),
+        ))
+    }
+
+    pub(crate) async fn read_proof_request<IO>(
+        &mut self,
+        io: &mut IO,
+    ) -> Result<ProofRequest, ProtocolError>
+    where
+        IO: AsyncRead + Unpin + Send,
+    {
+        match self {
+            Self::Plain => Ok(ProofRequest::Verify(ProofMessage::read_from(io).await?)),
+            Self::JsonRpc { id } => {
+                let request = read_json_frame::<_, RpcRequest<Value>>(io)
+                    .await?
+                    .expect_version()?;
+                *id = request.id;
+                match request.method.as_str() {
+                    METHOD_SESSION_VERIFY => Ok(ProofRequest::Verify(serde_json::from_value(
+                        request.params,
+                    )?)),
+                    METHOD_SESSION_SUBMIT => Ok(ProofRequest::Submit(serde_json::from_value(
+                        request.params,
+                    )?)),
+                    method => Err(ProtocolError::InvalidRpc(format!(
+                        "expected method '{METHOD_SESSION_VERIFY}' or '{METHOD_SESSION_SUBMIT}', got '{method}'"
+                    ))),
+                }
+            }
+        }
+    }
+
+    pub(crate) async fn write_submitted<IO>(
+        &self,
+        io: &mut IO,
+        result: &SubmitResult,
+    ) -> Result<(), ProtocolError>
+    where
+        IO: AsyncWrite + Unpin + Send,
+    {
+        match self {
+            Self::Plain => write_json_frame(io, result).await,
+            Self::JsonRpc { id } => {
+                write_json_frame(io, &RpcResponse::result(id.clone(), result)).await
+            }
+        }
+    }
+
+    pub(crate) async fn write_outcome<IO>(
+        &self,
+        io: &mut IO,
+        outcome: &VerificationOutcome,
+    ) -> Result<(), ProtocolError>
+    where
+        IO: AsyncWrite + Unpin + Send,
+    {
+        match self {
+            Self::Plain => outcome.write_to(io).await,
+            Self::JsonRpc { id } if outcome.success => {
+                write_json_frame(io, &RpcResponse::result(id.clone(), outcome)).await
+            }
+            Self::JsonRpc { id } => {
+                let error = RpcError {
+                    code: VERIFICATION_FAILED_CODE,
+                    message: outcome.message.clone(),
+                    data: Some(serde_json::to_value(outcome)?),
+                };
+                write_json_frame(io, &RpcResponse::<()>::error(id.clone(), error)).await
+            }
+        }
+    }
+}
+
+/// Client side of `session.init`; raw MPC-TLS traffic may follow once it returns.
+pub async fn init_session<IO>(
+    io: &mut IO,
+    id: impl Into<Value>,
+    session_init: &SessionInit,
+) -> Result<SessionInitResult, ProtocolError>
+where
+    IO: AsyncRead + AsyncWrite + Unpin + Send,
+{
+    call(io, RpcRequest::new(id, METHOD_SESSION_INIT, session_init)).await
+}
+
+/// Client side of `session.verify`.
+pub async fn verify_session<IO>(
+    io: &mut IO,
+    id: impl Into<Value>,
+    proof_message: &ProofMessage,
+) -> Result<VerificationOutcome, ProtocolError>
+where
+    IO: AsyncRead + AsyncWrite + Unpin + Send,
+{
+    call(
+        io,
+        RpcRequest::new(id, METHOD_SESSION_VERIFY, proof_message),
+    )
+    .await
+}
+
+/// Client side of `session.submit`; the outcome arrives through the job's
+/// webhook or `GET /jobs/{id}`.
+pub async fn submit_session<IO>(
+    io: &mut IO,
+    id: impl Into<Value>,
+    params: &SubmitParams,
+) -> Result<SubmitResult, ProtocolError>
+where
+    IO: AsyncRead + AsyncWrite + Unpin + Send,
+{
+    call(io, RpcRequest::new(id, METHOD_SESSION_SUBMIT, params)).await
+}
+
+async fn call<IO, P, R>(io: &mut IO, request: RpcRequest<P>) -> Result<R, ProtocolError>
+where
+    IO: AsyncRead + AsyncWrite + Unpin + Send,
+    P: Serialize,
+    R: DeserializeOwned,
+{
+    write_json_frame(io, &request).await?;
+    let response: RpcResponse<R> = read_json_frame(io).await?;
+    if response.id != request.id {
+        return Err(ProtocolError::InvalidRpc(format!(
+            "response id {} does not match request id {}",
+            response.id, request.id
+        )));
+    }
+    response.into_result()
+}
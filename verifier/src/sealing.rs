@@ -0,0 +1,187 @@
+use std::{collections::BTreeMap, fmt, fs, io, path::Path};
+
+use ring::{
+    aead::{AES_256_GCM, Aad, LessSafeKey, NONCE_LEN, Nonce, UnboundKey},
+    rand::{SecureRandom, SystemRandom},
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    errors::StoreError,
+    timestamp::{decode_hex, hex},
+};
+
+const KEY_LEN: usize = 32;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct StoreKeyFile {
+    current: String,
+    keys: BTreeMap<String, String>,
+}
+
+/// AES-256 store keys by id, read from a JSON key file such as
+/// `{"current": "2026-10", "keys": {"2026-10": "<64 hex digits>"}}`.
+///
+/// Every artifact is encrypted under its own data key, which is wrapped by
+/// the `current` store key. The other keys only open artifacts written
+/// before a rotation, until their data keys are re-wrapped.
+#[derive(Clone)]
+pub struct StoreKeys {
+    current: String,
+    keys: BTreeMap<String, [u8; KEY_LEN]>,
+}
+
+impl fmt::Debug for StoreKeys {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StoreKeys")
+            .field("current", &self.current)
+            .field("keys", &self.keys.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl StoreKeys {
+    /// Fails with [`StoreError::UnknownKey`] when `current` is not one of
+    /// `keys`.
+    pub fn new(
+        current: impl Into<String>,
+        keys: BTreeMap<String, [u8; KEY_LEN]>,
+    ) -> Result<Self, StoreError> {
+        let current = current.into();
+        if !keys.contains_key(&current) {
+            return Err(StoreError::UnknownKey(current));
+        }
+        Ok(Self { current, keys })
+    }
+
+    /// Reads a key file, checking every key and that `current` names one of
+    /// them.
+    pub fn from_json_file(path: impl AsRef<Path>) -> Result<Self, StoreError> {
+        let file: StoreKeyFile = serde_json::from_slice(&fs::read(path)?)?;
+        let keys = file
+            .keys
+            .into_iter()
+            .map(|(id, key)| {
+                let key = decode_hex(&key)
+                    .and_then(|key| <[u8; KEY_LEN]>::try_from(key).ok())
+                    .ok_or_else(|| StoreError::InvalidKey(id.clone()))?;
+                Ok((id, key))
+            })
+            .collect::<Result<_, StoreError>>()?;
+        Self::new(file.current, keys)
+    }
+
+    pub fn current_id(&self) -> &str {
+        &self.current
+    }
+
+    /// Encrypts `plaintext` under a fresh data key bound to `id`.
+    pub fn seal(&self, id: Uuid, plaintext: &[u8]) -> Result<SealedArtifact, StoreError> {
+        let mut data_key = [0; KEY_LEN];
+        fill_random(&mut data_key)?;
+        let ciphertext = seal_with(&data_key, id.as_bytes(), plaintext)?;
+        self.wrap(id, &data_key, hex(&ciphertext))
+    }
+
+    pub fn open(&self, id: Uuid, sealed: &SealedArtifact) -> Result<Vec<u8>, StoreError> {
+        let data_key = self.unwrap(id, sealed)?;
+        decode_hex(&sealed.ciphertext)
+            .and_then(|ciphertext| open_with(&data_key, id.as_bytes(), &ciphertext))
+            .ok_or(StoreError::Decrypt(id))
+    }
+
+    /// Wraps the data key of `sealed` with the current store key; the
+    /// ciphertext is kept as is.
+    pub fn rewrap(&self, id: Uuid, sealed: &SealedArtifact) -> Result<SealedArtifact, StoreError> {
+        let data_key = self.unwrap(id, sealed)?;
+        self.wrap(id, &data_key, sealed.ciphertext.clone())
+    }
+
+    fn wrap(
+        &self,
+        id: Uuid,
+        data_key: &[u8; KEY_LEN],
+        ciphertext: String,
+    ) -> Result<SealedArtifact, StoreError> {
+        let store_key = self
+            .keys
+            .get(&self.current)
+            .ok_or_else(|| StoreError::UnknownKey(self.current.clone()))?;
+        let wrapped_key = seal_with(store_key, &wrapping_aad(id, &self.current), data_key)?;
+        Ok(SealedArtifact {
+            id,
+            key_id: self.current.clone(),
+            wrapped_key: hex(&wrapped_key),
+            ciphertext,
+        })
+    }
+
+    fn unwrap(&self, id: Uuid, sealed: &SealedArtifact) -> Result<[u8; KEY_LEN], StoreError> {
+        let store_key = self
+            .keys
+            .get(&sealed.key_id)
+            .ok_or_else(|| StoreError::UnknownKey(sealed.key_id.clone()))?;
+        decode_hex(&sealed.wrapped_key)
+            .and_then(|wrapped| open_with(store_key, &wrapping_aad(id, &sealed.key_id), &wrapped))
+            .and_then(|data_key| <[u8; KEY_LEN]>::try_from(data_key).ok())
+            .ok_or(StoreError::Decrypt(id))
+    }
+}
+
+/// An artifact as stored when [`StoreKeys`] are configured. The id is also
+/// authenticated, so an envelope copied under another artifact's name does
+/// not open.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct SealedArtifact {
+    pub id: Uuid,
+    /// The store key wrapping the data key.
+    pub key_id: String,
+    /// Hex nonce and AES-256-GCM ciphertext of the data key.
+    pub wrapped_key: String,
+    /// Hex nonce and AES-256-GCM ciphertext of the artifact JSON.
+    pub ciphertext: String,
+}
+
+fn wrapping_aad(id: Uuid, key_id: &str) -> Vec<u8> {
+    [id.as_bytes().as_slice(), key_id.as_bytes()].concat()
+}
+
+fn fill_random(bytes: &mut [u8]) -> Result<(), StoreError> {
+    SystemRandom::new()
+        .fill(bytes)
+        .map_err(|_| io::Error::other("system random source failed").into())
+}
+
+/// The random nonce followed by the ciphertext and tag.
+fn seal_with(key: &[u8; KEY_LEN], aad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, StoreError> {
+    let mut nonce = [0; NONCE_LEN];
+    fill_random(&mut nonce)?;
+    let key = LessSafeKey::new(
+        UnboundKey::new(&AES_256_GCM, key).map_err(|_| io::Error::other("invalid AES-256 key"))?,
+    );
+    let mut sealed = plaintext.to_vec();
+    key.seal_in_place_append_tag(
+        Nonce::assume_unique_for_key(nonce),
+        Aad::from(aad),
+        &mut sealed,
+    )
+    .map_err(|_| io::Error::other("artifact too large to seal"))?;
+    Ok([nonce.as_slice(), &sealed].concat())
+}
+
+fn open_with(key: &[u8; KEY_LEN], aad: &[u8], sealed: &[u8]) -> Option<Vec<u8>> {
+    let (nonce, ciphertext) = sealed.split_at_checked(NONCE_LEN)?;
+    let key = LessSafeKey::new(UnboundKey::new(&AES_256_GCM, key).ok()?);
+    let mut in_out = ciphertext.to_vec();
+    let plaintext = key
+        .open_in_place(
+            Nonce::try_assume_unique_for_key(nonce).ok()?,
+            Aad::from(aad),
+            &mut in_out,
+        )
+        .ok()?;
+    Some(plaintext.to_vec())
+}
@@ -0,0 +1,208 @@
+use std::{collections::HashMap, fmt, path::Path, time::Duration};
+
+use async_compat::Compat;
+use http_body_util::Empty;
+use hyper::{Request, body::Bytes};
+use server::{app::get_app, handle_connection};
+use shared::{TestTlsConfig, get_or_create_test_tls_config};
+use smol::Timer;
+use tlsnotary::{
+    BodyFieldConfig, CertificateDer, KeyValueCommitConfig, MpcTlsConfig, Prover, ProverBuilder,
+    RootCertStore, ServerName, TlsClientConfig, TlsCommitConfig, prover::RevealConfig,
+};
+use tokio::io::{DuplexStream, duplex};
+use tracing::info;
+use zktlsn::{PaddingConfig, generate_proof};
+
+use crate::{
+    MAX_RECV_DATA, MAX_SENT_DATA, NotarizeStream, SESSION_NONCE_HEADER, ServiceOptions,
+    SessionInit, TraceContext, VerificationOutcome,
+    errors::ProtocolError,
+    protocol::{ORIGIN_CERT_PATH, ORIGIN_KEY_PATH, run_notarize_and_verify_stream},
+};
+
+const SELF_TEST_TIMEOUT: Duration = Duration::from_secs(300);
+const SELF_TEST_USER: &str = "selftest";
+const SELF_TEST_BALANCE: u64 = 100;
+/// Bytes buffered in each direction of the in-memory pipes the round runs
+/// over, so it needs no sockets from the platform.
+const SELF_TEST_PIPE_CAPACITY: usize = 1 << 16;
+/// Committed bytes of the balance, as the example prover pads them.
+pub(crate) const BALANCE_COMMITMENT_LENGTH: usize = 12;
+
+const MPC_STAGE: &str = "running MPC-TLS with the test origin";
+const PROOF_STAGE: &str = "generating the proof";
+const VERIFY_STAGE: &str = "verifying the session";
+
+/// Notarizes and proves one request to an in-process origin through the
+/// service's own session pipeline. Run before the listeners are bound, it
+/// turns a missing SRS or circuit artifact, an unusable origin certificate or
+/// a stalled executor into a startup failure naming the stage, rather than a
+/// failure of the first real session.
+///
+/// Only the traffic limits and nonce requirement of `options` apply: the
+/// session is not admitted, stored, logged or held to a policy.
+pub async fn run_self_test(options: &ServiceOptions) -> Result<(), ProtocolError> {
+    let options = ServiceOptions {
+        traffic_limits: options.traffic_limits,
+        require_session_nonce: options.require_session_nonce,
+        ..ServiceOptions::default()
+    };
+    smol::future::or(self_test_round(options), async {
+        Timer::after(SELF_TEST_TIMEOUT).await;
+        Err(stage_error(
+            "waiting for the round to finish",
+            format!("timed out after {SELF_TEST_TIMEOUT:?}"),
+        ))
+    })
+    .await
+}
+
+async fn self_test_round(options: ServiceOptions) -> Result<(), ProtocolError> {
+    let TestTlsConfig {
+        server_config,
+        cert_bytes,
+        ..
+    } = get_or_create_test_tls_config(Path::new(ORIGIN_CERT_PATH), Path::new(ORIGIN_KEY_PATH))
+        .map_err(|error| stage_error("loading the origin certificate", error))?;
+    let (origin_socket, prover_origin_socket) = duplex(SELF_TEST_PIPE_CAPACITY);
+    let (service_socket, prover_service_socket) = duplex(SELF_TEST_PIPE_CAPACITY);
+
+    let balances = HashMap::from([(SELF_TEST_USER.to_string(), SELF_TEST_BALANCE)]);
+    let origin = smol::spawn(handle_connection(
+        get_app(balances),
+        server_config,
+        Compat::new(origin_socket),
+    ));
+    let service = smol::spawn(run_notarize_and_verify_stream(service_socket, options));
+
+    let outcome = prove(
+        Compat::new(prover_service_socket),
+        Compat::new(prover_origin_socket),
+        cert_bytes,
+    )
+    .await?;
+    service
+        .await
+        .map_err(|error| stage_error(VERIFY_STAGE, error))?;
+    origin
+        .await
+        .map_err(|error| stage_error("serving the test origin", error))?;
+    if !outcome.success {
+        return Err(stage_error(VERIFY_STAGE, outcome.message));
+    }
+    info!(
+        verified_fields = ?outcome.verified_fields,
+        "Startup self-test passed"
+    );
+    Ok(())
+}
+
+/// The prover side of the round: the library prover notarizes over the
+/// session stream, which then carries the proof.
+async fn prove(
+    service_socket: Compat<DuplexStream>,
+    origin_socket: Compat<DuplexStream>,
+    cert_bytes: Vec<u8>,
+) -> Result<VerificationOutcome, ProtocolError> {
+    let stream = NotarizeStream::init(service_socket, &SessionInit::new(TraceContext::new()))
+        .await
+        .map_err(|error| stage_error("opening the session", error))?;
+    let request = balance_request(SELF_TEST_USER, stream.session_nonce())
+        .map_err(|error| stage_error(MPC_STAGE, error))?;
+    let prover =
+        balance_prover(request, cert_bytes).map_err(|error| stage_error(MPC_STAGE, error))?;
+    let (prover_output, stream) = prover
+        .prove_and_reclaim(stream, origin_socket)
+        .await
+        .map_err(|error| stage_error(MPC_STAGE, error))?;
+
+    let proof = smol::unblock(move || {
+        generate_proof(
+            &prover_output.transcript_commitments,
+            &prover_output.transcript_secrets,
+            &prover_output.received,
+            PaddingConfig::new(BALANCE_COMMITMENT_LENGTH),
+        )
+    })
+    .await
+    .map_err(|error| stage_error(PROOF_STAGE, error))?;
+
+    stream
+        .submit_verification(proof)
+        .await
+        .map_err(|error| stage_error(VERIFY_STAGE, error))
+}
+
+/// A prover for `request` to the test origin that reveals the username and
+/// commits to the balance.
+pub(crate) fn balance_prover(
+    request: Request<Empty<Bytes>>,
+    cert_bytes: Vec<u8>,
+) -> tlsnotary::Result<Prover> {
+    balance_prover_builder(request, cert_bytes)?.build()
+}
+
+/// [`balance_prover`] before it is built, for tests that vary it.
+pub(crate) fn balance_prover_builder(
+    request: Request<Empty<Bytes>>,
+    cert_bytes: Vec<u8>,
+) -> tlsnotary::Result<ProverBuilder> {
+    let (tls_client_config, tls_commit_config) = prover_config(cert_bytes)?;
+    Ok(Prover::builder()
+        .tls_client_config(tls_client_config)
+        .tls_commit_config(tls_commit_config)
+        .request(request)
+        .request_reveal_config(RevealConfig::reveal_all())
+        .response_reveal_config(RevealConfig {
+            reveal_body_fields: vec![BodyFieldConfig::Quoted(".username".into())],
+            reveal_keys_commit_values: vec![KeyValueCommitConfig::with_padding(
+                ".balance".into(),
+                BALANCE_COMMITMENT_LENGTH,
+            )],
+            ..RevealConfig::new()
+        }))
+}
+
+fn prover_config(cert_bytes: Vec<u8>) -> tlsnotary::Result<(TlsClientConfig, TlsCommitConfig)> {
+    let server_name = ServerName::Dns("localhost".to_string().try_into().map_err(|error| {
+        tlsnotary::Error::InvalidInput(format!("invalid DNS server name 'localhost': {error}"))
+    })?);
+    let tls_client_config = TlsClientConfig::builder()
+        .server_name(server_name)
+        .root_store(RootCertStore {
+            roots: vec![CertificateDer(cert_bytes)],
+        })
+        .build()?;
+    let tls_commit_config = TlsCommitConfig::builder()
+        .protocol(
+            MpcTlsConfig::builder()
+                .max_sent_data(MAX_SENT_DATA)
+                .max_recv_data(MAX_RECV_DATA)
+                .build()?,
+        )
+        .build()?;
+    Ok((tls_client_config, tls_commit_config))
+}
+
+/// `GET /api/balance/{user}`, revealing `session_nonce` when one was issued.
+pub(crate) fn balance_request(
+    user: &str,
+    session_nonce: Option<&str>,
+) -> Result<Request<Empty<Bytes>>, hyper::http::Error> {
+    let mut builder =
+        Request::get(format!("/api/balance/{user}")).header("content-type", "application/json");
+    if let Some(session_nonce) = session_nonce {
+        builder = builder.header(SESSION_NONCE_HEADER, session_nonce);
+    }
+    builder
+        .header("Connection", "close")
+        .body(Empty::<Bytes>::new())
+}
+
+fn stage_error(stage: &'static str, error: impl fmt::Display) -> ProtocolError {
+    ProtocolError::SelfTest {
+        stage,
+        reason: error.to_string(),
+    }
+}
@@ -0,0 +1,114 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+use smol::{
+    Timer,
+    channel::{Receiver, Sender},
+};
+
+pub const DEFAULT_SHUTDOWN_GRACE_PERIOD: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShutdownSummary {
+    /// Sessions that finished before shutdown was requested.
+    pub completed: usize,
+    /// In-flight sessions that finished within the grace period.
+    pub drained: usize,
+    /// In-flight sessions still running when the grace period ran out. They
+    /// are not cancelled: QUIC sessions fail as their endpoint closes, TCP
+    /// sessions run on until the process exits.
+    pub unfinished: usize,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    active: AtomicUsize,
+    completed: AtomicUsize,
+    draining: AtomicBool,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct SessionTracker {
+    counters: Arc<Counters>,
+    finished: Sender<()>,
+}
+
+impl SessionTracker {
+    pub(crate) fn new() -> (Self, Receiver<()>) {
+        let (finished, finished_rx) = smol::channel::unbounded();
+        (
+            Self {
+                counters: Arc::default(),
+                finished,
+            },
+            finished_rx,
+        )
+    }
+
+    /// Registers a new session, or returns `None` once draining has started.
+    pub(crate) fn start(&self) -> Option<SessionGuard> {
+        if self.counters.draining.load(Ordering::Acquire) {
+            return None;
+        }
+        self.counters.active.fetch_add(1, Ordering::AcqRel);
+        Some(SessionGuard {
+            tracker: self.clone(),
+        })
+    }
+
+    pub(crate) async fn drain(
+        &self,
+        finished: &Receiver<()>,
+        grace_period: Duration,
+    ) -> ShutdownSummary {
+        self.counters.draining.store(true, Ordering::Release);
+        let completed = self.counters.completed.load(Ordering::Acquire);
+        let in_flight = self.counters.active.load(Ordering::Acquire);
+        // A grace period too long to represent waits for every session.
+        let deadline = Instant::now().checked_add(grace_period);
+
+        while self.counters.active.load(Ordering::Acquire) > 0 {
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                break;
+            }
+            smol::future::or(
+                async {
+                    let _ = finished.recv().await;
+                },
+                async {
+                    deadline.map_or_else(Timer::never, Timer::at).await;
+                },
+            )
+            .await;
+        }
+
+        let unfinished = self.counters.active.load(Ordering::Acquire).min(in_flight);
+        ShutdownSummary {
+            completed,
+            drained: in_flight.saturating_sub(unfinished),
+            unfinished,
+        }
+    }
+}
+
+/// Marks a session as in flight until dropped.
+#[derive(Debug)]
+pub(crate) struct SessionGuard {
+    tracker: SessionTracker,
+}
+
+impl Drop for SessionGuard {
+    fn drop(&mut self) {
+        let counters = &self.tracker.counters;
+        counters.active.fetch_sub(1, Ordering::AcqRel);
+        counters.completed.fetch_add(1, Ordering::AcqRel);
+        let _ = self.tracker.finished.try_send(());
+    }
+}
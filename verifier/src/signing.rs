@@ -0,0 +1,64 @@
+use std::{fmt, fs, path::Path};
+
+use ring::signature::{ED25519, Ed25519KeyPair, KeyPair, UnparsedPublicKey};
+
+use crate::{
+    errors::StoreError,
+    timestamp::{decode_hex, hex},
+};
+
+/// The notary's Ed25519 key, read from a file of 64 hex digits holding its
+/// seed. It signs each attestation's digest together with its timestamp.
+pub struct NotaryKey {
+    key_pair: Ed25519KeyPair,
+}
+
+impl fmt::Debug for NotaryKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NotaryKey")
+            .field("public_key", &self.public_key())
+            .finish()
+    }
+}
+
+impl NotaryKey {
+    pub fn from_seed(seed: &[u8; 32]) -> Result<Self, StoreError> {
+        let key_pair = Ed25519KeyPair::from_seed_unchecked(seed)
+            .map_err(|_| StoreError::InvalidKey("notary signing key".to_string()))?;
+        Ok(Self { key_pair })
+    }
+
+    pub fn from_hex_file(path: impl AsRef<Path>) -> Result<Self, StoreError> {
+        let path = path.as_ref();
+        let seed = decode_hex(fs::read_to_string(path)?.trim())
+            .and_then(|seed| <[u8; 32]>::try_from(seed).ok())
+            .ok_or_else(|| StoreError::InvalidKey(path.display().to_string()))?;
+        Self::from_seed(&seed)
+    }
+
+    /// Hex public key consumers check signatures against.
+    pub fn public_key(&self) -> String {
+        hex(self.key_pair.public_key().as_ref())
+    }
+
+    /// Hex signature over `context` followed by `message`.
+    pub(crate) fn sign(&self, context: &[u8], message: &[u8]) -> String {
+        hex(self.key_pair.sign(&[context, message].concat()).as_ref())
+    }
+}
+
+/// Whether hex `signature` is `public_key`'s over `context` and `message`.
+pub(crate) fn verify_signature(
+    public_key: &str,
+    context: &[u8],
+    message: &[u8],
+    signature: &str,
+) -> bool {
+    decode_hex(public_key)
+        .zip(decode_hex(signature))
+        .is_some_and(|(public_key, signature)| {
+            UnparsedPublicKey::new(&ED25519, public_key)
+                .verify(&[context, message].concat(), &signature)
+                .is_ok()
+        })
+}
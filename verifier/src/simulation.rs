@@ -0,0 +1,61 @@
+use futures::{AsyncRead, AsyncWrite};
+use serde::{Deserialize, Serialize};
+use tlsnotary::{MpcTlsConfig, TlsCommitProtocolConfig, TranscriptCommitment, VerifierOutput};
+
+use crate::{
+    errors::ProtocolError,
+    protocol::{read_json_frame, write_json_frame},
+};
+
+/// What the verifier learned from an MPC-TLS session, replayed in its place
+/// by a service with [`crate::ServiceOptions::simulate`] set. The prover
+/// sends it as one frame where the MPC-TLS traffic would go; redacted bytes
+/// are zero, as in a partial transcript.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordedSession {
+    /// `None` when the prover did not prove the server identity.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server_name: Option<String>,
+    pub sent: Vec<u8>,
+    pub received: Vec<u8>,
+    pub transcript_commitments: Vec<TranscriptCommitment>,
+}
+
+impl RecordedSession {
+    /// Records the output of a real session, e.g. one run by the tlsnotary
+    /// tests, for replay.
+    pub fn from_verifier_output(output: &VerifierOutput) -> Self {
+        Self {
+            server_name: output.server_name.clone(),
+            sent: output.transcript.sent_unsafe().to_vec(),
+            received: output.transcript.received_unsafe().to_vec(),
+            transcript_commitments: output.transcript_commitments.clone(),
+        }
+    }
+
+    /// The MPC-TLS proposal a prover would have made for this transcript,
+    /// so the usual limits and admission budget apply.
+    pub(crate) fn protocol(&self) -> Result<TlsCommitProtocolConfig, ProtocolError> {
+        let config = MpcTlsConfig::builder()
+            .max_sent_data(self.sent.len())
+            .max_recv_data(self.received.len())
+            .build()
+            .map_err(tlsnotary::Error::from)?;
+        Ok(TlsCommitProtocolConfig::Mpc(config))
+    }
+
+    pub async fn read_from<IO>(io: &mut IO) -> Result<Self, ProtocolError>
+    where
+        IO: AsyncRead + Unpin + Send,
+    {
+        read_json_frame(io).await
+    }
+
+    pub async fn write_to<IO>(&self, io: &mut IO) -> Result<(), ProtocolError>
+    where
+        IO: AsyncWrite + Unpin + Send,
+    {
+        write_json_frame(io, self).await
+    }
+}
@@ -0,0 +1,415 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tlsnotary::TranscriptCommitment;
+use uuid::Uuid;
+use zktlsn::Proof;
+
+use crate::{
+    errors::StoreError,
+    protocol::VerificationOutcome,
+    sealing::{SealedArtifact, StoreKeys},
+    signing::{NotaryKey, verify_signature},
+    stream::TrafficTotals,
+    timestamp::NotaryTimestamp,
+};
+
+const ARTIFACT_EXTENSION: &str = "json";
+const ATTESTATION_SIGNATURE_CONTEXT: &[u8] = b"zk-tlsn-example attestation signature v1\0";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttestationArtifact {
+    pub id: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub server_name: String,
+    pub request: String,
+    pub response: String,
+    pub proof: Proof,
+    pub outcome: VerificationOutcome,
+    #[serde(default)]
+    pub traffic: TrafficTotals,
+    #[serde(default)]
+    pub transcript_commitments: Vec<TranscriptCommitment>,
+    /// Absent from artifacts written before bundles carried hashes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub integrity: Option<ArtifactIntegrity>,
+    /// When MPC-TLS finished, by the verifier's clock.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_time: Option<DateTime<Utc>>,
+    /// Stamped over [`Self::digest`] once the artifact is sealed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<NotaryTimestamp>,
+    /// Hex Ed25519 signature by the notary key over the digest followed by
+    /// the JSON of the timestamp; see [`Self::sign`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notary_signature: Option<String>,
+}
+
+/// Hex Blake3 hashes of the parts of an artifact, checked when it is read
+/// back.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArtifactIntegrity {
+    pub request: String,
+    pub response: String,
+    /// Of the camelCase JSON of the commitments, as of the proof below.
+    pub transcript_commitments: String,
+    pub proof: String,
+}
+
+impl ArtifactIntegrity {
+    fn of(
+        request: &str,
+        response: &str,
+        transcript_commitments: &[TranscriptCommitment],
+        proof: &Proof,
+    ) -> Result<Self, StoreError> {
+        Ok(Self {
+            request: blake3::hash(request.as_bytes()).to_hex().to_string(),
+            response: blake3::hash(response.as_bytes()).to_hex().to_string(),
+            transcript_commitments: blake3::hash(&serde_json::to_vec(transcript_commitments)?)
+                .to_hex()
+                .to_string(),
+            proof: blake3::hash(&serde_json::to_vec(proof)?)
+                .to_hex()
+                .to_string(),
+        })
+    }
+}
+
+/// Collects what one session produces, from the notarized transcript to the
+/// proof and its outcome, into a single [`AttestationArtifact`]. The id is
+/// fixed when the session starts, so the artifact's file name is known
+/// before it is written.
+#[derive(Debug, Clone)]
+pub struct ArtifactBuilder {
+    id: Uuid,
+    created_at: DateTime<Utc>,
+    server_name: String,
+    request: String,
+    response: String,
+    transcript_commitments: Vec<TranscriptCommitment>,
+    traffic: TrafficTotals,
+    session_time: Option<DateTime<Utc>>,
+}
+
+impl ArtifactBuilder {
+    pub fn new() -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            created_at: Utc::now(),
+            server_name: String::new(),
+            request: String::new(),
+            response: String::new(),
+            transcript_commitments: Vec::new(),
+            traffic: TrafficTotals::default(),
+            session_time: None,
+        }
+    }
+
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    #[must_use]
+    pub fn notarized(
+        mut self,
+        server_name: impl Into<String>,
+        request: impl Into<String>,
+        response: impl Into<String>,
+        transcript_commitments: Vec<TranscriptCommitment>,
+    ) -> Self {
+        self.server_name = server_name.into();
+        self.request = request.into();
+        self.response = response.into();
+        self.transcript_commitments = transcript_commitments;
+        self
+    }
+
+    #[must_use]
+    pub fn traffic(mut self, traffic: TrafficTotals) -> Self {
+        self.traffic = traffic;
+        self
+    }
+
+    #[must_use]
+    pub fn session_time(mut self, session_time: DateTime<Utc>) -> Self {
+        self.session_time = Some(session_time);
+        self
+    }
+
+    /// Seals the session into its artifact with the hashes of every part.
+    pub fn finish(
+        self,
+        proof: Proof,
+        outcome: VerificationOutcome,
+    ) -> Result<AttestationArtifact, StoreError> {
+        let integrity = ArtifactIntegrity::of(
+            &self.request,
+            &self.response,
+            &self.transcript_commitments,
+            &proof,
+        )?;
+        Ok(AttestationArtifact {
+            id: self.id,
+            created_at: self.created_at,
+            server_name: self.server_name,
+            request: self.request,
+            response: self.response,
+            proof,
+            outcome,
+            traffic: self.traffic,
+            transcript_commitments: self.transcript_commitments,
+            integrity: Some(integrity),
+            session_time: self.session_time,
+            timestamp: None,
+            notary_signature: None,
+        })
+    }
+}
+
+impl Default for ArtifactBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The parts of an artifact the transparency log commits to. The outcome is
+/// left out because it carries the log index, the timestamp because it is
+/// made over the digest.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AttestedContent<'a> {
+    id: Uuid,
+    created_at: DateTime<Utc>,
+    server_name: &'a str,
+    request: &'a str,
+    response: &'a str,
+    transcript_commitments: &'a [TranscriptCommitment],
+    traffic: TrafficTotals,
+    proof: &'a Proof,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    session_time: Option<DateTime<Utc>>,
+}
+
+impl AttestationArtifact {
+    /// Blake3 hash of the camelCase JSON of `id`, `createdAt`, `serverName`,
+    /// `request`, `response`, `transcriptCommitments`, `traffic`, `proof`
+    /// and, when set, `sessionTime`, in that order.
+    pub fn digest(&self) -> Result<blake3::Hash, StoreError> {
+        let content = AttestedContent {
+            id: self.id,
+            created_at: self.created_at,
+            server_name: &self.server_name,
+            request: &self.request,
+            response: &self.response,
+            transcript_commitments: &self.transcript_commitments,
+            traffic: self.traffic,
+            proof: &self.proof,
+            session_time: self.session_time,
+        };
+        Ok(blake3::hash(&serde_json::to_vec(&content)?))
+    }
+
+    /// Signs the digest followed by the camelCase JSON of the timestamp, so
+    /// the time cannot be swapped for another without the notary key.
+    pub fn sign(&mut self, key: &NotaryKey) -> Result<(), StoreError> {
+        let message = self.signed_message()?;
+        self.notary_signature = Some(key.sign(ATTESTATION_SIGNATURE_CONTEXT, &message));
+        Ok(())
+    }
+
+    /// Checks [`Self::notary_signature`] against the notary's hex public key;
+    /// unsigned artifacts fail.
+    pub fn verify_signature(&self, public_key: &str) -> Result<(), StoreError> {
+        let message = self.signed_message()?;
+        self.notary_signature
+            .as_deref()
+            .filter(|signature| {
+                verify_signature(
+                    public_key,
+                    ATTESTATION_SIGNATURE_CONTEXT,
+                    &message,
+                    signature,
+                )
+            })
+            .map(|_| ())
+            .ok_or(StoreError::Integrity {
+                id: self.id,
+                part: "notary signature",
+            })
+    }
+
+    fn signed_message(&self) -> Result<Vec<u8>, StoreError> {
+        let mut message = self.digest()?.as_bytes().to_vec();
+        message.extend(serde_json::to_vec(&self.timestamp)?);
+        Ok(message)
+    }
+
+    /// Checks the parts against the recorded [`ArtifactIntegrity`] and the
+    /// timestamp against the digest; artifacts without them pass.
+    pub fn check_integrity(&self) -> Result<(), StoreError> {
+        if let Some(timestamp) = &self.timestamp
+            && timestamp.verify(&self.digest()?).is_err()
+        {
+            return Err(StoreError::Integrity {
+                id: self.id,
+                part: "timestamp",
+            });
+        }
+        let Some(recorded) = &self.integrity else {
+            return Ok(());
+        };
+        let actual = ArtifactIntegrity::of(
+            &self.request,
+            &self.response,
+            &self.transcript_commitments,
+            &self.proof,
+        )?;
+        let part = if actual.request != recorded.request {
+            "request"
+        } else if actual.response != recorded.response {
+            "response"
+        } else if actual.transcript_commitments != recorded.transcript_commitments {
+            "transcript commitments"
+        } else if actual.proof != recorded.proof {
+            "proof"
+        } else {
+            return Ok(());
+        };
+        Err(StoreError::Integrity { id: self.id, part })
+    }
+}
+
+pub trait ArtifactStore: Send + Sync {
+    fn put(&self, artifact: &AttestationArtifact) -> Result<(), StoreError>;
+
+    fn get(&self, id: Uuid) -> Result<AttestationArtifact, StoreError>;
+
+    fn list(&self) -> Result<Vec<Uuid>, StoreError>;
+
+    /// Persists any buffered artifacts; called once during service shutdown.
+    fn flush(&self) -> Result<(), StoreError> {
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FilesystemArtifactStore {
+    root: PathBuf,
+    keys: Option<StoreKeys>,
+}
+
+impl FilesystemArtifactStore {
+    pub fn new(root: impl Into<PathBuf>) -> Result<Self, StoreError> {
+        let root = root.into();
+        fs::create_dir_all(&root)?;
+        Ok(Self { root, keys: None })
+    }
+
+    /// Encrypts artifacts as they are written. Plaintext artifacts already
+    /// in the directory are refused by `get` until [`Self::rewrap`] seals
+    /// them.
+    #[must_use]
+    pub fn with_keys(mut self, keys: StoreKeys) -> Self {
+        self.keys = Some(keys);
+        self
+    }
+
+    /// Re-wraps the data keys of artifacts sealed under an older store key
+    /// with the current one and seals plaintext artifacts, returning how
+    /// many were rewritten. Older keys can be dropped from the key file
+    /// afterwards.
+    pub fn rewrap(&self) -> Result<usize, StoreError> {
+        let Some(keys) = &self.keys else {
+            return Ok(0);
+        };
+        let mut rewritten = 0;
+        for id in self.list()? {
+            let bytes = fs::read(self.artifact_path(id))?;
+            let sealed = match serde_json::from_slice::<SealedArtifact>(&bytes) {
+                Ok(sealed) if sealed.key_id == keys.current_id() => continue,
+                Ok(sealed) => keys.rewrap(id, &sealed)?,
+                Err(_) => {
+                    let artifact: AttestationArtifact = serde_json::from_slice(&bytes)?;
+                    artifact.check_integrity()?;
+                    keys.seal(id, &bytes)?
+                }
+            };
+            self.write(id, &serde_json::to_vec_pretty(&sealed)?)?;
+            rewritten += 1;
+        }
+        Ok(rewritten)
+    }
+
+    fn artifact_path(&self, id: Uuid) -> PathBuf {
+        self.root
+            .join(id.to_string())
+            .with_extension(ARTIFACT_EXTENSION)
+    }
+
+    fn write(&self, id: Uuid, bytes: &[u8]) -> Result<(), StoreError> {
+        let path = self.artifact_path(id);
+        let staging = path.with_extension("tmp");
+        fs::write(&staging, bytes)?;
+        fs::rename(staging, path)?;
+        Ok(())
+    }
+}
+
+impl ArtifactStore for FilesystemArtifactStore {
+    fn put(&self, artifact: &AttestationArtifact) -> Result<(), StoreError> {
+        let json = serde_json::to_vec_pretty(artifact)?;
+        match &self.keys {
+            Some(keys) => self.write(
+                artifact.id,
+                &serde_json::to_vec_pretty(&keys.seal(artifact.id, &json)?)?,
+            ),
+            None => self.write(artifact.id, &json),
+        }
+    }
+
+    fn get(&self, id: Uuid) -> Result<AttestationArtifact, StoreError> {
+        let path = self.artifact_path(id);
+        if !path.exists() {
+            return Err(StoreError::NotFound(id));
+        }
+        let bytes = fs::read(path)?;
+        let artifact: AttestationArtifact = match serde_json::from_slice::<SealedArtifact>(&bytes) {
+            Ok(sealed) => {
+                let keys = self.keys.as_ref().ok_or(StoreError::Sealed(id))?;
+                serde_json::from_slice(&keys.open(id, &sealed)?)?
+            }
+            Err(_) if self.keys.is_some() => return Err(StoreError::Unsealed(id)),
+            Err(_) => serde_json::from_slice(&bytes)?,
+        };
+        artifact.check_integrity()?;
+        Ok(artifact)
+    }
+
+    fn list(&self) -> Result<Vec<Uuid>, StoreError> {
+        let mut ids = fs::read_dir(&self.root)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<Vec<_>, _>>()?
+            .iter()
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|ext| ext == ARTIFACT_EXTENSION)
+            })
+            .filter_map(|path| artifact_id(path))
+            .collect::<Vec<_>>();
+        ids.sort();
+        Ok(ids)
+    }
+}
+
+fn artifact_id(path: &Path) -> Option<Uuid> {
+    path.file_stem()
+        .and_then(|stem| stem.to_str())
+        .and_then(|stem| Uuid::parse_str(stem).ok())
+}
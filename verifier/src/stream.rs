@@ -0,0 +1,164 @@
+use std::{
+    io,
+    pin::Pin,
+    sync::{
+        Arc, OnceLock,
+        atomic::{AtomicU64, Ordering},
+    },
+    task::{Context, Poll},
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+use crate::errors::ProtocolError;
+
+/// Caps on the bytes exchanged with the prover in one session. MPC-TLS
+/// traffic grows with the negotiated plaintext limits but at many bytes per
+/// plaintext byte, so the caps are configured on their own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct TrafficLimits {
+    pub max_bytes_received: u64,
+    pub max_bytes_sent: u64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrafficTotals {
+    /// Bytes read from the prover.
+    pub bytes_received: u64,
+    /// Bytes written to the prover.
+    pub bytes_sent: u64,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    received: AtomicU64,
+    sent: AtomicU64,
+    /// Direction and cap of the first limit a stream stopped at.
+    exceeded: OnceLock<(&'static str, u64)>,
+}
+
+/// Counts session traffic per direction through the [`MeteredStream`]s it
+/// hands out and fails them once a [`TrafficLimits`] cap would be crossed.
+#[derive(Debug, Clone)]
+pub struct TrafficMeter {
+    counters: Arc<Counters>,
+    limits: Option<TrafficLimits>,
+}
+
+impl TrafficMeter {
+    pub fn new(limits: Option<TrafficLimits>) -> Self {
+        Self {
+            counters: Arc::default(),
+            limits,
+        }
+    }
+
+    pub fn wrap<S>(&self, inner: S) -> MeteredStream<S> {
+        MeteredStream {
+            inner,
+            meter: self.clone(),
+        }
+    }
+
+    pub fn totals(&self) -> TrafficTotals {
+        TrafficTotals {
+            bytes_received: self.counters.received.load(Ordering::Relaxed),
+            bytes_sent: self.counters.sent.load(Ordering::Relaxed),
+        }
+    }
+
+    /// The limit a wrapped stream stopped at, if any. The MPC layers wrap the
+    /// I/O error they see, so callers check this to report the cause.
+    pub fn exceeded(&self) -> Option<ProtocolError> {
+        self.counters
+            .exceeded
+            .get()
+            .map(|&(direction, limit)| ProtocolError::TrafficLimitExceeded { direction, limit })
+    }
+
+    fn stop(&self, direction: &'static str, limit: u64) -> io::Error {
+        let _ = self.counters.exceeded.set((direction, limit));
+        io::Error::other(ProtocolError::TrafficLimitExceeded { direction, limit }.to_string())
+    }
+}
+
+/// Stream wrapper that feeds a [`TrafficMeter`]. It holds no buffer of its
+/// own, so backpressure from either side passes straight through.
+pub struct MeteredStream<S> {
+    inner: S,
+    meter: TrafficMeter,
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for MeteredStream<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let limit = self.meter.limits.map(|limits| limits.max_bytes_received);
+        // One byte past the cap, so a close right at the cap still reads as
+        // the end of the stream while any further data fails the session.
+        let allowed = limit.map_or(buf.remaining(), |limit| {
+            let received = self.meter.counters.received.load(Ordering::Relaxed);
+            usize::try_from(limit.saturating_sub(received).saturating_add(1))
+                .map_or(buf.remaining(), |allowed| allowed.min(buf.remaining()))
+        });
+        let mut capped = ReadBuf::new(buf.initialize_unfilled_to(allowed));
+        let result = Pin::new(&mut self.inner).poll_read(cx, &mut capped);
+        let read = capped.filled().len();
+        if let Poll::Ready(Ok(())) = result {
+            let total = count(&self.meter.counters.received, read);
+            if let Some(limit) = limit
+                && total > limit
+            {
+                // A failed read hands back no data.
+                return Poll::Ready(Err(self.meter.stop("received", limit)));
+            }
+        }
+        buf.advance(read);
+        result
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for MeteredStream<S> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        if let Some(limits) = self.meter.limits {
+            let sent = self.meter.counters.sent.load(Ordering::Relaxed);
+            let len = u64::try_from(buf.len()).unwrap_or(u64::MAX);
+            if sent.saturating_add(len) > limits.max_bytes_sent {
+                return Poll::Ready(Err(self.meter.stop("sent", limits.max_bytes_sent)));
+            }
+        }
+        let result = Pin::new(&mut self.inner).poll_write(cx, buf);
+        if let Poll::Ready(Ok(written)) = result {
+            count(&self.meter.counters.sent, written);
+        }
+        result
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+/// Adds `bytes` to `counter`, saturating, and returns the new total.
+fn count(counter: &AtomicU64, bytes: usize) -> u64 {
+    let bytes = u64::try_from(bytes).unwrap_or(u64::MAX);
+    let previous = match counter.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |total| {
+        Some(total.saturating_add(bytes))
+    }) {
+        Ok(previous) | Err(previous) => previous,
+    };
+    previous.saturating_add(bytes)
+}
@@ -0,0 +1,3309 @@
+//! Test utilities for the verifier service
+//!
+//! Helpers for driving the job queue and its webhooks without a prover.
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use smol::Timer;
+use uuid::Uuid;
+use zktlsn::{Proof, ProofMetadata};
+
+use crate::{
+    ArtifactBuilder, AttestationArtifact, JobQueue, JobStatus, TrafficTotals, VerificationOutcome,
+};
+
+pub const TEST_WEBHOOK_SECRET: [u8; 32] = *b"verifier-test-webhook-secret!!!!";
+const JOB_WAIT: Duration = Duration::from_secs(10);
+
+/// Outcome returned by a job verifying `server_name`.
+pub fn test_outcome(server_name: &str) -> VerificationOutcome {
+    VerificationOutcome::success(
+        server_name.to_string(),
+        vec![".balance".to_string()],
+        "verified".to_string(),
+    )
+}
+
+/// A sealed artifact without commitments, which need a notarized session.
+pub fn test_artifact(
+    server_name: &str,
+    request: &str,
+    response: &str,
+    proof_bytes: Vec<u8>,
+    traffic: TrafficTotals,
+    session_time: DateTime<Utc>,
+) -> AttestationArtifact {
+    let proof = Proof::new(
+        vec![1; 32],
+        proof_bytes,
+        ProofMetadata {
+            circuit_id: "test".to_string(),
+            circuit_version: "0".to_string(),
+            created_at: session_time,
+            commitment_ids: Vec::new(),
+            expires_at: None,
+        },
+    );
+    ArtifactBuilder::new()
+        .notarized(server_name, request, response, Vec::new())
+        .traffic(traffic)
+        .session_time(session_time)
+        .finish(proof, test_outcome(server_name))
+        .expect("test artifact should seal")
+}
+
+/// `text` with every byte of `secret` replaced by `filler`, the way a
+/// prover's redaction leaves it in the verifier's transcript.
+pub fn redact_with(text: &str, secret: &str, filler: u8) -> Vec<u8> {
+    let start = text.find(secret).expect("secret should occur in the text");
+    text.bytes()
+        .enumerate()
+        .map(|(index, byte)| {
+            if (start..start + secret.len()).contains(&index) {
+                filler
+            } else {
+                byte
+            }
+        })
+        .collect()
+}
+
+/// Polls the job until it completes, panicking after [`JOB_WAIT`].
+pub async fn wait_for_outcome(queue: &JobQueue, id: Uuid) -> VerificationOutcome {
+    let poll = async {
+        loop {
+            match queue.status(id).await {
+                Some(JobStatus::Completed { outcome }) => return outcome,
+                Some(JobStatus::Queued | JobStatus::Running) => {
+                    Timer::after(Duration::from_millis(10)).await;
+                }
+                None => panic!("job {id} disappeared before completing"),
+            }
+        }
+    };
+    smol::future::or(poll, async {
+        Timer::after(JOB_WAIT).await;
+        panic!("job {id} did not complete within {JOB_WAIT:?}")
+    })
+    .await
+}
+
+#[cfg(test)]
+mod integration {
+    use std::{
+        collections::{BTreeMap, HashMap},
+        net::{Ipv4Addr, Ipv6Addr, SocketAddr},
+        path::Path,
+    };
+
+    use async_compat::Compat;
+    use axum::{
+        Router,
+        body::{Body, Bytes},
+        http::{HeaderMap, Request, StatusCode, header},
+        routing::post,
+    };
+    use chrono::TimeDelta;
+    use http_body_util::BodyExt;
+    use proptest::prelude::*;
+    use ring::{
+        digest::{SHA512, digest},
+        signature::{Ed25519KeyPair, KeyPair},
+    };
+    use serde_json::json;
+    use shared::{TestTlsConfig, get_or_create_test_quic_config, get_or_create_test_tls_config};
+    use smol::net::{TcpListener, TcpStream};
+    use tlsnotary::{MpcTlsConfig, TlsCommitProtocolConfig};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tower::ServiceExt;
+    use zktlsn::{PaddingConfig, ThresholdClaim, generate_predicate_proof, generate_proof};
+
+    use super::*;
+    use crate::{
+        Admin, AdminConfig, AdmissionControl, AdmissionPermit, AdmissionStatus, ArtifactStore,
+        ClientError, FieldDisclosure, FilesystemArtifactStore, HealthStatus, IdempotencyKey,
+        InclusionProof, JobQueueConfig, JobResult, Listener, ListenerConfig, ListenerMetrics,
+        ListenerStats, ListenerTransport, LoadShedder, LoadSheddingConfig, LogRoot, MAX_RECV_DATA,
+        MAX_SENT_DATA, NotarizationPolicy, NotarizeStream, NotaryConfig, NotaryKey,
+        NotaryTimestamp, PolicySet, ProofMessage, ProtocolError, RoughtimeAttestation,
+        ServiceOptions, SessionInit, SessionPhase, SessionSummary, SharedTranscript,
+        ShutdownSummary, StoreError, StoreKeys, TimestampError, TraceContext, TrafficLimits,
+        TrafficMeter, TransparencyLog,
+        jobs::SIGNATURE_HEADER,
+        protocol::{
+            NonceBinding, ORIGIN_CERT_PATH, ORIGIN_KEY_PATH, parse_transcript, read_json_frame,
+            response_field_disclosures, run_notarize_and_verify_stream,
+        },
+        rpc::{
+            Framing, JSONRPC_VERSION, METHOD_SESSION_INIT, METHOD_SESSION_NOTARIZE,
+            METHOD_SESSION_SUBMIT, METHOD_SESSION_VERIFY, ProofRequest, SERVER_BUSY_CODE,
+            SESSION_NONCE_HEADER, SubmitParams, SubmitResult, VERIFICATION_FAILED_CODE,
+        },
+        run_self_test,
+        selftest::{
+            BALANCE_COMMITMENT_LENGTH, balance_prover, balance_prover_builder, balance_request,
+        },
+        serve_listeners_until, serve_router,
+        shutdown::SessionTracker,
+        timestamp::{attestation_nonce, decode_hex, parse_message, verify_response},
+        webhook_signature,
+    };
+
+    const TEST_ADMIN_TOKEN: &str = "admin-test-token-0123456789abcdef";
+
+    fn loopback_queue(ttl_secs: u64) -> std::sync::Arc<JobQueue> {
+        JobQueue::start(
+            JobQueueConfig {
+                job_ttl_secs: ttl_secs,
+                webhook_allowed_hosts: vec!["127.0.0.1".to_string()],
+                ..JobQueueConfig::default()
+            },
+            TEST_WEBHOOK_SECRET,
+        )
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(16))]
+
+        #[test]
+        fn test_idempotency_key_returns_job_only_for_same_submission(
+            key in "[a-z0-9-]{1,32}",
+            payload in proptest::collection::vec(any::<u8>(), 0..64),
+            other in proptest::collection::vec(any::<u8>(), 0..64),
+            server_name in "[a-z]{1,16}\\.example",
+        ) {
+            prop_assume!(payload != other);
+            smol::block_on(async {
+                let queue = loopback_queue(60);
+                let outcome = test_outcome(&server_name);
+                let expected = outcome.clone();
+                let id = queue
+                    .enqueue(None, Some(IdempotencyKey::new(key.clone(), &payload)), move || outcome)
+                    .await
+                    .expect("first submission should be queued");
+
+                let resubmitted = queue
+                    .enqueue(
+                        None,
+                        Some(IdempotencyKey::new(key.clone(), &payload)),
+                        || panic!("a resubmission must not verify again"),
+                    )
+                    .await
+                    .expect("resubmission should return the existing job");
+                prop_assert_eq!(resubmitted, id);
+
+                let conflict = queue
+                    .enqueue(
+                        None,
+                        Some(IdempotencyKey::new(key.clone(), &other)),
+                        || panic!("a conflicting submission must not verify"),
+                    )
+                    .await;
+                prop_assert!(
+                    matches!(&conflict, Err(ProtocolError::IdempotencyConflict(reused)) if *reused == key),
+                    "expected an idempotency conflict, got {:?}",
+                    conflict
+                );
+
+                let outcome = wait_for_outcome(&queue, id).await;
+                prop_assert_eq!(outcome.server_name, expected.server_name);
+                prop_assert!(outcome.success);
+                Ok(())
+            })?;
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn test_transcripts_with_non_utf8_filler_are_parsed(
+            username in "[a-z]{3,12}",
+            token in "[A-Za-z0-9]{8,24}",
+            balance in 0u64..1_000_000,
+            filler in 0xF8u8..=0xFF,
+        ) {
+            let request = format!(
+                "GET /api/balance/{username} HTTP/1.1\r\nhost: localhost\r\nauthorization: Bearer {token}\r\n\r\n"
+            );
+            let response = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\n\r\n{{\"username\":\"{username}\",\"balance\":{balance}}}"
+            );
+            let sent = redact_with(&request, &format!("Bearer {token}"), filler);
+            let received = redact_with(&response, &balance.to_string(), filler);
+            let transcript = SharedTranscript::new(&sent, &received);
+
+            prop_assert_eq!(transcript.sent(), sent.as_slice());
+            prop_assert_eq!(transcript.received(), received.as_slice());
+            let (sent_text, received_text) = (transcript.sent_text(), transcript.received_text());
+            prop_assert_eq!(sent_text.len(), sent.len());
+            prop_assert_eq!(received_text.len(), received.len());
+            prop_assert!(parser::redacted::Request::from_bytes(&sent).is_err());
+
+            let (parsed_request, parsed_response) =
+                parse_transcript(&transcript).expect("filler should not stop parsing");
+            let host = parsed_request
+                .headers
+                .get("host")
+                .and_then(|headers| headers.first())
+                .and_then(|header| header.value.clone())
+                .expect("host header should be revealed");
+            prop_assert_eq!(sent_text.get(host), Some("localhost"));
+            let authorization = parsed_request
+                .headers
+                .get("authorization")
+                .and_then(|headers| headers.first())
+                .expect("authorization header name should be revealed");
+            prop_assert!(authorization.value.is_none());
+            prop_assert_eq!(received_text.get(parsed_response.status_code), Some("200"));
+        }
+    }
+
+    type ArtifactEdit = (&'static str, fn(&mut AttestationArtifact));
+
+    const ATTESTED_EDITS: [ArtifactEdit; 10] = [
+        ("id", |artifact| artifact.id = Uuid::new_v4()),
+        ("created_at", |artifact| {
+            artifact.created_at += chrono::Duration::seconds(1);
+        }),
+        ("server_name", |artifact| artifact.server_name.push('x')),
+        ("request", |artifact| artifact.request.push('x')),
+        ("response", |artifact| artifact.response.push('x')),
+        ("bytes_received", |artifact| {
+            artifact.traffic.bytes_received = artifact.traffic.bytes_received.wrapping_add(1);
+        }),
+        ("bytes_sent", |artifact| {
+            artifact.traffic.bytes_sent = artifact.traffic.bytes_sent.wrapping_add(1);
+        }),
+        ("proof", |artifact| artifact.proof.proof.push(0)),
+        ("verification_key", |artifact| {
+            artifact.proof.verification_key.push(0);
+        }),
+        ("session_time", |artifact| artifact.session_time = None),
+    ];
+
+    proptest! {
+        #[test]
+        fn test_digest_commits_to_every_attested_field(
+            server_name in "[a-z]{1,16}\\.example",
+            request in "[ -~]{0,64}",
+            response in "[ -~]{0,64}",
+            proof_bytes in proptest::collection::vec(any::<u8>(), 0..64),
+            bytes_received in any::<u64>(),
+            bytes_sent in any::<u64>(),
+            session_secs in 0i64..4_000_000_000,
+        ) {
+            let session_time = DateTime::from_timestamp(session_secs, 0)
+                .expect("generated timestamp should be in range");
+            let artifact = test_artifact(
+                &server_name,
+                &request,
+                &response,
+                proof_bytes,
+                TrafficTotals { bytes_received, bytes_sent },
+                session_time,
+            );
+            let digest = artifact.digest().expect("digest should hash");
+
+            for (field, edit) in ATTESTED_EDITS {
+                let mut edited = artifact.clone();
+                edit(&mut edited);
+                prop_assert_ne!(
+                    edited.digest().expect("digest should hash"),
+                    digest,
+                    "digest should change with {}",
+                    field
+                );
+            }
+
+            let mut logged = artifact.clone();
+            logged.outcome.log_index = Some(bytes_sent);
+            prop_assert_eq!(logged.digest().expect("digest should hash"), digest);
+        }
+    }
+
+    const ROUGHTIME_LONG_TERM_SEED: [u8; 32] = *b"roughtime-fixture-long-term-key!";
+    const ROUGHTIME_DELEGATED_SEED: [u8; 32] = *b"roughtime-fixture-delegated-key!";
+    /// 2026-10-16T12:00:00Z, in microseconds.
+    const ROUGHTIME_MIDPOINT: u64 = 1_792_152_000_000_000;
+    const ROUGHTIME_DAY_MICROS: u64 = 86_400_000_000;
+    const ROUGHTIME_RADIUS_MICROS: u32 = 1_000_000;
+    /// The attestation digest the recorded response stamps is this
+    /// message's Blake3 hash.
+    const ROUGHTIME_FIXTURE_MESSAGE: &[u8] = b"roughtime fixture";
+    const ROUGHTIME_FIXTURE_PUBLIC_KEY: &str =
+        "784c983481184e7adc8a973e76ecc1f90f55b013e7e65e4ee162b4bd07a49012";
+    /// A response in Google's Roughtime wire format, signed by a delegation
+    /// of the long-term key above, for the nonce of the fixture digest.
+    const ROUGHTIME_FIXTURE_RESPONSE: &str = concat!(
+        "050000004000000040000000a40000003c01000053494700504154485352455043455254494e4458",
+        "6aec94e48e5bd0ca3e050bc84a115bf5705179a31b431ce49ada1a79965d9f314fb9a34c9b79c58f",
+        "f586d5326649cb6908cd16cf4534c4a1f054a378f3b0740d03000000040000000c00000052414449",
+        "4d494450524f4f5440420f0000f057e7f35d0600274548e15a7ece47e7c6906945fc4f6ef8ca726c",
+        "a1a89e35b9184cc449635f42462568b8e7f2e0700037112e8ba99b4eab05cd6630a8d1e02b8c5994",
+        "f669832702000000400000005349470044454c45ea6a089abf29140a3b7023b2df7fecd451b86c2a",
+        "f05452a9a707d166496bcdb4eff58b594aa28cdc492c5c77f58242d27a5a7e28a7ec5938cd8c3c7e",
+        "1cbe75010300000020000000280000005055424b4d494e544d4158549d519c668723735d53a6d986",
+        "d81e9cb19bbd2016995fd9174741da8a332d7c0d009080c9df5d060000502f05085e060000000000",
+    );
+    const NOTARY_TEST_SEED: [u8; 32] = *b"verifier-test-notary-signing-key";
+
+    fn roughtime_message(fields: &[([u8; 4], Vec<u8>)]) -> Vec<u8> {
+        let count = u32::try_from(fields.len()).expect("tag count should fit a u32");
+        let mut message = count.to_le_bytes().to_vec();
+        let mut offset = 0u32;
+        for (_, value) in fields.split_last().map_or(&[][..], |(_, rest)| rest) {
+            offset = u32::try_from(value.len())
+                .ok()
+                .and_then(|len| offset.checked_add(len))
+                .expect("value offset should fit a u32");
+            message.extend_from_slice(&offset.to_le_bytes());
+        }
+        for (tag, _) in fields {
+            message.extend_from_slice(tag);
+        }
+        for (_, value) in fields {
+            message.extend_from_slice(value);
+        }
+        message
+    }
+
+    /// A response like the fixture's, signed by the same keys for `nonce`,
+    /// with the given midpoint and delegation validity.
+    fn roughtime_response(nonce: &[u8; 64], midpoint: u64, mint: u64, maxt: u64) -> Vec<u8> {
+        let long_term = Ed25519KeyPair::from_seed_unchecked(&ROUGHTIME_LONG_TERM_SEED)
+            .expect("long-term seed should be an Ed25519 key");
+        let delegated = Ed25519KeyPair::from_seed_unchecked(&ROUGHTIME_DELEGATED_SEED)
+            .expect("delegated seed should be an Ed25519 key");
+        let delegation = roughtime_message(&[
+            (*b"PUBK", delegated.public_key().as_ref().to_vec()),
+            (*b"MINT", mint.to_le_bytes().to_vec()),
+            (*b"MAXT", maxt.to_le_bytes().to_vec()),
+        ]);
+        let root = digest(&SHA512, &[[0x00].as_slice(), nonce].concat());
+        let signed_response = roughtime_message(&[
+            (*b"RADI", ROUGHTIME_RADIUS_MICROS.to_le_bytes().to_vec()),
+            (*b"MIDP", midpoint.to_le_bytes().to_vec()),
+            (*b"ROOT", root.as_ref().to_vec()),
+        ]);
+        let delegation_signature = long_term.sign(
+            &[
+                b"RoughTime v1 delegation signature--\0".as_slice(),
+                &delegation,
+            ]
+            .concat(),
+        );
+        let response_signature = delegated.sign(
+            &[
+                b"RoughTime v1 response signature\0".as_slice(),
+                &signed_response,
+            ]
+            .concat(),
+        );
+        let cert = roughtime_message(&[
+            (*b"SIG\0", delegation_signature.as_ref().to_vec()),
+            (*b"DELE", delegation),
+        ]);
+        roughtime_message(&[
+            (*b"SIG\0", response_signature.as_ref().to_vec()),
+            (*b"PATH", Vec::new()),
+            (*b"SREP", signed_response),
+            (*b"CERT", cert),
+            (*b"INDX", 0u32.to_le_bytes().to_vec()),
+        ])
+    }
+
+    fn fixture_response() -> Vec<u8> {
+        decode_hex(ROUGHTIME_FIXTURE_RESPONSE).expect("fixture response should be hex")
+    }
+
+    fn fixture_public_key() -> Vec<u8> {
+        decode_hex(ROUGHTIME_FIXTURE_PUBLIC_KEY).expect("fixture public key should be hex")
+    }
+
+    fn fixture_nonce() -> [u8; 64] {
+        attestation_nonce(&blake3::hash(ROUGHTIME_FIXTURE_MESSAGE))
+    }
+
+    fn fixture_midpoint() -> DateTime<Utc> {
+        i64::try_from(ROUGHTIME_MIDPOINT)
+            .ok()
+            .and_then(DateTime::from_timestamp_micros)
+            .expect("fixture midpoint should be in range")
+    }
+
+    fn fixture_timestamp(local_time: DateTime<Utc>) -> NotaryTimestamp {
+        NotaryTimestamp {
+            local_time,
+            roughtime: Some(RoughtimeAttestation {
+                server: "roughtime.example.com:2002".to_string(),
+                public_key: ROUGHTIME_FIXTURE_PUBLIC_KEY.to_string(),
+                midpoint: fixture_midpoint(),
+                radius_micros: ROUGHTIME_RADIUS_MICROS,
+                response: ROUGHTIME_FIXTURE_RESPONSE.to_string(),
+            }),
+        }
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(256))]
+
+        #[test]
+        fn test_roughtime_parsing_never_panics(
+            bytes in proptest::collection::vec(any::<u8>(), 0..512),
+            cut in any::<prop::sample::Index>(),
+            flip in any::<prop::sample::Index>(),
+            mask in 1u8..,
+        ) {
+            let _ = parse_message(&bytes);
+            let _ = verify_response(&fixture_public_key(), &fixture_nonce(), &bytes);
+
+            let response = fixture_response();
+            let truncated = response.get(..cut.index(response.len())).unwrap_or_default();
+            prop_assert!(verify_response(&fixture_public_key(), &fixture_nonce(), truncated).is_err());
+
+            // Only the unsigned index may change without breaking a signature,
+            // and it cannot move the signed time.
+            let mut flipped = response.clone();
+            if let Some(byte) = flipped.get_mut(flip.index(response.len())) {
+                *byte ^= mask;
+            }
+            if let Ok((midpoint, _)) = verify_response(&fixture_public_key(), &fixture_nonce(), &flipped) {
+                prop_assert_eq!(midpoint, fixture_midpoint());
+            }
+        }
+    }
+
+    fn store_keys(current: &str, keys: &[(&str, [u8; 32])]) -> StoreKeys {
+        StoreKeys::new(
+            current,
+            keys.iter()
+                .map(|(id, key)| (id.to_string(), *key))
+                .collect::<BTreeMap<_, _>>(),
+        )
+        .expect("current key should be among the keys")
+    }
+
+    fn stored_artifact(server_name: &str) -> AttestationArtifact {
+        test_artifact(
+            server_name,
+            "GET / HTTP/1.1\r\n\r\n",
+            "HTTP/1.1 200 OK\r\n\r\n",
+            vec![7; 16],
+            TrafficTotals::default(),
+            Utc::now(),
+        )
+    }
+
+    fn stored_digest(store: &FilesystemArtifactStore, id: Uuid) -> Result<String, StoreError> {
+        store
+            .get(id)
+            .and_then(|artifact| artifact.digest())
+            .map(|digest| digest.to_hex().to_string())
+    }
+
+    async fn admin_request(
+        admin: &std::sync::Arc<Admin>,
+        method: &str,
+        uri: &str,
+        authorization: Option<&str>,
+    ) -> (StatusCode, Bytes) {
+        let mut request = Request::builder().method(method).uri(uri);
+        if let Some(authorization) = authorization {
+            request = request.header(header::AUTHORIZATION, authorization);
+        }
+        let response = std::sync::Arc::clone(admin)
+            .router()
+            .oneshot(request.body(Body::empty()).expect("request should build"))
+            .await
+            .expect("admin router should respond");
+        let status = response.status();
+        let body = response
+            .into_body()
+            .collect()
+            .await
+            .expect("admin response body should be readable")
+            .to_bytes();
+        (status, body)
+    }
+
+    fn config_from_toml(contents: &str) -> Result<NotaryConfig, ProtocolError> {
+        let path = std::env::temp_dir().join(format!("verifier-config-{}.toml", Uuid::new_v4()));
+        std::fs::write(&path, contents).expect("config file should be written");
+        let config = NotaryConfig::from_toml_file(&path);
+        std::fs::remove_file(&path).expect("config file should be removed");
+        config
+    }
+
+    fn config_with_admin_token(token: &str) -> Result<NotaryConfig, ProtocolError> {
+        config_from_toml(&format!(
+            "[admin]\naddr = \"127.0.0.1:5003\"\ntoken = \"{token}\"\n"
+        ))
+    }
+
+    fn appended_log(log: TransparencyLog, digests: &[[u8; 32]]) -> (TransparencyLog, Vec<Uuid>) {
+        let ids = digests
+            .iter()
+            .enumerate()
+            .map(|(index, digest)| {
+                let id = Uuid::new_v4();
+                let leaf_index = log
+                    .append(id, blake3::Hash::from_bytes(*digest))
+                    .expect("in-memory append should succeed");
+                assert_eq!(
+                    usize::try_from(leaf_index).expect("leaf index should fit"),
+                    index
+                );
+                id
+            })
+            .collect();
+        (log, ids)
+    }
+
+    /// Copies of `proof` with one part altered, none of which may verify.
+    fn tampered_proofs(proof: &InclusionProof, other_digest: &str) -> Vec<InclusionProof> {
+        let mut tampered = vec![
+            InclusionProof {
+                digest: other_digest.to_string(),
+                ..proof.clone()
+            },
+            InclusionProof {
+                root_hash: other_digest.to_string(),
+                ..proof.clone()
+            },
+            InclusionProof {
+                leaf_index: proof.tree_size,
+                ..proof.clone()
+            },
+            InclusionProof {
+                audit_path: [proof.audit_path.clone(), vec![other_digest.to_string()]].concat(),
+                ..proof.clone()
+            },
+        ];
+        for position in 0..proof.audit_path.len() {
+            let mut audit_path = proof.audit_path.clone();
+            audit_path.remove(position);
+            tampered.push(InclusionProof {
+                audit_path: audit_path.clone(),
+                ..proof.clone()
+            });
+            audit_path.insert(position, other_digest.to_string());
+            tampered.push(InclusionProof {
+                audit_path,
+                ..proof.clone()
+            });
+        }
+        if let Some(leaf_index) = proof
+            .leaf_index
+            .checked_add(1)
+            .and_then(|next| next.checked_rem(proof.tree_size))
+            .filter(|next| *next != proof.leaf_index)
+        {
+            tampered.push(InclusionProof {
+                leaf_index,
+                ..proof.clone()
+            });
+        }
+        tampered
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(32))]
+
+        #[test]
+        fn test_inclusion_proofs_verify_for_every_leaf(
+            digests in proptest::collection::hash_set(any::<[u8; 32]>(), 1..48),
+            other in any::<[u8; 32]>(),
+        ) {
+            let digests = digests.into_iter().collect::<Vec<_>>();
+            prop_assume!(!digests.contains(&other));
+            let (log, ids) = appended_log(TransparencyLog::new(), &digests);
+            let root = log.root();
+            prop_assert_eq!(
+                usize::try_from(root.tree_size).expect("tree size should fit"),
+                digests.len()
+            );
+            let other = blake3::Hash::from_bytes(other).to_hex().to_string();
+
+            for id in &ids {
+                let proof = log.inclusion_proof(*id).expect("appended id should have a proof");
+                prop_assert_eq!(proof.tree_size, root.tree_size);
+                prop_assert_eq!(&proof.root_hash, &root.root_hash);
+                prop_assert!(proof.verify(), "proof for leaf {} should verify", proof.leaf_index);
+                for tampered in tampered_proofs(&proof, &other) {
+                    prop_assert!(!tampered.verify(), "tampered proof verified: {:?}", tampered);
+                }
+            }
+            prop_assert!(log.inclusion_proof(Uuid::new_v4()).is_none());
+        }
+
+        #[test]
+        fn test_signed_tree_heads_expose_swapped_entries(
+            digests in proptest::collection::hash_set(any::<[u8; 32]>(), 1..24),
+            other in any::<[u8; 32]>(),
+            swapped in any::<prop::sample::Index>(),
+        ) {
+            let digests = digests.into_iter().collect::<Vec<_>>();
+            prop_assume!(!digests.contains(&other));
+            let key = NotaryKey::from_seed(&NOTARY_TEST_SEED).expect("seed should be a notary key");
+            let public_key = key.public_key();
+            let (log, ids) = appended_log(
+                TransparencyLog::new().with_notary_key(std::sync::Arc::new(key)),
+                &digests,
+            );
+            let root = log.root();
+            prop_assert!(root.verify_signature(&public_key));
+            prop_assert!(!LogRoot { signature: None, ..root.clone() }.verify_signature(&public_key));
+            prop_assert!(!root.verify_signature(
+                &NotaryKey::from_seed(&[1; 32]).expect("seed should be a notary key").public_key()
+            ));
+            let grown = root.tree_size.checked_add(1).expect("tree size should not overflow");
+            prop_assert!(!LogRoot { tree_size: grown, ..root.clone() }.verify_signature(&public_key));
+
+            let other_hex = blake3::Hash::from_bytes(other).to_hex().to_string();
+            for id in &ids {
+                let proof = log.inclusion_proof(*id).expect("appended id should have a proof");
+                prop_assert_eq!(&proof.root_signature, &root.signature);
+                prop_assert!(proof.verify_signed(&public_key));
+                for tampered in tampered_proofs(&proof, &other_hex) {
+                    prop_assert!(!tampered.verify_signed(&public_key), "tampered proof verified: {:?}", tampered);
+                }
+            }
+
+            // A log with one entry swapped proves its entries against its own
+            // root, which the notary never signed.
+            let index = swapped.index(digests.len());
+            let mut forged_digests = digests.clone();
+            if let Some(digest) = forged_digests.get_mut(index) {
+                *digest = other;
+            }
+            let (forged_log, forged_ids) = appended_log(TransparencyLog::new(), &forged_digests);
+            let forged = forged_log
+                .inclusion_proof(*forged_ids.get(index).expect("swapped index should be appended"))
+                .expect("appended id should have a proof");
+            prop_assert!(forged.verify());
+            prop_assert!(!forged.verify_signed(&public_key));
+            prop_assert!(!InclusionProof {
+                root_signature: root.signature.clone(),
+                ..forged
+            }
+            .verify_signed(&public_key));
+        }
+
+        #[test]
+        fn test_reopened_log_keeps_root_and_indices(
+            digests in proptest::collection::vec(any::<[u8; 32]>(), 0..24),
+        ) {
+            let path = std::env::temp_dir()
+                .join(format!("verifier-log-{}", Uuid::new_v4()))
+                .join("log.jsonl");
+            let log = TransparencyLog::open(&path).expect("log file should open");
+            let ids = digests
+                .iter()
+                .map(|digest| {
+                    let id = Uuid::new_v4();
+                    log.append(id, blake3::Hash::from_bytes(*digest))
+                        .expect("append should be written");
+                    id
+                })
+                .collect::<Vec<_>>();
+            let root = log.root();
+            drop(log);
+
+            let reopened = TransparencyLog::open(&path).expect("log file should reopen");
+            prop_assert_eq!(reopened.root(), root);
+            for (index, id) in ids.iter().enumerate() {
+                let proof = reopened
+                    .inclusion_proof(*id)
+                    .expect("replayed id should have a proof");
+                prop_assert_eq!(
+                    usize::try_from(proof.leaf_index).expect("leaf index should fit"),
+                    index
+                );
+                prop_assert!(proof.verify());
+            }
+            if let Some(dir) = path.parent() {
+                std::fs::remove_dir_all(dir).expect("temporary log should be removed");
+            }
+        }
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(32))]
+
+        #[test]
+        fn test_sealed_artifacts_open_only_with_their_key_and_id(
+            plaintext in proptest::collection::vec(any::<u8>(), 0..512),
+            old_key in any::<[u8; 32]>(),
+            new_key in any::<[u8; 32]>(),
+        ) {
+            prop_assume!(old_key != new_key);
+            let id = Uuid::new_v4();
+            let keys = store_keys("old", &[("old", old_key)]);
+            let sealed = keys.seal(id, &plaintext).expect("plaintext should seal");
+            prop_assert_eq!(sealed.key_id.as_str(), "old");
+            prop_assert_eq!(keys.open(id, &sealed).expect("sealed artifact should open"), plaintext.clone());
+
+            let wrong_key = store_keys("old", &[("old", new_key)]);
+            prop_assert!(matches!(wrong_key.open(id, &sealed), Err(StoreError::Decrypt(_))));
+            prop_assert!(matches!(keys.open(Uuid::new_v4(), &sealed), Err(StoreError::Decrypt(_))));
+            let mut relabelled = sealed.clone();
+            relabelled.key_id = "new".to_string();
+            let both = store_keys("new", &[("old", old_key), ("new", new_key)]);
+            prop_assert!(matches!(both.open(id, &relabelled), Err(StoreError::Decrypt(_))));
+
+            prop_assert_eq!(both.open(id, &sealed).expect("rotated keys should open old artifacts"), plaintext.clone());
+            let rewrapped = both.rewrap(id, &sealed).expect("data key should rewrap");
+            prop_assert_eq!(rewrapped.key_id.as_str(), "new");
+            prop_assert_eq!(&rewrapped.ciphertext, &sealed.ciphertext);
+            let only_new = store_keys("new", &[("new", new_key)]);
+            prop_assert_eq!(only_new.open(id, &rewrapped).expect("rewrapped artifact should open"), plaintext);
+            prop_assert!(matches!(only_new.open(id, &sealed), Err(StoreError::UnknownKey(_))));
+            prop_assert!(matches!(keys.open(id, &rewrapped), Err(StoreError::UnknownKey(_))));
+        }
+    }
+
+    #[test]
+    fn test_proof_message_threshold_claims_roundtrip() {
+        let artifact = stored_artifact("localhost");
+        let claim = ThresholdClaim {
+            predicate: tlsnotary::Predicate::GreaterThan(50),
+            proof: artifact.proof.clone(),
+        };
+        let message = ProofMessage::new(artifact.proof).with_threshold(".balance", claim);
+        let json = serde_json::to_value(&message).expect("proof message should serialize");
+        assert_eq!(
+            json.get("thresholds")
+                .and_then(|thresholds| thresholds.get(".balance"))
+                .and_then(|claim| claim.get("predicate")),
+            Some(&serde_json::json!({ "greaterThan": 50 }))
+        );
+        let decoded: ProofMessage =
+            serde_json::from_value(json).expect("proof message should deserialize");
+        let decoded_claim = decoded
+            .thresholds
+            .get(".balance")
+            .expect("threshold claim should survive the roundtrip");
+        assert_eq!(
+            decoded_claim.predicate,
+            tlsnotary::Predicate::GreaterThan(50)
+        );
+
+        let bare = serde_json::to_value(ProofMessage::new(decoded.proof))
+            .expect("proof message should serialize");
+        assert!(bare.get("thresholds").is_none());
+    }
+
+    #[test]
+    fn test_outcome_and_artifact_carry_field_disclosures_by_keypath() {
+        let response = "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\n\r\n{\"username\":\"alice\",\"balance\":100,\"note\":\"hello\"}";
+        let received = String::from_utf8(redact_with(response, "100", 0))
+            .expect("zero filler should stay UTF-8");
+        let received = redact_with(&received, "\"hello\"", 0);
+        let transcript = SharedTranscript::new(
+            b"GET /api/balance/alice HTTP/1.1\r\nhost: localhost\r\n\r\n",
+            &received,
+        );
+        let (_, parsed_response) =
+            parse_transcript(&transcript).expect("redacted transcript should parse");
+        let bound_commitments = HashMap::from([(".balance".to_string(), ())]);
+
+        let disclosures = response_field_disclosures(&parsed_response, &bound_commitments);
+        assert_eq!(
+            disclosures,
+            BTreeMap::from([
+                (".balance".to_string(), FieldDisclosure::Committed),
+                (".note".to_string(), FieldDisclosure::Redacted),
+                (".username".to_string(), FieldDisclosure::Revealed),
+            ])
+        );
+
+        let outcome = VerificationOutcome {
+            field_disclosures: disclosures.clone(),
+            ..test_outcome("localhost")
+        };
+        let json = serde_json::to_value(&outcome).expect("outcome should serialize");
+        assert_eq!(
+            json.get("fieldDisclosures")
+                .and_then(|disclosures| disclosures.get(".balance")),
+            Some(&serde_json::json!("committed"))
+        );
+
+        let dir = std::env::temp_dir().join(format!("verifier-store-{}", Uuid::new_v4()));
+        let store = FilesystemArtifactStore::new(&dir).expect("store directory should be created");
+        let mut artifact = stored_artifact("localhost");
+        artifact.outcome = outcome;
+        store.put(&artifact).expect("artifact should be written");
+        let stored = store.get(artifact.id).expect("artifact should load");
+        assert_eq!(stored.outcome.field_disclosures, disclosures);
+        std::fs::remove_dir_all(&dir).expect("store directory should be removed");
+    }
+
+    #[test]
+    fn test_keyed_store_refuses_plaintext_until_rewrapped() {
+        let dir = std::env::temp_dir().join(format!("verifier-store-{}", Uuid::new_v4()));
+        let (old_key, new_key) = ([1; 32], [2; 32]);
+        let plain = FilesystemArtifactStore::new(&dir).expect("store directory should be created");
+        let legacy = stored_artifact("legacy.example");
+        plain
+            .put(&legacy)
+            .expect("plaintext artifact should be written");
+        let legacy_digest =
+            stored_digest(&plain, legacy.id).expect("plaintext artifact should load");
+
+        let keyed = plain
+            .clone()
+            .with_keys(store_keys("old", &[("old", old_key)]));
+        assert!(matches!(
+            stored_digest(&keyed, legacy.id),
+            Err(StoreError::Unsealed(_))
+        ));
+        assert_eq!(keyed.rewrap().expect("plaintext should be sealed"), 1);
+        assert_eq!(
+            stored_digest(&keyed, legacy.id).expect("sealed artifact should load"),
+            legacy_digest
+        );
+        assert!(matches!(
+            stored_digest(&plain, legacy.id),
+            Err(StoreError::Sealed(_))
+        ));
+
+        let rotated = plain
+            .clone()
+            .with_keys(store_keys("new", &[("old", old_key), ("new", new_key)]));
+        let fresh = stored_artifact("fresh.example");
+        rotated
+            .put(&fresh)
+            .expect("artifact should be sealed under the new key");
+        assert!(matches!(
+            stored_digest(&keyed, fresh.id),
+            Err(StoreError::UnknownKey(_))
+        ));
+        assert_eq!(rotated.rewrap().expect("old data keys should rewrap"), 1);
+        assert_eq!(rotated.rewrap().expect("nothing is left to rewrap"), 0);
+
+        let only_new = plain
+            .clone()
+            .with_keys(store_keys("new", &[("new", new_key)]));
+        assert_eq!(
+            stored_digest(&only_new, legacy.id).expect("rewrapped artifact should load"),
+            legacy_digest
+        );
+        assert!(stored_digest(&only_new, fresh.id).is_ok());
+        let wrong_key = plain.with_keys(store_keys("new", &[("new", old_key)]));
+        assert!(matches!(
+            stored_digest(&wrong_key, fresh.id),
+            Err(StoreError::Decrypt(_))
+        ));
+        std::fs::remove_dir_all(&dir).expect("store directory should be removed");
+    }
+
+    #[test]
+    fn test_config_rejects_unknown_fields() {
+        for contents in [
+            "certPath = \"cert.pem\"\nunknownField = 1\n",
+            "cert_path = \"cert.pem\"\n",
+            "[jobQueue]\nworkers = 2\nqueueDepth = 8\n",
+            "[admin]\naddr = \"127.0.0.1:5003\"\ntoken = \"admin-test-token-0123456789abcdef\"\nport = 5003\n",
+            "[[listeners]]\ntransport = \"quic\"\naddr = \"[::]:5000\"\nreusePort = true\n",
+            "[trafficLimits]\nmaxBytesReceived = 1\nmaxBytesSent = 1\nmaxBytes = 2\n",
+        ] {
+            let loaded = config_from_toml(contents);
+            assert!(
+                matches!(loaded, Err(ProtocolError::InvalidConfig(_))),
+                "{contents:?} should be refused, got {loaded:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_config_sets_up_the_transparency_log_and_job_queue() {
+        let dir = std::env::temp_dir().join(format!("verifier-config-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).expect("config directory should be created");
+        let log_path = dir.join("log.jsonl");
+        let key_path = dir.join("webhook.key");
+        let contents = format!(
+            "transparencyLog = {log_path:?}\nwebhookKeyFile = {key_path:?}\nstatusAddr = \"127.0.0.1:5001\"\n\n[jobQueue]\nworkers = 3\njobTtlSecs = 60\n"
+        );
+
+        let config = config_from_toml(&contents).expect("config should parse");
+        assert_eq!(
+            config.job_queue,
+            Some(JobQueueConfig {
+                workers: 3,
+                job_ttl_secs: 60,
+                ..JobQueueConfig::default()
+            })
+        );
+        assert_eq!(config.transparency_log.as_deref(), Some(log_path.as_path()));
+        assert!(matches!(
+            config.service_options(),
+            Err(ProtocolError::Io(_))
+        ));
+
+        std::fs::write(&key_path, "zz").expect("webhook key should be written");
+        assert!(matches!(
+            config.service_options(),
+            Err(ProtocolError::InvalidConfig(_))
+        ));
+        std::fs::write(&key_path, format!("{}\n", "ab".repeat(32)))
+            .expect("webhook key should be written");
+        let options = config
+            .service_options()
+            .expect("service options should load");
+        assert!(options.job_queue.is_some());
+        assert!(options.transparency_log.is_some());
+        assert!(log_path.exists());
+
+        let without_key = NotaryConfig {
+            webhook_key_file: None,
+            ..config
+        };
+        assert!(matches!(
+            without_key.service_options(),
+            Err(ProtocolError::InvalidConfig(_))
+        ));
+        std::fs::remove_dir_all(&dir).expect("config directory should be removed");
+    }
+
+    #[test]
+    fn test_config_rejects_empty_and_short_admin_tokens() {
+        for token in ["", "   ", "change-me", "short-token-0123456789abcdef"] {
+            let loaded = config_with_admin_token(token);
+            assert!(
+                matches!(loaded, Err(ProtocolError::InvalidConfig(_))),
+                "token {token:?} should be refused, got {loaded:?}"
+            );
+        }
+        let config =
+            config_with_admin_token(TEST_ADMIN_TOKEN).expect("a long token should be accepted");
+        assert_eq!(
+            config.admin.map(|admin| admin.token).as_deref(),
+            Some(TEST_ADMIN_TOKEN)
+        );
+    }
+
+    fn test_admin(token: &str) -> Result<std::sync::Arc<Admin>, ProtocolError> {
+        let config = AdminConfig {
+            addr: SocketAddr::from((Ipv4Addr::LOCALHOST, 0)),
+            token: token.to_string(),
+        };
+        Admin::new(&config, None, &ServiceOptions::default())
+    }
+
+    #[test]
+    fn test_admin_refuses_short_tokens_and_trims_the_configured_one() {
+        smol::block_on(async {
+            for token in ["", "change-me", "  short-token-0123456789abcdef  "] {
+                let admin = test_admin(token);
+                assert!(
+                    matches!(admin, Err(ProtocolError::InvalidConfig(_))),
+                    "token {token:?} should be refused, got {admin:?}"
+                );
+            }
+
+            let admin = test_admin(&format!("  {TEST_ADMIN_TOKEN}\n"))
+                .expect("a long token with surrounding whitespace should be accepted");
+            let (status, _) = admin_request(
+                &admin,
+                "GET",
+                "/admin/sessions",
+                Some(&format!("Bearer {TEST_ADMIN_TOKEN}")),
+            )
+            .await;
+            assert_eq!(status, StatusCode::OK);
+        });
+    }
+
+    #[test]
+    fn test_admin_routes_require_the_bearer_token() {
+        smol::block_on(async {
+            let admin = test_admin(TEST_ADMIN_TOKEN).expect("a long token should be accepted");
+            let session = format!("/admin/sessions/{}", Uuid::new_v4());
+            let routes = [
+                ("GET", "/admin/sessions", StatusCode::OK),
+                ("DELETE", session.as_str(), StatusCode::NOT_FOUND),
+                ("POST", "/admin/reload", StatusCode::UNPROCESSABLE_ENTITY),
+                ("GET", "/admin/load", StatusCode::NOT_FOUND),
+            ];
+            let wrong_length = format!("Bearer {TEST_ADMIN_TOKEN}0");
+            let wrong_token = format!("Bearer {}", TEST_ADMIN_TOKEN.to_uppercase());
+            let other_scheme = format!("Basic {TEST_ADMIN_TOKEN}");
+            let valid = format!("Bearer {TEST_ADMIN_TOKEN}");
+
+            for (method, uri, authorized_status) in routes {
+                for authorization in [
+                    None,
+                    Some("Bearer "),
+                    Some(wrong_length.as_str()),
+                    Some(wrong_token.as_str()),
+                    Some(other_scheme.as_str()),
+                ] {
+                    let (status, _) = admin_request(&admin, method, uri, authorization).await;
+                    assert_eq!(
+                        status,
+                        StatusCode::UNAUTHORIZED,
+                        "{method} {uri} with {authorization:?}"
+                    );
+                }
+                let (status, _) = admin_request(&admin, method, uri, Some(&valid)).await;
+                assert_eq!(status, authorized_status, "{method} {uri}");
+            }
+        });
+    }
+
+    #[test]
+    fn test_admin_lists_and_cancels_live_sessions() {
+        smol::block_on(async {
+            let admin = test_admin(TEST_ADMIN_TOKEN).expect("a long token should be accepted");
+            let authorization = format!("Bearer {TEST_ADMIN_TOKEN}");
+            let live = admin.start_session(&mut ServiceOptions::default());
+            live.set_phase(SessionPhase::Notarizing);
+            live.set_policy(Some("balance"));
+
+            let (status, body) =
+                admin_request(&admin, "GET", "/admin/sessions", Some(&authorization)).await;
+            assert_eq!(status, StatusCode::OK);
+            let sessions: Vec<SessionSummary> =
+                serde_json::from_slice(&body).expect("sessions should be JSON summaries");
+            let [summary] = sessions.as_slice() else {
+                panic!("expected one live session, got {sessions:?}");
+            };
+            assert_eq!(summary.phase, SessionPhase::Notarizing);
+            assert_eq!(summary.policy.as_deref(), Some("balance"));
+            assert_eq!(summary.traffic, TrafficTotals::default());
+
+            let cancel = format!("/admin/sessions/{}", summary.id);
+            let (status, _) = admin_request(&admin, "DELETE", &cancel, Some(&authorization)).await;
+            assert_eq!(status, StatusCode::NO_CONTENT);
+            smol::future::or(live.cancelled(), async {
+                Timer::after(JOB_WAIT).await;
+                panic!("cancelled session was not signalled");
+            })
+            .await;
+
+            drop(live);
+            let (status, body) =
+                admin_request(&admin, "GET", "/admin/sessions", Some(&authorization)).await;
+            assert_eq!(status, StatusCode::OK);
+            let sessions: Vec<SessionSummary> =
+                serde_json::from_slice(&body).expect("sessions should be JSON summaries");
+            assert!(sessions.is_empty());
+            let (status, _) = admin_request(&admin, "DELETE", &cancel, Some(&authorization)).await;
+            assert_eq!(status, StatusCode::NOT_FOUND);
+        });
+    }
+
+    #[test]
+    fn test_jobs_without_keys_are_distinct_and_report_status() {
+        smol::block_on(async {
+            let queue = loopback_queue(60);
+            let first = queue
+                .enqueue(None, None, || test_outcome("first.example"))
+                .await
+                .expect("first job should be queued");
+            let second = queue
+                .enqueue(None, None, || {
+                    VerificationOutcome::failure("second.example".to_string(), "bad".to_string())
+                })
+                .await
+                .expect("second job should be queued");
+            assert_ne!(first, second);
+            assert!(queue.status(Uuid::new_v4()).await.is_none());
+
+            let first = wait_for_outcome(&queue, first).await;
+            let second = wait_for_outcome(&queue, second).await;
+            assert!(first.success);
+            assert!(!second.success);
+        });
+    }
+
+    #[test]
+    fn test_finished_jobs_are_evicted_after_their_ttl() {
+        smol::block_on(async {
+            let queue = loopback_queue(0);
+            let (done_sender, done) = smol::channel::bounded(1);
+            let id = queue
+                .enqueue(
+                    None,
+                    Some(IdempotencyKey::new("retry", b"payload")),
+                    move || {
+                        let outcome = test_outcome("evicted.example");
+                        done_sender
+                            .send_blocking(())
+                            .expect("test should wait for the job");
+                        outcome
+                    },
+                )
+                .await
+                .expect("job should be queued");
+            done.recv().await.expect("job should run");
+
+            let evicted = async {
+                while queue.status(id).await.is_some() {
+                    Timer::after(Duration::from_millis(10)).await;
+                }
+            };
+            smol::future::or(evicted, async {
+                Timer::after(JOB_WAIT).await;
+                panic!("finished job was not evicted");
+            })
+            .await;
+
+            let requeued = queue
+                .enqueue(None, Some(IdempotencyKey::new("retry", b"other")), || {
+                    test_outcome("evicted.example")
+                })
+                .await
+                .expect("an evicted key should be free again");
+            assert_ne!(requeued, id);
+        });
+    }
+
+    #[test]
+    fn test_webhooks_to_internal_addresses_are_refused() {
+        smol::block_on(async {
+            let queue = JobQueue::start(JobQueueConfig::default(), TEST_WEBHOOK_SECRET);
+            for url in [
+                "http://127.0.0.1:5002/admin/reload",
+                "http://localhost/hook",
+                "http://[::1]:8080/hook",
+                "http://10.1.2.3/hook",
+                "http://169.254.169.254/latest/meta-data",
+                "http://[fe80::1]/hook",
+                "http://[::ffff:192.168.0.1]/hook",
+                "ftp://example.com/hook",
+            ] {
+                let queued = queue
+                    .enqueue(Some(url), None, || test_outcome("internal.example"))
+                    .await;
+                assert!(
+                    matches!(queued, Err(ProtocolError::InvalidWebhook(_))),
+                    "{url} should be refused, got {queued:?}"
+                );
+            }
+        });
+    }
+
+    #[test]
+    fn test_unresponsive_webhook_times_out_without_blocking_workers() {
+        smol::block_on(async {
+            let listener = TcpListener::bind("127.0.0.1:0")
+                .await
+                .expect("webhook listener should bind");
+            let port = listener
+                .local_addr()
+                .expect("webhook listener should have an address")
+                .port();
+            let (accepted_sender, accepted) = smol::channel::unbounded();
+            smol::spawn(async move {
+                let mut held = Vec::new();
+                while let Ok((stream, _)) = listener.accept().await {
+                    held.push(stream);
+                    if accepted_sender.send(()).await.is_err() {
+                        break;
+                    }
+                }
+            })
+            .detach();
+
+            let queue = JobQueue::start(
+                JobQueueConfig {
+                    workers: 1,
+                    webhook_attempts: 2,
+                    webhook_timeout_secs: 1,
+                    webhook_allowed_hosts: vec!["127.0.0.1".to_string()],
+                    ..JobQueueConfig::default()
+                },
+                TEST_WEBHOOK_SECRET,
+            );
+            let hanging = queue
+                .enqueue(Some(&format!("http://127.0.0.1:{port}/hook")), None, || {
+                    test_outcome("hanging.example")
+                })
+                .await
+                .expect("job with an allowed webhook should be queued");
+            assert!(wait_for_outcome(&queue, hanging).await.success);
+            accepted.recv().await.expect("webhook should be attempted");
+
+            let next = queue
+                .enqueue(None, None, || test_outcome("next.example"))
+                .await
+                .expect("second job should be queued");
+            let verified_before_retry = smol::future::or(
+                async { wait_for_outcome(&queue, next).await.success },
+                async {
+                    accepted.recv().await.expect("webhook should be retried");
+                    false
+                },
+            )
+            .await;
+            assert!(
+                verified_before_retry,
+                "a hanging webhook should not hold up the worker"
+            );
+
+            smol::future::or(
+                async {
+                    accepted
+                        .recv()
+                        .await
+                        .expect("timed out webhook should be retried");
+                },
+                async {
+                    Timer::after(JOB_WAIT).await;
+                    panic!("webhook attempt was not cut off");
+                },
+            )
+            .await;
+        });
+    }
+
+    #[test]
+    fn test_webhook_delivers_signed_result() {
+        smol::block_on(async {
+            let (sender, received) = smol::channel::bounded(1);
+            let router = Router::new().route(
+                "/hook",
+                post(move |headers: HeaderMap, body: Bytes| {
+                    let sender = sender.clone();
+                    async move {
+                        let signature = headers
+                            .get(SIGNATURE_HEADER)
+                            .and_then(|value| value.to_str().ok())
+                            .map(str::to_string);
+                        sender
+                            .send((signature, body))
+                            .await
+                            .expect("test should receive the webhook");
+                    }
+                }),
+            );
+            let listener = TcpListener::bind("127.0.0.1:0")
+                .await
+                .expect("webhook listener should bind");
+            let port = listener
+                .local_addr()
+                .expect("webhook listener should have an address")
+                .port();
+            smol::spawn(serve_router(listener, router)).detach();
+
+            let queue = loopback_queue(60);
+            let id = queue
+                .enqueue(Some(&format!("http://127.0.0.1:{port}/hook")), None, || {
+                    test_outcome("webhook.example")
+                })
+                .await
+                .expect("job with an allowed webhook should be queued");
+            let (signature, body) = received.recv().await.expect("webhook should be delivered");
+
+            assert_eq!(
+                signature.as_deref(),
+                Some(webhook_signature(&TEST_WEBHOOK_SECRET, &body).as_str())
+            );
+            assert_ne!(
+                signature.as_deref(),
+                Some(webhook_signature(&[0; 32], &body).as_str())
+            );
+            let result: JobResult =
+                serde_json::from_slice(&body).expect("webhook body should be a job result");
+            assert_eq!(result.job_id, id);
+            assert!(result.outcome.success);
+        });
+    }
+
+    #[test]
+    fn test_recorded_roughtime_response_verifies() {
+        assert_eq!(
+            verify_response(&fixture_public_key(), &fixture_nonce(), &fixture_response())
+                .expect("recorded response should verify"),
+            (fixture_midpoint(), ROUGHTIME_RADIUS_MICROS)
+        );
+        let digest = blake3::hash(ROUGHTIME_FIXTURE_MESSAGE);
+        let timestamp = fixture_timestamp(Utc::now());
+        timestamp
+            .verify(&digest)
+            .expect("recorded timestamp should verify");
+        assert_eq!(timestamp.time(), fixture_midpoint());
+
+        let mut shifted = timestamp.clone();
+        if let Some(roughtime) = &mut shifted.roughtime {
+            roughtime.midpoint += TimeDelta::seconds(1);
+        }
+        assert!(matches!(
+            shifted.verify(&digest),
+            Err(TimestampError::Malformed(_))
+        ));
+        assert!(matches!(
+            timestamp.verify(&blake3::hash(b"another attestation")),
+            Err(TimestampError::NonceMismatch)
+        ));
+    }
+
+    #[test]
+    fn test_tampered_roughtime_responses_are_rejected() {
+        let public_key = fixture_public_key();
+        let nonce = fixture_nonce();
+        let response = fixture_response();
+        assert_eq!(
+            roughtime_response(
+                &nonce,
+                ROUGHTIME_MIDPOINT,
+                ROUGHTIME_MIDPOINT - ROUGHTIME_DAY_MICROS,
+                ROUGHTIME_MIDPOINT + ROUGHTIME_DAY_MICROS,
+            ),
+            response
+        );
+
+        // The top-level signature is the first value, right after the
+        // five-tag header.
+        let mut flipped = response.clone();
+        if let Some(byte) = flipped.get_mut(40) {
+            *byte ^= 1;
+        }
+        assert!(matches!(
+            verify_response(&public_key, &nonce, &flipped),
+            Err(TimestampError::BadSignature("response"))
+        ));
+        let other_key = Ed25519KeyPair::from_seed_unchecked(&ROUGHTIME_DELEGATED_SEED)
+            .expect("delegated seed should be an Ed25519 key");
+        assert!(matches!(
+            verify_response(other_key.public_key().as_ref(), &nonce, &response),
+            Err(TimestampError::BadSignature("delegation"))
+        ));
+        assert!(matches!(
+            verify_response(
+                &public_key,
+                &attestation_nonce(&blake3::hash(b"another attestation")),
+                &response
+            ),
+            Err(TimestampError::NonceMismatch)
+        ));
+
+        let past_delegation = roughtime_response(
+            &nonce,
+            ROUGHTIME_MIDPOINT + ROUGHTIME_DAY_MICROS + 1,
+            ROUGHTIME_MIDPOINT - ROUGHTIME_DAY_MICROS,
+            ROUGHTIME_MIDPOINT + ROUGHTIME_DAY_MICROS,
+        );
+        assert!(matches!(
+            verify_response(&public_key, &nonce, &past_delegation),
+            Err(TimestampError::OutsideDelegation)
+        ));
+        let unrepresentable = roughtime_response(&nonce, u64::MAX, 0, u64::MAX);
+        assert!(matches!(
+            verify_response(&public_key, &nonce, &unrepresentable),
+            Err(TimestampError::Malformed("MIDP is out of range"))
+        ));
+
+        // Cut inside the tag table.
+        assert!(matches!(
+            verify_response(&public_key, &nonce, response.get(..20).unwrap_or_default()),
+            Err(TimestampError::Malformed("bad tag count"))
+        ));
+        assert!(matches!(
+            parse_message(&[0; 4]),
+            Err(TimestampError::Malformed("bad tag count"))
+        ));
+    }
+
+    #[test]
+    fn test_session_time_window_is_inclusive_either_way() {
+        let session_time = fixture_midpoint();
+        let policy =
+            NotarizationPolicy::new("windowed").session_time_window(Duration::from_secs(60));
+        let local = |offset_secs: i64| NotaryTimestamp {
+            local_time: session_time + TimeDelta::seconds(offset_secs),
+            roughtime: None,
+        };
+
+        for offset_secs in [-60, -59, 0, 59, 60] {
+            assert!(
+                policy
+                    .check_session_time(session_time, &local(offset_secs))
+                    .is_ok(),
+                "{offset_secs}s should be within the window"
+            );
+        }
+        for offset_secs in [-61, 61, i64::from(u32::MAX)] {
+            assert!(
+                matches!(
+                    policy.check_session_time(session_time, &local(offset_secs)),
+                    Err(ProtocolError::PolicyViolation { .. })
+                ),
+                "{offset_secs}s should be outside the window"
+            );
+        }
+
+        // The Roughtime midpoint takes precedence over the local clock.
+        let skewed = fixture_timestamp(session_time + TimeDelta::days(1));
+        assert!(policy.check_session_time(session_time, &skewed).is_ok());
+        assert!(
+            policy
+                .check_session_time(session_time - TimeDelta::seconds(61), &skewed)
+                .is_err()
+        );
+
+        let unbounded = NotarizationPolicy::new("unbounded");
+        assert!(
+            unbounded
+                .check_session_time(session_time, &local(i64::from(u32::MAX)))
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_notary_signature_covers_digest_and_timestamp() {
+        let key = NotaryKey::from_seed(&NOTARY_TEST_SEED).expect("seed should be a notary key");
+        let mut artifact = stored_artifact("localhost");
+        artifact.timestamp = Some(fixture_timestamp(Utc::now()));
+        assert!(artifact.verify_signature(&key.public_key()).is_err());
+        artifact.sign(&key).expect("artifact should sign");
+        artifact
+            .verify_signature(&key.public_key())
+            .expect("signature should verify");
+
+        let stored: AttestationArtifact = serde_json::from_slice(
+            &serde_json::to_vec(&artifact).expect("artifact should serialize"),
+        )
+        .expect("artifact should deserialize");
+        stored
+            .verify_signature(&key.public_key())
+            .expect("stored signature should verify");
+
+        let other_key = NotaryKey::from_seed(&[1; 32]).expect("seed should be a notary key");
+        let mut retimed = artifact.clone();
+        if let Some(timestamp) = &mut retimed.timestamp {
+            timestamp.local_time += TimeDelta::seconds(1);
+        }
+        let mut unstamped = artifact.clone();
+        unstamped.timestamp = None;
+        let mut edited = artifact.clone();
+        edited.response.push(' ');
+        for (case, artifact, public_key) in [
+            ("other key", &artifact, other_key.public_key()),
+            ("retimed", &retimed, key.public_key()),
+            ("unstamped", &unstamped, key.public_key()),
+            ("edited", &edited, key.public_key()),
+        ] {
+            assert!(
+                matches!(
+                    artifact.verify_signature(&public_key),
+                    Err(StoreError::Integrity {
+                        part: "notary signature",
+                        ..
+                    })
+                ),
+                "{case} artifact should fail the signature check"
+            );
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    enum AdmissionStep {
+        Admit(usize),
+        Release(prop::sample::Index),
+    }
+
+    fn admission_step() -> impl Strategy<Value = AdmissionStep> {
+        prop_oneof![
+            (0usize..40_000).prop_map(AdmissionStep::Admit),
+            any::<prop::sample::Index>().prop_map(AdmissionStep::Release),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn test_admitted_cost_never_exceeds_the_budget(
+            budget in 0usize..100_000,
+            steps in proptest::collection::vec(admission_step(), 0..32),
+        ) {
+            let control = std::sync::Arc::new(AdmissionControl::new(budget));
+            let mut permits = Vec::new();
+            for step in steps {
+                match step {
+                    AdmissionStep::Admit(cost) => {
+                        let held = control.status().in_use;
+                        match control.try_admit(cost) {
+                            Ok(permit) => permits.push(permit),
+                            Err(error) => {
+                                prop_assert!(
+                                    matches!(
+                                        error,
+                                        ProtocolError::AdmissionRejected { cost: rejected, available }
+                                            if rejected == cost && available == budget.saturating_sub(held)
+                                    ),
+                                    "unexpected error {}",
+                                    error
+                                );
+                                prop_assert!(held.checked_add(cost).is_none_or(|total| total > budget));
+                            }
+                        }
+                    }
+                    AdmissionStep::Release(index) => {
+                        if !permits.is_empty() {
+                            permits.swap_remove(index.index(permits.len()));
+                        }
+                    }
+                }
+                let status = control.status();
+                prop_assert_eq!(status.in_use, permits.iter().map(AdmissionPermit::cost).sum::<usize>());
+                prop_assert!(status.in_use <= budget);
+                prop_assert_eq!(status.available, budget.saturating_sub(status.in_use));
+            }
+            drop(permits);
+            prop_assert_eq!(control.status().in_use, 0);
+        }
+    }
+
+    #[test]
+    fn test_admission_reserves_and_releases_session_costs() {
+        let cost = AdmissionControl::session_cost(MAX_SENT_DATA, MAX_RECV_DATA);
+        let budget = cost.checked_mul(2).expect("budget should fit");
+        let control = std::sync::Arc::new(AdmissionControl::new(budget));
+
+        let first = control.try_admit(cost).expect("first session should fit");
+        let second = control.try_admit(cost).expect("second session should fit");
+        assert_eq!(first.cost(), cost);
+        assert_eq!(
+            control.status(),
+            AdmissionStatus {
+                budget,
+                in_use: budget,
+                available: 0,
+            }
+        );
+        assert!(matches!(
+            control.try_admit(1),
+            Err(ProtocolError::AdmissionRejected {
+                cost: 1,
+                available: 0
+            })
+        ));
+
+        drop(first);
+        assert_eq!(control.status().available, cost);
+        let over = cost.checked_add(1).expect("cost should fit");
+        assert!(matches!(
+            control.try_admit(over),
+            Err(ProtocolError::AdmissionRejected { available, .. }) if available == cost
+        ));
+        assert_eq!(control.status().in_use, cost);
+
+        // Lowering the budget keeps admitted sessions but refuses new ones.
+        control.set_budget(1);
+        assert_eq!(control.status().available, 0);
+        assert!(control.try_admit(1).is_err());
+        drop(second);
+        assert_eq!(control.status().in_use, 0);
+        let small = control
+            .try_admit(1)
+            .expect("session should fit the lowered budget");
+        assert_eq!(small.cost(), 1);
+    }
+
+    #[test]
+    fn test_admission_rejects_costs_that_overflow() {
+        assert_eq!(AdmissionControl::session_cost(usize::MAX, 1), usize::MAX);
+        assert_eq!(
+            AdmissionControl::session_cost(usize::MAX, usize::MAX),
+            usize::MAX
+        );
+        let default = std::sync::Arc::new(AdmissionControl::default());
+        assert!(matches!(
+            default.try_admit(AdmissionControl::session_cost(usize::MAX, usize::MAX)),
+            Err(ProtocolError::AdmissionRejected { .. })
+        ));
+
+        // Even an unbounded budget refuses a sum that would wrap.
+        let unbounded = std::sync::Arc::new(AdmissionControl::new(usize::MAX));
+        let held = unbounded.try_admit(1).expect("one byte should fit");
+        assert!(matches!(
+            unbounded.try_admit(usize::MAX),
+            Err(ProtocolError::AdmissionRejected { available, .. }) if available == usize::MAX - 1
+        ));
+        assert_eq!(unbounded.status().in_use, held.cost());
+    }
+
+    #[test]
+    fn test_health_reports_the_remaining_budget() {
+        smol::block_on(async {
+            let control = std::sync::Arc::new(AdmissionControl::new(1000));
+            let _permit = control.try_admit(300).expect("session should fit");
+            let response = std::sync::Arc::clone(&control)
+                .router()
+                .oneshot(
+                    Request::get("/health")
+                        .body(Body::empty())
+                        .expect("request should build"),
+                )
+                .await
+                .expect("health route should respond");
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = response
+                .into_body()
+                .collect()
+                .await
+                .expect("health body should be readable")
+                .to_bytes();
+            let health: HealthStatus =
+                serde_json::from_slice(&body).expect("health body should be JSON");
+            assert_eq!(
+                health.admission,
+                AdmissionStatus {
+                    budget: 1000,
+                    in_use: 300,
+                    available: 700,
+                }
+            );
+        });
+    }
+
+    fn lag_shedder(max_loop_lag_ms: u64, resume_percent: u8) -> LoadShedder {
+        LoadShedder::new(LoadSheddingConfig {
+            max_loop_lag_ms: Some(max_loop_lag_ms),
+            resume_percent,
+            ..LoadSheddingConfig::default()
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn test_load_shedding_never_flaps_within_the_band(
+            max_loop_lag_ms in 1u64..u64::MAX,
+            resume_percent in 0u8..100,
+            shedding in any::<bool>(),
+            samples in proptest::collection::vec(any::<prop::sample::Index>(), 1..32),
+        ) {
+            // Samples above the low-water mark and at or under the high one.
+            let low = u128::from(max_loop_lag_ms) * u128::from(resume_percent) / 100;
+            let low = u64::try_from(low).expect("low-water mark should be under the maximum");
+            prop_assume!(low < max_loop_lag_ms);
+            let band = max_loop_lag_ms - low;
+
+            let shedder = lag_shedder(max_loop_lag_ms, resume_percent);
+            if shedding {
+                shedder.record(None, Duration::from_millis(u64::MAX));
+            }
+            prop_assert_eq!(shedder.status().shedding, shedding);
+            for sample in samples {
+                let index = u64::try_from(sample.index(usize::MAX)).expect("index should fit a u64");
+                let lag_ms = low + 1 + index % band;
+                shedder.record(None, Duration::from_millis(lag_ms));
+                prop_assert_eq!(shedder.status().shedding, shedding, "flapped at {} ms", lag_ms);
+            }
+        }
+    }
+
+    #[test]
+    fn test_load_shedding_enters_above_and_leaves_below_the_marks() {
+        let shedder = lag_shedder(100, 80);
+        shedder.record(None, Duration::from_millis(100));
+        assert!(
+            !shedder.status().shedding,
+            "the high-water mark itself is allowed"
+        );
+        shedder.admit().expect("sessions should be admitted");
+
+        shedder.record(None, Duration::from_millis(101));
+        assert!(shedder.status().shedding);
+        assert!(matches!(shedder.admit(), Err(ProtocolError::Overloaded(_))));
+        assert_eq!(shedder.status().shed_sessions, 1);
+
+        // Between the marks the state holds either way.
+        for lag_ms in [99, 81, 100, 90] {
+            shedder.record(None, Duration::from_millis(lag_ms));
+            assert!(
+                shedder.status().shedding,
+                "{lag_ms} ms should keep shedding"
+            );
+        }
+        shedder.record(None, Duration::from_millis(80));
+        assert!(!shedder.status().shedding);
+        shedder.admit().expect("sessions should be admitted again");
+        for lag_ms in [81, 100] {
+            shedder.record(None, Duration::from_millis(lag_ms));
+            assert!(
+                !shedder.status().shedding,
+                "{lag_ms} ms should not start shedding"
+            );
+        }
+        assert_eq!(shedder.status().shed_sessions, 1);
+    }
+
+    #[test]
+    fn test_load_shedding_needs_every_sample_to_recover() {
+        let shedder = LoadShedder::new(LoadSheddingConfig {
+            max_memory_bytes: Some(1000),
+            max_loop_lag_ms: Some(100),
+            resume_percent: 50,
+            ..LoadSheddingConfig::default()
+        });
+        shedder.record(Some(1001), Duration::ZERO);
+        assert!(shedder.status().shedding);
+        assert_eq!(shedder.status().memory_bytes, Some(1001));
+
+        shedder.record(Some(400), Duration::from_millis(60));
+        assert!(
+            shedder.status().shedding,
+            "lag is still over its low-water mark"
+        );
+        shedder.record(Some(600), Duration::from_millis(10));
+        assert!(
+            shedder.status().shedding,
+            "memory is still over its low-water mark"
+        );
+        shedder.record(Some(500), Duration::from_millis(50));
+        assert!(!shedder.status().shedding);
+
+        // Platforms without a memory reading never shed on memory.
+        shedder.record(None, Duration::ZERO);
+        assert!(!shedder.status().shedding);
+        assert_eq!(shedder.status().memory_bytes, None);
+    }
+
+    proptest! {
+        #[test]
+        fn test_traffic_meter_counts_both_directions(
+            incoming in proptest::collection::vec(any::<u8>(), 0..2048),
+            outgoing in proptest::collection::vec(proptest::collection::vec(any::<u8>(), 0..256), 0..8),
+        ) {
+            smol::block_on(async {
+                let meter = TrafficMeter::new(None);
+                let mut reader = meter.wrap(std::io::Cursor::new(incoming.clone()));
+                let mut read = Vec::new();
+                reader
+                    .read_to_end(&mut read)
+                    .await
+                    .expect("unlimited read should finish");
+                prop_assert_eq!(&read, &incoming);
+
+                // Streams wrapped by one meter share its totals.
+                let mut writer = meter.wrap(std::io::Cursor::new(Vec::new()));
+                for chunk in &outgoing {
+                    writer.write_all(chunk).await.expect("unlimited write should finish");
+                }
+                let sent = outgoing.iter().map(Vec::len).sum::<usize>();
+                prop_assert_eq!(
+                    meter.totals(),
+                    TrafficTotals {
+                        bytes_received: u64::try_from(incoming.len()).expect("length should fit"),
+                        bytes_sent: u64::try_from(sent).expect("length should fit"),
+                    }
+                );
+                prop_assert!(meter.exceeded().is_none());
+                Ok(())
+            })?;
+        }
+
+        #[test]
+        fn test_traffic_limits_stop_reads_and_writes_past_the_cap(
+            limit in 0u64..512,
+            len in 0usize..1024,
+        ) {
+            smol::block_on(async {
+                let limits = TrafficLimits {
+                    max_bytes_received: limit,
+                    max_bytes_sent: limit,
+                };
+                let within = u64::try_from(len).expect("length should fit") <= limit;
+                let cap = usize::try_from(limit).expect("limit should fit");
+
+                let meter = TrafficMeter::new(Some(limits));
+                let mut reader = meter.wrap(std::io::Cursor::new(vec![7; len]));
+                let mut read = Vec::new();
+                let result = reader.read_to_end(&mut read).await;
+                if within {
+                    prop_assert_eq!(result.expect("read within the cap should finish"), len);
+                    prop_assert!(meter.exceeded().is_none());
+                } else {
+                    prop_assert!(result.is_err());
+                    // Reads are capped, so no byte past the cap is handed back.
+                    prop_assert!(read.len() <= cap);
+                    prop_assert!(meter.totals().bytes_received <= limit.saturating_add(1));
+                    prop_assert!(matches!(
+                        meter.exceeded(),
+                        Some(ProtocolError::TrafficLimitExceeded { direction: "received", limit: stopped })
+                            if stopped == limit
+                    ), "the read should stop at the received cap");
+                }
+
+                let meter = TrafficMeter::new(Some(limits));
+                let mut writer = meter.wrap(std::io::Cursor::new(Vec::new()));
+                let result = writer.write_all(&vec![7; len]).await;
+                if within {
+                    result.expect("write within the cap should finish");
+                    prop_assert_eq!(meter.totals().bytes_sent, u64::try_from(len).expect("length should fit"));
+                } else {
+                    prop_assert!(result.is_err());
+                    prop_assert_eq!(meter.totals().bytes_sent, 0);
+                    prop_assert!(matches!(
+                        meter.exceeded(),
+                        Some(ProtocolError::TrafficLimitExceeded { direction: "sent", .. })
+                    ), "the write should stop at the sent cap");
+                }
+                Ok(())
+            })?;
+        }
+    }
+
+    async fn test_quic_server_config() -> quinn::ServerConfig {
+        let dir = std::env::temp_dir().join(format!("verifier-listener-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).expect("certificate directory should be created");
+        get_or_create_test_quic_config(&dir.join("cert.pem"), &dir.join("key.pem"))
+            .await
+            .expect("test QUIC config should be created")
+            .server_config
+    }
+
+    /// Polls the listener's stats until `done` holds, panicking after
+    /// [`JOB_WAIT`].
+    async fn wait_for_stats(metrics: &ListenerMetrics, done: impl Fn(ListenerStats) -> bool) {
+        let poll = async {
+            while !done(metrics.stats()) {
+                Timer::after(Duration::from_millis(10)).await;
+            }
+        };
+        smol::future::or(poll, async {
+            Timer::after(JOB_WAIT).await;
+            panic!("listener stats stuck at {:?}", metrics.stats())
+        })
+        .await;
+    }
+
+    #[test]
+    fn test_listeners_bind_both_transports() {
+        smol::block_on(async {
+            let server_config = test_quic_server_config().await;
+            let localhost = SocketAddr::from((Ipv4Addr::LOCALHOST, 0));
+            let listeners = ListenerConfig::bind_all(
+                &[
+                    ListenerConfig::quic(localhost),
+                    ListenerConfig::tcp(localhost),
+                ],
+                &server_config,
+            )
+            .expect("listeners on ephemeral ports should bind");
+
+            let transports: Vec<_> = listeners.iter().map(Listener::transport).collect();
+            assert_eq!(
+                transports,
+                [ListenerTransport::Quic, ListenerTransport::Tcp]
+            );
+            for listener in &listeners {
+                let addr = listener
+                    .local_addr()
+                    .expect("bound listener should have an address");
+                assert_eq!(addr.ip(), localhost.ip());
+                assert_ne!(addr.port(), 0);
+            }
+        });
+    }
+
+    #[test]
+    fn test_ipv6_wildcard_takes_ipv4_unless_ipv6_only() {
+        smol::block_on(async {
+            let server_config = test_quic_server_config().await;
+            for ipv6_only in [false, true] {
+                let listener = ListenerConfig::tcp(SocketAddr::from((Ipv6Addr::UNSPECIFIED, 0)))
+                    .ipv6_only(ipv6_only)
+                    .bind(&server_config)
+                    .expect("IPv6 wildcard listener should bind");
+                let port = listener
+                    .local_addr()
+                    .expect("bound listener should have an address")
+                    .port();
+
+                let connected = TcpStream::connect((Ipv4Addr::LOCALHOST, port)).await;
+                assert_eq!(
+                    connected.is_ok(),
+                    !ipv6_only,
+                    "IPv4 connection with ipv6_only = {ipv6_only}: {connected:?}"
+                );
+            }
+        });
+    }
+
+    #[test]
+    fn test_listener_file_must_name_a_listener() {
+        let path = std::env::temp_dir().join(format!("verifier-listeners-{}.json", Uuid::new_v4()));
+        std::fs::write(&path, "[]").expect("listener file should be written");
+        let loaded = ListenerConfig::from_json_file(&path);
+        assert!(
+            matches!(loaded, Err(ProtocolError::InvalidConfig(_))),
+            "an empty listener list should be refused, got {loaded:?}"
+        );
+
+        std::fs::write(
+            &path,
+            r#"[{"transport": "tcp", "addr": "[::]:5000", "ipv6Only": true}]"#,
+        )
+        .expect("listener file should be written");
+        assert_eq!(
+            ListenerConfig::from_json_file(&path).expect("listener file should load"),
+            [
+                ListenerConfig::tcp(SocketAddr::from((Ipv6Addr::UNSPECIFIED, 5000)))
+                    .ipv6_only(true)
+            ]
+        );
+        std::fs::remove_file(&path).expect("listener file should be removed");
+    }
+
+    #[test]
+    fn test_tcp_fallback_serves_sessions_and_closes_on_shutdown() {
+        smol::block_on(async {
+            let server_config = test_quic_server_config().await;
+            let listener = ListenerConfig::tcp(SocketAddr::from((Ipv4Addr::LOCALHOST, 0)))
+                .bind(&server_config)
+                .expect("TCP listener should bind");
+            let addr = listener
+                .local_addr()
+                .expect("bound listener should have an address");
+            let metrics = listener.metrics();
+            let (stop, stopped) = smol::channel::bounded::<()>(1);
+            let service = smol::spawn(serve_listeners_until(
+                vec![listener],
+                ServiceOptions {
+                    shutdown_grace_period: JOB_WAIT,
+                    ..ServiceOptions::default()
+                },
+                async move {
+                    stopped.recv().await.ok();
+                },
+            ));
+
+            // A malformed session fails without taking the listener down.
+            let mut malformed = TcpStream::connect(addr)
+                .await
+                .expect("TCP listener should accept");
+            smol::io::AsyncWriteExt::write_all(&mut malformed, b"not a session")
+                .await
+                .expect("malformed session should be sent");
+            drop(malformed);
+            wait_for_stats(&metrics, |stats| stats.failed_sessions == 1).await;
+
+            let in_flight = TcpStream::connect(addr)
+                .await
+                .expect("TCP listener should accept");
+            wait_for_stats(&metrics, |stats| stats.sessions == 2).await;
+            stop.send(()).await.expect("service should be running");
+
+            // Once the service took the signal its accept loop is gone, and
+            // draining closes the socket so new connections are refused
+            // rather than left waiting in the backlog.
+            smol::future::or(
+                async {
+                    while !stop.is_empty() {
+                        Timer::after(Duration::from_millis(10)).await;
+                    }
+                    while TcpStream::connect(addr).await.is_ok() {
+                        Timer::after(Duration::from_millis(10)).await;
+                    }
+                },
+                async {
+                    Timer::after(JOB_WAIT).await;
+                    panic!("TCP listener kept accepting while draining");
+                },
+            )
+            .await;
+
+            drop(in_flight);
+            let summary = service.await;
+            assert_eq!(
+                summary,
+                ShutdownSummary {
+                    completed: 1,
+                    drained: 1,
+                    unfinished: 0,
+                }
+            );
+            assert_eq!(
+                metrics.stats(),
+                ListenerStats {
+                    connections: 2,
+                    sessions: 2,
+                    failed_sessions: 2,
+                    refused_sessions: 0,
+                }
+            );
+        });
+    }
+
+    #[test]
+    fn test_drain_counts_sessions_that_finish_and_outlive_the_grace_period() {
+        smol::block_on(async {
+            let (tracker, finished) = SessionTracker::new();
+            drop(
+                tracker
+                    .start()
+                    .expect("sessions should start before draining"),
+            );
+            let quick = tracker
+                .start()
+                .expect("sessions should start before draining");
+            let stuck = tracker
+                .start()
+                .expect("sessions should start before draining");
+            smol::spawn(async move {
+                Timer::after(Duration::from_millis(20)).await;
+                drop(quick);
+            })
+            .detach();
+
+            let summary = tracker.drain(&finished, Duration::from_millis(500)).await;
+            assert_eq!(
+                summary,
+                ShutdownSummary {
+                    completed: 1,
+                    drained: 1,
+                    unfinished: 1,
+                }
+            );
+            assert!(
+                tracker.start().is_none(),
+                "no session should start once draining"
+            );
+            drop(stuck);
+        });
+    }
+
+    #[test]
+    fn test_drain_without_a_representable_deadline_waits_for_every_session() {
+        smol::block_on(async {
+            let (tracker, finished) = SessionTracker::new();
+            let session = tracker
+                .start()
+                .expect("sessions should start before draining");
+            smol::spawn(async move {
+                Timer::after(Duration::from_millis(20)).await;
+                drop(session);
+            })
+            .detach();
+
+            let summary = tracker.drain(&finished, Duration::MAX).await;
+            assert_eq!(
+                summary,
+                ShutdownSummary {
+                    completed: 0,
+                    drained: 1,
+                    unfinished: 0,
+                }
+            );
+        });
+    }
+
+    type ScriptedStream = Compat<tokio::io::Join<std::io::Cursor<Vec<u8>>, Vec<u8>>>;
+
+    /// Length-prefixed JSON frames, as the session stream carries them.
+    fn encode_frames(frames: &[serde_json::Value]) -> Vec<u8> {
+        frames
+            .iter()
+            .flat_map(|frame| {
+                let payload = serde_json::to_vec(frame).expect("frame should serialize");
+                let len = u32::try_from(payload.len()).expect("frame length should fit");
+                len.to_be_bytes().into_iter().chain(payload)
+            })
+            .collect()
+    }
+
+    /// A session stream that reads `input` and keeps what is written to it.
+    fn scripted_stream(input: Vec<u8>) -> ScriptedStream {
+        Compat::new(tokio::io::join(std::io::Cursor::new(input), Vec::new()))
+    }
+
+    async fn written_frames(stream: ScriptedStream) -> Vec<serde_json::Value> {
+        let (_, written) = stream.into_inner().into_inner();
+        let len = u64::try_from(written.len()).expect("output length should fit");
+        let mut written = futures::io::Cursor::new(written);
+        let mut frames = Vec::new();
+        while written.position() < len {
+            frames.push(
+                read_json_frame(&mut written)
+                    .await
+                    .expect("written frames should decode"),
+            );
+        }
+        frames
+    }
+
+    fn rpc_id() -> impl Strategy<Value = serde_json::Value> {
+        prop_oneof![
+            Just(serde_json::Value::Null),
+            any::<i64>().prop_map(serde_json::Value::from),
+            "[a-z0-9-]{0,16}".prop_map(serde_json::Value::from),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn test_json_rpc_framing_echoes_request_ids(
+            init_id in rpc_id(),
+            verify_id in rpc_id(),
+            success in any::<bool>(),
+        ) {
+            smol::block_on(async {
+                let session_init = SessionInit::new(TraceContext::new());
+                let message = ProofMessage::new(stored_artifact("localhost").proof);
+                let mut stream = scripted_stream(encode_frames(&[
+                    json!({
+                        "jsonrpc": JSONRPC_VERSION,
+                        "id": init_id,
+                        "method": METHOD_SESSION_INIT,
+                        "params": session_init,
+                    }),
+                    json!({
+                        "jsonrpc": JSONRPC_VERSION,
+                        "id": verify_id,
+                        "method": METHOD_SESSION_VERIFY,
+                        "params": message,
+                    }),
+                ]));
+
+                let (mut framing, accepted, session_nonce) =
+                    Framing::accept_session(&mut stream, None)
+                        .await
+                        .expect("a session.init request should be accepted");
+                prop_assert_eq!(accepted, session_init);
+                let session_nonce =
+                    session_nonce.expect("a session.init request should get a nonce");
+                let request = framing
+                    .read_proof_request(&mut stream)
+                    .await
+                    .expect("a session.verify request should be read");
+                prop_assert!(
+                    matches!(request, ProofRequest::Verify(_)),
+                    "session.verify should ask for verification, got {:?}",
+                    request
+                );
+                let outcome = if success {
+                    test_outcome("localhost")
+                } else {
+                    VerificationOutcome::failure("localhost".to_string(), "rejected".to_string())
+                };
+                framing
+                    .write_outcome(&mut stream, &outcome)
+                    .await
+                    .expect("the outcome should be written");
+
+                let answer = if success {
+                    json!({ "jsonrpc": JSONRPC_VERSION, "id": verify_id, "result": outcome })
+                } else {
+                    json!({
+                        "jsonrpc": JSONRPC_VERSION,
+                        "id": verify_id,
+                        "error": {
+                            "code": VERIFICATION_FAILED_CODE,
+                            "message": "rejected",
+                            "data": outcome,
+                        },
+                    })
+                };
+                prop_assert_eq!(
+                    written_frames(stream).await,
+                    vec![
+                        json!({
+                            "jsonrpc": JSONRPC_VERSION,
+                            "id": init_id,
+                            "result": {
+                                "nextMethod": METHOD_SESSION_NOTARIZE,
+                                "sessionNonce": session_nonce,
+                            },
+                        }),
+                        answer,
+                    ]
+                );
+                Ok(())
+            })?;
+        }
+    }
+
+    #[test]
+    fn test_plain_framing_round_trip() {
+        smol::block_on(async {
+            let session_init = SessionInit::new(TraceContext::new()).with_policy("balance");
+            let message = ProofMessage::new(stored_artifact("localhost").proof);
+            let mut stream = scripted_stream(encode_frames(&[
+                serde_json::to_value(&session_init).expect("session init should serialize"),
+                serde_json::to_value(&message).expect("proof message should serialize"),
+            ]));
+
+            let (mut framing, accepted, session_nonce) = Framing::accept_session(&mut stream, None)
+                .await
+                .expect("a bare opening frame should be accepted");
+            assert!(matches!(framing, Framing::Plain));
+            assert_eq!(accepted, session_init);
+            assert_eq!(
+                session_nonce, None,
+                "a bare frame has no reply to carry a nonce"
+            );
+            match framing
+                .read_proof_request(&mut stream)
+                .await
+                .expect("a bare proof message should be read")
+            {
+                ProofRequest::Verify(received) => assert_eq!(
+                    serde_json::to_value(received).expect("proof message should serialize"),
+                    serde_json::to_value(&message).expect("proof message should serialize")
+                ),
+                ProofRequest::Submit(params) => {
+                    panic!("a plain session can only verify, got {params:?}")
+                }
+            }
+
+            let outcome = test_outcome("localhost");
+            framing
+                .write_outcome(&mut stream, &outcome)
+                .await
+                .expect("the outcome should be written");
+            let submitted = SubmitResult {
+                job_id: Uuid::new_v4(),
+            };
+            framing
+                .write_submitted(&mut stream, &submitted)
+                .await
+                .expect("the job id should be written");
+            assert_eq!(
+                written_frames(stream).await,
+                [
+                    serde_json::to_value(&outcome).expect("outcome should serialize"),
+                    json!({ "jobId": submitted.job_id }),
+                ]
+            );
+        });
+    }
+
+    #[test]
+    fn test_fresh_trace_contexts_are_well_formed() {
+        let trace_context = TraceContext::new();
+        let trace_id = trace_context
+            .trace_id()
+            .expect("fresh trace id should be valid");
+        let span_id = trace_context
+            .parent_span_id()
+            .expect("fresh span id should be valid");
+
+        assert_eq!(
+            trace_context.traceparent,
+            format!("00-{trace_id}-{span_id}-01")
+        );
+        assert_ne!(
+            trace_context.traceparent,
+            TraceContext::new().traceparent,
+            "each trace context should be fresh"
+        );
+    }
+
+    #[test]
+    fn test_framing_refuses_malformed_opening_frames_without_replying() {
+        smol::block_on(async {
+            let session_init = SessionInit::new(TraceContext::new());
+            let json_frames: [(serde_json::Value, fn(&ProtocolError) -> bool); 5] = [
+                (
+                    json!({
+                        "jsonrpc": "1.0",
+                        "id": 1,
+                        "method": METHOD_SESSION_INIT,
+                        "params": session_init,
+                    }),
+                    |error| matches!(error, ProtocolError::InvalidRpc(_)),
+                ),
+                (
+                    json!({
+                        "jsonrpc": JSONRPC_VERSION,
+                        "id": 1,
+                        "method": METHOD_SESSION_VERIFY,
+                        "params": session_init,
+                    }),
+                    |error| matches!(error, ProtocolError::InvalidRpc(_)),
+                ),
+                (
+                    json!({ "jsonrpc": JSONRPC_VERSION, "id": 1, "method": METHOD_SESSION_INIT }),
+                    |error| matches!(error, ProtocolError::Json(_)),
+                ),
+                (json!({ "traceparent": "not-a-traceparent" }), |error| {
+                    matches!(error, ProtocolError::InvalidTraceContext(_))
+                }),
+                (
+                    json!({
+                        "jsonrpc": JSONRPC_VERSION,
+                        "id": 1,
+                        "method": METHOD_SESSION_INIT,
+                        "params": { "traceparent": "00-zz-zz-01" },
+                    }),
+                    |error| matches!(error, ProtocolError::InvalidTraceContext(_)),
+                ),
+            ];
+            let raw_frames: [(Vec<u8>, fn(&ProtocolError) -> bool); 3] = [
+                (b"\x00\x00\x00\x05hello".to_vec(), |error| {
+                    matches!(error, ProtocolError::Json(_))
+                }),
+                (u32::MAX.to_be_bytes().to_vec(), |error| {
+                    matches!(error, ProtocolError::FrameTooLarge(_))
+                }),
+                (b"\x00\x00\x00\x10{}".to_vec(), |error| {
+                    matches!(error, ProtocolError::Io(_))
+                }),
+            ];
+
+            let cases = json_frames
+                .into_iter()
+                .map(|(frame, expected)| (encode_frames(&[frame]), expected))
+                .chain(raw_frames);
+            for (input, expected) in cases {
+                let mut stream = scripted_stream(input.clone());
+                match Framing::accept_session(&mut stream, None).await {
+                    Ok((framing, session_init, _)) => {
+                        panic!("{input:?} should be refused, got {framing:?} with {session_init:?}")
+                    }
+                    Err(error) => assert!(expected(&error), "{input:?} failed with {error}"),
+                }
+                assert_eq!(
+                    written_frames(stream).await,
+                    Vec::<serde_json::Value>::new(),
+                    "{input:?} should get no reply"
+                );
+            }
+        });
+    }
+
+    #[test]
+    fn test_json_rpc_framing_refuses_unknown_proof_methods() {
+        smol::block_on(async {
+            let message = ProofMessage::new(stored_artifact("localhost").proof);
+            let requests: [(serde_json::Value, fn(&ProtocolError) -> bool); 3] = [
+                (
+                    json!({
+                        "jsonrpc": JSONRPC_VERSION,
+                        "id": 2,
+                        "method": "session.cancel",
+                        "params": message,
+                    }),
+                    |error| matches!(error, ProtocolError::InvalidRpc(_)),
+                ),
+                (
+                    json!({
+                        "jsonrpc": "1.0",
+                        "id": 2,
+                        "method": METHOD_SESSION_VERIFY,
+                        "params": message,
+                    }),
+                    |error| matches!(error, ProtocolError::InvalidRpc(_)),
+                ),
+                (
+                    json!({
+                        "jsonrpc": JSONRPC_VERSION,
+                        "id": 2,
+                        "method": METHOD_SESSION_VERIFY,
+                        "params": { "proof": "not a proof" },
+                    }),
+                    |error| matches!(error, ProtocolError::Json(_)),
+                ),
+            ];
+            for (request, expected) in requests {
+                let mut stream = scripted_stream(encode_frames(&[request.clone()]));
+                let mut framing = Framing::JsonRpc { id: json!(1) };
+                match framing.read_proof_request(&mut stream).await {
+                    Ok(proof_request) => {
+                        panic!("{request} should be refused, got {proof_request:?}")
+                    }
+                    Err(error) => assert!(expected(&error), "{request} failed with {error}"),
+                }
+            }
+
+            let params = SubmitParams {
+                proof: message.proof,
+                thresholds: BTreeMap::new(),
+                webhook_url: None,
+                idempotency_key: Some("retry-1".to_string()),
+            };
+            let mut stream = scripted_stream(encode_frames(&[json!({
+                "jsonrpc": JSONRPC_VERSION,
+                "id": "submit-9",
+                "method": METHOD_SESSION_SUBMIT,
+                "params": params,
+            })]));
+            let mut framing = Framing::JsonRpc { id: json!(1) };
+            match framing
+                .read_proof_request(&mut stream)
+                .await
+                .expect("a session.submit request should be read")
+            {
+                ProofRequest::Submit(received) => {
+                    assert_eq!(received.idempotency_key.as_deref(), Some("retry-1"));
+                }
+                ProofRequest::Verify(message) => {
+                    panic!("session.submit should queue the proof, got {message:?}")
+                }
+            }
+            let submitted = SubmitResult {
+                job_id: Uuid::new_v4(),
+            };
+            framing
+                .write_submitted(&mut stream, &submitted)
+                .await
+                .expect("the job id should be written");
+            assert_eq!(
+                written_frames(stream).await,
+                [json!({
+                    "jsonrpc": JSONRPC_VERSION,
+                    "id": "submit-9",
+                    "result": { "jobId": submitted.job_id },
+                })]
+            );
+        });
+    }
+
+    /// A notarized request revealing each of `nonces` in its own
+    /// [`SESSION_NONCE_HEADER`].
+    fn request_with_nonces(nonces: &[&str]) -> Vec<u8> {
+        let headers = nonces
+            .iter()
+            .map(|nonce| format!("{SESSION_NONCE_HEADER}: {nonce}\r\n"))
+            .collect::<String>();
+        format!("GET /api/balance/alice HTTP/1.1\r\nhost: localhost\r\n{headers}\r\n").into_bytes()
+    }
+
+    fn check_nonce(binding: &NonceBinding, sent: &[u8]) -> Result<(), ProtocolError> {
+        let transcript = SharedTranscript::new(sent, b"HTTP/1.1 200 OK\r\n\r\n");
+        let (parsed_request, _) =
+            parse_transcript(&transcript).expect("notarized request should parse");
+        binding.check(&parsed_request, &transcript.sent_text())
+    }
+
+    proptest! {
+        #[test]
+        fn test_session_nonce_must_be_the_one_issued(
+            issued in "[0-9a-f]{32}",
+            replayed in "[0-9a-f]{32}",
+            required in any::<bool>(),
+        ) {
+            let binding = NonceBinding {
+                issued: Some(issued.clone()),
+                required,
+            };
+            prop_assert!(check_nonce(&binding, &request_with_nonces(&[&issued])).is_ok());
+            // A transcript notarized in a session that was issued `replayed`.
+            let replay = check_nonce(&binding, &request_with_nonces(&[&replayed]));
+            prop_assert_eq!(replay.is_ok(), replayed == issued, "replay gave {:?}", replay);
+            let both = check_nonce(&binding, &request_with_nonces(&[&issued, &replayed]));
+            prop_assert_eq!(both.is_ok(), replayed == issued, "both nonces gave {:?}", both);
+        }
+    }
+
+    #[test]
+    fn test_session_nonce_requirement_covers_unrevealed_and_unissued_nonces() {
+        let issued = Uuid::new_v4().simple().to_string();
+        let revealed = request_with_nonces(&[&issued]);
+        let redacted = redact_with(
+            &String::from_utf8(revealed.clone()).expect("request should be UTF-8"),
+            &issued,
+            0,
+        );
+        for required in [false, true] {
+            let binding = NonceBinding {
+                issued: Some(issued.clone()),
+                required,
+            };
+            assert!(binding.check_issued().is_ok());
+            for unrevealed in [request_with_nonces(&[]), redacted.clone()] {
+                let checked = check_nonce(&binding, &unrevealed);
+                assert_eq!(
+                    checked.is_ok(),
+                    !required,
+                    "required = {required}: {checked:?}"
+                );
+            }
+        }
+
+        // Bare-frame sessions are issued no nonce, so revealing one is a
+        // replay and requiring one refuses them outright.
+        let unissued = NonceBinding {
+            issued: None,
+            required: false,
+        };
+        assert!(unissued.check_issued().is_ok());
+        assert!(check_nonce(&unissued, &request_with_nonces(&[])).is_ok());
+        assert!(matches!(
+            check_nonce(&unissued, &revealed),
+            Err(ProtocolError::SessionNonce(_))
+        ));
+        let required = NonceBinding {
+            issued: None,
+            required: true,
+        };
+        assert!(matches!(
+            required.check_issued(),
+            Err(ProtocolError::SessionNonce(_))
+        ));
+    }
+
+    #[test]
+    fn test_required_nonce_refuses_bare_frame_sessions_when_they_open() {
+        smol::block_on(async {
+            let (service_socket, prover_socket) = tokio::io::duplex(1 << 16);
+            let service = smol::spawn(run_notarize_and_verify_stream(
+                service_socket,
+                ServiceOptions {
+                    require_session_nonce: true,
+                    ..ServiceOptions::default()
+                },
+            ));
+            let mut prover = Compat::new(prover_socket);
+            SessionInit::new(TraceContext::new())
+                .write_to(&mut prover)
+                .await
+                .expect("opening frame should be sent");
+
+            let outcome = VerificationOutcome::read_from(&mut prover)
+                .await
+                .expect("the refusal should be reported");
+            assert!(!outcome.success);
+            let refused = service.await;
+            assert!(
+                matches!(refused, Err(ProtocolError::SessionNonce(_))),
+                "got {refused:?}"
+            );
+        });
+    }
+
+    fn mpc_proposal(max_sent_data: usize, max_recv_data: usize) -> TlsCommitProtocolConfig {
+        TlsCommitProtocolConfig::Mpc(
+            MpcTlsConfig::builder()
+                .max_sent_data(max_sent_data)
+                .max_recv_data(max_recv_data)
+                .build()
+                .expect("MPC-TLS config should build"),
+        )
+    }
+
+    fn is_violation_of(checked: Result<(), ProtocolError>, name: &str) -> bool {
+        matches!(checked, Err(ProtocolError::PolicyViolation { policy, .. }) if policy == name)
+    }
+
+    proptest! {
+        #[test]
+        fn test_requested_policy_takes_precedence_over_the_default(
+            defined in proptest::collection::btree_set("[a-c]{1,2}", 0..4),
+            requested in proptest::option::of("[a-c]{1,2}"),
+            default in proptest::option::of("[a-c]{1,2}"),
+        ) {
+            let policies = defined.iter().fold(PolicySet::default(), |policies, name| {
+                policies.with_policy(NotarizationPolicy::new(name.as_str()))
+            });
+            let policies = match &default {
+                Some(default) => policies.with_default_policy(default.as_str()),
+                None => policies,
+            };
+
+            let resolved = policies
+                .resolve(requested.as_deref())
+                .map(|policy| policy.map(|policy| policy.name.clone()))
+                .map_err(|error| error.to_string());
+            let expected = match requested.as_ref().or(default.as_ref()) {
+                Some(name) if defined.contains(name) => Ok(Some(name.clone())),
+                Some(name) => Err(ProtocolError::UnknownPolicy(name.clone()).to_string()),
+                None => Ok(None),
+            };
+            prop_assert_eq!(resolved, expected);
+        }
+    }
+
+    #[test]
+    fn test_policy_set_replaces_same_named_policies_and_checks_its_default() {
+        let policies = PolicySet::default()
+            .with_policy(NotarizationPolicy::new("balance").allowed_host("old.example"))
+            .with_policy(NotarizationPolicy::new("balance").allowed_host("localhost"));
+        assert_eq!(
+            policies.policies,
+            [NotarizationPolicy::new("balance").allowed_host("localhost")]
+        );
+
+        let path = std::env::temp_dir().join(format!("verifier-policies-{}.json", Uuid::new_v4()));
+        std::fs::write(
+            &path,
+            r#"{"defaultPolicy": "missing", "policies": [{"name": "balance"}]}"#,
+        )
+        .expect("policy file should be written");
+        let loaded = PolicySet::from_json_file(&path);
+        assert!(
+            matches!(loaded, Err(ProtocolError::InvalidConfig(_))),
+            "an undefined default should be refused, got {loaded:?}"
+        );
+
+        std::fs::write(
+            &path,
+            r#"{
+                "defaultPolicy": "balance",
+                "policies": [{
+                    "name": "balance",
+                    "allowedHosts": ["localhost"],
+                    "maxSentData": 4096,
+                    "provenFields": [".balance"]
+                }]
+            }"#,
+        )
+        .expect("policy file should be written");
+        let policies = PolicySet::from_json_file(&path).expect("policy file should load");
+        assert_eq!(
+            policies
+                .resolve(None)
+                .expect("the default policy should resolve"),
+            Some(
+                &NotarizationPolicy::new("balance")
+                    .allowed_host("localhost")
+                    .max_sent_data(4096)
+                    .proven_field(".balance")
+            )
+        );
+        std::fs::remove_file(&path).expect("policy file should be removed");
+    }
+
+    #[test]
+    fn test_policy_matches_hosts_and_data_limits() {
+        let policy = NotarizationPolicy::new("balance")
+            .allowed_host("LocalHost")
+            .max_sent_data(4096)
+            .max_recv_data(16384);
+        let open = NotarizationPolicy::new("open");
+
+        assert!(policy.check_server_name(Some("localhost")).is_ok());
+        assert!(is_violation_of(
+            policy.check_server_name(Some("evil.example")),
+            "balance"
+        ));
+        assert!(is_violation_of(policy.check_server_name(None), "balance"));
+        assert!(open.check_server_name(Some("evil.example")).is_ok());
+        assert!(open.check_server_name(None).is_ok());
+
+        assert!(policy.check_protocol(&mpc_proposal(4096, 16384)).is_ok());
+        assert!(is_violation_of(
+            policy.check_protocol(&mpc_proposal(4097, 16384)),
+            "balance"
+        ));
+        assert!(is_violation_of(
+            policy.check_protocol(&mpc_proposal(4096, 16385)),
+            "balance"
+        ));
+        assert!(
+            open.check_protocol(&mpc_proposal(MAX_SENT_DATA, MAX_RECV_DATA))
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_policy_rejects_sessions_that_hide_or_skip_required_fields() {
+        let sent = "GET /api/balance/alice HTTP/1.1\r\nhost: localhost\r\nauthorization: Bearer secret-token\r\n\r\n";
+        let received = "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\n\r\n{\"username\":\"alice\",\"balance\":100}";
+        let parse = |sent: &[u8], received: &[u8]| {
+            parse_transcript(&SharedTranscript::new(sent, received))
+                .expect("transcript should parse")
+        };
+        let (request, response) = parse(sent.as_bytes(), received.as_bytes());
+        let (hidden_request, hidden_response) = parse(
+            &redact_with(sent, "Bearer secret-token", 0),
+            &redact_with(received, "\"alice\"", 0),
+        );
+        let no_commitments = HashMap::<String, zktlsn::BoundCommitment>::new();
+
+        let policy = NotarizationPolicy::new("balance")
+            .revealed_request_header("Authorization")
+            .revealed_response_field(".username");
+        assert!(
+            policy
+                .check_disclosures(&request, &response, &no_commitments)
+                .is_ok()
+        );
+        assert!(is_violation_of(
+            policy.check_disclosures(&hidden_request, &response, &no_commitments),
+            "balance"
+        ));
+        assert!(is_violation_of(
+            policy.check_disclosures(&request, &hidden_response, &no_commitments),
+            "balance"
+        ));
+
+        let absent = NotarizationPolicy::new("absent").revealed_response_field(".email");
+        assert!(is_violation_of(
+            absent.check_disclosures(&request, &response, &no_commitments),
+            "absent"
+        ));
+        let committed = NotarizationPolicy::new("committed").committed_response_field(".balance");
+        assert!(is_violation_of(
+            committed.check_disclosures(&request, &response, &no_commitments),
+            "committed"
+        ));
+
+        let proven = NotarizationPolicy::new("proven").proven_field(".balance");
+        assert!(
+            proven
+                .check_proven_fields(&[".balance".to_string()])
+                .is_ok()
+        );
+        assert!(is_violation_of(proven.check_proven_fields(&[]), "proven"));
+    }
+
+    #[test]
+    fn test_sessions_naming_an_unknown_policy_are_refused_when_they_open() {
+        smol::block_on(async {
+            let policies = PolicySet::default().with_policy(NotarizationPolicy::new("balance"));
+            let (service_socket, prover_socket) = tokio::io::duplex(1 << 16);
+            let service = smol::spawn(run_notarize_and_verify_stream(
+                service_socket,
+                ServiceOptions {
+                    policies: Some(std::sync::Arc::new(policies)),
+                    ..ServiceOptions::default()
+                },
+            ));
+            let mut prover = Compat::new(prover_socket);
+            SessionInit::new(TraceContext::new())
+                .with_policy("missing")
+                .write_to(&mut prover)
+                .await
+                .expect("opening frame should be sent");
+
+            let outcome = VerificationOutcome::read_from(&mut prover)
+                .await
+                .expect("the refusal should be reported");
+            assert!(!outcome.success);
+            assert!(outcome.message.contains("missing"), "{}", outcome.message);
+            let refused = service.await;
+            assert!(
+                matches!(&refused, Err(ProtocolError::UnknownPolicy(name)) if name == "missing"),
+                "got {refused:?}"
+            );
+        });
+    }
+
+    #[test]
+    fn test_client_reports_failed_verifications_as_outcomes() {
+        smol::block_on(async {
+            let (client_socket, service_socket) = tokio::io::duplex(1 << 16);
+            let service = smol::spawn(async move {
+                let mut io = Compat::new(service_socket);
+                let (mut framing, _, session_nonce) =
+                    Framing::accept_session(&mut io, None).await?;
+                let request = framing.read_proof_request(&mut io).await?;
+                let outcome = VerificationOutcome::failure(
+                    "localhost".to_string(),
+                    "proof rejected".to_string(),
+                );
+                framing.write_outcome(&mut io, &outcome).await?;
+                Ok::<_, ProtocolError>((session_nonce, request))
+            });
+
+            let stream = NotarizeStream::init(
+                Compat::new(client_socket),
+                &SessionInit::new(TraceContext::new()),
+            )
+            .await
+            .expect("session.init should be answered");
+            let session_nonce = stream.session_nonce().map(str::to_string);
+            let outcome = stream
+                .submit_verification(stored_artifact("localhost").proof)
+                .await
+                .expect("a failed verification should still be an outcome");
+            assert!(!outcome.success);
+            assert_eq!(outcome.message, "proof rejected");
+
+            let (issued, request) = service.await.expect("the service side should finish");
+            assert!(session_nonce.is_some());
+            assert_eq!(session_nonce, issued);
+            assert!(matches!(request, ProofRequest::Verify(_)));
+        });
+    }
+
+    #[test]
+    fn test_client_queues_verification_and_reads_the_job_id() {
+        smol::block_on(async {
+            let (client_socket, service_socket) = tokio::io::duplex(1 << 16);
+            let job_id = Uuid::new_v4();
+            let service = smol::spawn(async move {
+                let mut io = Compat::new(service_socket);
+                let (mut framing, ..) = Framing::accept_session(&mut io, None).await?;
+                let request = framing.read_proof_request(&mut io).await?;
+                framing
+                    .write_submitted(&mut io, &SubmitResult { job_id })
+                    .await?;
+                Ok::<_, ProtocolError>(request)
+            });
+
+            let stream = NotarizeStream::init(
+                Compat::new(client_socket),
+                &SessionInit::new(TraceContext::new()),
+            )
+            .await
+            .expect("session.init should be answered");
+            let params = SubmitParams {
+                proof: stored_artifact("localhost").proof,
+                thresholds: BTreeMap::new(),
+                webhook_url: None,
+                idempotency_key: Some("retry-1".to_string()),
+            };
+            let submitted = stream
+                .queue_verification(&params)
+                .await
+                .expect("session.submit should be answered");
+            assert_eq!(submitted.job_id, job_id);
+
+            let request = service.await.expect("the service side should finish");
+            assert!(
+                matches!(
+                    &request,
+                    ProofRequest::Submit(SubmitParams { idempotency_key: Some(key), .. })
+                        if key == "retry-1"
+                ),
+                "queue_verification should submit, got {request:?}"
+            );
+        });
+    }
+
+    async fn init_against(answer: serde_json::Value) -> Result<(), ClientError> {
+        NotarizeStream::init(
+            scripted_stream(encode_frames(&[answer])),
+            &SessionInit::new(TraceContext::new()),
+        )
+        .await
+        .map(drop)
+    }
+
+    #[test]
+    fn test_client_refuses_unexpected_init_answers() {
+        smol::block_on(async {
+            let verify_next = init_against(json!({
+                "jsonrpc": JSONRPC_VERSION,
+                "id": 0,
+                "result": { "nextMethod": METHOD_SESSION_VERIFY },
+            }))
+            .await;
+            assert!(
+                matches!(
+                    &verify_next,
+                    Err(ClientError::UnexpectedMethod(method)) if method == METHOD_SESSION_VERIFY
+                ),
+                "got {verify_next:?}"
+            );
+
+            let wrong_id = init_against(json!({
+                "jsonrpc": JSONRPC_VERSION,
+                "id": 5,
+                "result": { "nextMethod": METHOD_SESSION_NOTARIZE },
+            }))
+            .await;
+            assert!(
+                matches!(
+                    wrong_id,
+                    Err(ClientError::Protocol(ProtocolError::InvalidRpc(_)))
+                ),
+                "got {wrong_id:?}"
+            );
+
+            let busy = init_against(json!({
+                "jsonrpc": JSONRPC_VERSION,
+                "id": 0,
+                "error": { "code": SERVER_BUSY_CODE, "message": "shedding load" },
+            }))
+            .await;
+            assert!(
+                matches!(
+                    &busy,
+                    Err(ClientError::Protocol(ProtocolError::ProofVerificationFailed(message)))
+                        if message == "shedding load"
+                ),
+                "got {busy:?}"
+            );
+        });
+    }
+
+    #[test]
+    fn test_client_round_trip_through_the_service_pipeline() {
+        smol::block_on(async {
+            let TestTlsConfig {
+                server_config,
+                cert_bytes,
+                ..
+            } = get_or_create_test_tls_config(
+                Path::new(ORIGIN_CERT_PATH),
+                Path::new(ORIGIN_KEY_PATH),
+            )
+            .expect("the origin certificate should load");
+            let (origin_socket, prover_origin_socket) = tokio::io::duplex(1 << 16);
+            let (service_socket, prover_service_socket) = tokio::io::duplex(1 << 16);
+            let origin = smol::spawn(server::handle_connection(
+                server::app::get_app(HashMap::from([("alice".to_string(), 100)])),
+                server_config,
+                Compat::new(origin_socket),
+            ));
+            let service = smol::spawn(run_notarize_and_verify_stream(
+                service_socket,
+                ServiceOptions {
+                    require_session_nonce: true,
+                    ..ServiceOptions::default()
+                },
+            ));
+
+            let stream = NotarizeStream::init(
+                Compat::new(prover_service_socket),
+                &SessionInit::new(TraceContext::new()),
+            )
+            .await
+            .expect("session.init should be answered");
+            let request = balance_request("alice", stream.session_nonce())
+                .expect("the balance request should build");
+            let (prover_output, stream) = balance_prover(request, cert_bytes)
+                .expect("the prover should build")
+                .prove_and_reclaim(stream, Compat::new(prover_origin_socket))
+                .await
+                .expect("MPC-TLS with the origin should succeed");
+            let proof = generate_proof(
+                &prover_output.transcript_commitments,
+                &prover_output.transcript_secrets,
+                &prover_output.received,
+                PaddingConfig::new(BALANCE_COMMITMENT_LENGTH),
+            )
+            .expect("the balance proof should be generated");
+            let threshold = ThresholdClaim {
+                predicate: tlsnotary::Predicate::GreaterThan(50),
+                proof: generate_predicate_proof(
+                    &prover_output.transcript_commitments,
+                    &prover_output.transcript_secrets,
+                    &prover_output.received,
+                    ".balance",
+                    tlsnotary::Predicate::GreaterThan(50),
+                )
+                .expect("the threshold proof should be generated"),
+            };
+            let outcome = stream
+                .submit_proof_message(
+                    ProofMessage::new(proof).with_threshold(".balance", threshold),
+                )
+                .await
+                .expect("verification should be answered");
+
+            assert!(outcome.success, "{}", outcome.message);
+            assert_eq!(outcome.server_name, "localhost");
+            assert!(outcome.verified_fields.contains(&".balance".to_string()));
+            assert_eq!(
+                outcome.field_disclosures.get(".username"),
+                Some(&FieldDisclosure::Revealed)
+            );
+            assert_eq!(
+                outcome.field_disclosures.get(".balance"),
+                Some(&FieldDisclosure::Committed)
+            );
+            service.await.expect("the session should succeed");
+            origin.await.expect("the origin should serve the request");
+        });
+    }
+
+    #[test]
+    fn test_self_test_passes() {
+        smol::block_on(async {
+            run_self_test(&ServiceOptions::default())
+                .await
+                .expect("the self-test should pass");
+            run_self_test(&ServiceOptions {
+                require_session_nonce: true,
+                ..ServiceOptions::default()
+            })
+            .await
+            .expect("the self-test should reveal the session nonce");
+        });
+    }
+
+    #[test]
+    fn test_self_test_fails_over_traffic_limits() {
+        smol::block_on(async {
+            let result = run_self_test(&ServiceOptions {
+                traffic_limits: Some(TrafficLimits {
+                    max_bytes_received: 1 << 10,
+                    max_bytes_sent: 1 << 10,
+                }),
+                ..ServiceOptions::default()
+            })
+            .await;
+            assert!(
+                matches!(result, Err(ProtocolError::SelfTest { .. })),
+                "a session over its traffic limits should fail the self-test, got {result:?}"
+            );
+        });
+    }
+
+    #[test]
+    fn test_service_tolerates_a_withheld_server_identity() {
+        smol::block_on(async {
+            let TestTlsConfig {
+                server_config,
+                cert_bytes,
+                ..
+            } = get_or_create_test_tls_config(
+                Path::new(ORIGIN_CERT_PATH),
+                Path::new(ORIGIN_KEY_PATH),
+            )
+            .expect("the origin certificate should load");
+            let (origin_socket, prover_origin_socket) = tokio::io::duplex(1 << 16);
+            let (service_socket, prover_service_socket) = tokio::io::duplex(1 << 16);
+            let origin = smol::spawn(server::handle_connection(
+                server::app::get_app(HashMap::from([("alice".to_string(), 100)])),
+                server_config,
+                Compat::new(origin_socket),
+            ));
+            let service = smol::spawn(run_notarize_and_verify_stream(
+                service_socket,
+                ServiceOptions::default(),
+            ));
+
+            let stream = NotarizeStream::init(
+                Compat::new(prover_service_socket),
+                &SessionInit::new(TraceContext::new()),
+            )
+            .await
+            .expect("session.init should be answered");
+            let request = balance_request("alice", stream.session_nonce())
+                .expect("the balance request should build");
+            let (prover_output, stream) = balance_prover_builder(request, cert_bytes)
+                .expect("the prover config should build")
+                .prove_server_identity(false)
+                .build()
+                .expect("the prover should build")
+                .prove_and_reclaim(stream, Compat::new(prover_origin_socket))
+                .await
+                .expect("MPC-TLS with the origin should succeed");
+            let proof = generate_proof(
+                &prover_output.transcript_commitments,
+                &prover_output.transcript_secrets,
+                &prover_output.received,
+                PaddingConfig::new(BALANCE_COMMITMENT_LENGTH),
+            )
+            .expect("the balance proof should be generated");
+            let outcome = stream
+                .submit_verification(proof)
+                .await
+                .expect("verification should be answered");
+
+            assert!(outcome.success, "{}", outcome.message);
+            assert_eq!(outcome.server_name, "");
+            service
+                .await
+                .expect("a session without a proven server identity should succeed");
+            origin.await.expect("the origin should serve the request");
+        });
+    }
+}
+
+#[cfg(all(test, feature = "simulation"))]
+mod simulation {
+    use std::{collections::HashMap, path::Path};
+
+    use async_compat::Compat;
+    use shared::{TestTlsConfig, get_or_create_test_tls_config};
+    use tlsnotary::{Verifier, VerifierOutput};
+    use zktlsn::{PaddingConfig, generate_proof};
+
+    use super::*;
+    use crate::{
+        ClientError, FieldDisclosure, MAX_SENT_DATA, NotarizeStream, ProtocolError,
+        RecordedSession, ServiceOptions, SessionInit, TraceContext,
+        protocol::{
+            ORIGIN_CERT_PATH, ORIGIN_KEY_PATH, create_verifier_config,
+            run_notarize_and_verify_stream, write_json_frame,
+        },
+        selftest::{BALANCE_COMMITMENT_LENGTH, balance_prover, balance_request},
+    };
+
+    const PIPE_CAPACITY: usize = 1 << 16;
+    const SENT: &[u8] = b"GET /api/balance/alice HTTP/1.1\r\nhost: localhost\r\n\r\n";
+
+    /// Notarizes alice's balance with the library verifier, as the sessions
+    /// a recording is taken from are run, and proves the committed balance.
+    async fn notarized_balance() -> (VerifierOutput, Proof) {
+        let TestTlsConfig {
+            server_config,
+            cert_bytes,
+            ..
+        } = get_or_create_test_tls_config(Path::new(ORIGIN_CERT_PATH), Path::new(ORIGIN_KEY_PATH))
+            .expect("the origin certificate should load");
+        let (origin_socket, prover_origin_socket) = tokio::io::duplex(PIPE_CAPACITY);
+        let (verifier_socket, prover_verifier_socket) = tokio::io::duplex(PIPE_CAPACITY);
+        let origin = smol::spawn(server::handle_connection(
+            server::app::get_app(HashMap::from([("alice".to_string(), 100)])),
+            server_config,
+            Compat::new(origin_socket),
+        ));
+        let verifier = smol::spawn(
+            Verifier::builder()
+                .verifier_config(create_verifier_config().expect("verifier config should build"))
+                .build()
+                .expect("the verifier should build")
+                .verify(Compat::new(verifier_socket)),
+        );
+
+        let request = balance_request("alice", None).expect("the balance request should build");
+        let prover_output = balance_prover(request, cert_bytes)
+            .expect("the prover should build")
+            .prove(
+                Compat::new(prover_verifier_socket),
+                Compat::new(prover_origin_socket),
+            )
+            .await
+            .expect("MPC-TLS with the origin should succeed");
+        let verifier_output = verifier.await.expect("the verifier should finish");
+        origin.await.expect("the origin should serve the request");
+
+        let proof = generate_proof(
+            &prover_output.transcript_commitments,
+            &prover_output.transcript_secrets,
+            &prover_output.received,
+            PaddingConfig::new(BALANCE_COMMITMENT_LENGTH),
+        )
+        .expect("the balance proof should be generated");
+        (verifier_output, proof)
+    }
+
+    /// Replays `recorded` through a simulating service and submits `proof`,
+    /// returning the client's outcome and the service's result.
+    async fn replay(
+        recorded: &RecordedSession,
+        proof: Proof,
+    ) -> (
+        Result<VerificationOutcome, ClientError>,
+        Result<(), ProtocolError>,
+    ) {
+        let (client_socket, service_socket) = tokio::io::duplex(PIPE_CAPACITY);
+        let service = smol::spawn(run_notarize_and_verify_stream(
+            service_socket,
+            ServiceOptions {
+                simulate: true,
+                ..ServiceOptions::default()
+            },
+        ));
+        let mut stream = NotarizeStream::init(
+            Compat::new(client_socket),
+            &SessionInit::new(TraceContext::new()),
+        )
+        .await
+        .expect("session.init should be answered");
+        let outcome = match recorded.write_to(&mut stream).await {
+            Ok(()) => stream.submit_verification(proof).await,
+            Err(error) => Err(error.into()),
+        };
+        (outcome, service.await)
+    }
+
+    /// Sends `frame` where a simulating service expects the recording and
+    /// returns the service's result.
+    async fn replay_frame(frame: &impl serde::Serialize) -> Result<(), ProtocolError> {
+        let (client_socket, service_socket) = tokio::io::duplex(PIPE_CAPACITY);
+        let service = smol::spawn(run_notarize_and_verify_stream(
+            service_socket,
+            ServiceOptions {
+                simulate: true,
+                ..ServiceOptions::default()
+            },
+        ));
+        let mut stream = NotarizeStream::init(
+            Compat::new(client_socket),
+            &SessionInit::new(TraceContext::new()),
+        )
+        .await
+        .expect("session.init should be answered");
+        write_json_frame(&mut stream, frame)
+            .await
+            .expect("the frame should be sent");
+        service.await
+    }
+
+    fn recording(sent: &[u8], received: &[u8]) -> RecordedSession {
+        RecordedSession {
+            server_name: Some("localhost".to_string()),
+            sent: sent.to_vec(),
+            received: received.to_vec(),
+            transcript_commitments: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_replayed_session_verifies_like_the_notarized_one() {
+        smol::block_on(async {
+            let (verifier_output, proof) = notarized_balance().await;
+            let recorded = RecordedSession::from_verifier_output(&verifier_output);
+            assert_eq!(recorded.server_name, verifier_output.server_name);
+            assert_eq!(recorded.sent, verifier_output.transcript.sent_unsafe());
+            assert_eq!(
+                recorded.received,
+                verifier_output.transcript.received_unsafe()
+            );
+            assert_eq!(
+                serde_json::to_value(&recorded.transcript_commitments)
+                    .expect("commitments should serialize"),
+                serde_json::to_value(&verifier_output.transcript_commitments)
+                    .expect("commitments should serialize"),
+            );
+
+            let (outcome, served) = replay(&recorded, proof.clone()).await;
+            served.expect("the replayed session should succeed");
+            let outcome = outcome.expect("verification should be answered");
+            assert!(outcome.success, "{}", outcome.message);
+            assert_eq!(outcome.server_name, "localhost");
+            assert!(outcome.verified_fields.contains(&".balance".to_string()));
+            assert_eq!(
+                outcome.field_disclosures.get(".username"),
+                Some(&FieldDisclosure::Revealed)
+            );
+            assert_eq!(
+                outcome.field_disclosures.get(".balance"),
+                Some(&FieldDisclosure::Committed)
+            );
+
+            let unnamed = RecordedSession {
+                server_name: None,
+                ..recorded.clone()
+            };
+            let (outcome, served) = replay(&unnamed, proof.clone()).await;
+            served.expect("a session without a proven server identity should succeed");
+            let outcome = outcome.expect("verification should be answered");
+            assert!(outcome.success, "{}", outcome.message);
+            assert_eq!(outcome.server_name, "");
+
+            let uncommitted = RecordedSession {
+                transcript_commitments: Vec::new(),
+                ..recorded
+            };
+            let (outcome, served) = replay(&uncommitted, proof).await;
+            assert!(
+                matches!(&outcome, Ok(VerificationOutcome { success: false, .. })),
+                "a recording without commitments should fail verification, got {outcome:?}"
+            );
+            assert!(
+                matches!(served, Err(ProtocolError::NoCommitmentsFound)),
+                "got {served:?}"
+            );
+        });
+    }
+
+    #[test]
+    fn test_replay_refuses_invalid_recordings() {
+        smol::block_on(async {
+            let malformed = replay_frame(&serde_json::json!({ "serverName": "localhost" })).await;
+            assert!(
+                matches!(malformed, Err(ProtocolError::Json(_))),
+                "got {malformed:?}"
+            );
+
+            let not_http = replay_frame(&recording(SENT, b"not an http response")).await;
+            assert!(
+                matches!(not_http, Err(ProtocolError::ResponseParse(_))),
+                "got {not_http:?}"
+            );
+
+            let oversized_sent = [SENT, vec![b'a'; MAX_SENT_DATA].as_slice()].concat();
+            let oversized = replay_frame(&recording(
+                &oversized_sent,
+                b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n",
+            ))
+            .await;
+            assert!(
+                matches!(oversized, Err(ProtocolError::InvalidConfig(_))),
+                "got {oversized:?}"
+            );
+        });
+    }
+}
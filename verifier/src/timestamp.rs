@@ -0,0 +1,321 @@
+use std::{collections::HashMap, net::UdpSocket, time::Duration};
+
+use chrono::{DateTime, Utc};
+use ring::{
+    digest::{SHA512, digest},
+    signature::{ED25519, UnparsedPublicKey},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::errors::TimestampError;
+
+const ROUGHTIME_TIMEOUT: Duration = Duration::from_secs(3);
+/// Requests are padded to this size so servers cannot amplify them.
+const ROUGHTIME_REQUEST_LEN: usize = 1024;
+const NONCE_LEN: usize = 64;
+const DELEGATION_CONTEXT: &[u8] = b"RoughTime v1 delegation signature--\0";
+const RESPONSE_CONTEXT: &[u8] = b"RoughTime v1 response signature\0";
+const NONCE_CONTEXT: &str = "zk-tlsn-example 2026-10 attestation timestamp nonce";
+
+const TAG_NONC: [u8; 4] = *b"NONC";
+const TAG_PAD: [u8; 4] = *b"PAD\xff";
+const TAG_SIG: [u8; 4] = *b"SIG\0";
+const TAG_SREP: [u8; 4] = *b"SREP";
+const TAG_CERT: [u8; 4] = *b"CERT";
+const TAG_INDX: [u8; 4] = *b"INDX";
+const TAG_PATH: [u8; 4] = *b"PATH";
+const TAG_ROOT: [u8; 4] = *b"ROOT";
+const TAG_MIDP: [u8; 4] = *b"MIDP";
+const TAG_RADI: [u8; 4] = *b"RADI";
+const TAG_DELE: [u8; 4] = *b"DELE";
+const TAG_MINT: [u8; 4] = *b"MINT";
+const TAG_MAXT: [u8; 4] = *b"MAXT";
+const TAG_PUBK: [u8; 4] = *b"PUBK";
+
+/// A Roughtime server speaking the original (Google) protocol.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct RoughtimeServer {
+    pub addr: String,
+    /// Hex Ed25519 long-term public key.
+    pub public_key: String,
+}
+
+/// Where attestations get their time: the local clock, corroborated by a
+/// Roughtime server when one is configured.
+#[derive(Debug, Clone, Default)]
+pub struct TimeSource {
+    roughtime: Option<RoughtimeServer>,
+}
+
+impl TimeSource {
+    pub fn local() -> Self {
+        Self::default()
+    }
+
+    pub fn roughtime(server: RoughtimeServer) -> Self {
+        Self {
+            roughtime: Some(server),
+        }
+    }
+
+    /// Stamps the attestation with blake3 digest `digest`. A Roughtime
+    /// server signs a nonce derived from the digest, proving the attestation
+    /// existed by the time it answered. Blocks for the round trip.
+    pub fn stamp(&self, digest: &blake3::Hash) -> Result<NotaryTimestamp, TimestampError> {
+        let local_time = Utc::now();
+        let Some(server) = &self.roughtime else {
+            return Ok(NotaryTimestamp {
+                local_time,
+                roughtime: None,
+            });
+        };
+
+        let nonce = attestation_nonce(digest);
+        let socket = UdpSocket::bind(("::", 0)).or_else(|_| UdpSocket::bind(("0.0.0.0", 0)))?;
+        socket.set_read_timeout(Some(ROUGHTIME_TIMEOUT))?;
+        socket.connect(&server.addr)?;
+        socket.send(&roughtime_request(&nonce)?)?;
+        let mut response = vec![0; 4096];
+        let len = socket.recv(&mut response)?;
+        response.truncate(len);
+
+        let public_key = decode_hex(&server.public_key)
+            .ok_or_else(|| TimestampError::InvalidKey(server.public_key.clone()))?;
+        let (midpoint, radius_micros) = verify_response(&public_key, &nonce, &response)?;
+        Ok(NotaryTimestamp {
+            local_time,
+            roughtime: Some(RoughtimeAttestation {
+                server: server.addr.clone(),
+                public_key: server.public_key.clone(),
+                midpoint,
+                radius_micros,
+                response: hex(&response),
+            }),
+        })
+    }
+}
+
+/// When an attestation was issued. Recorded alongside it rather than hashed
+/// into its digest, which the Roughtime nonce is derived from; the notary
+/// signature covers both.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotaryTimestamp {
+    /// The verifier's clock, which nothing but the verifier vouches for.
+    pub local_time: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub roughtime: Option<RoughtimeAttestation>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RoughtimeAttestation {
+    pub server: String,
+    /// Hex Ed25519 long-term key the response was checked against.
+    pub public_key: String,
+    pub midpoint: DateTime<Utc>,
+    /// The server's uncertainty either side of `midpoint`.
+    pub radius_micros: u32,
+    /// Hex signed response, so consumers can re-check it with
+    /// [`NotaryTimestamp::verify`].
+    pub response: String,
+}
+
+impl NotaryTimestamp {
+    /// The Roughtime midpoint when attested, the local clock otherwise.
+    pub fn time(&self) -> DateTime<Utc> {
+        self.roughtime
+            .as_ref()
+            .map_or(self.local_time, |roughtime| roughtime.midpoint)
+    }
+
+    /// Re-checks the Roughtime response against the server key and the
+    /// digest of the attestation it claims to stamp. Local timestamps carry
+    /// no signature and always pass.
+    pub fn verify(&self, digest: &blake3::Hash) -> Result<(), TimestampError> {
+        let Some(roughtime) = &self.roughtime else {
+            return Ok(());
+        };
+        let public_key = decode_hex(&roughtime.public_key)
+            .ok_or_else(|| TimestampError::InvalidKey(roughtime.public_key.clone()))?;
+        let response = decode_hex(&roughtime.response)
+            .ok_or(TimestampError::Malformed("response is not hex"))?;
+        let (midpoint, radius_micros) =
+            verify_response(&public_key, &attestation_nonce(digest), &response)?;
+        if midpoint != roughtime.midpoint || radius_micros != roughtime.radius_micros {
+            return Err(TimestampError::Malformed(
+                "recorded time differs from the signed response",
+            ));
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn attestation_nonce(digest: &blake3::Hash) -> [u8; NONCE_LEN] {
+    let mut nonce = [0; NONCE_LEN];
+    blake3::Hasher::new_derive_key(NONCE_CONTEXT)
+        .update(digest.as_bytes())
+        .finalize_xof()
+        .fill(&mut nonce);
+    nonce
+}
+
+fn roughtime_request(nonce: &[u8; NONCE_LEN]) -> Result<Vec<u8>, TimestampError> {
+    // Two tags: count, one offset, both tags, then the values.
+    let pad_offset = u32::try_from(NONCE_LEN)
+        .map_err(|_| TimestampError::Malformed("nonce does not fit an offset"))?;
+    let mut request = Vec::with_capacity(ROUGHTIME_REQUEST_LEN);
+    request.extend_from_slice(&2u32.to_le_bytes());
+    request.extend_from_slice(&pad_offset.to_le_bytes());
+    request.extend_from_slice(&TAG_NONC);
+    request.extend_from_slice(&TAG_PAD);
+    request.extend_from_slice(nonce);
+    request.resize(ROUGHTIME_REQUEST_LEN, 0);
+    Ok(request)
+}
+
+/// Checks the delegation and response signatures and that `nonce` is in the
+/// signed Merkle tree; returns the midpoint and radius.
+pub(crate) fn verify_response(
+    public_key: &[u8],
+    nonce: &[u8; NONCE_LEN],
+    response: &[u8],
+) -> Result<(DateTime<Utc>, u32), TimestampError> {
+    let message = parse_message(response)?;
+    let signed_response = field(&message, TAG_SREP)?;
+    let cert = parse_message(field(&message, TAG_CERT)?)?;
+    let delegation = field(&cert, TAG_DELE)?;
+
+    let delegation_signed = [DELEGATION_CONTEXT, delegation].concat();
+    UnparsedPublicKey::new(&ED25519, public_key)
+        .verify(&delegation_signed, field(&cert, TAG_SIG)?)
+        .map_err(|_| TimestampError::BadSignature("delegation"))?;
+    let delegation = parse_message(delegation)?;
+    let response_signed = [RESPONSE_CONTEXT, signed_response].concat();
+    UnparsedPublicKey::new(&ED25519, field(&delegation, TAG_PUBK)?)
+        .verify(&response_signed, field(&message, TAG_SIG)?)
+        .map_err(|_| TimestampError::BadSignature("response"))?;
+
+    let signed_response = parse_message(signed_response)?;
+    let midpoint = read_u64(field(&signed_response, TAG_MIDP)?)?;
+    let radius = read_u32(field(&signed_response, TAG_RADI)?)?;
+    if midpoint < read_u64(field(&delegation, TAG_MINT)?)?
+        || midpoint > read_u64(field(&delegation, TAG_MAXT)?)?
+    {
+        return Err(TimestampError::OutsideDelegation);
+    }
+
+    let path = field(&message, TAG_PATH)?;
+    if !path.len().is_multiple_of(NONCE_LEN) {
+        return Err(TimestampError::Malformed("PATH is not a list of hashes"));
+    }
+    let mut index = read_u32(field(&message, TAG_INDX)?)?;
+    let mut hash = sha512(&[&[0x00], nonce.as_slice()]);
+    for sibling in path.chunks(NONCE_LEN) {
+        hash = if index & 1 == 0 {
+            sha512(&[&[0x01], &hash, sibling])
+        } else {
+            sha512(&[&[0x01], sibling, &hash])
+        };
+        index >>= 1;
+    }
+    if hash.as_slice() != field(&signed_response, TAG_ROOT)? {
+        return Err(TimestampError::NonceMismatch);
+    }
+
+    let midpoint = i64::try_from(midpoint)
+        .ok()
+        .and_then(DateTime::from_timestamp_micros)
+        .ok_or(TimestampError::Malformed("MIDP is out of range"))?;
+    Ok((midpoint, radius))
+}
+
+/// Splits a Roughtime message into its tagged values. The header is the
+/// tag count, an offset for every value after the first, then the tags.
+pub(crate) fn parse_message(bytes: &[u8]) -> Result<HashMap<[u8; 4], &[u8]>, TimestampError> {
+    let (header, values) = bytes
+        .get(..4)
+        .and_then(|count| read_u32(count).ok())
+        .and_then(|count| usize::try_from(count).ok())
+        .filter(|&count| count > 0)
+        .and_then(|count| count.checked_mul(8))
+        .and_then(|header_len| bytes.split_at_checked(header_len))
+        .ok_or(TimestampError::Malformed("bad tag count"))?;
+    let table = header.get(4..).unwrap_or_default();
+    // The table holds one word fewer offsets than tags.
+    let (offsets, tags) = table
+        .split_at_checked(table.len().saturating_sub(4) / 2)
+        .ok_or(TimestampError::Malformed("bad tag count"))?;
+
+    let offsets = offsets
+        .chunks_exact(4)
+        .map(|word| {
+            read_u32(word).and_then(|offset| {
+                usize::try_from(offset).map_err(|_| TimestampError::Malformed("bad value offset"))
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let starts = std::iter::once(0).chain(offsets.iter().copied());
+    let ends = offsets.iter().copied().chain(std::iter::once(values.len()));
+    starts
+        .zip(ends)
+        .zip(tags.chunks_exact(4))
+        .map(|((start, end), tag)| {
+            let value = values
+                .get(start..end)
+                .filter(|_| start.is_multiple_of(4))
+                .ok_or(TimestampError::Malformed("bad value offset"))?;
+            let tag = <[u8; 4]>::try_from(tag).map_err(|_| TimestampError::Malformed("bad tag"))?;
+            Ok((tag, value))
+        })
+        .collect()
+}
+
+fn field<'a>(
+    message: &HashMap<[u8; 4], &'a [u8]>,
+    tag: [u8; 4],
+) -> Result<&'a [u8], TimestampError> {
+    message
+        .get(&tag)
+        .copied()
+        .ok_or(TimestampError::MissingTag(tag))
+}
+
+fn read_u32(bytes: &[u8]) -> Result<u32, TimestampError> {
+    bytes
+        .try_into()
+        .map(u32::from_le_bytes)
+        .map_err(|_| TimestampError::Malformed("expected a 32-bit value"))
+}
+
+fn read_u64(bytes: &[u8]) -> Result<u64, TimestampError> {
+    bytes
+        .try_into()
+        .map(u64::from_le_bytes)
+        .map_err(|_| TimestampError::Malformed("expected a 64-bit value"))
+}
+
+fn sha512(parts: &[&[u8]]) -> [u8; NONCE_LEN] {
+    let mut hash = [0; NONCE_LEN];
+    hash.copy_from_slice(digest(&SHA512, &parts.concat()).as_ref());
+    hash
+}
+
+pub(crate) fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+pub(crate) fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    hex.as_bytes()
+        .chunks_exact(2)
+        .map(|pair| {
+            std::str::from_utf8(pair)
+                .ok()
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+        })
+        .collect()
+}
@@ -0,0 +1,50 @@
+use std::borrow::Cow;
+
+use bytes::Bytes;
+
+/// Both directions of a notarized transcript in one shared buffer. Clones
+/// share the allocation, and parsed ranges index into [`Self::sent`] and
+/// [`Self::received`] without further copies. Redacted bytes need not be
+/// UTF-8.
+#[derive(Debug, Clone)]
+pub struct SharedTranscript {
+    bytes: Bytes,
+    sent_len: usize,
+}
+
+impl SharedTranscript {
+    pub fn new(sent: &[u8], received: &[u8]) -> Self {
+        Self {
+            bytes: Bytes::from([sent, received].concat()),
+            sent_len: sent.len(),
+        }
+    }
+
+    #[must_use]
+    pub fn sent(&self) -> &[u8] {
+        self.bytes
+            .split_at_checked(self.sent_len)
+            .map(|(sent, _)| sent)
+            .unwrap_or_default()
+    }
+
+    #[must_use]
+    pub fn received(&self) -> &[u8] {
+        self.bytes
+            .split_at_checked(self.sent_len)
+            .map(|(_, received)| received)
+            .unwrap_or_default()
+    }
+
+    /// [`Self::sent`] with bytes that are not UTF-8 masked to NUL, so parsed
+    /// ranges index it too.
+    #[must_use]
+    pub fn sent_text(&self) -> Cow<'_, str> {
+        parser::mask_invalid_utf8(self.sent())
+    }
+
+    #[must_use]
+    pub fn received_text(&self) -> Cow<'_, str> {
+        parser::mask_invalid_utf8(self.received())
+    }
+}
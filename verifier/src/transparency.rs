@@ -0,0 +1,419 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::Path,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use axum::{
+    Json, Router,
+    extract::{Path as UrlPath, State},
+    http::StatusCode,
+    routing::get,
+};
+use blake3::Hash;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    errors::StoreError,
+    signing::{NotaryKey, verify_signature},
+};
+
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+const TREE_HEAD_SIGNATURE_CONTEXT: &[u8] = b"zk-tlsn-example log tree head signature v1\0";
+
+/// One line of the log file.
+#[derive(Serialize, Deserialize)]
+struct LogEntry {
+    id: Uuid,
+    digest: String,
+}
+
+#[derive(Default)]
+struct LogState {
+    digests: Vec<Hash>,
+    /// `levels[k][i]` is the hash of the perfect subtree over leaves
+    /// `i << k..(i + 1) << k`, so level 0 holds the leaves. Appending only
+    /// completes subtrees, and none is hashed twice.
+    levels: Vec<Vec<Hash>>,
+    index_by_id: HashMap<Uuid, u64>,
+    tree_size: u64,
+}
+
+impl LogState {
+    fn push(&mut self, id: Uuid, digest: Hash) -> Result<u64, StoreError> {
+        let index = self.tree_size;
+        let tree_size = index
+            .checked_add(1)
+            .ok_or_else(|| std::io::Error::other("transparency log is full"))?;
+        self.index_by_id.entry(id).or_insert(index);
+        self.digests.push(digest);
+        self.tree_size = tree_size;
+
+        let mut node = leaf_hash(&digest);
+        for level in 0.. {
+            if self.levels.len() == level {
+                self.levels.push(Vec::new());
+            }
+            let Some(nodes) = self.levels.get_mut(level) else {
+                break;
+            };
+            nodes.push(node);
+            let [.., left, right] = nodes.as_slice() else {
+                break;
+            };
+            if !nodes.len().is_multiple_of(2) {
+                break;
+            }
+            node = node_hash(left, right);
+        }
+        Ok(index)
+    }
+
+    fn root_hash(&self) -> Hash {
+        self.subtree_hash(0, self.digests.len())
+    }
+
+    /// RFC 9162 tree hash of leaves `start..end`, read from `levels` where
+    /// the range is a perfect subtree. Leaves past the end hash as empty.
+    fn subtree_hash(&self, start: usize, end: usize) -> Hash {
+        let len = end.saturating_sub(start);
+        let cached = (len.is_power_of_two() && start.is_multiple_of(len))
+            .then(|| usize::try_from(len.ilog2()).ok())
+            .flatten()
+            .and_then(|level| self.levels.get(level)?.get(start >> level));
+        match (cached, len) {
+            (Some(hash), _) => *hash,
+            (None, 0 | 1) => blake3::hash(b""),
+            (None, _) => {
+                let split = start.saturating_add(split_point(len));
+                node_hash(
+                    &self.subtree_hash(start, split),
+                    &self.subtree_hash(split, end),
+                )
+            }
+        }
+    }
+
+    fn audit_path(&self, index: usize, start: usize, end: usize) -> Vec<Hash> {
+        let len = end.saturating_sub(start);
+        if len <= 1 {
+            return Vec::new();
+        }
+        let split = start.saturating_add(split_point(len));
+        let (mut path, sibling) = if index < split {
+            (
+                self.audit_path(index, start, split),
+                self.subtree_hash(split, end),
+            )
+        } else {
+            (
+                self.audit_path(index, split, end),
+                self.subtree_hash(start, split),
+            )
+        };
+        path.push(sibling);
+        path
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogRoot {
+    pub tree_size: u64,
+    /// Hex blake3 Merkle tree hash.
+    pub root_hash: String,
+    /// Hex Ed25519 signature by the notary key over the tree head; absent
+    /// when the log has no key.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+}
+
+impl LogRoot {
+    /// Whether [`Self::signature`] is the notary's over this tree size and
+    /// root hash; unsigned roots fail.
+    pub fn verify_signature(&self, public_key: &str) -> bool {
+        verify_tree_head(
+            public_key,
+            self.tree_size,
+            &self.root_hash,
+            self.signature.as_deref(),
+        )
+    }
+}
+
+/// Proof that an attestation is leaf `leaf_index` of the tree whose root is
+/// `root_hash`, following the RFC 9162 audit path construction.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InclusionProof {
+    pub id: Uuid,
+    pub leaf_index: u64,
+    pub tree_size: u64,
+    /// Hex attestation digest the leaf was made from.
+    pub digest: String,
+    pub audit_path: Vec<String>,
+    pub root_hash: String,
+    /// [`LogRoot::signature`] of the tree the proof is for.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub root_signature: Option<String>,
+}
+
+impl InclusionProof {
+    /// Recomputes the root from the digest and audit path.
+    pub fn verify(&self) -> bool {
+        let (Ok(digest), Ok(root)) = (
+            Hash::from_hex(&self.digest),
+            Hash::from_hex(&self.root_hash),
+        ) else {
+            return false;
+        };
+        let Ok(path) = self
+            .audit_path
+            .iter()
+            .map(Hash::from_hex)
+            .collect::<Result<Vec<_>, _>>()
+        else {
+            return false;
+        };
+        let Some(last) = self.tree_size.checked_sub(1) else {
+            return false;
+        };
+        if self.leaf_index > last {
+            return false;
+        }
+
+        let (mut index, mut last) = (self.leaf_index, last);
+        let mut hash = leaf_hash(&digest);
+        for sibling in &path {
+            if last == 0 {
+                return false;
+            }
+            if index & 1 == 1 || index == last {
+                hash = node_hash(sibling, &hash);
+                while index & 1 == 0 && index != 0 {
+                    index >>= 1;
+                    last >>= 1;
+                }
+            } else {
+                hash = node_hash(&hash, sibling);
+            }
+            index >>= 1;
+            last >>= 1;
+        }
+        last == 0 && hash == root
+    }
+
+    /// [`Self::verify`], with the root signed by the notary whose hex public
+    /// key is `public_key`. Without the signature a log could serve a tree
+    /// with an entry swapped and a proof against its root.
+    pub fn verify_signed(&self, public_key: &str) -> bool {
+        self.verify()
+            && verify_tree_head(
+                public_key,
+                self.tree_size,
+                &self.root_hash,
+                self.root_signature.as_deref(),
+            )
+    }
+}
+
+/// Append-only Merkle log of issued attestations, with one leaf per
+/// attestation digest. Consumers compare roots and check inclusion proofs to
+/// audit that the verifier does not issue conflicting attestations. Entries
+/// are appended to a file when the log is opened from one, and tree heads
+/// are signed when it has a [`NotaryKey`].
+pub struct TransparencyLog {
+    state: Mutex<LogState>,
+    file: Option<Mutex<File>>,
+    notary_key: Option<Arc<NotaryKey>>,
+}
+
+impl Default for TransparencyLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TransparencyLog {
+    /// An in-memory log, lost on restart.
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::default(),
+            file: None,
+            notary_key: None,
+        }
+    }
+
+    /// Replays the entries in `path`, creating it if needed, and appends new
+    /// ones to it.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, StoreError> {
+        let path = path.as_ref();
+        let mut state = LogState::default();
+        if path.exists() {
+            for line in BufReader::new(File::open(path)?).lines() {
+                let line = line?;
+                if line.is_empty() {
+                    continue;
+                }
+                let entry: LogEntry = serde_json::from_str(&line)?;
+                let digest = Hash::from_hex(&entry.digest)
+                    .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?;
+                state.push(entry.id, digest)?;
+            }
+        } else if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            state: Mutex::new(state),
+            file: Some(Mutex::new(file)),
+            notary_key: None,
+        })
+    }
+
+    /// Signs every root and inclusion proof the log serves with `notary_key`.
+    #[must_use]
+    pub fn with_notary_key(mut self, notary_key: Arc<NotaryKey>) -> Self {
+        self.notary_key = Some(notary_key);
+        self
+    }
+
+    /// Appends the attestation and returns its leaf index. Appending an id
+    /// again returns its existing index.
+    pub fn append(&self, id: Uuid, digest: Hash) -> Result<u64, StoreError> {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(&index) = state.index_by_id.get(&id) {
+            return Ok(index);
+        }
+        if let Some(file) = &self.file {
+            let mut line = serde_json::to_vec(&LogEntry {
+                id,
+                digest: digest.to_hex().to_string(),
+            })?;
+            line.push(b'\n');
+            let mut file = file.lock().unwrap_or_else(PoisonError::into_inner);
+            file.write_all(&line)?;
+            file.sync_data()?;
+        }
+        state.push(id, digest)
+    }
+
+    pub fn root(&self) -> LogRoot {
+        let (tree_size, root_hash) = {
+            let state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+            (state.tree_size, state.root_hash())
+        };
+        LogRoot {
+            tree_size,
+            root_hash: root_hash.to_hex().to_string(),
+            signature: self.sign_tree_head(tree_size, &root_hash),
+        }
+    }
+
+    /// `None` when `id` was never appended.
+    pub fn inclusion_proof(&self, id: Uuid) -> Option<InclusionProof> {
+        let (tree_size, digest, audit_path, root_hash, leaf_index) = {
+            let state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+            let leaf_index = *state.index_by_id.get(&id)?;
+            let index = usize::try_from(leaf_index).ok()?;
+            (
+                state.tree_size,
+                *state.digests.get(index)?,
+                state.audit_path(index, 0, state.digests.len()),
+                state.root_hash(),
+                leaf_index,
+            )
+        };
+        Some(InclusionProof {
+            id,
+            leaf_index,
+            tree_size,
+            digest: digest.to_hex().to_string(),
+            audit_path: audit_path
+                .iter()
+                .map(|hash| hash.to_hex().to_string())
+                .collect(),
+            root_hash: root_hash.to_hex().to_string(),
+            root_signature: self.sign_tree_head(tree_size, &root_hash),
+        })
+    }
+
+    fn sign_tree_head(&self, tree_size: u64, root_hash: &Hash) -> Option<String> {
+        self.notary_key.as_deref().map(|notary_key| {
+            notary_key.sign(
+                TREE_HEAD_SIGNATURE_CONTEXT,
+                &tree_head(tree_size, root_hash),
+            )
+        })
+    }
+
+    pub fn router(self: Arc<Self>) -> Router {
+        Router::new()
+            .route("/log/root", get(get_root))
+            .route("/log/proof/{id}", get(get_inclusion_proof))
+            .with_state(self)
+    }
+}
+
+async fn get_root(State(log): State<Arc<TransparencyLog>>) -> Json<LogRoot> {
+    Json(log.root())
+}
+
+async fn get_inclusion_proof(
+    State(log): State<Arc<TransparencyLog>>,
+    UrlPath(id): UrlPath<Uuid>,
+) -> Result<Json<InclusionProof>, StatusCode> {
+    log.inclusion_proof(id)
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// The signed message: the big-endian tree size followed by the root hash.
+fn tree_head(tree_size: u64, root_hash: &Hash) -> Vec<u8> {
+    [tree_size.to_be_bytes().as_slice(), root_hash.as_bytes()].concat()
+}
+
+fn verify_tree_head(
+    public_key: &str,
+    tree_size: u64,
+    root_hash: &str,
+    signature: Option<&str>,
+) -> bool {
+    let Ok(root_hash) = Hash::from_hex(root_hash) else {
+        return false;
+    };
+    signature.is_some_and(|signature| {
+        verify_signature(
+            public_key,
+            TREE_HEAD_SIGNATURE_CONTEXT,
+            &tree_head(tree_size, &root_hash),
+            signature,
+        )
+    })
+}
+
+fn leaf_hash(digest: &Hash) -> Hash {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(&[LEAF_PREFIX]);
+    hasher.update(digest.as_bytes());
+    hasher.finalize()
+}
+
+fn node_hash(left: &Hash, right: &Hash) -> Hash {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(&[NODE_PREFIX]);
+    hasher.update(left.as_bytes());
+    hasher.update(right.as_bytes());
+    hasher.finalize()
+}
+
+/// Largest power of two below `len`, for `len > 1`.
+fn split_point(len: usize) -> usize {
+    len.saturating_sub(1)
+        .checked_ilog2()
+        .map_or(0, |bits| 1 << bits)
+}
@@ -1,7 +1,6 @@
 use std::{io::Error as IoError, net::SocketAddr, path::Path};
 
 use async_compat::Compat;
-use futures::AsyncWriteExt;
 use http_body_util::{BodyExt, Empty};
 use hyper::{StatusCode, body::Bytes};
 use hyper_util::rt::TokioIo;
@@ -14,16 +13,21 @@ use smol::net::TcpStream;
 use tlsnotary::{
     CertificateDer, HashAlgId, MpcTlsConfig, ProveConfig, ProverConfig, RootCertStore, ServerName,
     Session, TlsClientConfig, TlsCommitConfig, TranscriptCommitConfig, TranscriptCommitmentKind,
-    prover::{RevealConfig, reveal_request, reveal_response},
+    prover::{CommitHashAlgs, RequestLineConfig, RevealConfig, reveal_request, reveal_response},
 };
 use tracing::{error, info, instrument};
-use verifier::{ProofMessage, VerificationOutcome};
+use verifier::{
+    NotarizeStream, QuicStream, SESSION_NONCE_HEADER, SessionInit, TraceContext,
+    VerificationOutcome, VerifierClient,
+};
 use zktlsn::{PaddingConfig, generate_proof};
 
 /// Maximum sent data size (4 KB)
 const MAX_SENT_DATA: usize = 1 << 12;
 /// Maximum received data size (16 KB)
 const MAX_RECV_DATA: usize = 1 << 14;
+/// Notarization policy the verifier example serves
+const BALANCE_POLICY: &str = "localhost-balance-v1";
 
 type ExampleResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
 
@@ -82,12 +86,17 @@ async fn run() -> ExampleResult<()> {
     endpoint.set_default_client_config(client_config);
 
     let verifier_addr: SocketAddr = "[::1]:5000".parse()?;
-    let connection = endpoint.connect(verifier_addr, "localhost")?.await?;
+    let client = VerifierClient::create_session(&endpoint, verifier_addr, "localhost").await?;
     info!(%verifier_addr, "Connected to verifier");
     progress.tick("connected to verifier");
 
-    let (send, recv) = connection.open_bi().await?;
-    let stream = tokio::io::join(recv, send);
+    let session_init = SessionInit::new(TraceContext::new()).with_policy(BALANCE_POLICY);
+    let stream = client.open_notarize_stream(&session_init).await?;
+    info!(
+        traceparent = %session_init.trace_context.traceparent,
+        policy = BALANCE_POLICY,
+        "Opened notarization stream"
+    );
     progress.tick("opened QUIC bidirectional stream");
     let verification_result = run_single_stream_prover_flow(stream).await?;
     progress.tick("received verification result");
@@ -110,12 +119,12 @@ async fn run() -> ExampleResult<()> {
 }
 
 #[instrument(skip(stream), fields(phase = "notarize+prove+verify"))]
-async fn run_single_stream_prover_flow<IO>(stream: IO) -> ExampleResult<VerificationOutcome>
-where
-    IO: tokio::io::AsyncRead + tokio::io::AsyncWrite + Send + Unpin + 'static,
-{
+async fn run_single_stream_prover_flow(
+    stream: NotarizeStream<QuicStream>,
+) -> ExampleResult<VerificationOutcome> {
     let mut progress = StepProgress::new(8);
-    let session = Session::new(Compat::new(stream));
+    let session_nonce = stream.session_nonce().map(str::to_owned);
+    let session = Session::new(stream);
     let (driver, mut handle) = session.split();
     let driver_task = smol::spawn(driver);
     progress.tick("created TLSN session");
@@ -148,7 +157,9 @@ where
         hyper::client::conn::http1::handshake(tls_connection).await?;
 
     let request_task = async move {
-        let response = request_sender.send_request(create_test_request()?).await?;
+        let response = request_sender
+            .send_request(create_test_request(session_nonce.as_deref())?)
+            .await?;
         if response.status() != StatusCode::OK {
             return Err(IoError::other(format!(
                 "unexpected backend status: {}",
@@ -215,7 +226,7 @@ where
     let prover_output = prover.prove(&prove_config).await?;
     prover.close().await?;
     handle.close();
-    let mut stream = driver_task.await?;
+    let stream = driver_task.await?;
     progress.tick("generated TLSN commitments and secrets");
 
     info!(
@@ -237,9 +248,7 @@ where
     );
     progress.tick("generated ZK proof");
 
-    ProofMessage::new(proof).write_to(&mut stream).await?;
-    let verification_result = VerificationOutcome::read_from(&mut stream).await?;
-    stream.close().await?;
+    let verification_result = stream.submit_verification(proof).await?;
     progress.tick("submitted proof and read verifier response");
 
     Ok(verification_result)
@@ -271,22 +280,37 @@ fn create_prover_config(
     Ok((tls_client_config, tls_commit_config))
 }
 
-fn create_test_request() -> Result<hyper::Request<Empty<Bytes>>, hyper::http::Error> {
-    hyper::Request::builder()
+fn create_test_request(
+    session_nonce: Option<&str>,
+) -> Result<hyper::Request<Empty<Bytes>>, hyper::http::Error> {
+    let mut builder = hyper::Request::builder()
         .method("GET")
         .uri("/api/balance/alice")
-        .header("content-type", "application/json")
+        .header("content-type", "application/json");
+    if let Some(session_nonce) = session_nonce {
+        builder = builder.header(SESSION_NONCE_HEADER, session_nonce);
+    }
+    builder
         .header("Connection", "close")
         .body(Empty::<Bytes>::new())
 }
 
 fn create_request_reveal_config() -> RevealConfig {
     RevealConfig {
-        reveal_headers: vec!["content-type".into()],
+        reveal_headers: vec!["content-type".into(), SESSION_NONCE_HEADER.into()],
         commit_headers: vec!["connection".into()],
         reveal_body_fields: vec![],
         commit_body_fields: vec![],
         reveal_keys_commit_values: vec![],
+        commit_keys_reveal_values: vec![],
+        reveal_sub_ranges: vec![],
+        commit_sub_ranges: vec![],
+        commit_full_body: false,
+        commit_status_class: false,
+        commit_hash_algs: CommitHashAlgs::default(),
+        commit_target_ranges: vec![],
+        request_line: RequestLineConfig::default(),
+        body_commit_ranges: None,
     }
 }
 
@@ -299,6 +323,15 @@ fn create_response_reveal_config() -> RevealConfig {
         reveal_body_fields: vec![BodyFieldConfig::Quoted(".username".into())],
         commit_body_fields: vec![],
         reveal_keys_commit_values: vec![KeyValueCommitConfig::with_padding(".balance".into(), 12)],
+        commit_keys_reveal_values: vec![],
+        reveal_sub_ranges: vec![],
+        commit_sub_ranges: vec![],
+        commit_full_body: false,
+        commit_status_class: false,
+        commit_hash_algs: CommitHashAlgs::default(),
+        commit_target_ranges: vec![],
+        request_line: RequestLineConfig::default(),
+        body_commit_ranges: None,
     }
 }
 
@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 
 use server::{app::get_app, handle_connection};
-use tracing::error;
+use tracing::{Instrument, error};
 
 type ExampleResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
 
@@ -36,13 +36,16 @@ async fn run() -> ExampleResult<()> {
         let app = app.clone();
         let server_config = test_tls_config.server_config.clone();
 
-        smol::spawn(async move {
-            if let Err(error) = handle_connection(app, server_config, stream).await {
-                tracing::error!(error = %error, "Connection error");
-                return;
+        smol::spawn(
+            async move {
+                if let Err(error) = handle_connection(app, server_config, stream).await {
+                    tracing::error!(error = %error, "Connection error");
+                    return;
+                }
+                tracing::info!("Connection handled successfully");
             }
-            tracing::info!("Connection handled successfully");
-        })
+            .instrument(shared::session_span(addr)),
+        )
         .detach();
     }
 }
@@ -1,9 +1,21 @@
-use std::{net::SocketAddr, path::Path};
+use std::{env, net::SocketAddr, path::Path, sync::Arc};
 
-use quinn::Endpoint;
+use async_signal::{Signal, Signals};
+use futures::StreamExt;
 use shared::{TestQuicConfig, get_or_create_test_quic_config, init_logging};
-use tracing::error;
-use verifier::serve;
+use tracing::{error, info};
+use verifier::{
+    AdmissionControl, FilesystemArtifactStore, JobQueue, JobQueueConfig, ListenerConfig,
+    NotarizationPolicy, PolicySet, ServiceOptions, TransparencyLog, serve_listeners_until,
+    serve_router,
+};
+
+/// Demo key for signing job webhooks; real deployments load their own.
+const WEBHOOK_SECRET: [u8; 32] = *b"zk-tlsn-example-webhook-secret!!";
+const JOB_WORKERS: usize = 2;
+const BALANCE_POLICY: &str = "localhost-balance-v1";
+/// JSON file listing the listeners to bind; defaults to QUIC on [::1]:5000.
+const LISTENERS_ENV: &str = "VERIFIER_LISTENERS";
 
 type ExampleResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
 
@@ -23,10 +35,53 @@ fn main() {
 async fn run() -> ExampleResult<()> {
     let TestQuicConfig { server_config, .. } =
         get_or_create_test_quic_config(Path::new("cert.pem"), Path::new("key.pem")).await?;
-    let addr: SocketAddr = "[::1]:5000".parse()?;
+    let listener_configs = match env::var_os(LISTENERS_ENV) {
+        Some(path) => ListenerConfig::from_json_file(path)?,
+        None => vec![ListenerConfig::quic("[::1]:5000".parse::<SocketAddr>()?)],
+    };
+    let listeners = ListenerConfig::bind_all(&listener_configs, &server_config)?;
+    let artifact_store = FilesystemArtifactStore::new("attestations")?;
+    let transparency_log = Arc::new(TransparencyLog::open("attestations/log.jsonl")?);
+    let job_queue = JobQueue::start(
+        JobQueueConfig {
+            workers: JOB_WORKERS,
+            ..JobQueueConfig::default()
+        },
+        WEBHOOK_SECRET,
+    );
+    let status_listener = smol::net::TcpListener::bind("[::1]:5001").await?;
+    info!("Verification job status at http://[::1]:5001/jobs/{{id}}");
+    info!("Transparency log at http://[::1]:5001/log/root and /log/proof/{{id}}");
+    let status_router = Arc::clone(&job_queue)
+        .router()
+        .merge(Arc::clone(&transparency_log).router());
+    smol::spawn(serve_router(status_listener, status_router)).detach();
+    let options = ServiceOptions {
+        artifact_store: Some(Arc::new(artifact_store)),
+        admission_control: Some(Arc::new(AdmissionControl::default())),
+        job_queue: Some(job_queue),
+        policies: Some(Arc::new(balance_policies())),
+        transparency_log: Some(transparency_log),
+        require_session_nonce: true,
+        ..ServiceOptions::default()
+    };
 
-    let endpoint = Endpoint::server(server_config, addr)?;
-    tracing::info!("Reliable streams server listening on {}", addr);
-    serve(endpoint).await;
+    let mut signals = Signals::new([Signal::Int, Signal::Term])?;
+    let shutdown = async move {
+        signals.next().await;
+    };
+    let summary = serve_listeners_until(listeners, options, shutdown).await;
+    info!(?summary, "Verifier example shut down");
     Ok(())
 }
+
+fn balance_policies() -> PolicySet {
+    PolicySet::default().with_policy(
+        NotarizationPolicy::new(BALANCE_POLICY)
+            .allowed_host("localhost")
+            .revealed_request_header("content-type")
+            .revealed_response_field(".username")
+            .committed_response_field(".balance")
+            .proven_field(".balance"),
+    )
+}
@@ -1,13 +1,38 @@
 use std::collections::{BTreeMap, HashMap};
 
-use tlsnotary::{Direction, PlaintextHash, TranscriptCommitment};
+use serde::{Deserialize, Serialize};
+use tlsnotary::{Direction, PlaintextHash, Predicate, TranscriptCommitment};
 
-use crate::{Result, ZkTlsnError};
+use crate::{Proof, Result, ZkTlsnError, verify_predicate_proof};
+
+pub(crate) const MAX_KEY_VALUE_GAP: usize = 2;
 
 #[derive(Debug, Clone)]
 pub struct BoundCommitment {
     pub key_range: std::ops::Range<usize>,
     pub hash: PlaintextHash,
+    /// Set by [`bind_commitments_with_thresholds`].
+    pub threshold: Option<ThresholdClaim>,
+}
+
+/// A threshold on a committed value, e.g. balance greater than 1000, and the
+/// predicate proof showing it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThresholdClaim {
+    pub predicate: Predicate,
+    pub proof: Proof,
+}
+
+impl BoundCommitment {
+    /// Checks that the threshold proof, if any, is over this commitment's
+    /// hash and shows the claimed predicate.
+    pub fn verify(&self) -> Result<()> {
+        match &self.threshold {
+            Some(claim) => verify_predicate_proof(&claim.proof, &self.hash, claim.predicate),
+            None => Ok(()),
+        }
+    }
 }
 
 pub fn bind_commitments_to_keys(
@@ -41,6 +66,7 @@ pub fn bind_commitments_to_keys(
                         BoundCommitment {
                             key_range: key.clone(),
                             hash: hash.clone(),
+                            threshold: None,
                         },
                     )
                 })
@@ -53,13 +79,33 @@ pub fn bind_commitments_to_keys(
     Ok(bindings)
 }
 
+/// [`bind_commitments_to_keys`], with each claim in `thresholds` attached to
+/// the commitment bound to its keypath. A claim for a keypath without one is
+/// an error rather than being dropped.
+pub fn bind_commitments_with_thresholds(
+    parsed_response: &parser::redacted::Response,
+    transcript_commitments: &[TranscriptCommitment],
+    thresholds: HashMap<String, ThresholdClaim>,
+) -> Result<HashMap<String, BoundCommitment>> {
+    let mut bindings = bind_commitments_to_keys(parsed_response, transcript_commitments)?;
+    for (keypath, claim) in thresholds {
+        let binding = bindings.get_mut(&keypath).ok_or_else(|| {
+            ZkTlsnError::InvalidInput(format!(
+                "no commitment is bound to {keypath} for its threshold claim"
+            ))
+        })?;
+        binding.threshold = Some(claim);
+    }
+    Ok(bindings)
+}
+
 fn find_nearest_commitment<'a>(
     commitments_by_position: &'a BTreeMap<usize, &'a PlaintextHash>,
     key_end: usize,
 ) -> Option<&'a PlaintextHash> {
     commitments_by_position
         .range(key_end..)
-        .take_while(|(start, _)| (*start).saturating_sub(key_end) <= 2)
+        .take_while(|(start, _)| (*start).saturating_sub(key_end) <= MAX_KEY_VALUE_GAP)
         .map(|(_, hash)| *hash)
         .next()
 }
@@ -1,3 +1,4 @@
+use chrono::{DateTime, Utc};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -32,6 +33,15 @@ pub enum ZkTlsnError {
     #[error("Proof is invalid")]
     InvalidProof,
 
+    #[error("Proof is for {proven}, expected {expected}")]
+    PredicateMismatch { proven: String, expected: String },
+
+    #[error("Proof was made with circuit {proven}, expected {expected}")]
+    CircuitMismatch { proven: String, expected: String },
+
+    #[error("Proof expired at {0}")]
+    ProofExpired(DateTime<Utc>),
+
     #[error("Noir error: {0}")]
     NoirError(String),
 
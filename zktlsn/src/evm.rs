@@ -0,0 +1,151 @@
+use noir::barretenberg::{
+    prove::prove_ultra_honk_keccak,
+    verify::{get_ultra_honk_keccak_verification_key, verify_ultra_honk_keccak},
+};
+use serde::{Deserialize, Serialize};
+use tlsnotary::{TranscriptCommitment, TranscriptSecret};
+
+use crate::{
+    error::{Result, ZkTlsnError},
+    padding::PaddingConfig,
+    prover::{CIRCUIT, received_proof_input, witness_from_input},
+    verifier::COMMITTED_HASH_BYTES,
+};
+
+const WORD_BYTES: usize = 32;
+/// `bytes4(keccak256("verify(bytes,bytes32[])"))` of the Barretenberg Solidity verifier.
+pub const VERIFY_SELECTOR: [u8; 4] = [0xea, 0x50, 0xd0, 0xe4];
+
+/// An UltraHonk proof over the Keccak transcript, split the way the
+/// Barretenberg Solidity verifier takes it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EvmProof {
+    pub verification_key: Vec<u8>,
+    /// Proof bytes without the public inputs.
+    pub proof: Vec<u8>,
+    /// One field element per committed hash byte, big-endian.
+    pub public_inputs: Vec<[u8; WORD_BYTES]>,
+}
+
+impl EvmProof {
+    fn from_raw(verification_key: Vec<u8>, raw_proof: &[u8]) -> Result<Self> {
+        let public_input_bytes = COMMITTED_HASH_BYTES * WORD_BYTES;
+        if raw_proof.len() < public_input_bytes || !raw_proof.len().is_multiple_of(WORD_BYTES) {
+            return Err(ZkTlsnError::InvalidInput(format!(
+                "invalid proof length {}, need a multiple of {WORD_BYTES} of at least {public_input_bytes}",
+                raw_proof.len()
+            )));
+        }
+        let (inputs, proof) = raw_proof.split_at(public_input_bytes);
+        let public_inputs = inputs
+            .chunks_exact(WORD_BYTES)
+            .map(|word| {
+                let mut field = [0u8; WORD_BYTES];
+                field.copy_from_slice(word);
+                field
+            })
+            .collect();
+        Ok(Self {
+            verification_key,
+            proof: proof.to_vec(),
+            public_inputs,
+        })
+    }
+
+    /// The committed hash carried by the public inputs.
+    pub fn committed_hash(&self) -> Result<[u8; COMMITTED_HASH_BYTES]> {
+        if self.public_inputs.len() != COMMITTED_HASH_BYTES {
+            return Err(ZkTlsnError::InvalidInput(format!(
+                "expected {COMMITTED_HASH_BYTES} public inputs, got {}",
+                self.public_inputs.len()
+            )));
+        }
+        let mut committed_hash = [0u8; COMMITTED_HASH_BYTES];
+        for (index, field) in self.public_inputs.iter().enumerate() {
+            if field[..WORD_BYTES - 1].iter().any(|&byte| byte != 0) {
+                return Err(ZkTlsnError::InvalidInput(format!(
+                    "public input {index} does not fit in u8"
+                )));
+            }
+            committed_hash[index] = field[WORD_BYTES - 1];
+        }
+        Ok(committed_hash)
+    }
+
+    /// ABI-encoded call to `verify(bytes _proof, bytes32[] _publicInputs)`.
+    #[must_use]
+    pub fn calldata(&self) -> Vec<u8> {
+        let padded_proof_len = self.proof.len().div_ceil(WORD_BYTES) * WORD_BYTES;
+        let proof_offset = 2 * WORD_BYTES;
+        let inputs_offset = proof_offset + WORD_BYTES + padded_proof_len;
+
+        let mut calldata = Vec::with_capacity(
+            VERIFY_SELECTOR.len() + inputs_offset + WORD_BYTES * (1 + self.public_inputs.len()),
+        );
+        calldata.extend_from_slice(&VERIFY_SELECTOR);
+        calldata.extend_from_slice(&abi_word(proof_offset));
+        calldata.extend_from_slice(&abi_word(inputs_offset));
+        calldata.extend_from_slice(&abi_word(self.proof.len()));
+        calldata.extend_from_slice(&self.proof);
+        calldata.resize(calldata.len() + padded_proof_len - self.proof.len(), 0);
+        calldata.extend_from_slice(&abi_word(self.public_inputs.len()));
+        self.public_inputs
+            .iter()
+            .for_each(|input| calldata.extend_from_slice(input));
+        calldata
+    }
+
+    /// Verifies the proof off-chain against the bundled circuit, as the
+    /// Solidity verifier generated from it would.
+    pub fn verify(&self) -> Result<()> {
+        let bytecode = CIRCUIT.bytecode()?;
+        let computed_vk = get_ultra_honk_keccak_verification_key(&bytecode, false, false)
+            .map_err(ZkTlsnError::NoirError)?;
+        if computed_vk != self.verification_key {
+            return Err(ZkTlsnError::VerificationKeyMismatch);
+        }
+        let raw_proof = self
+            .public_inputs
+            .iter()
+            .flatten()
+            .chain(&self.proof)
+            .copied()
+            .collect();
+        let is_valid = verify_ultra_honk_keccak(raw_proof, self.verification_key.clone(), false)
+            .map_err(ZkTlsnError::NoirError)?;
+        if !is_valid {
+            return Err(ZkTlsnError::InvalidProof);
+        }
+        Ok(())
+    }
+}
+
+/// Like [`crate::generate_proof`], but proves over the Keccak transcript so
+/// the result can be checked by the Barretenberg Solidity verifier.
+pub fn generate_evm_proof(
+    transcript_commitments: &[TranscriptCommitment],
+    transcript_secrets: &[TranscriptSecret],
+    received_data: &[u8],
+    padding_config: PaddingConfig,
+) -> Result<EvmProof> {
+    let proof_input = received_proof_input(
+        transcript_commitments,
+        transcript_secrets,
+        received_data,
+        padding_config,
+    )?;
+    let bytecode = CIRCUIT.bytecode()?;
+    let witness = witness_from_input(&proof_input)?;
+    let vk = get_ultra_honk_keccak_verification_key(&bytecode, false, false)
+        .map_err(ZkTlsnError::NoirError)?;
+    let raw_proof = prove_ultra_honk_keccak(&bytecode, witness, vk.clone(), false, false)
+        .map_err(ZkTlsnError::NoirError)?;
+    EvmProof::from_raw(vk, &raw_proof)
+}
+
+fn abi_word(value: usize) -> [u8; WORD_BYTES] {
+    let mut word = [0u8; WORD_BYTES];
+    let bytes = (value as u64).to_be_bytes();
+    word[WORD_BYTES - bytes.len()..].copy_from_slice(&bytes);
+    word
+}
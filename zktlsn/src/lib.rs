@@ -1,21 +1,36 @@
 mod commitment;
 mod error;
+mod evm;
+mod metadata;
 mod padding;
+mod predicate;
 mod prover;
+mod status;
 mod verifier;
 
 #[cfg(test)]
 mod tests;
 
-pub use commitment::{BoundCommitment, bind_commitments_to_keys};
+pub use commitment::{
+    BoundCommitment, ThresholdClaim, bind_commitments_to_keys, bind_commitments_with_thresholds,
+};
 pub use error::{Result, ZkTlsnError};
+pub use evm::{EvmProof, VERIFY_SELECTOR, generate_evm_proof};
+pub use metadata::ProofMetadata;
 use noir::barretenberg::srs::setup_srs_from_bytecode;
 pub use padding::PaddingConfig;
+pub use predicate::{
+    PREDICATE_VALUE_LENGTH, PredicateProofs, generate_predicate_proof, verify_predicate_proof,
+};
 pub use prover::{Proof, generate_proof};
-pub use verifier::{extract_committed_hash_from_proof, verify_proof, verify_proof_against_hash};
+pub use status::{generate_status_class_proof, verify_status_class_proof};
+pub use verifier::{
+    CoveredCommitment, extract_committed_hash_from_proof, verify_proof, verify_proof_against_hash,
+    verify_proof_with_claimed_bindings,
+};
 
 pub fn setup_barretenberg_srs() -> Result<()> {
-    let bytecode = prover::load_circuit_bytecode()?;
+    let bytecode = prover::CIRCUIT.bytecode()?;
     setup_srs_from_bytecode(&bytecode, None, false).map_err(ZkTlsnError::NoirError)?;
     Ok(())
 }
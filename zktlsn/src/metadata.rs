@@ -0,0 +1,120 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tlsnotary::{CommitmentDomain, Direction, PlaintextHash, TranscriptCommitment};
+
+use crate::error::{Result, ZkTlsnError};
+
+/// A compiled Noir program a proof can be made with.
+pub(crate) struct Circuit {
+    pub(crate) id: &'static str,
+    program_json: &'static str,
+}
+
+impl Circuit {
+    pub(crate) const fn new(id: &'static str, program_json: &'static str) -> Self {
+        Self { id, program_json }
+    }
+
+    pub(crate) fn bytecode(&self) -> Result<String> {
+        self.field("bytecode")?
+            .as_str()
+            .ok_or(ZkTlsnError::BytecodeNotFound)
+            .map(String::from)
+    }
+
+    /// The hash nargo records for the compiled program, which changes with
+    /// any change to the circuit.
+    pub(crate) fn version(&self) -> Result<String> {
+        match self.field("hash")? {
+            Value::Null => Err(ZkTlsnError::InvalidInput(format!(
+                "program json of {} has no hash",
+                self.id
+            ))),
+            Value::String(hash) => Ok(hash),
+            hash => Ok(hash.to_string()),
+        }
+    }
+
+    fn field(&self, name: &str) -> Result<Value> {
+        let mut json: Value = serde_json::from_str(self.program_json)?;
+        Ok(json[name].take())
+    }
+}
+
+/// What a [`crate::Proof`] was made with and how long it stays valid. It
+/// travels alongside the proof and is not covered by it, so verification
+/// only checks it against the local circuits and the proof's public inputs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProofMetadata {
+    pub circuit_id: String,
+    pub circuit_version: String,
+    pub created_at: DateTime<Utc>,
+    /// [`CommitmentDomain`] ids of the transcript commitments the proof is
+    /// about, which name their direction and ordinal as well as their hash.
+    pub commitment_ids: Vec<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl ProofMetadata {
+    pub(crate) fn new(circuit: &Circuit, commitment_id: String) -> Result<Self> {
+        Ok(Self {
+            circuit_id: circuit.id.to_string(),
+            circuit_version: circuit.version()?,
+            created_at: Utc::now(),
+            commitment_ids: vec![commitment_id],
+            expires_at: None,
+        })
+    }
+
+    pub fn is_expired_at(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+
+    pub(crate) fn check(&self, circuit: &Circuit, committed_hash: &[u8]) -> Result<()> {
+        let expected = format!("{}@{}", circuit.id, circuit.version()?);
+        let proven = format!("{}@{}", self.circuit_id, self.circuit_version);
+        if proven != expected {
+            return Err(ZkTlsnError::CircuitMismatch { proven, expected });
+        }
+        if let Some(expires_at) = self.expires_at
+            && self.is_expired_at(Utc::now())
+        {
+            return Err(ZkTlsnError::ProofExpired(expires_at));
+        }
+
+        let committed_hash = hex(committed_hash);
+        let mut directions = self
+            .commitment_ids
+            .iter()
+            .filter_map(|id| CommitmentDomain::parse_id(id))
+            .filter(|(_, hash)| *hash == committed_hash)
+            .map(|(domain, _)| domain.direction);
+        match directions.next() {
+            Some(Direction::Received) => Ok(()),
+            Some(Direction::Sent) => Err(ZkTlsnError::InvalidCommitmentDirection),
+            None => Err(ZkTlsnError::CommittedHashMismatch),
+        }
+    }
+
+    /// Checks that the proof names `commitment` at its place among the
+    /// verifier's commitments, not just its hash.
+    pub(crate) fn check_domain(
+        &self,
+        verifier_commitments: &[TranscriptCommitment],
+        commitment: &PlaintextHash,
+    ) -> Result<()> {
+        let id = CommitmentDomain::of(verifier_commitments, commitment)
+            .ok_or(ZkTlsnError::CommittedHashMismatch)?
+            .id(commitment);
+        if !self.commitment_ids.contains(&id) {
+            return Err(ZkTlsnError::CommittedHashMismatch);
+        }
+        Ok(())
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
@@ -0,0 +1,173 @@
+use std::collections::HashMap;
+
+use tlsnotary::{
+    Direction, PlaintextHash, Predicate, PredicateProofVerifier, TranscriptCommitment,
+    TranscriptSecret,
+};
+
+use crate::{
+    Proof,
+    error::{Result, ZkTlsnError},
+    metadata::Circuit,
+    padding::PaddingConfig,
+    prover::{prepare_proof_input, prove_witness},
+    verifier::{
+        COMMITTED_HASH_BYTES, HONK_FIELD_BYTES, extract_committed_hash_from_proof,
+        verify_proof_with_circuit,
+    },
+};
+
+/// Committed bytes the predicate circuit reads: the digits and any padding.
+pub const PREDICATE_VALUE_LENGTH: usize = 12;
+const U64_BYTES: usize = 8;
+
+const PREDICATE_CIRCUIT: Circuit = Circuit::new(
+    "predicate_circuit",
+    include_str!("../../target/predicate_circuit.json"),
+);
+
+/// Threshold and comparison flag, as the circuit takes them after the hash.
+fn predicate_public_inputs(predicate: Predicate) -> [u64; 2] {
+    match predicate {
+        Predicate::GreaterThan(threshold) => [threshold, 0],
+        Predicate::LessThan(threshold) => [threshold, 1],
+    }
+}
+
+/// Proves that the number committed for `keypath` in `received_data`
+/// satisfies `predicate` without revealing it. The value must be committed
+/// over [`PREDICATE_VALUE_LENGTH`] bytes starting at its first digit.
+pub fn generate_predicate_proof(
+    transcript_commitments: &[TranscriptCommitment],
+    transcript_secrets: &[TranscriptSecret],
+    received_data: &[u8],
+    keypath: &str,
+    predicate: Predicate,
+) -> Result<Proof> {
+    let response = parser::standard::Response::from_bytes(received_data)
+        .map_err(|e| ZkTlsnError::InvalidInput(format!("failed to parse response: {e}")))?;
+    let value_start = match response.body.get(keypath) {
+        Some(parser::standard::Body::KeyValue { value, .. }) => value.start,
+        _ => {
+            return Err(ZkTlsnError::InvalidInput(format!(
+                "field {keypath} is not a key-value pair in the response"
+            )));
+        }
+    };
+
+    let commitment = transcript_commitments
+        .iter()
+        .find_map(|c| match c {
+            TranscriptCommitment::Hash(h)
+                if h.direction == Direction::Received && h.idx.min() == Some(value_start) =>
+            {
+                Some(h.clone())
+            }
+            _ => None,
+        })
+        .ok_or(ZkTlsnError::NoReceivedCommitments)?;
+    let secret = transcript_secrets
+        .iter()
+        .find_map(|s| match s {
+            TranscriptSecret::Hash(h)
+                if h.direction == Direction::Received && h.idx.min() == Some(value_start) =>
+            {
+                Some(h.clone())
+            }
+            _ => None,
+        })
+        .ok_or(ZkTlsnError::NoReceivedSecrets)?;
+
+    let proof_input = prepare_proof_input(
+        received_data,
+        transcript_commitments,
+        commitment,
+        secret,
+        PaddingConfig::new(PREDICATE_VALUE_LENGTH),
+    )?;
+    prove_witness(
+        &PREDICATE_CIRCUIT,
+        &proof_input,
+        &predicate_public_inputs(predicate),
+    )
+}
+
+/// Checks that `proof` shows `predicate` for the value behind `commitment`.
+pub fn verify_predicate_proof(
+    proof: &Proof,
+    commitment: &PlaintextHash,
+    predicate: Predicate,
+) -> Result<()> {
+    let proof_committed_hash = extract_committed_hash_from_proof(proof)?;
+    if commitment.hash.value.as_bytes() != proof_committed_hash.as_slice() {
+        return Err(ZkTlsnError::CommittedHashMismatch);
+    }
+
+    let expected = predicate_public_inputs(predicate);
+    let proven = [
+        public_input_u64(proof, COMMITTED_HASH_BYTES)?,
+        public_input_u64(proof, COMMITTED_HASH_BYTES + 1)?,
+    ];
+    if proven != expected {
+        return Err(ZkTlsnError::PredicateMismatch {
+            proven: format!("threshold {} with flag {}", proven[0], proven[1]),
+            expected: format!("{predicate:?}"),
+        });
+    }
+
+    verify_proof_with_circuit(proof, &PREDICATE_CIRCUIT)
+}
+
+fn public_input_u64(proof: &Proof, index: usize) -> Result<u64> {
+    let field = proof
+        .proof
+        .chunks_exact(HONK_FIELD_BYTES)
+        .nth(index)
+        .ok_or_else(|| ZkTlsnError::InvalidInput(format!("proof has no public input {index}")))?;
+    let (high, low) = field.split_at(HONK_FIELD_BYTES - U64_BYTES);
+    if high.iter().any(|&byte| byte != 0) {
+        return Err(ZkTlsnError::InvalidInput(format!(
+            "public input {index} does not fit in u64"
+        )));
+    }
+    let mut bytes = [0u8; U64_BYTES];
+    bytes.copy_from_slice(low);
+    Ok(u64::from_be_bytes(bytes))
+}
+
+/// Predicate proofs by keypath, for [`tlsnotary::Validator::validate_with_proofs`].
+#[derive(Debug, Clone, Default)]
+pub struct PredicateProofs {
+    proofs: HashMap<String, Proof>,
+}
+
+impl PredicateProofs {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn with_proof(mut self, keypath: impl Into<String>, proof: Proof) -> Self {
+        self.proofs.insert(keypath.into(), proof);
+        self
+    }
+}
+
+impl PredicateProofVerifier for PredicateProofs {
+    fn verify_predicate(
+        &self,
+        keypath: &str,
+        commitment: &PlaintextHash,
+        predicate: Predicate,
+    ) -> std::result::Result<(), tlsnotary::Error> {
+        let proof = self.proofs.get(keypath).ok_or_else(|| {
+            tlsnotary::Error::InvalidInput(format!("no predicate proof for field '{keypath}'"))
+        })?;
+        verify_predicate_proof(proof, commitment, predicate).map_err(|error| {
+            tlsnotary::Error::InvalidTranscript(format!(
+                "predicate proof for field '{keypath}' rejected: {error}"
+            ))
+        })
+    }
+}
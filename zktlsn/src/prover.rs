@@ -1,33 +1,48 @@
+use chrono::{DateTime, Utc};
 use noir::{
+    FieldElement,
     barretenberg::{prove::prove_ultra_honk, verify::get_ultra_honk_verification_key},
     blackbox_solver::blake3,
+    native_types::WitnessMap,
     witness::from_vec_str_to_witness_map,
 };
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
 use tlsnotary::{
-    Direction, HashAlgId, PlaintextHash, PlaintextHashSecret, TranscriptCommitment,
-    TranscriptSecret,
+    CommitmentDomain, Direction, HashAlgId, PlaintextHash, PlaintextHashSecret,
+    TranscriptCommitment, TranscriptSecret,
 };
 
 use crate::{
     error::{Result, ZkTlsnError},
+    metadata::{Circuit, ProofMetadata},
     padding::PaddingConfig,
 };
 
+pub(crate) const CIRCUIT: Circuit =
+    Circuit::new("circuit", include_str!("../../target/circuit.json"));
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Proof {
     pub verification_key: Vec<u8>,
     pub proof: Vec<u8>,
+    pub metadata: ProofMetadata,
 }
 
 impl Proof {
-    pub fn new(verification_key: Vec<u8>, proof: Vec<u8>) -> Self {
+    pub fn new(verification_key: Vec<u8>, proof: Vec<u8>, metadata: ProofMetadata) -> Self {
         Self {
             verification_key,
             proof,
+            metadata,
         }
     }
+
+    /// Makes verification reject the proof from `expires_at` on.
+    #[must_use]
+    pub fn with_expiry(mut self, expires_at: DateTime<Utc>) -> Self {
+        self.metadata.expires_at = Some(expires_at);
+        self
+    }
 }
 
 pub fn generate_proof(
@@ -36,16 +51,31 @@ pub fn generate_proof(
     received_data: &[u8],
     padding_config: PaddingConfig,
 ) -> Result<Proof> {
+    let proof_input = received_proof_input(
+        transcript_commitments,
+        transcript_secrets,
+        received_data,
+        padding_config,
+    )?;
+
+    generate_zk_proof(&CIRCUIT, &proof_input)
+}
+
+pub(crate) fn received_proof_input(
+    transcript_commitments: &[TranscriptCommitment],
+    transcript_secrets: &[TranscriptSecret],
+    received_data: &[u8],
+    padding_config: PaddingConfig,
+) -> Result<ProofInput> {
     let received_commitment = extract_received_commitment(transcript_commitments)?;
     let received_secret = extract_received_secret(transcript_secrets)?;
-    let proof_input = prepare_proof_input(
+    prepare_proof_input(
         received_data,
+        transcript_commitments,
         received_commitment,
         received_secret,
         padding_config,
-    )?;
-
-    generate_zk_proof(&proof_input)
+    )
 }
 
 fn extract_received_commitment(commitments: &[TranscriptCommitment]) -> Result<PlaintextHash> {
@@ -69,14 +99,16 @@ fn extract_received_secret(secrets: &[TranscriptSecret]) -> Result<PlaintextHash
 }
 
 #[derive(Debug, Clone)]
-struct ProofInput {
+pub(crate) struct ProofInput {
+    commitment_id: String,
     committed_hash: Vec<u8>,
     committed_data: Vec<u8>,
     blinder: Vec<u8>,
 }
 
-fn prepare_proof_input(
+pub(crate) fn prepare_proof_input(
     received_data: &[u8],
+    transcript_commitments: &[TranscriptCommitment],
     commitment: PlaintextHash,
     secret: PlaintextHashSecret,
     padding_config: PaddingConfig,
@@ -124,34 +156,62 @@ fn prepare_proof_input(
     if tlsnotary_hash != committed_hash.as_slice() {
         return Err(ZkTlsnError::HashVerificationFailed);
     }
+    let commitment_id = CommitmentDomain::of(transcript_commitments, &commitment)
+        .ok_or_else(|| {
+            ZkTlsnError::InvalidInput(
+                "commitment is not among the transcript commitments".to_string(),
+            )
+        })?
+        .id(&commitment);
 
     Ok(ProofInput {
+        commitment_id,
         committed_hash: committed_hash.to_vec(),
         committed_data,
         blinder,
     })
 }
 
-pub(crate) fn load_circuit_bytecode() -> Result<String> {
-    const PROGRAM_JSON: &str = include_str!("../../target/circuit.json");
-    let json: Value = serde_json::from_str(PROGRAM_JSON)?;
-    json["bytecode"]
-        .as_str()
-        .ok_or(ZkTlsnError::BytecodeNotFound)
-        .map(String::from)
+pub(crate) fn witness_from_input(input: &ProofInput) -> Result<WitnessMap<FieldElement>> {
+    witness_with_public_inputs(input, &[])
 }
 
-fn generate_zk_proof(input: &ProofInput) -> Result<Proof> {
-    let bytecode = load_circuit_bytecode()?;
-    let inputs: Vec<String> = [&input.committed_hash, &input.committed_data, &input.blinder]
+/// Orders the witness as circuits declare it: committed hash, any further
+/// public inputs, committed data, blinder.
+pub(crate) fn witness_with_public_inputs(
+    input: &ProofInput,
+    public_inputs: &[u64],
+) -> Result<WitnessMap<FieldElement>> {
+    let inputs: Vec<String> = input
+        .committed_hash
         .iter()
-        .flat_map(|v| v.iter().map(|b| b.to_string()))
+        .map(u8::to_string)
+        .chain(public_inputs.iter().map(u64::to_string))
+        .chain(
+            [&input.committed_data, &input.blinder]
+                .iter()
+                .flat_map(|v| v.iter().map(u8::to_string)),
+        )
         .collect();
     let input_refs: Vec<&str> = inputs.iter().map(String::as_str).collect();
 
-    let witness = from_vec_str_to_witness_map(input_refs).map_err(ZkTlsnError::NoirError)?;
+    from_vec_str_to_witness_map(input_refs).map_err(ZkTlsnError::NoirError)
+}
+
+pub(crate) fn generate_zk_proof(circuit: &Circuit, input: &ProofInput) -> Result<Proof> {
+    prove_witness(circuit, input, &[])
+}
+
+pub(crate) fn prove_witness(
+    circuit: &Circuit,
+    input: &ProofInput,
+    public_inputs: &[u64],
+) -> Result<Proof> {
+    let bytecode = circuit.bytecode()?;
+    let witness = witness_with_public_inputs(input, public_inputs)?;
     let vk = get_ultra_honk_verification_key(&bytecode, false).map_err(ZkTlsnError::NoirError)?;
     let proof =
         prove_ultra_honk(&bytecode, witness, vk.clone(), false).map_err(ZkTlsnError::NoirError)?;
-    Ok(Proof::new(vk, proof))
+    let metadata = ProofMetadata::new(circuit, input.commitment_id.clone())?;
+    Ok(Proof::new(vk, proof, metadata))
 }
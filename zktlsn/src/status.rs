@@ -0,0 +1,90 @@
+use std::ops::Range;
+
+use tlsnotary::{Direction, PlaintextHash, TranscriptCommitment, TranscriptSecret};
+
+use crate::{
+    Proof,
+    error::{Result, ZkTlsnError},
+    metadata::Circuit,
+    padding::PaddingConfig,
+    prover::{generate_zk_proof, prepare_proof_input},
+    verifier::{extract_committed_hash_from_proof, verify_proof_with_circuit},
+};
+
+const STATUS_CODE_LENGTH: usize = 3;
+
+const STATUS_CIRCUIT: Circuit = Circuit::new(
+    "status_circuit",
+    include_str!("../../target/status_circuit.json"),
+);
+
+/// Proves that the committed status code of `received_data` is 2xx without
+/// revealing it. Requires a response committed with `commit_status_class`.
+pub fn generate_status_class_proof(
+    transcript_commitments: &[TranscriptCommitment],
+    transcript_secrets: &[TranscriptSecret],
+    received_data: &[u8],
+) -> Result<Proof> {
+    let response = parser::standard::Response::from_bytes(received_data)
+        .map_err(|e| ZkTlsnError::InvalidInput(format!("failed to parse response: {e}")))?;
+    let commitment = status_code_commitment(transcript_commitments, &response.status_code)?;
+    let secret = transcript_secrets
+        .iter()
+        .find_map(|s| match s {
+            TranscriptSecret::Hash(h)
+                if h.direction == Direction::Received
+                    && h.idx.min() == Some(response.status_code.start)
+                    && h.idx.end() == Some(response.status_code.end) =>
+            {
+                Some(h.clone())
+            }
+            _ => None,
+        })
+        .ok_or(ZkTlsnError::NoReceivedSecrets)?;
+
+    let proof_input = prepare_proof_input(
+        received_data,
+        transcript_commitments,
+        commitment.clone(),
+        secret,
+        PaddingConfig::new(STATUS_CODE_LENGTH),
+    )?;
+    generate_zk_proof(&STATUS_CIRCUIT, &proof_input)
+}
+
+/// Checks that `proof` shows a 2xx status for the status code hidden in
+/// `parsed_response` and committed among `verifier_commitments`.
+pub fn verify_status_class_proof(
+    proof: &Proof,
+    parsed_response: &parser::redacted::Response,
+    verifier_commitments: &[TranscriptCommitment],
+) -> Result<()> {
+    let commitment = status_code_commitment(verifier_commitments, &parsed_response.status_code)?;
+    let proof_committed_hash = extract_committed_hash_from_proof(proof)?;
+    if commitment.hash.value.as_bytes() != proof_committed_hash.as_slice() {
+        return Err(ZkTlsnError::CommittedHashMismatch);
+    }
+    proof
+        .metadata
+        .check_domain(verifier_commitments, commitment)?;
+    verify_proof_with_circuit(proof, &STATUS_CIRCUIT)
+}
+
+fn status_code_commitment<'a>(
+    commitments: &'a [TranscriptCommitment],
+    status_code: &Range<usize>,
+) -> Result<&'a PlaintextHash> {
+    commitments
+        .iter()
+        .find_map(|c| match c {
+            TranscriptCommitment::Hash(h)
+                if h.direction == Direction::Received
+                    && h.idx.min() == Some(status_code.start)
+                    && h.idx.end() == Some(status_code.end) =>
+            {
+                Some(h)
+            }
+            _ => None,
+        })
+        .ok_or(ZkTlsnError::NoReceivedCommitments)
+}
@@ -16,7 +16,9 @@ use hyper::Request;
 use smol::net::unix::UnixStream;
 use tlsnotary::{
     CertificateDer, MpcTlsConfig, ProverOutput, RootCertStore, ServerName, TlsClientConfig,
-    TlsCommitConfig, VerifierConfig, prover::RevealConfig, verifier::VerifierOutput,
+    TlsCommitConfig, VerifierConfig,
+    prover::{CommitHashAlgs, RequestLineConfig, RevealConfig},
+    verifier::VerifierOutput,
 };
 
 /// Socket pairs for prover-server and prover-verifier communication
@@ -95,6 +97,15 @@ pub fn create_request_reveal_config() -> RevealConfig {
         reveal_body_fields: vec![],
         commit_body_fields: vec![],
         reveal_keys_commit_values: vec![],
+        commit_keys_reveal_values: vec![],
+        reveal_sub_ranges: vec![],
+        commit_sub_ranges: vec![],
+        commit_full_body: false,
+        commit_status_class: false,
+        commit_hash_algs: CommitHashAlgs::default(),
+        commit_target_ranges: vec![],
+        request_line: RequestLineConfig::default(),
+        body_commit_ranges: None,
     }
 }
 
@@ -108,6 +119,15 @@ pub fn create_response_reveal_config() -> RevealConfig {
         reveal_body_fields: vec![BodyFieldConfig::Quoted(".username".into())],
         commit_body_fields: vec![],
         reveal_keys_commit_values: vec![KeyValueCommitConfig::with_padding(".balance".into(), 12)],
+        commit_keys_reveal_values: vec![],
+        reveal_sub_ranges: vec![],
+        commit_sub_ranges: vec![],
+        commit_full_body: false,
+        commit_status_class: false,
+        commit_hash_algs: CommitHashAlgs::default(),
+        commit_target_ranges: vec![],
+        request_line: RequestLineConfig::default(),
+        body_commit_ranges: None,
     }
 }
 
@@ -133,7 +153,8 @@ pub fn verify_prover_output(prover_output: &ProverOutput) {
 /// Verifies basic verifier output properties
 pub fn verify_verifier_output_basic(verifier_output: &VerifierOutput) {
     assert_eq!(
-        verifier_output.server_name, "localhost",
+        verifier_output.server_name.as_deref(),
+        Some("localhost"),
         "Verifier should verify correct server name"
     );
 
@@ -207,6 +228,53 @@ pub fn verify_parsed_response(verifier_output: &VerifierOutput, received_data: &
     verify_response_body(parsed_response, received_data);
 }
 
+/// Checks that verification honours the metadata carried with `proof`.
+pub fn verify_proof_metadata(proof: &crate::Proof) {
+    assert_eq!(proof.metadata.circuit_id, "circuit");
+    assert_eq!(proof.metadata.commitment_ids.len(), 1);
+    assert!(proof.metadata.commitment_ids[0].starts_with("received:"));
+    assert!(proof.metadata.expires_at.is_none());
+
+    let valid = proof
+        .clone()
+        .with_expiry(chrono::Utc::now() + chrono::Duration::hours(1));
+    crate::verify_proof(&valid).expect("Unexpired proof should verify");
+
+    let expired = proof
+        .clone()
+        .with_expiry(chrono::Utc::now() - chrono::Duration::seconds(1));
+    assert!(matches!(
+        crate::verify_proof(&expired),
+        Err(crate::ZkTlsnError::ProofExpired(_))
+    ));
+
+    let mut rebuilt = proof.clone();
+    rebuilt.metadata.circuit_version = "0".to_string();
+    assert!(matches!(
+        crate::verify_proof(&rebuilt),
+        Err(crate::ZkTlsnError::CircuitMismatch { .. })
+    ));
+
+    let mut rebound = proof.clone();
+    rebound.metadata.commitment_ids = vec!["00".repeat(32)];
+    assert!(matches!(
+        crate::verify_proof(&rebound),
+        Err(crate::ZkTlsnError::CommittedHashMismatch)
+    ));
+
+    let mut misdirected = proof.clone();
+    misdirected.metadata.commitment_ids = proof
+        .metadata
+        .commitment_ids
+        .iter()
+        .map(|id| id.replacen("received:", "sent:", 1))
+        .collect();
+    assert!(matches!(
+        crate::verify_proof(&misdirected),
+        Err(crate::ZkTlsnError::InvalidCommitmentDirection)
+    ));
+}
+
 pub fn verify_balance_commitment_and_proof(
     verifier_output: &VerifierOutput,
     proof: &crate::Proof,
@@ -250,6 +318,33 @@ pub fn verify_balance_commitment_and_proof(
     commitment_hash.copy_from_slice(commitment_hash_bytes);
     crate::verify_proof_against_hash(proof, &commitment_hash)?;
 
+    let balance_index = verifier_output
+        .transcript_commitments
+        .iter()
+        .position(|commitment| {
+            matches!(
+                commitment,
+                tlsnotary::TranscriptCommitment::Hash(hash)
+                    if hash.idx.min() == Some(value_start)
+                        && hash.hash.value.as_bytes() == commitment_hash_bytes
+            )
+        })
+        .expect("Balance commitment should be among verifier commitments");
+    let claimed_bindings = HashMap::from([(".balance".to_string(), balance_index)]);
+    let covered = crate::verify_proof_with_claimed_bindings(
+        proof,
+        parsed_response,
+        &verifier_output.transcript_commitments,
+        &claimed_bindings,
+    )?;
+    assert_eq!(
+        covered,
+        vec![crate::CoveredCommitment {
+            keypath: ".balance".to_string(),
+            commitment_index: balance_index,
+        }]
+    );
+
     tracing::info!("Successfully verified balance commitment and ZK proof");
     tracing::info!(
         "Bound commitments: {} field(s) with committed values",
@@ -285,7 +380,7 @@ fn verify_response_body(parsed_response: &parser::redacted::Response, received_d
             assert!(key.start < key.end);
             assert!(value.is_none());
         }
-        parser::redacted::Body::Value(_) => {
+        parser::redacted::Body::Value(_) | parser::redacted::Body::PartialValue { .. } => {
             panic!("Balance should be a key-value pair, not just a value");
         }
     }
@@ -301,7 +396,7 @@ fn verify_username_field(username_field: &parser::redacted::Body, received_data:
                 panic!("Username should have a value");
             }
         }
-        parser::redacted::Body::Value(_) => {
+        parser::redacted::Body::Value(_) | parser::redacted::Body::PartialValue { .. } => {
             panic!("Username should be a key-value pair, not just a value");
         }
     }
@@ -309,6 +404,8 @@ fn verify_username_field(username_field: &parser::redacted::Body, received_data:
 
 #[cfg(test)]
 mod integration {
+    use std::collections::BTreeMap;
+
     use futures::join;
     use noir::blackbox_solver::blake3;
     use server::{app::get_app, handle_connection};
@@ -327,6 +424,31 @@ mod integration {
         assert_eq!(blake3("123".as_bytes()).unwrap(), expected);
     }
 
+    #[test]
+    fn test_evm_proof_calldata_layout() {
+        let mut public_input = [0u8; 32];
+        public_input[31] = 7;
+        let proof = crate::EvmProof {
+            verification_key: vec![],
+            proof: vec![0xab; 40],
+            public_inputs: vec![public_input],
+        };
+
+        let calldata = proof.calldata();
+        let word = |index: usize| &calldata[4 + index * 32..4 + (index + 1) * 32];
+
+        assert_eq!(calldata[..4], crate::VERIFY_SELECTOR);
+        assert_eq!(calldata.len(), 4 + 32 * 7);
+        assert_eq!(word(0)[31], 0x40);
+        assert_eq!(word(1)[31], 0xa0);
+        assert_eq!(word(2)[31], 40);
+        assert_eq!(word(3), [0xab; 32]);
+        assert_eq!(word(4)[..8], [0xab; 8]);
+        assert!(word(4)[8..].iter().all(|&byte| byte == 0));
+        assert_eq!(word(5)[31], 1);
+        assert_eq!(word(6), public_input);
+    }
+
     #[test]
     fn test_end_to_end_proof_generation_verification_and_zkproof_generation() {
         shared::init_test_logging();
@@ -402,6 +524,112 @@ mod integration {
 
             verify_balance_commitment_and_proof(&verifier_output, &proof)
                 .expect("Balance commitment and proof verification should succeed");
+            verify_proof_metadata(&proof);
+
+            let threshold = crate::ThresholdClaim {
+                predicate: tlsnotary::Predicate::GreaterThan(50),
+                proof: crate::generate_predicate_proof(
+                    &prover_output.transcript_commitments,
+                    &prover_output.transcript_secrets,
+                    &prover_output.received,
+                    ".balance",
+                    tlsnotary::Predicate::GreaterThan(50),
+                )
+                .expect("Predicate proof generation should succeed"),
+            };
+            let parsed_response = verifier_output
+                .parsed_response
+                .as_ref()
+                .expect("Verifier should parse the response");
+            let bindings = crate::bind_commitments_with_thresholds(
+                parsed_response,
+                &verifier_output.transcript_commitments,
+                HashMap::from([(".balance".to_string(), threshold.clone())]),
+            )
+            .expect("Threshold should bind to the balance commitment");
+            bindings
+                .get(".balance")
+                .expect("Balance should have a bound commitment")
+                .verify()
+                .expect("Bound threshold should verify");
+            assert_eq!(
+                verifier::verify_thresholds(
+                    parsed_response,
+                    &verifier_output.transcript_commitments,
+                    BTreeMap::from([(".balance".to_string(), threshold.clone())]),
+                )
+                .expect("Verifier pipeline should accept the bound threshold"),
+                vec![".balance".to_string()]
+            );
+
+            let forged = crate::ThresholdClaim {
+                predicate: tlsnotary::Predicate::GreaterThan(1000),
+                ..threshold.clone()
+            };
+            let bindings = crate::bind_commitments_with_thresholds(
+                parsed_response,
+                &verifier_output.transcript_commitments,
+                HashMap::from([(".balance".to_string(), forged.clone())]),
+            )
+            .expect("Forged threshold should still bind to the balance commitment");
+            assert!(matches!(
+                bindings
+                    .get(".balance")
+                    .expect("Balance should have a bound commitment")
+                    .verify(),
+                Err(crate::ZkTlsnError::PredicateMismatch { .. })
+            ));
+            assert!(matches!(
+                verifier::verify_thresholds(
+                    parsed_response,
+                    &verifier_output.transcript_commitments,
+                    BTreeMap::from([(".balance".to_string(), forged)]),
+                ),
+                Err(verifier::ProtocolError::ProofVerificationFailed(_))
+            ));
+            assert!(matches!(
+                verifier::verify_thresholds(
+                    parsed_response,
+                    &verifier_output.transcript_commitments,
+                    BTreeMap::from([(".username".to_string(), threshold)]),
+                ),
+                Err(verifier::ProtocolError::ProofVerificationFailed(_))
+            ));
+
+            let artifact = verifier::ArtifactBuilder::new()
+                .notarized(
+                    "localhost",
+                    sent_data.as_str(),
+                    received_data.as_str(),
+                    verifier_output.transcript_commitments.clone(),
+                )
+                .finish(
+                    proof.clone(),
+                    verifier::VerificationOutcome::success(
+                        "localhost".to_string(),
+                        vec![".balance".to_string()],
+                        "verified".to_string(),
+                    ),
+                )
+                .expect("Artifact should seal");
+            let digest = artifact.digest().expect("Artifact digest should hash");
+            for index in 0..artifact.transcript_commitments.len() {
+                let mut dropped = artifact.clone();
+                dropped.transcript_commitments.remove(index);
+                assert_ne!(
+                    dropped.digest().expect("Artifact digest should hash"),
+                    digest,
+                    "Digest should commit to transcript commitment {index}"
+                );
+            }
+            let mut reordered = artifact.clone();
+            reordered.transcript_commitments.reverse();
+            if reordered.transcript_commitments.len() > 1 {
+                assert_ne!(
+                    reordered.digest().expect("Artifact digest should hash"),
+                    digest
+                );
+            }
         });
     }
 }
@@ -1,18 +1,38 @@
+use std::collections::HashMap;
+
 use noir::barretenberg::verify::{get_ultra_honk_verification_key, verify_ultra_honk};
+use tlsnotary::{Direction, PlaintextHash, TranscriptCommitment};
 
 use crate::{
     Proof,
+    commitment::MAX_KEY_VALUE_GAP,
     error::{Result, ZkTlsnError},
-    prover::load_circuit_bytecode,
+    metadata::Circuit,
+    prover::CIRCUIT,
 };
 
-const HONK_FIELD_BYTES: usize = 32;
-const COMMITTED_HASH_BYTES: usize = 32;
+pub(crate) const HONK_FIELD_BYTES: usize = 32;
+pub(crate) const COMMITTED_HASH_BYTES: usize = 32;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoveredCommitment {
+    pub keypath: String,
+    pub commitment_index: usize,
+}
 
+/// Verifies `proof` against the balance circuit, rejecting it when its
+/// metadata names another circuit or build, has expired, or does not list
+/// the commitment the proof is about.
 pub fn verify_proof(proof: &Proof) -> Result<()> {
-    let bytecode = load_circuit_bytecode()?;
-    let computed_vk =
-        get_ultra_honk_verification_key(&bytecode, false).map_err(ZkTlsnError::NoirError)?;
+    verify_proof_with_circuit(proof, &CIRCUIT)
+}
+
+pub(crate) fn verify_proof_with_circuit(proof: &Proof, circuit: &Circuit) -> Result<()> {
+    proof
+        .metadata
+        .check(circuit, &extract_committed_hash_from_proof(proof)?)?;
+    let computed_vk = get_ultra_honk_verification_key(&circuit.bytecode()?, false)
+        .map_err(ZkTlsnError::NoirError)?;
     if computed_vk != proof.verification_key {
         return Err(ZkTlsnError::VerificationKeyMismatch);
     }
@@ -71,3 +91,88 @@ pub fn verify_proof_against_hash(
     }
     Ok(())
 }
+
+pub fn verify_proof_with_claimed_bindings(
+    proof: &Proof,
+    parsed_response: &parser::redacted::Response,
+    verifier_commitments: &[TranscriptCommitment],
+    claimed_bindings: &HashMap<String, usize>,
+) -> Result<Vec<CoveredCommitment>> {
+    let proof_committed_hash = extract_committed_hash_from_proof(proof)?;
+
+    let mut covered = claimed_bindings
+        .iter()
+        .map(|(keypath, &commitment_index)| {
+            let hash = claimed_commitment(verifier_commitments, commitment_index)?;
+            check_commitment_follows_key(parsed_response, keypath, hash)?;
+            Ok((keypath, commitment_index, hash))
+        })
+        .collect::<Result<Vec<_>>>()?
+        .into_iter()
+        .filter(|(_, _, hash)| hash.hash.value.as_bytes() == proof_committed_hash.as_slice())
+        .map(|(keypath, commitment_index, _)| CoveredCommitment {
+            keypath: keypath.clone(),
+            commitment_index,
+        })
+        .collect::<Vec<_>>();
+
+    if covered.is_empty() {
+        return Err(ZkTlsnError::CommittedHashMismatch);
+    }
+    for coverage in &covered {
+        let hash = claimed_commitment(verifier_commitments, coverage.commitment_index)?;
+        proof.metadata.check_domain(verifier_commitments, hash)?;
+    }
+
+    verify_proof(proof)?;
+    covered.sort_by_key(|coverage| coverage.commitment_index);
+    Ok(covered)
+}
+
+fn claimed_commitment(
+    verifier_commitments: &[TranscriptCommitment],
+    commitment_index: usize,
+) -> Result<&PlaintextHash> {
+    match verifier_commitments.get(commitment_index) {
+        Some(TranscriptCommitment::Hash(hash)) if hash.direction == Direction::Received => Ok(hash),
+        Some(TranscriptCommitment::Hash(_)) => Err(ZkTlsnError::InvalidCommitmentDirection),
+        Some(_) => Err(ZkTlsnError::InvalidInput(format!(
+            "commitment {commitment_index} is not a hash commitment"
+        ))),
+        None => Err(ZkTlsnError::InvalidInput(format!(
+            "commitment index {commitment_index} out of range ({} commitments)",
+            verifier_commitments.len()
+        ))),
+    }
+}
+
+fn check_commitment_follows_key(
+    parsed_response: &parser::redacted::Response,
+    keypath: &str,
+    hash: &PlaintextHash,
+) -> Result<()> {
+    let key = match parsed_response.body.get(keypath) {
+        Some(parser::redacted::Body::KeyValue { key, value: None }) => key,
+        Some(_) => {
+            return Err(ZkTlsnError::InvalidInput(format!(
+                "field {keypath} is not a key with a redacted value"
+            )));
+        }
+        None => {
+            return Err(ZkTlsnError::InvalidInput(format!(
+                "field {keypath} not found in redacted response"
+            )));
+        }
+    };
+
+    let start = hash.idx.min().ok_or_else(|| {
+        ZkTlsnError::InvalidInput("received transcript commitment is missing range start".into())
+    })?;
+    if start < key.end || start - key.end > MAX_KEY_VALUE_GAP {
+        return Err(ZkTlsnError::InvalidInput(format!(
+            "commitment starting at {start} is not bound to key {keypath} ending at {}",
+            key.end
+        )));
+    }
+    Ok(())
+}